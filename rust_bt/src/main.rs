@@ -1,5 +1,8 @@
-use rust_core::engine::{Backtest, Strategy};
-use rust_core::stats::compute_stats;
+use rust_core::engine::{Backtest, CashFlow, Strategy};
+use rust_core::params::{run_label, Parameterized};
+use rust_core::manifest::RunManifest;
+use rust_core::artifacts::ArtifactWriter;
+use rust_core::stats::{compute_stats_with_config, print_tag_stats, risk_of_ruin, stats_by_exit_tag, stats_by_tag, Benchmark, RuinConfig, StatsConfig, StatsFormat};
 #[allow(unused_imports)]
 use rust_core::strategies::statarb_spread::StatArbSpreadStrategy;
 #[allow(unused_imports)]
@@ -7,6 +10,10 @@ use rust_core::strategies::sma::SmaStrategy;
 #[allow(unused_imports)]
 use rust_core::strategies::simple_strategy::SimpleStrategy;
 #[allow(unused_imports)]
+use rust_core::strategies::bollinger_reversion::BollingerReversionStrategy;
+#[allow(unused_imports)]
+use rust_core::strategies::donchian_breakout::DonchianBreakoutStrategy;
+#[allow(unused_imports)]
 use rust_core::strategies::statarb_pairs::StatArbPairsStrategy;
 #[allow(unused_imports)]
 use rust_core::strategies::scaled_statarb_pairs::ScaledStatArbPairsStrategy;
@@ -14,7 +21,8 @@ use rust_core::strategies::scaled_statarb_pairs::ScaledStatArbPairsStrategy;
 use rust_core::strategies::dynamic_pairs::DynamicPairsStrategy;
 #[allow(unused_imports)]
 use rust_core::strategies::ml_statarb_pairs::MLStatArbPairsStrategy;
-use rust_core::data_handler::handle_ohlc;
+use rust_core::data_handler::cache::handle_ohlc_cached;
+use rust_core::diagnostics::{broker_diagnostics, DiagnosticsConfig};
 use std::time::Instant;
 
 fn main() {
@@ -22,7 +30,9 @@ fn main() {
     let start = Instant::now();
 
     // CHANGE PATH
-    let data = handle_ohlc("/Users/jarlen/NHNTrading/rust_bt/rust_bt/data/SP500_DJIA_2m_clean.csv").expect("Failed to load CSV data");
+    let data_path = "/Users/jarlen/NHNTrading/rust_bt/rust_bt/data/SP500_DJIA_2m_clean.csv";
+    // parsed data is cached next to the csv (<file>.cache) and rebuilt when the csv changes
+    let data = handle_ohlc_cached(data_path).expect("Failed to load CSV data");
 
     let cash = 100_000.0;
     let commission = 0.0;
@@ -32,9 +42,32 @@ fn main() {
     let hedging = false;
     let exclusive_orders = false;
     let scaling_enabled = true;
+    let execution_delay = 1; // bars between signal and fill for market orders
+    let seed = 42; // seeds broker.rng, the only randomness strategies should use
+    // buy & hold reference for stats and the equity plot: Close, Close2, or External(load_benchmark(path, &data.date)?)
+    let benchmark = Benchmark::Close;
+    // scheduled deposits (+) / withdrawals (-), e.g. vec![CashFlow::new("2024-01-02", 10_000.0)]
+    let cash_flows: Vec<CashFlow> = Vec::new();
+    // restrict the run to a date range (inclusive), e.g. (Some("2024-01-02"), Some("2024-06-28")); None keeps all data
+    let date_range: (Option<&str>, Option<&str>) = (None, None);
+    // number format of the stats table: StatsFormat::default() (1234567.89 $), StatsFormat::us("$") or StatsFormat::eu("€")
+    let stats_format = StatsFormat::default();
+    // trade log, plots, stats and manifest go to <output_dir>/<label>_<timestamp>/; None writes output_* files here
+    let output_dir: Option<&str> = Some("runs");
+
+    // tunable parameters can be overridden on the command line, e.g. `cargo run -- lookback=20 zscore_threshold=1.5`;
+    // `--params` lists them with their ranges
+    let mut strategy = StatArbSpreadStrategy::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--params") {
+        strategy.print_params();
+        return;
+    }
+    strategy.apply_args(&args).expect("Invalid strategy parameter");
+    let params = strategy.param_values();
 
     // boxed instance of strategy
-    let strategy: Box<dyn Strategy> = Box::new(StatArbSpreadStrategy::new());
+    let strategy: Box<dyn Strategy> = Box::new(strategy);
 
     let mut backtest = Backtest::new(
         data,
@@ -49,24 +82,132 @@ fn main() {
         scaling_enabled, // enable scaling
     );
 
+    if date_range.0.is_some() || date_range.1.is_some() {
+        backtest.set_date_range(date_range.0, date_range.1);
+    }
+    backtest.broker.execution_delay = execution_delay;
+    backtest.broker.set_seed(seed);
+    backtest.broker.set_cash_flows(cash_flows);
+    // runs with parameter overrides are labeled (e.g. statarb_lookback20_..., in plot titles and the run directory name)
+    if !args.is_empty() {
+        backtest.set_label(&run_label("statarb", &params));
+    }
+    if let Some(dir) = output_dir {
+        match ArtifactWriter::new(dir, backtest.label.as_deref()) {
+            Ok(artifacts) => {
+                println!("writing run artifacts to {}", artifacts.dir.display());
+                backtest.set_artifacts(artifacts);
+            }
+            Err(e) => eprintln!("error creating output directory, writing to the working directory: {}", e),
+        }
+    }
+
+    // everything needed to re-run this backtest with Backtest::from_manifest
+    match RunManifest::capture(&backtest, "statarb_spread", params, data_path) {
+        Ok(manifest) => {
+            if let Err(e) = manifest.save(&backtest.output_path("output_manifest.json")) {
+                eprintln!("error saving manifest: {}", e);
+            }
+        }
+        Err(e) => eprintln!("error creating manifest: {}", e),
+    }
+
     backtest.run();
 
-    let stats = compute_stats(
+    let stats_config = StatsConfig {
+        benchmark,
+        cash_flows: backtest.broker.cash_flow_history.clone(),
+        intrabar_equity: backtest.broker.intrabar_equity.clone(),
+        size_multipliers: backtest.broker.size_multiplier_history.clone(),
+        ..StatsConfig::default()
+    };
+    let stats = compute_stats_with_config(
         &backtest.broker.closed_trades,
         &backtest.broker.equity,
         &backtest.data,
         0.0421, // risk free rate as fraction
-        backtest.broker.max_margin_usage // pass max margin usage
+        backtest.broker.max_margin_usage, // pass max margin usage
+        &backtest.broker.gross_exposure_history(),
+        &backtest.broker.cost_summary(),
+        &stats_config,
     );
 
-    println!("{}", stats);
+    println!("{}", stats.display(&stats_format));
+    println!("{}", stats.summary_line(&stats_format));
+    // machine-readable copy of the stats for scripts and ci
+    match stats.to_json() {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(backtest.output_path("output_stats.json"), json) {
+                eprintln!("error saving stats: {}", e);
+            }
+        }
+        Err(e) => eprintln!("error serializing stats: {}", e),
+    }
+    // buy & hold through the same broker settings, so the comparison includes commissions, spread and margin
+    let buy_and_hold = backtest.run_buy_and_hold();
+    let buy_and_hold_config = StatsConfig {
+        intrabar_equity: buy_and_hold.broker.intrabar_equity.clone(),
+        size_multipliers: buy_and_hold.broker.size_multiplier_history.clone(),
+        ..stats_config.clone()
+    };
+    let buy_and_hold_stats = compute_stats_with_config(
+        &buy_and_hold.broker.closed_trades,
+        &buy_and_hold.broker.equity,
+        &buy_and_hold.data,
+        0.0421,
+        buy_and_hold.broker.max_margin_usage,
+        &buy_and_hold.broker.gross_exposure_history(),
+        &buy_and_hold.broker.cost_summary(),
+        &buy_and_hold_config,
+    );
+    println!("{}", stats.compare(&buy_and_hold_stats, "Buy & Hold", &stats_format));
+    print_tag_stats("Performance by Entry Tag", &stats_by_tag(&backtest.broker.closed_trades));
+    print_tag_stats("Performance by Exit Tag", &stats_by_exit_tag(&backtest.broker.closed_trades));
+    println!("{}", broker_diagnostics(&backtest.broker, &DiagnosticsConfig::default()));
+    println!("{}", risk_of_ruin(&backtest.broker.closed_trades, &backtest.broker.equity, &RuinConfig::default()));
     println!("time taken: {:?}", start.elapsed());
     
-    if let Err(e) = backtest.plot_equity_and_benchmark(&backtest.data.close, "output_equity.png") {
+    let plotted = match stats_config.benchmark.series(&backtest.data) {
+        Some(benchmark) => backtest.plot_equity_and_benchmark(benchmark, "output_equity.png"),
+        None => backtest.plot("output_equity.png"),
+    };
+    if let Err(e) = plotted {
+        eprintln!("error generating plot: {}", e);
+    }
+
+    if let Err(e) = backtest.plot_equity_and_benchmark(&buy_and_hold.broker.equity, "output_equity_vs_buy_and_hold.png") {
+        eprintln!("error generating plot: {}", e);
+    }
+
+    if let Err(e) = backtest.plot_underwater("output_underwater.png") {
+        eprintln!("error generating plot: {}", e);
+    }
+
+    if let Err(e) = backtest.plot_relative_underwater(&buy_and_hold.broker.equity, "output_underwater_vs_buy_and_hold.png") {
         eprintln!("error generating plot: {}", e);
     }
 
     if let Err(e) = backtest.plot_margin_usage("output_margin_usage.png") {
         eprintln!("error generating plot: {}", e);
     }
+
+    if let Err(e) = backtest.plot_exposure("output_exposure.png") {
+        eprintln!("error generating plot: {}", e);
+    }
+
+    if let Err(e) = backtest.plot_position_timeline("output_positions.png") {
+        eprintln!("error generating plot: {}", e);
+    }
+
+    if let Err(e) = backtest.plot_trade_histogram("output_trade_pnl.png") {
+        eprintln!("error generating plot: {}", e);
+    }
+
+    if let Err(e) = backtest.plot_holding_periods("output_holding_periods.png") {
+        eprintln!("error generating plot: {}", e);
+    }
+
+    if let Err(e) = backtest.plot_pnl_vs_holding("output_pnl_vs_holding.png") {
+        eprintln!("error generating plot: {}", e);
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,123 @@
+// broker hot path: time per bar and heap allocations per bar on a long synthetic run.
+// `cargo bench -p rust_core --bench broker` prints the allocation report, then the criterion timings.
+use criterion::{criterion_group, Criterion};
+use rust_core::engine::{Backtest, Broker, OhlcData, Order, Strategy, TakeProfitLevel};
+use rust_core::util::Rng;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// counts every allocation made through the global allocator
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// random walk bars for both instruments
+fn synthetic_data(bars: usize) -> OhlcData {
+    let mut rng = Rng::new(7);
+    let (mut price, mut price2) = (4000.0, 35000.0);
+    let mut data = OhlcData {
+        date: Vec::with_capacity(bars),
+        open: Vec::with_capacity(bars),
+        high: Vec::with_capacity(bars),
+        low: Vec::with_capacity(bars),
+        close: Vec::with_capacity(bars),
+        close2: Vec::with_capacity(bars),
+        volume: None,
+        halts: Vec::new(),
+    };
+    for i in 0..bars {
+        let open = price;
+        price *= 1.0 + 0.001 * rng.normal();
+        price2 *= 1.0 + 0.001 * rng.normal();
+        data.date.push(format!("bar {}", i));
+        data.open.push(open);
+        data.high.push(open.max(price) * (1.0 + 0.0005 * rng.next_f64()));
+        data.low.push(open.min(price) * (1.0 - 0.0005 * rng.next_f64()));
+        data.close.push(price);
+        data.close2.push(price2);
+    }
+    data
+}
+
+// keeps the order queue busy: an entry with a stop loss and a two-rung take-profit ladder
+// whenever flat, and a time exit after 20 bars
+struct ChurnStrategy;
+
+impl Strategy for ChurnStrategy {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        if !broker.trades.is_empty() || !broker.orders.is_empty() {
+            return;
+        }
+        let price = broker.data.close[index];
+        let side = if index % 2 == 0 { 1.0 } else { -1.0 };
+        let order = Order {
+            size: 10.0 * side,
+            sl: Some(price * (1.0 - side * 0.002)),
+            instrument: 1,
+            tp_ladder: TakeProfitLevel::from_sigma(price, price * 0.001, side, &[(1.0, 0.5), (2.0, 0.5)]),
+            max_holding_bars: Some(20),
+            ..Default::default()
+        };
+        let _ = broker.new_order(order, price);
+    }
+}
+
+fn backtest(data: &OhlcData) -> Backtest {
+    Backtest::new(data.clone(), Box::new(ChurnStrategy), 1_000_000.0, 0.0, 0.0, 0.05, false, true, false, false)
+}
+
+fn allocation_report(bars: usize) {
+    let data = synthetic_data(bars);
+    let mut bt = backtest(&data);
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    bt.run_silent();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "{} bars, {} closed trades: {} allocations ({:.3} per bar, {:.2} per trade)",
+        bars,
+        bt.broker.closed_trades.len(),
+        allocations,
+        allocations as f64 / bars as f64,
+        allocations as f64 / bt.broker.closed_trades.len().max(1) as f64,
+    );
+}
+
+fn bench_run(c: &mut Criterion) {
+    let data = synthetic_data(100_000);
+    c.bench_function("backtest 100k bars", |b| {
+        b.iter(|| {
+            let mut bt = backtest(&data);
+            bt.run_silent();
+            bt.broker.closed_trades.len()
+        })
+    });
+}
+
+criterion_group!(benches, bench_run);
+
+fn main() {
+    allocation_report(2_000_000);
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
@@ -0,0 +1,146 @@
+// cross-instrument analysis: return correlations and hierarchical clustering
+use crate::engine::OhlcData;
+use crate::plot::plot_correlation_heatmap;
+use crate::screening::date_aligned_returns;
+use crate::util::correlation;
+
+// one merge step of the agglomerative clustering; cluster ids < n are single symbols,
+// ids >= n refer to the cluster created by merge (id - n)
+#[derive(Clone, Debug)]
+pub struct ClusterMerge {
+    pub left: usize,
+    pub right: usize,
+    pub distance: f64,
+    pub size: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct CorrelationReport {
+    pub symbols: Vec<String>,
+    pub matrix: Vec<Vec<f64>>,     // return correlation matrix in input order
+    pub merges: Vec<ClusterMerge>, // dendrogram, n - 1 merges
+    pub order: Vec<usize>,         // leaf order of the dendrogram (similar symbols are adjacent)
+}
+
+// compute the pearson correlation matrix of close log returns. each pair is joined on its common dates
+// like the screening benchmark correlation, so series of different lengths or with missing days are
+// compared over the same intervals; a pair with fewer than two common returns has correlation 0
+pub fn correlation_matrix(symbols: &[(String, OhlcData)]) -> Vec<Vec<f64>> {
+    let n = symbols.len();
+    let mut matrix = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        matrix[i][i] = 1.0;
+        for j in (i + 1)..n {
+            let (a, b) = (&symbols[i].1, &symbols[j].1);
+            let c = if a.close.is_empty() {
+                0.0
+            } else {
+                let (returns, other) = date_aligned_returns(a, 0, a.close.len() - 1, b);
+                correlation(&returns, &other)
+            };
+            matrix[i][j] = c;
+            matrix[j][i] = c;
+        }
+    }
+    matrix
+}
+
+// average-linkage agglomerative clustering on the distance d = sqrt(0.5 * (1 - corr)).
+// returns the merge steps and the resulting leaf order.
+pub fn hierarchical_clustering(matrix: &[Vec<f64>]) -> (Vec<ClusterMerge>, Vec<usize>) {
+    let n = matrix.len();
+    let dist = |i: usize, j: usize| (0.5 * (1.0 - matrix[i][j])).max(0.0).sqrt();
+
+    // active clusters: (cluster id, member leaves)
+    let mut clusters: Vec<(usize, Vec<usize>)> = (0..n).map(|i| (i, vec![i])).collect();
+    let mut merges = Vec::with_capacity(n.saturating_sub(1));
+
+    while clusters.len() > 1 {
+        let mut best = (0, 1, f64::INFINITY);
+        for a in 0..clusters.len() {
+            for b in (a + 1)..clusters.len() {
+                let members_a = &clusters[a].1;
+                let members_b = &clusters[b].1;
+                let total: f64 = members_a.iter()
+                    .flat_map(|&i| members_b.iter().map(move |&j| (i, j)))
+                    .map(|(i, j)| dist(i, j))
+                    .sum();
+                let avg = total / (members_a.len() * members_b.len()) as f64;
+                if avg < best.2 {
+                    best = (a, b, avg);
+                }
+            }
+        }
+        let (a, b, distance) = best;
+        // remove the higher index first so the lower one stays valid
+        let (right_id, right_members) = clusters.remove(b);
+        let (left_id, mut left_members) = clusters.remove(a);
+        left_members.extend(right_members);
+        merges.push(ClusterMerge {
+            left: left_id,
+            right: right_id,
+            distance,
+            size: left_members.len(),
+        });
+        clusters.push((n + merges.len() - 1, left_members));
+    }
+
+    let order = clusters.pop().map(|(_, members)| members).unwrap_or_default();
+    (merges, order)
+}
+
+// build a full correlation and clustering report from loaded ohlc data, using close prices
+pub fn correlation_report(symbols: &[(String, OhlcData)]) -> CorrelationReport {
+    let matrix = correlation_matrix(symbols);
+    let (merges, order) = hierarchical_clustering(&matrix);
+    CorrelationReport {
+        symbols: symbols.iter().map(|(symbol, _)| symbol.clone()).collect(),
+        matrix,
+        merges,
+        order,
+    }
+}
+
+impl CorrelationReport {
+    // correlation matrix and labels reordered by the clustering leaf order
+    pub fn ordered(&self) -> (Vec<String>, Vec<Vec<f64>>) {
+        let labels = self.order.iter().map(|&i| self.symbols[i].clone()).collect();
+        let matrix = self.order.iter()
+            .map(|&i| self.order.iter().map(|&j| self.matrix[i][j]).collect())
+            .collect();
+        (labels, matrix)
+    }
+
+    // the most correlated pairs, highest first (useful for pair selection)
+    pub fn top_pairs(&self, n: usize) -> Vec<(String, String, f64)> {
+        let mut pairs = Vec::new();
+        for i in 0..self.symbols.len() {
+            for j in (i + 1)..self.symbols.len() {
+                pairs.push((self.symbols[i].clone(), self.symbols[j].clone(), self.matrix[i][j]));
+            }
+        }
+        pairs.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+        pairs.truncate(n);
+        pairs
+    }
+
+    // render the clustered correlation heatmap
+    pub fn plot(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let (labels, matrix) = self.ordered();
+        plot_correlation_heatmap(&labels, &matrix, output_path)
+    }
+
+    // write the correlation matrix to csv in clustered order
+    pub fn save_csv(&self, file_path: &str) -> std::io::Result<()> {
+        use std::fs::File;
+        use std::io::Write;
+        let (labels, matrix) = self.ordered();
+        let mut file = File::create(file_path)?;
+        writeln!(file, ",{}", labels.join(","))?;
+        for (label, row) in labels.iter().zip(matrix.iter()) {
+            let values: Vec<String> = row.iter().map(|v| format!("{:.4}", v)).collect();
+            writeln!(file, "{},{}", label, values.join(","))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+// output directory of a run: trade log, plots, stats and manifest are written into one directory per run
+// instead of output_* files in the working directory
+use serde::Serialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug)]
+pub struct ArtifactWriter {
+    pub dir: PathBuf,
+}
+
+impl ArtifactWriter {
+    /// a fresh directory `<base>/<label>_<utc timestamp>` (label defaults to "run"), created with its parents.
+    /// a run started in the same second as an earlier one with the same label gets a "_2", "_3", ... suffix.
+    pub fn new(base: &str, label: Option<&str>) -> std::io::Result<Self> {
+        let stamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+        let name = format!("{}_{}", label.unwrap_or("run"), stamp);
+        let mut dir = Path::new(base).join(&name);
+        let mut n = 2;
+        while dir.exists() {
+            dir = Path::new(base).join(format!("{}_{}", name, n));
+            n += 1;
+        }
+        std::fs::create_dir_all(&dir)?;
+        Ok(ArtifactWriter { dir })
+    }
+
+    /// write into `dir` as is (created if missing), e.g. to re-use a directory across runs
+    pub fn in_dir(dir: &str) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(ArtifactWriter { dir: PathBuf::from(dir) })
+    }
+
+    // path of an artifact in the run directory
+    pub fn path(&self, name: &str) -> String {
+        self.dir.join(name).to_string_lossy().into_owned()
+    }
+
+    pub fn write(&self, name: &str, contents: &str) -> std::io::Result<String> {
+        let path = self.path(name);
+        std::fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    // pretty json of any serializable result (stats, manifest, reports)
+    pub fn write_json<T: Serialize>(&self, name: &str, value: &T) -> Result<String, Box<dyn Error>> {
+        Ok(self.write(name, &serde_json::to_string_pretty(value)?)?)
+    }
+}
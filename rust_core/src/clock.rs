@@ -0,0 +1,64 @@
+// time source for the live path; swap SystemClock for a TestClock to make runs deterministic
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub type ClockRef = Arc<dyn Clock>;
+
+// wall clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+pub fn system_clock() -> ClockRef {
+    Arc::new(SystemClock)
+}
+
+// manually driven clock; time only moves when set or advanced
+#[derive(Debug)]
+pub struct TestClock {
+    millis: AtomicI64,
+}
+
+impl TestClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        TestClock {
+            millis: AtomicI64::new(start.timestamp_millis()),
+        }
+    }
+
+    pub fn set(&self, time: DateTime<Utc>) {
+        self.millis.store(time.timestamp_millis(), Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.millis.fetch_add(by.num_milliseconds(), Ordering::SeqCst);
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(self.millis.load(Ordering::SeqCst))
+            .single()
+            .unwrap_or_else(Utc::now)
+    }
+}
+
+// parse a quote timestamp (rfc3339 as sent by saxo, or "%Y-%m-%d %H:%M:%S")
+pub fn parse_quote_time(date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(date) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|dt| dt.and_utc())
+}
@@ -0,0 +1,123 @@
+// leveled and throttled console output for the live loop: messages below the configured level are
+// dropped, and the per-tick stats line is replaced by a summary every N seconds and/or N ticks
+use chrono::{DateTime, Duration, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug, // per-tick detail such as quotes and signals
+    Info,  // fills, closes, resyncs
+    Warn,  // rejected orders, margin calls, data gaps
+    Error,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            other => Err(format!("unknown log level '{}', expected debug, info, warn or error", other)),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// console reporter of a live session. `summary_interval` and `summary_ticks` throttle the stats
+/// summary: it is due once either has passed since the last one (both None prints it every tick).
+#[derive(Clone, Debug)]
+pub struct ConsoleReporter {
+    pub level: LogLevel,
+    pub summary_interval: Option<Duration>,
+    pub summary_ticks: Option<usize>,
+    last_summary: Option<DateTime<Utc>>,
+    ticks_since_summary: usize,
+}
+
+impl Default for ConsoleReporter {
+    fn default() -> Self {
+        ConsoleReporter {
+            level: LogLevel::Info,
+            summary_interval: Some(Duration::seconds(10)),
+            summary_ticks: None,
+            last_summary: None,
+            ticks_since_summary: 0,
+        }
+    }
+}
+
+impl ConsoleReporter {
+    pub fn new(level: LogLevel, summary_interval: Option<Duration>, summary_ticks: Option<usize>) -> Self {
+        ConsoleReporter { level, summary_interval, summary_ticks, ..ConsoleReporter::default() }
+    }
+
+    pub fn enabled(&self, level: LogLevel) -> bool {
+        level >= self.level
+    }
+
+    // print a message at `level`; warnings and errors go to stderr
+    pub fn log(&self, level: LogLevel, message: impl fmt::Display) {
+        if !self.enabled(level) {
+            return;
+        }
+        if level >= LogLevel::Warn {
+            eprintln!("[{}] {}", level, message);
+        } else {
+            println!("[{}] {}", level, message);
+        }
+    }
+
+    pub fn debug(&self, message: impl fmt::Display) {
+        self.log(LogLevel::Debug, message);
+    }
+
+    pub fn info(&self, message: impl fmt::Display) {
+        self.log(LogLevel::Info, message);
+    }
+
+    pub fn warn(&self, message: impl fmt::Display) {
+        self.log(LogLevel::Warn, message);
+    }
+
+    pub fn error(&self, message: impl fmt::Display) {
+        self.log(LogLevel::Error, message);
+    }
+
+    // count one tick at time `now` and tell whether the summary should be printed; the first tick
+    // always gets one. summaries are info level, so warn and error silence them.
+    pub fn summary_due(&mut self, now: DateTime<Utc>) -> bool {
+        self.ticks_since_summary += 1;
+        if !self.enabled(LogLevel::Info) {
+            return false;
+        }
+        let by_time = match (self.summary_interval, self.last_summary) {
+            (Some(interval), Some(last)) => now - last >= interval,
+            (_, None) => true,
+            (None, Some(_)) => false,
+        };
+        let by_ticks = self.summary_ticks.is_some_and(|n| self.ticks_since_summary >= n);
+        let every_tick = self.summary_interval.is_none() && self.summary_ticks.is_none();
+        if by_time || by_ticks || every_tick {
+            self.last_summary = Some(now);
+            self.ticks_since_summary = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// cross-validation of the bar backtester against the tick-replay live engine: both run over the same
+// recorded quotes, and their trades are paired up to measure how far the bar simulation drifts from what
+// the live engine does with the same strategy logic (fill prices, missing or extra trades, pnl)
+use crate::clock::{parse_quote_time, TestClock};
+use crate::data_handler::ticks_to_bars_indexed;
+use crate::engine::Backtest;
+use crate::live_engine::{LiveBacktest, TickSnapshot};
+use crate::stats::Distribution;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Clone, Debug)]
+pub struct CrossValidationConfig {
+    pub primary: String,           // instrument 1 of the backtest
+    pub secondary: Option<String>, // instrument 2 (close2), None for single-instrument strategies
+    // a bar trade and a live trade on the same instrument and side are paired when their entries are at
+    // most this many ticks apart
+    pub max_entry_lag: usize,
+}
+
+// one trade of either engine, on the tick axis of the recording
+#[derive(Clone, Debug)]
+pub struct ComparedTrade {
+    pub instrument: String,
+    pub size: f64,
+    pub entry_tick: usize,
+    pub entry_price: f64,
+    pub exit_tick: Option<usize>,
+    pub exit_price: Option<f64>,
+    pub pnl: f64, // realized, 0.0 while open
+}
+
+// a bar trade and the live trade it was matched with. price differences are live minus bar in the
+// direction of the trade, so positive means the live engine filled worse.
+#[derive(Clone, Debug)]
+pub struct TradePair {
+    pub bar: ComparedTrade,
+    pub live: ComparedTrade,
+    pub entry_lag: i64, // live entry tick - bar entry tick
+    pub entry_diff: f64,
+    pub exit_diff: Option<f64>, // None unless both trades are closed
+    pub pnl_diff: f64,          // live pnl - bar pnl
+}
+
+#[derive(Clone, Debug)]
+pub struct DivergenceReport {
+    pub ticks: usize,
+    pub bars: usize,
+    pub bar_trades: usize,
+    pub live_trades: usize,
+    pub pairs: Vec<TradePair>,
+    pub unmatched_bar: Vec<ComparedTrade>,  // trades only the backtest took
+    pub unmatched_live: Vec<ComparedTrade>, // trades only the live engine took
+    pub entry_diff: Distribution,
+    pub exit_diff: Distribution,
+    pub entry_lag: Distribution,
+    pub bar_pnl: f64, // realized pnl of all closed trades
+    pub live_pnl: f64,
+    pub bar_final_equity: f64,
+    pub live_final_equity: f64,
+}
+
+impl DivergenceReport {
+    // share of all trades of both engines that found a partner
+    pub fn match_rate(&self) -> f64 {
+        let total = self.bar_trades + self.live_trades;
+        if total == 0 {
+            return 1.0;
+        }
+        (2 * self.pairs.len()) as f64 / total as f64
+    }
+}
+
+/// run `backtest` on pseudo-bars built from `ticks` (data_handler::ticks_to_bars; the backtest's data is
+/// replaced) and replay the same ticks through `live`, then pair their trades. both engines should be set
+/// up with the same strategy logic and account settings; what is left is the simulation bias of the bars
+/// (fills at the next bar's mid instead of the current bid/ask, no spread unless modelled).
+pub fn cross_validate(
+    ticks: &[TickSnapshot],
+    backtest: &mut Backtest,
+    live: &mut LiveBacktest,
+    config: &CrossValidationConfig,
+) -> DivergenceReport {
+    let (bars, bar_ticks) = ticks_to_bars_indexed(ticks, &config.primary, config.secondary.as_deref());
+    let n_bars = bars.close.len();
+    backtest.data = bars.clone();
+    backtest.broker.set_data(bars);
+    backtest.run_silent();
+
+    let start = ticks.iter().find_map(|t| parse_quote_time(&t.date)).unwrap_or_else(chrono::Utc::now);
+    // ticks already in the live history (preloaded) come before the recording
+    let offset = live.broker.live_data.ticks.len();
+    live.replay(ticks, Arc::new(TestClock::new(start)));
+
+    // bar indices map back to the tick that produced the bar
+    let name = |instrument: u8| match instrument {
+        1 => config.primary.clone(),
+        _ => config.secondary.clone().unwrap_or_default(),
+    };
+    let to_tick = |bar: usize| bar_ticks.get(bar).copied().unwrap_or(ticks.len());
+    let bar_trades: Vec<ComparedTrade> = backtest.broker.closed_trades.iter()
+        .chain(backtest.broker.trades.iter())
+        .map(|t| ComparedTrade {
+            instrument: name(t.instrument),
+            size: t.size,
+            entry_tick: to_tick(t.entry_index),
+            entry_price: t.entry_price,
+            exit_tick: t.exit_index.map(to_tick),
+            exit_price: t.exit_price,
+            pnl: t.pnl(),
+        })
+        .collect();
+    let live_trades: Vec<ComparedTrade> = live.broker.closed_trades.iter()
+        .chain(live.broker.trades.iter())
+        .filter(|t| t.entry_index >= offset)
+        .map(|t| ComparedTrade {
+            instrument: t.instrument.clone(),
+            size: t.size,
+            entry_tick: t.entry_index - offset,
+            entry_price: t.entry_price,
+            exit_tick: t.exit_index.map(|i| i.saturating_sub(offset)),
+            exit_price: t.exit_price,
+            pnl: t.pnl(),
+        })
+        .collect();
+
+    let (pairs, unmatched_bar, unmatched_live) = pair_trades(&bar_trades, &live_trades, config.max_entry_lag);
+    let entry_diffs: Vec<f64> = pairs.iter().map(|p| p.entry_diff).collect();
+    let exit_diffs: Vec<f64> = pairs.iter().filter_map(|p| p.exit_diff).collect();
+    let lags: Vec<f64> = pairs.iter().map(|p| p.entry_lag as f64).collect();
+
+    DivergenceReport {
+        ticks: ticks.len(),
+        bars: n_bars,
+        bar_trades: bar_trades.len(),
+        live_trades: live_trades.len(),
+        entry_diff: Distribution::from_values(&entry_diffs),
+        exit_diff: Distribution::from_values(&exit_diffs),
+        entry_lag: Distribution::from_values(&lags),
+        bar_pnl: backtest.broker.closed_trades.iter().map(|t| t.pnl()).sum(),
+        live_pnl: live.broker.closed_trades.iter().filter(|t| t.entry_index >= offset).map(|t| t.pnl()).sum(),
+        bar_final_equity: backtest.broker.equity.last().copied().unwrap_or(backtest.cash),
+        live_final_equity: live.broker.live_equity.last().copied().unwrap_or(live.broker.live_cash),
+        pairs,
+        unmatched_bar,
+        unmatched_live,
+    }
+}
+
+// greedy pairing in bar entry order: each bar trade takes the unmatched live trade on the same instrument
+// and side whose entry is closest, within max_entry_lag ticks
+fn pair_trades(
+    bar_trades: &[ComparedTrade],
+    live_trades: &[ComparedTrade],
+    max_entry_lag: usize,
+) -> (Vec<TradePair>, Vec<ComparedTrade>, Vec<ComparedTrade>) {
+    let mut bar_order: Vec<usize> = (0..bar_trades.len()).collect();
+    bar_order.sort_by_key(|&i| bar_trades[i].entry_tick);
+    let mut taken = vec![false; live_trades.len()];
+    let mut pairs = Vec::new();
+    let mut unmatched_bar = Vec::new();
+
+    for i in bar_order {
+        let bar = &bar_trades[i];
+        let candidate = live_trades.iter().enumerate()
+            .filter(|(j, live)| {
+                !taken[*j]
+                    && live.instrument == bar.instrument
+                    && live.size.signum() == bar.size.signum()
+                    && live.entry_tick.abs_diff(bar.entry_tick) <= max_entry_lag
+            })
+            .min_by_key(|(_, live)| live.entry_tick.abs_diff(bar.entry_tick))
+            .map(|(j, _)| j);
+        match candidate {
+            Some(j) => {
+                taken[j] = true;
+                let live = &live_trades[j];
+                let side = bar.size.signum();
+                pairs.push(TradePair {
+                    entry_lag: live.entry_tick as i64 - bar.entry_tick as i64,
+                    entry_diff: (live.entry_price - bar.entry_price) * side,
+                    // a worse exit is a lower sell for a long and a higher buy for a short
+                    exit_diff: match (bar.exit_price, live.exit_price) {
+                        (Some(b), Some(l)) => Some((b - l) * side),
+                        _ => None,
+                    },
+                    pnl_diff: live.pnl - bar.pnl,
+                    bar: bar.clone(),
+                    live: live.clone(),
+                });
+            }
+            None => unmatched_bar.push(bar.clone()),
+        }
+    }
+    let unmatched_live = live_trades.iter().zip(taken.iter())
+        .filter(|(_, &taken)| !taken)
+        .map(|(trade, _)| trade.clone())
+        .collect();
+    (pairs, unmatched_bar, unmatched_live)
+}
+
+impl fmt::Display for DivergenceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n\nBacktest vs Live Divergence:")?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<35} {:>15}", "Ticks / Bars", format!("{} / {}", self.ticks, self.bars))?;
+        writeln!(f, "{:<35} {:>15}", "Trades (backtest)", self.bar_trades)?;
+        writeln!(f, "{:<35} {:>15}", "Trades (live)", self.live_trades)?;
+        writeln!(f, "{:<35} {:>15}", "Matched Trades", self.pairs.len())?;
+        writeln!(f, "{:<35} {:>15}", "Backtest Only", self.unmatched_bar.len())?;
+        writeln!(f, "{:<35} {:>15}", "Live Only", self.unmatched_live.len())?;
+        writeln!(f, "{:<35} {:>15.2}", "Match Rate [%]", self.match_rate() * 100.0)?;
+        writeln!(f, "{:<35} {:>15.2}", "Entry Lag [ticks] (mean)", self.entry_lag.mean)?;
+        writeln!(f, "{:<35} {:>15.5}", "Entry Price Diff (mean)", self.entry_diff.mean)?;
+        writeln!(f, "{:<35} {:>15.5}", "Entry Price Diff (p5)", self.entry_diff.p5)?;
+        writeln!(f, "{:<35} {:>15.5}", "Entry Price Diff (p95)", self.entry_diff.p95)?;
+        writeln!(f, "{:<35} {:>15.5}", "Exit Price Diff (mean)", self.exit_diff.mean)?;
+        writeln!(f, "{:<35} {:>15.5}", "Exit Price Diff (p5)", self.exit_diff.p5)?;
+        writeln!(f, "{:<35} {:>15.5}", "Exit Price Diff (p95)", self.exit_diff.p95)?;
+        writeln!(f, "{:<35} {:>15.2}", "Realized PnL (backtest)", self.bar_pnl)?;
+        writeln!(f, "{:<35} {:>15.2}", "Realized PnL (live)", self.live_pnl)?;
+        writeln!(f, "{:<35} {:>15.2}", "Final Equity (backtest)", self.bar_final_equity)?;
+        writeln!(f, "{:<35} {:>15.2}", "Final Equity (live)", self.live_final_equity)?;
+        write!(f, "====================")
+    }
+}
@@ -0,0 +1,149 @@
+// binary cache of parsed csv data, stored next to the csv as <file>.cache.
+// the cache records the content hash of the csv it was built from and the MissingDataPolicy it was aligned
+// with, and is rebuilt when either changes.
+use super::{read_ohlc, MissingDataPolicy};
+use crate::engine::OhlcData;
+use crate::util::content_hash;
+use std::error::Error;
+
+const MAGIC: &[u8; 4] = b"RBTC";
+// bump when the layout or the csv parsing rules change; the policy is in the header, so a new default
+// MissingDataPolicy rebuilds old caches without a bump
+const VERSION: u32 = 2;
+
+pub fn cache_path(csv_path: &str) -> String {
+    format!("{}.cache", csv_path)
+}
+
+/// load an ohlc csv through its cache: the csv is hashed and, if an up-to-date cache exists, the
+/// parsed data is read from it instead of parsing the csv again. blank close2 fields are aligned with
+/// the default MissingDataPolicy, which keeps every row.
+pub fn handle_ohlc_cached(path: &str) -> Result<OhlcData, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    let hash = content_hash(&bytes);
+    let cache = cache_path(path);
+    let policy = MissingDataPolicy::default();
+
+    if let Ok(cached) = std::fs::read(&cache) {
+        match decode(&cached, hash, policy) {
+            Some(data) => return Ok(data),
+            None => println!("{}: cache out of date, re-parsing", path),
+        }
+    }
+
+    let (data, _) = read_ohlc(&bytes[..], policy)?;
+    if let Err(e) = std::fs::write(&cache, encode(&data, hash, policy)) {
+        eprintln!("warning: could not write data cache {}: {}", cache, e);
+    }
+    Ok(data)
+}
+
+fn put_f64s(out: &mut Vec<u8>, values: &[f64]) {
+    out.extend_from_slice(&(values.len() as u64).to_le_bytes());
+    for v in values {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+// the alignment policy as a tag and its parameter
+fn policy_header(policy: MissingDataPolicy) -> (u8, u64) {
+    match policy {
+        MissingDataPolicy::Drop => (0, 0),
+        MissingDataPolicy::ForwardFill { max_staleness } => (1, max_staleness as u64),
+        MissingDataPolicy::Interpolate => (2, 0),
+        MissingDataPolicy::Carry => (3, 0),
+    }
+}
+
+/// serialize parsed data into the cache layout: magic, version, csv hash, policy, date strings (length
+/// prefixed), price columns, optional volume
+pub fn encode(data: &OhlcData, hash: u64, policy: MissingDataPolicy) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.close.len() * 64);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&hash.to_le_bytes());
+    let (tag, param) = policy_header(policy);
+    out.push(tag);
+    out.extend_from_slice(&param.to_le_bytes());
+    out.extend_from_slice(&(data.date.len() as u64).to_le_bytes());
+    for date in data.date.iter() {
+        out.extend_from_slice(&(date.len() as u32).to_le_bytes());
+        out.extend_from_slice(date.as_bytes());
+    }
+    for column in [&data.open, &data.high, &data.low, &data.close, &data.close2] {
+        put_f64s(&mut out, column);
+    }
+    match data.volume.as_ref() {
+        Some(volume) => {
+            out.push(1);
+            put_f64s(&mut out, volume);
+        }
+        None => out.push(0),
+    }
+    out
+}
+
+// reads the cache layout front to back; None on any mismatch or truncation
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn f64s(&mut self) -> Option<Vec<f64>> {
+        let len = self.u64()? as usize;
+        let raw = self.take(len.checked_mul(8)?)?;
+        Some(raw.chunks_exact(8).map(|c| f64::from_le_bytes(c.try_into().unwrap())).collect())
+    }
+}
+
+/// read data written by `encode`; None if the cache is from another version, another csv (hash) or another
+/// policy, or is truncated
+pub fn decode(bytes: &[u8], expected_hash: u64, policy: MissingDataPolicy) -> Option<OhlcData> {
+    let mut cursor = Cursor { bytes, pos: 0 };
+    if cursor.take(4)? != MAGIC || cursor.u32()? != VERSION || cursor.u64()? != expected_hash {
+        return None;
+    }
+    let (tag, param) = policy_header(policy);
+    if cursor.take(1)?[0] != tag || cursor.u64()? != param {
+        return None;
+    }
+    let rows = cursor.u64()? as usize;
+    let mut date = Vec::with_capacity(rows.min(bytes.len()));
+    for _ in 0..rows {
+        let len = cursor.u32()? as usize;
+        date.push(String::from_utf8(cursor.take(len)?.to_vec()).ok()?);
+    }
+    let data = OhlcData {
+        date,
+        open: cursor.f64s()?,
+        high: cursor.f64s()?,
+        low: cursor.f64s()?,
+        close: cursor.f64s()?,
+        close2: cursor.f64s()?,
+        volume: match cursor.take(1)?[0] {
+            1 => Some(cursor.f64s()?),
+            _ => None,
+        },
+        halts: Vec::new(),
+    };
+    let n = data.date.len();
+    if [data.open.len(), data.high.len(), data.low.len(), data.close.len(), data.close2.len()].iter().any(|&len| len != n) {
+        return None;
+    }
+    Some(data)
+}
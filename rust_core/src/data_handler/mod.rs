@@ -3,16 +3,182 @@ use std::error::Error;
 use crate::engine::OhlcData;
 use crate::live_engine::LiveData;
 use crate::live_engine::TickSnapshot;
+use crate::clock::parse_quote_time;
 use std::collections::HashMap;
 use serde_json::Value;
 use regex::Regex;
 use nom;
 
+pub mod cache;
+
+// how rows with a missing secondary price (close2, or a second symbol) are aligned
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissingDataPolicy {
+    Drop,                                   // drop the row
+    ForwardFill { max_staleness: usize },   // carry the last price for up to max_staleness bars, drop beyond
+    Interpolate,                            // linear between the surrounding prices, drop at the edges
+    Carry,                                  // carry the last price without limit, the first price back to the start; keeps every row
+}
+
+impl Default for MissingDataPolicy {
+    fn default() -> Self {
+        MissingDataPolicy::Carry
+    }
+}
+
+// missing secondary prices found while aligning, and what the policy did with them
+#[derive(Clone, Debug, Default)]
+pub struct GapReport {
+    pub rows: usize,    // rows before alignment
+    pub missing: usize, // rows without a secondary price
+    pub filled: usize,  // missing rows kept with a filled price
+    pub dropped: usize, // missing rows removed
+    pub gaps: Vec<(String, String, usize)>, // (first date, last date, bars) of each run of missing prices
+}
+
+impl GapReport {
+    pub fn longest_gap(&self) -> usize {
+        self.gaps.iter().map(|(_, _, bars)| *bars).max().unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for GapReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "gap report: {} of {} rows missing a secondary price, {} filled, {} dropped, {} gaps (longest {} bars)",
+            self.missing, self.rows, self.filled, self.dropped, self.gaps.len(), self.longest_gap())?;
+        for (first, last, bars) in self.gaps.iter().take(10) {
+            writeln!(f, "  {} to {}: {} bars", first, last, bars)?;
+        }
+        if self.gaps.len() > 10 {
+            writeln!(f, "  ... {} more", self.gaps.len() - 10)?;
+        }
+        Ok(())
+    }
+}
+
+// fill missing values according to the policy; None marks rows that have to be dropped
+fn fill_missing(values: &[Option<f64>], policy: MissingDataPolicy) -> Vec<Option<f64>> {
+    match policy {
+        MissingDataPolicy::Drop => values.to_vec(),
+        MissingDataPolicy::ForwardFill { max_staleness } => {
+            let mut last: Option<f64> = None;
+            let mut stale = 0;
+            values.iter().map(|v| match v {
+                Some(v) => {
+                    last = Some(*v);
+                    stale = 0;
+                    Some(*v)
+                }
+                None => {
+                    stale += 1;
+                    if stale <= max_staleness { last } else { None }
+                }
+            }).collect()
+        }
+        MissingDataPolicy::Interpolate => {
+            let mut filled = values.to_vec();
+            let mut prev: Option<usize> = None;
+            for i in 0..values.len() {
+                if values[i].is_none() {
+                    continue;
+                }
+                if let Some(p) = prev {
+                    let (a, b) = (values[p].unwrap(), values[i].unwrap());
+                    for (j, slot) in filled.iter_mut().enumerate().take(i).skip(p + 1) {
+                        *slot = Some(a + (b - a) * (j - p) as f64 / (i - p) as f64);
+                    }
+                }
+                prev = Some(i);
+            }
+            filled
+        }
+        MissingDataPolicy::Carry => {
+            let mut last = values.iter().flatten().next().copied();
+            values.iter().map(|v| {
+                if v.is_some() {
+                    last = *v;
+                }
+                last
+            }).collect()
+        }
+    }
+}
+
+/// put a secondary price series with gaps into close2 using the policy; rows the policy cannot fill are
+/// removed from every column. a series that is missing entirely (single-instrument file) is left as zeros.
+pub fn align_secondary(data: &OhlcData, secondary: &[Option<f64>], policy: MissingDataPolicy) -> (OhlcData, GapReport) {
+    let mut report = GapReport { rows: data.close.len(), ..GapReport::default() };
+    if secondary.iter().all(|v| v.is_none()) {
+        return (OhlcData { close2: vec![0.0; data.close.len()], ..data.clone() }, report);
+    }
+
+    let mut run_start: Option<usize> = None;
+    for i in 0..=secondary.len() {
+        match (secondary.get(i).map(|v| v.is_none()), run_start) {
+            (Some(true), None) => run_start = Some(i),
+            (Some(false), Some(start)) | (None, Some(start)) => {
+                report.gaps.push((data.date[start].clone(), data.date[i - 1].clone(), i - start));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    let filled = fill_missing(secondary, policy);
+    let keep: Vec<usize> = (0..filled.len()).filter(|&i| filled[i].is_some()).collect();
+    report.missing = secondary.iter().filter(|v| v.is_none()).count();
+    report.dropped = filled.len() - keep.len();
+    report.filled = report.missing - report.dropped;
+
+    let pick = |column: &[f64]| keep.iter().map(|&i| column[i]).collect::<Vec<f64>>();
+    let aligned = OhlcData {
+        date: keep.iter().map(|&i| data.date[i].clone()).collect(),
+        open: pick(&data.open),
+        high: pick(&data.high),
+        low: pick(&data.low),
+        close: pick(&data.close),
+        close2: keep.iter().map(|&i| filled[i].unwrap()).collect(),
+        volume: data.volume.as_ref().map(|v| pick(v)),
+        halts: Vec::new(),
+    };
+    (aligned, report)
+}
+
+/// align a second symbol to the dates of `primary` (exact date match) and return it as close2
+pub fn align_pair(primary: &OhlcData, secondary: &OhlcData, policy: MissingDataPolicy) -> (OhlcData, GapReport) {
+    let prices: HashMap<&str, f64> = secondary.date.iter()
+        .map(|d| d.as_str())
+        .zip(secondary.close.iter().copied())
+        .collect();
+    let matched: Vec<Option<f64>> = primary.date.iter().map(|d| prices.get(d.as_str()).copied()).collect();
+    align_secondary(primary, &matched, policy)
+}
+
 // data handler for simple csv
+// blank close2 fields are carried from the nearest price, so every row of the file is kept
 pub fn handle_ohlc(path: &str) -> Result<OhlcData, Box<dyn Error>> {
+    Ok(handle_ohlc_with_policy(path, MissingDataPolicy::default())?.0)
+}
+
+// load an ohlc csv with an explicit policy for blank close2 fields, returning the gap report with the data
+pub fn handle_ohlc_with_policy(path: &str, policy: MissingDataPolicy) -> Result<(OhlcData, GapReport), Box<dyn Error>> {
+    read_ohlc(std::fs::File::open(path)?, policy)
+}
+
+// a price column; with the decimal feature it must be a plain decimal with at most decimal::DECIMALS
+// digits, so it converts to the broker's decimal arithmetic without loss
+fn parse_price(field: &str) -> Result<f64, Box<dyn Error>> {
+    #[cfg(feature = "decimal")]
+    return Ok(field.trim().parse::<crate::decimal::Decimal>()?.to_f64());
+    #[cfg(not(feature = "decimal"))]
+    return Ok(field.parse::<f64>()?);
+}
+
+// parse ohlc csv content (date,open,high,low,close[,close2[,volume]] with a header row)
+pub fn read_ohlc<R: std::io::Read>(reader: R, policy: MissingDataPolicy) -> Result<(OhlcData, GapReport), Box<dyn Error>> {
     let mut rdr = ReaderBuilder::new()
         .has_headers(true)
-        .from_path(path)?;
+        .from_reader(reader);
 
     let mut date = Vec::new();
     let mut open = Vec::new();
@@ -20,33 +186,287 @@ pub fn handle_ohlc(path: &str) -> Result<OhlcData, Box<dyn Error>> {
     let mut low = Vec::new();
     let mut close = Vec::new();
     let mut close2 = Vec::new();
+    let mut volume = Vec::new();
     
     for result in rdr.records() {
         let record = result?;
         date.push(record[0].to_string());
-        open.push(record[1].parse::<f64>()?);
-        high.push(record[2].parse::<f64>()?);
-        low.push(record[3].parse::<f64>()?);
-        close.push(record[4].parse::<f64>()?);
-        let close2_val = if record[5].trim().is_empty() {
-            0.0
-        } else {
-            record[5].parse::<f64>()?
+        open.push(parse_price(&record[1])?);
+        high.push(parse_price(&record[2])?);
+        low.push(parse_price(&record[3])?);
+        close.push(parse_price(&record[4])?);
+        // close2 is optional so single-instrument files load as well; blanks are aligned below
+        let close2_val = match record.get(5) {
+            Some(field) if !field.trim().is_empty() => Some(field.parse::<f64>()?)
+                .filter(|v| v.is_finite())
+                .map(|_| parse_price(field))
+                .transpose()?,
+            _ => None,
         };
         close2.push(close2_val);
+        // optional volume column after close2
+        if let Some(field) = record.get(6) {
+            if !field.trim().is_empty() {
+                volume.push(field.parse::<f64>()?);
+            }
+        }
     }
     
-    Ok(OhlcData {
+    // only keep volume if every row had one
+    let volume = if !volume.is_empty() && volume.len() == close.len() {
+        Some(volume)
+    } else {
+        None
+    };
+
+    let data = OhlcData {
         date,
         open,
         high,
         low,
         close,
-        close2,
+        close2: Vec::new(),
+        volume,
+        halts: Vec::new(),
+    };
+    Ok(align_secondary(&data, &close2, policy))
+}
+
+// load every csv in a directory as one symbol each; the file stem is used as the symbol name.
+// symbols are returned sorted by name so runs are deterministic.
+pub fn handle_ohlc_dir(dir: &str) -> Result<Vec<(String, OhlcData)>, Box<dyn Error>> {
+    let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map(|ext| ext == "csv").unwrap_or(false))
+        .collect();
+    paths.sort();
+
+    let mut symbols = Vec::with_capacity(paths.len());
+    for path in paths {
+        let symbol = path.file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let data = handle_ohlc(path.to_str().unwrap_or(""))?;
+        symbols.push((symbol, data));
+    }
+    Ok(symbols)
+}
+
+// load an external benchmark csv (date,value or a date,open,high,low,close file) and align it to
+// the backtest dates: each bar takes the latest benchmark value at or before its date, bars before
+// the first benchmark row take the first value. daily benchmark dates ("%Y-%m-%d") count as midnight.
+pub fn load_benchmark(path: &str, dates: &[String]) -> Result<Vec<f64>, Box<dyn Error>> {
+    let parse = parse_bar_time;
+
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(path)?;
+    let mut rows = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let date = parse(&record[0]).ok_or_else(|| format!("unrecognised benchmark date: {}", &record[0]))?;
+        // close column of an ohlc file, otherwise the second column
+        let value_col = if record.len() >= 5 { 4 } else { 1 };
+        rows.push((date, record[value_col].parse::<f64>()?));
+    }
+    rows.sort_by_key(|(date, _)| *date);
+    if rows.is_empty() {
+        return Err(format!("benchmark file {} has no rows", path).into());
+    }
+
+    let mut aligned = Vec::with_capacity(dates.len());
+    let mut next = 0;
+    let mut current = rows[0].1;
+    for date in dates.iter() {
+        let date = parse(date).ok_or_else(|| format!("unrecognised backtest date: {}", date))?;
+        while next < rows.len() && rows[next].0 <= date {
+            current = rows[next].1;
+            next += 1;
+        }
+        aligned.push(current);
+    }
+    Ok(aligned)
+}
+
+/// turn recorded quotes (e.g. LiveData::ticks from a live session) into irregular pseudo-bars for the
+/// backtester: every `primary` quote becomes one bar with open = high = low = close = mid, close2 is the
+/// latest `secondary` mid (quotes before the first secondary quote are skipped; 0.0 without a secondary).
+/// dates keep the quote timestamps, so run stats with Annualization::ElapsedTime.
+pub fn ticks_to_bars(ticks: &[TickSnapshot], primary: &str, secondary: Option<&str>) -> OhlcData {
+    ticks_to_bars_indexed(ticks, primary, secondary).0
+}
+
+/// ticks_to_bars, plus the position in `ticks` of the quote behind every bar
+pub fn ticks_to_bars_indexed(ticks: &[TickSnapshot], primary: &str, secondary: Option<&str>) -> (OhlcData, Vec<usize>) {
+    let mut sources = Vec::new();
+    let mut data = OhlcData {
+        date: Vec::new(),
+        open: Vec::new(),
+        high: Vec::new(),
+        low: Vec::new(),
+        close: Vec::new(),
+        close2: Vec::new(),
         volume: None,
+        halts: Vec::new(),
+    };
+    let mut last_secondary: Option<f64> = None;
+    for (position, tick) in ticks.iter().enumerate() {
+        let mid = (tick.ask + tick.bid) / 2.0;
+        if Some(tick.instrument.as_str()) == secondary {
+            last_secondary = Some(mid);
+            continue;
+        }
+        if tick.instrument != primary {
+            continue;
+        }
+        let close2 = match (secondary, last_secondary) {
+            (None, _) => 0.0,
+            (Some(_), Some(price)) => price,
+            (Some(_), None) => continue,
+        };
+        data.date.push(tick.date.clone());
+        data.open.push(mid);
+        data.high.push(mid);
+        data.low.push(mid);
+        data.close.push(mid);
+        data.close2.push(close2);
+        sources.push(position);
+    }
+    (data, sources)
+}
+
+// parse a bar date: a quote timestamp, or a plain "%Y-%m-%d" day taken as midnight
+fn parse_bar_time(date: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    parse_quote_time(date).or_else(|| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|dt| dt.and_utc())
     })
 }
 
+// bar length of a resampling rule: a count followed by s, min, H or D, e.g. "30s", "5min", "1H", "1D"
+pub fn parse_resample_rule(rule: &str) -> Result<chrono::Duration, Box<dyn Error>> {
+    let rule = rule.trim();
+    let split = rule.find(|c: char| !c.is_ascii_digit()).unwrap_or(rule.len());
+    let (count, unit) = rule.split_at(split);
+    let count: i64 = if count.is_empty() { 1 } else { count.parse()? };
+    let duration = match unit {
+        "s" | "S" => chrono::Duration::seconds(count),
+        "min" | "T" | "m" => chrono::Duration::minutes(count),
+        "H" | "h" => chrono::Duration::hours(count),
+        "D" | "d" => chrono::Duration::days(count),
+        _ => return Err(format!("unknown resample rule: {}", rule).into()),
+    };
+    if count <= 0 {
+        return Err(format!("resample rule must be positive: {}", rule).into());
+    }
+    Ok(duration)
+}
+
+// (bucket start, bar index) for every bar, bucketed on utc multiples of the rule
+fn resample_buckets(data: &OhlcData, rule: &str) -> Result<(i64, Vec<i64>), Box<dyn Error>> {
+    let step = parse_resample_rule(rule)?.num_seconds();
+    let buckets = data.date.iter()
+        .map(|d| {
+            let t = parse_bar_time(d).ok_or_else(|| format!("unrecognised bar date: {}", d))?.timestamp();
+            Ok(t - t.rem_euclid(step))
+        })
+        .collect::<Result<Vec<i64>, Box<dyn Error>>>()?;
+    Ok((step, buckets))
+}
+
+fn bucket_date(start: i64) -> String {
+    chrono::DateTime::from_timestamp(start, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+/// downsample to a coarser bar length: first open, highest high, lowest low, last close, summed volume.
+/// close2 takes the last value in each bar so both instruments stay aligned on the bar close (and stays
+/// empty for single-instrument data). bars are labelled with their start time; empty periods (nights, weekends) are skipped.
+pub fn resample(data: &OhlcData, rule: &str) -> Result<OhlcData, Box<dyn Error>> {
+    let (_, buckets) = resample_buckets(data, rule)?;
+    let mut out = OhlcData {
+        date: Vec::new(),
+        open: Vec::new(),
+        high: Vec::new(),
+        low: Vec::new(),
+        close: Vec::new(),
+        close2: Vec::new(),
+        volume: data.volume.as_ref().map(|_| Vec::new()),
+        halts: Vec::new(),
+    };
+    let mut current: Option<i64> = None;
+    for (i, &bucket) in buckets.iter().enumerate() {
+        let close2 = (!data.close2.is_empty()).then(|| data.close2.get(i).copied().unwrap_or(0.0));
+        let volume = data.volume.as_ref().map(|v| v[i]).unwrap_or(0.0);
+        if current == Some(bucket) {
+            let last = out.close.len() - 1;
+            out.high[last] = out.high[last].max(data.high[i]);
+            out.low[last] = out.low[last].min(data.low[i]);
+            out.close[last] = data.close[i];
+            if let Some(close2) = close2 {
+                out.close2[last] = close2;
+            }
+            if let Some(v) = out.volume.as_mut() {
+                v[last] += volume;
+            }
+        } else {
+            current = Some(bucket);
+            out.date.push(bucket_date(bucket));
+            out.open.push(data.open[i]);
+            out.high.push(data.high[i]);
+            out.low.push(data.low[i]);
+            out.close.push(data.close[i]);
+            out.close2.extend(close2);
+            if let Some(v) = out.volume.as_mut() {
+                v.push(volume);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// upsample to a finer bar length: each source bar is placed in its period and the periods up to the
+/// next source bar are filled with flat bars at the previous close (close2 carried forward, zero volume).
+/// use on data without long gaps, or slice it by date first, as every empty period is filled.
+pub fn upsample(data: &OhlcData, rule: &str) -> Result<OhlcData, Box<dyn Error>> {
+    let (step, buckets) = resample_buckets(data, rule)?;
+    let out = resample(data, rule)?;
+    let mut starts = buckets;
+    starts.dedup();
+
+    let mut filled = out.slice(0..0);
+    for (k, &start) in starts.iter().enumerate() {
+        filled.date.push(out.date[k].clone());
+        filled.open.push(out.open[k]);
+        filled.high.push(out.high[k]);
+        filled.low.push(out.low[k]);
+        filled.close.push(out.close[k]);
+        filled.close2.extend(out.close2.get(k));
+        if let (Some(v), Some(src)) = (filled.volume.as_mut(), out.volume.as_ref()) {
+            v.push(src[k]);
+        }
+        let next = starts.get(k + 1).copied().unwrap_or(start + step);
+        let mut t = start + step;
+        while t < next {
+            filled.date.push(bucket_date(t));
+            filled.open.push(out.close[k]);
+            filled.high.push(out.close[k]);
+            filled.low.push(out.close[k]);
+            filled.close.push(out.close[k]);
+            filled.close2.extend(out.close2.get(k));
+            if let Some(v) = filled.volume.as_mut() {
+                v.push(0.0);
+            }
+            t += step;
+        }
+    }
+    Ok(filled)
+}
+
 //ACTUALLY WORKS
 
 pub fn parse_live_data_with_reference_nom(raw: &str, expected_ref: &str) -> LiveData {
@@ -124,7 +544,7 @@ pub fn parse_live_data_with_reference_nom(raw: &str, expected_ref: &str) -> Live
         }
     }
 
-    LiveData { ticks, current }
+    LiveData { ticks, current, events: Vec::new() }
 }
 
 
@@ -209,7 +629,7 @@ pub fn parse_live_data_with_reference_nom2(
         }
     }
 
-    LiveData { ticks, current }
+    LiveData { ticks, current, events: Vec::new() }
 }
 
 /// Parse potentially concatenated streaming data with multiple instruments
@@ -325,5 +745,160 @@ pub fn parse_multipart_live_data(raw: &str) -> LiveData {
         }
     }
     
-    LiveData { ticks, current }
+    LiveData { ticks, current, events: Vec::new() }
+}
+
+// one message of a saxo streaming frame
+#[derive(Clone, Debug)]
+pub struct SaxoMessage {
+    pub message_id: u64,
+    pub reference_id: String,
+    pub payload_format: u8, // 0 = json, 1 = protobuf
+    pub payload: Vec<u8>,
+}
+
+impl SaxoMessage {
+    // control messages (_heartbeat, _disconnect, _resetsubscriptions, ...) use reserved reference ids
+    pub fn is_control(&self) -> bool {
+        self.reference_id.starts_with('_')
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SaxoFrameError {
+    Truncated { offset: usize, needed: usize, available: usize },
+    InvalidReferenceId { offset: usize },
+}
+
+impl std::fmt::Display for SaxoFrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaxoFrameError::Truncated { offset, needed, available } => {
+                write!(f, "truncated saxo frame at byte {}: need {} bytes, {} available", offset, needed, available)
+            }
+            SaxoFrameError::InvalidReferenceId { offset } => {
+                write!(f, "reference id at byte {} is not ascii", offset)
+            }
+        }
+    }
+}
+
+impl Error for SaxoFrameError {}
+
+fn take<'a>(frame: &'a [u8], offset: usize, len: usize) -> Result<&'a [u8], SaxoFrameError> {
+    frame.get(offset..offset + len).ok_or(SaxoFrameError::Truncated {
+        offset,
+        needed: len,
+        available: frame.len().saturating_sub(offset),
+    })
+}
+
+/// decode the message starting at `offset` of a saxo streaming frame; returns the message and
+/// the offset of the next one. layout (little-endian):
+/// message id u64 | reserved 2 bytes | ref id size u8 | ref id ascii | payload format u8 | payload size u32 | payload
+pub fn decode_saxo_message(frame: &[u8], offset: usize) -> Result<(SaxoMessage, usize), SaxoFrameError> {
+    let mut pos = offset;
+    let message_id = u64::from_le_bytes(take(frame, pos, 8)?.try_into().unwrap());
+    pos += 8 + 2;
+    let ref_len = take(frame, pos, 1)?[0] as usize;
+    pos += 1;
+    let ref_bytes = take(frame, pos, ref_len)?;
+    if !ref_bytes.is_ascii() {
+        return Err(SaxoFrameError::InvalidReferenceId { offset: pos });
+    }
+    let reference_id = String::from_utf8_lossy(ref_bytes).to_string();
+    pos += ref_len;
+    let payload_format = take(frame, pos, 1)?[0];
+    pos += 1;
+    let payload_size = u32::from_le_bytes(take(frame, pos, 4)?.try_into().unwrap()) as usize;
+    pos += 4;
+    let payload = take(frame, pos, payload_size)?.to_vec();
+    pos += payload_size;
+
+    Ok((SaxoMessage { message_id, reference_id, payload_format, payload }, pos))
+}
+
+/// decode every message of a saxo streaming frame
+pub fn decode_saxo_frame(frame: &[u8]) -> Result<Vec<SaxoMessage>, SaxoFrameError> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    while offset < frame.len() {
+        let (message, next) = decode_saxo_message(frame, offset)?;
+        messages.push(message);
+        offset = next;
+    }
+    Ok(messages)
+}
+
+// build a tick from a price update (or a subscription snapshot); delta updates without any price are skipped
+pub fn tick_from_price_update(instrument: &str, update: &Value) -> Option<TickSnapshot> {
+    let quote = update.get("Quote")?;
+    let date = update.get("LastUpdated").and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+    // Try to get Ask and Bid, fallback to Mid.
+    let (ask, bid) = if let (Some(a), Some(b)) = (
+        quote.get("Ask").and_then(|v| v.as_f64()),
+        quote.get("Bid").and_then(|v| v.as_f64()),
+    ) {
+        (a, b)
+    } else if let Some(mid) = quote.get("Mid").and_then(|v| v.as_f64()) {
+        (mid, mid)
+    } else {
+        return None;
+    };
+
+    if ask > 0.0 || bid > 0.0 {
+        Some(TickSnapshot { instrument: instrument.to_string(), date, ask, bid })
+    } else {
+        None
+    }
+}
+
+/// parse a binary saxo frame into live data, using each message's reference id as the instrument.
+/// control and non-json messages are skipped; decoding stops at the first malformed message
+/// but keeps the ticks decoded before it.
+pub fn parse_saxo_frame(frame: &[u8]) -> LiveData {
+    parse_saxo_frame_sequenced(frame).0
+}
+
+/// parse_saxo_frame, also returning the (message id, reference id) of every decoded message,
+/// control messages included, for sequence tracking
+pub fn parse_saxo_frame_sequenced(frame: &[u8]) -> (LiveData, Vec<(u64, String)>) {
+    let mut ticks: Vec<TickSnapshot> = Vec::new();
+    let mut current: HashMap<String, TickSnapshot> = HashMap::new();
+    let mut sequence: Vec<(u64, String)> = Vec::new();
+
+    let mut offset = 0;
+    while offset < frame.len() {
+        let (message, next) = match decode_saxo_message(frame, offset) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                eprintln!("{}", e);
+                break;
+            }
+        };
+        offset = next;
+        sequence.push((message.message_id, message.reference_id.clone()));
+
+        if message.is_control() || message.payload_format != 0 {
+            continue;
+        }
+        let parsed = match serde_json::from_slice::<Value>(&message.payload) {
+            Ok(parsed) => parsed,
+            Err(_) => continue,
+        };
+        // a payload is either a single update or an array of updates
+        let updates = match parsed {
+            Value::Array(items) => items,
+            single => vec![single],
+        };
+        for update in updates.iter() {
+            if let Some(tick) = tick_from_price_update(&message.reference_id, update) {
+                ticks.push(tick.clone());
+                current.insert(tick.instrument.clone(), tick);
+            }
+        }
+    }
+
+    (LiveData { ticks, current, events: Vec::new() }, sequence)
 }
@@ -0,0 +1,166 @@
+// fixed-point decimal numbers for the "decimal" feature: 12 fractional digits in an i128, so adjusted
+// prices, commissions and cash are computed exactly instead of accumulating binary rounding error.
+// prices stay f64 in OhlcData; they enter decimal arithmetic through from_f64, which takes the shortest
+// decimal that round-trips to the f64 (the value that was in the csv), and leave it through to_f64.
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+use std::str::FromStr;
+
+pub const DECIMALS: u32 = 12;
+const SCALE: i128 = 1_000_000_000_000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal(i128); // value * 10^DECIMALS
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecimalError {
+    Invalid(String),
+    TooPrecise(String), // more than DECIMALS fractional digits
+}
+
+impl fmt::Display for DecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecimalError::Invalid(raw) => write!(f, "'{}' is not a decimal number", raw),
+            DecimalError::TooPrecise(raw) => write!(f, "'{}' has more than {} decimals", raw, DECIMALS),
+        }
+    }
+}
+
+impl std::error::Error for DecimalError {}
+
+// round n / d half away from zero
+fn div_round(n: i128, d: i128) -> i128 {
+    let q = n / d;
+    let r = n % d;
+    if 2 * r.abs() >= d.abs() {
+        q + n.signum() * d.signum()
+    } else {
+        q
+    }
+}
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal(0);
+    pub const ONE: Decimal = Decimal(SCALE);
+
+    /// the shortest decimal that round-trips to `value`, rounded to DECIMALS digits
+    pub fn from_f64(value: f64) -> Decimal {
+        if !value.is_finite() {
+            return Decimal::ZERO;
+        }
+        let repr = format!("{}", value);
+        let (int_part, frac_part) = repr.split_once('.').unwrap_or((&repr, ""));
+        let negative = int_part.starts_with('-');
+        let digits = |s: &str| s.trim_start_matches('-').parse::<i128>().unwrap_or(0);
+        let mut mantissa = digits(int_part) * SCALE;
+        let kept = frac_part.len().min(DECIMALS as usize);
+        if kept > 0 {
+            mantissa += digits(&frac_part[..kept]) * 10i128.pow(DECIMALS - kept as u32);
+        }
+        // round on the first dropped digit
+        if frac_part.as_bytes().get(DECIMALS as usize).is_some_and(|d| *d >= b'5') {
+            mantissa += 1;
+        }
+        Decimal(if negative { -mantissa } else { mantissa })
+    }
+
+    pub fn to_f64(self) -> f64 {
+        // through the decimal string, so the nearest f64 is returned
+        self.to_string().parse().unwrap_or(0.0)
+    }
+
+    /// round half away from zero to `digits` fractional digits (at most DECIMALS)
+    pub fn round_dp(self, digits: u32) -> Decimal {
+        if digits >= DECIMALS {
+            return self;
+        }
+        let step = 10i128.pow(DECIMALS - digits);
+        Decimal(div_round(self.0, step) * step)
+    }
+
+    pub fn abs(self) -> Decimal {
+        Decimal(self.0.abs())
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = DecimalError;
+
+    /// exact parse of a plain decimal such as "-1234.5678"; more than DECIMALS fractional digits is an error
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let s = raw.trim();
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let valid = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if (int_part.is_empty() && frac_part.is_empty()) || !valid(int_part) || !valid(frac_part) {
+            return Err(DecimalError::Invalid(raw.to_string()));
+        }
+        if frac_part.len() > DECIMALS as usize {
+            return Err(DecimalError::TooPrecise(raw.to_string()));
+        }
+        let int_value = if int_part.is_empty() { 0 } else {
+            int_part.parse::<i128>().map_err(|_| DecimalError::Invalid(raw.to_string()))?
+        };
+        let frac_value = if frac_part.is_empty() { 0 } else {
+            frac_part.parse::<i128>().map_err(|_| DecimalError::Invalid(raw.to_string()))?
+                * 10i128.pow(DECIMALS - frac_part.len() as u32)
+        };
+        let mantissa = int_value * SCALE + frac_value;
+        Ok(Decimal(if negative { -mantissa } else { mantissa }))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let int_part = (self.0 / SCALE).abs();
+        let frac_part = (self.0 % SCALE).abs();
+        if frac_part == 0 {
+            return write!(f, "{}{}", sign, int_part);
+        }
+        let frac = format!("{:0width$}", frac_part, width = DECIMALS as usize);
+        write!(f, "{}{}.{}", sign, int_part, frac.trim_end_matches('0'))
+    }
+}
+
+impl Add for Decimal {
+    type Output = Decimal;
+    fn add(self, other: Decimal) -> Decimal {
+        Decimal(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Decimal {
+    fn add_assign(&mut self, other: Decimal) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for Decimal {
+    type Output = Decimal;
+    fn sub(self, other: Decimal) -> Decimal {
+        Decimal(self.0 - other.0)
+    }
+}
+
+impl Neg for Decimal {
+    type Output = Decimal;
+    fn neg(self) -> Decimal {
+        Decimal(-self.0)
+    }
+}
+
+impl Mul for Decimal {
+    type Output = Decimal;
+    // split into whole and fractional parts so the intermediate products stay within i128,
+    // rounded half away from zero at the last digit
+    fn mul(self, other: Decimal) -> Decimal {
+        let whole = (self.0 / SCALE) * other.0;
+        let frac = div_round((self.0 % SCALE) * other.0, SCALE);
+        Decimal(whole + frac)
+    }
+}
@@ -0,0 +1,147 @@
+// trade clustering and overtrading diagnostics
+use crate::engine::{Broker, OrderError, Trade};
+use crate::stats::Distribution;
+use std::collections::HashSet;
+use std::fmt;
+
+// thresholds for flagging overtrading
+#[derive(Clone, Debug)]
+pub struct DiagnosticsConfig {
+    pub burst_window: usize,         // window length in bars
+    pub burst_threshold: usize,      // entries within one window that count as a burst
+    pub max_rejection_pct: f64,      // warn if trade-limit rejections exceed this share of order attempts
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> Self {
+        DiagnosticsConfig {
+            burst_window: 10,
+            burst_threshold: 5,
+            max_rejection_pct: 10.0,
+        }
+    }
+}
+
+// a run of entries packed into a short window
+#[derive(Clone, Debug)]
+pub struct Burst {
+    pub start_index: usize,
+    pub end_index: usize,
+    pub entries: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct TradeDiagnostics {
+    pub num_entries: usize,
+    pub time_between_entries: Distribution, // bars between consecutive entries
+    pub bursts: Vec<Burst>,
+    pub order_attempts: usize,
+    pub trade_limit_rejections: usize,
+    pub margin_rejections: usize,
+    pub other_rejections: usize,
+    pub warnings: Vec<String>,
+}
+
+// find maximal runs of entries where every window of `window` bars holds at least `threshold` entries
+fn find_bursts(entries: &[usize], window: usize, threshold: usize) -> Vec<Burst> {
+    let mut bursts: Vec<Burst> = Vec::new();
+    if threshold == 0 || entries.len() < threshold {
+        return bursts;
+    }
+    for i in 0..=(entries.len() - threshold) {
+        let start = entries[i];
+        let end = entries[i + threshold - 1];
+        if end - start >= window {
+            continue;
+        }
+        // extend the previous burst if the windows overlap, otherwise open a new one
+        match bursts.last_mut() {
+            Some(last) if start <= last.end_index => {
+                let added = entries[i..i + threshold].iter().filter(|&&e| e > last.end_index).count();
+                last.end_index = end;
+                last.entries += added;
+            }
+            _ => bursts.push(Burst { start_index: start, end_index: end, entries: threshold }),
+        }
+    }
+    bursts
+}
+
+/// analyse entry timing of trades and the broker's order rejections
+pub fn trade_diagnostics(
+    trades: &[Trade],
+    order_attempts: usize,
+    rejected_orders: &[(usize, OrderError)],
+    config: &DiagnosticsConfig,
+) -> TradeDiagnostics {
+    // the closed parts of a partially closed trade keep its id and entry: one entry each
+    let mut seen = HashSet::new();
+    let mut entries: Vec<usize> = trades.iter()
+        .filter(|t| seen.insert((t.id, t.entry_index, t.instrument)))
+        .map(|t| t.entry_index)
+        .collect();
+    entries.sort_unstable();
+
+    let gaps: Vec<f64> = entries.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+    let bursts = find_bursts(&entries, config.burst_window, config.burst_threshold);
+
+    let trade_limit_rejections = rejected_orders.iter().filter(|(_, e)| *e == OrderError::TradeLimitExceeded).count();
+    let margin_rejections = rejected_orders.iter().filter(|(_, e)| *e == OrderError::MarginExceeded).count();
+    let other_rejections = rejected_orders.len() - trade_limit_rejections - margin_rejections;
+
+    let mut warnings = Vec::new();
+    let rejection_pct = if order_attempts > 0 {
+        trade_limit_rejections as f64 / order_attempts as f64 * 100.0
+    } else {
+        0.0
+    };
+    if rejection_pct > config.max_rejection_pct {
+        warnings.push(format!(
+            "{} of {} orders ({:.1}%) rejected with TradeLimitExceeded; the strategy keeps signalling while at its position limit",
+            trade_limit_rejections, order_attempts, rejection_pct
+        ));
+    }
+    if !bursts.is_empty() {
+        warnings.push(format!(
+            "{} entry bursts of {}+ entries within {} bars",
+            bursts.len(), config.burst_threshold, config.burst_window
+        ));
+    }
+
+    TradeDiagnostics {
+        num_entries: entries.len(),
+        time_between_entries: Distribution::from_values(&gaps),
+        bursts,
+        order_attempts,
+        trade_limit_rejections,
+        margin_rejections,
+        other_rejections,
+        warnings,
+    }
+}
+
+// convenience wrapper using the broker's closed trades and rejection log
+pub fn broker_diagnostics(broker: &Broker, config: &DiagnosticsConfig) -> TradeDiagnostics {
+    trade_diagnostics(&broker.closed_trades, broker.order_attempts, &broker.rejected_orders, config)
+}
+
+impl fmt::Display for TradeDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n\nTrade Diagnostics:")?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<35} {:>15}", "Entries", self.num_entries)?;
+        writeln!(f, "{:<35} {:>15.2}", "Bars Between Entries (mean)", self.time_between_entries.mean)?;
+        writeln!(f, "{:<35} {:>15.2}", "Bars Between Entries (p5)", self.time_between_entries.p5)?;
+        writeln!(f, "{:<35} {:>15.2}", "Bars Between Entries (median)", self.time_between_entries.median)?;
+        writeln!(f, "{:<35} {:>15.2}", "Bars Between Entries (p95)", self.time_between_entries.p95)?;
+        writeln!(f, "{:<35} {:>15}", "Entry Bursts", self.bursts.len())?;
+        writeln!(f, "{:<35} {:>15}", "Order Attempts", self.order_attempts)?;
+        writeln!(f, "{:<35} {:>15}", "Trade Limit Rejections", self.trade_limit_rejections)?;
+        writeln!(f, "{:<35} {:>15}", "Margin Rejections", self.margin_rejections)?;
+        writeln!(f, "{:<35} {:>15}", "Other Rejections", self.other_rejections)?;
+        for warning in self.warnings.iter() {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        write!(f, "====================")
+    }
+}
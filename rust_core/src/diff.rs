@@ -0,0 +1,167 @@
+// comparison of two runs, closed trades and equity curves within tolerances, to check that a refactored
+// engine reproduces the results of the previous implementation
+use crate::engine::{Broker, Trade};
+use std::fmt;
+
+#[derive(Clone, Debug)]
+pub struct DiffConfig {
+    pub price_tolerance: f64,  // absolute, for entry and exit prices
+    pub size_tolerance: f64,   // absolute, for trade sizes
+    pub equity_tolerance: f64, // absolute, per bar of the equity curves
+    pub max_listed: usize,     // mismatching trade fields listed in the report
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        DiffConfig {
+            price_tolerance: 1e-9,
+            size_tolerance: 1e-9,
+            equity_tolerance: 1e-6,
+            max_listed: 10,
+        }
+    }
+}
+
+// one field of one trade that differs between the runs
+#[derive(Clone, Debug)]
+pub struct TradeMismatch {
+    pub position: usize, // position in the runs' trades, ordered by entry bar
+    pub field: &'static str,
+    pub a: String,
+    pub b: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct ResultDiff {
+    pub num_trades_a: usize,
+    pub num_trades_b: usize,
+    pub mismatched_trades: usize, // compared pairs with at least one differing field
+    pub trade_mismatches: Vec<TradeMismatch>,
+    pub pnl_a: f64,
+    pub pnl_b: f64,
+    pub equity_len_a: usize,
+    pub equity_len_b: usize,
+    pub first_equity_divergence: Option<usize>, // first bar where the curves differ by more than the tolerance
+    pub max_equity_diff: f64,
+    pub max_equity_diff_index: usize,
+    pub final_equity_a: f64,
+    pub final_equity_b: f64,
+}
+
+impl ResultDiff {
+    // true when trades and equity agree within the tolerances
+    pub fn is_match(&self) -> bool {
+        self.num_trades_a == self.num_trades_b
+            && self.mismatched_trades == 0
+            && self.equity_len_a == self.equity_len_b
+            && self.first_equity_divergence.is_none()
+    }
+}
+
+// trades ordered by entry bar, instrument and exit bar, so runs that close trades in a different order
+// still line up
+fn ordered(trades: &[Trade]) -> Vec<&Trade> {
+    let mut ordered: Vec<&Trade> = trades.iter().collect();
+    ordered.sort_by_key(|t| (t.entry_index, t.instrument, t.exit_index.unwrap_or(usize::MAX)));
+    ordered
+}
+
+fn trade_mismatches(position: usize, a: &Trade, b: &Trade, config: &DiffConfig) -> Vec<TradeMismatch> {
+    let mut found = Vec::new();
+    let mut check = |field: &'static str, differs: bool, a: String, b: String| {
+        if differs {
+            found.push(TradeMismatch { position, field, a, b });
+        }
+    };
+    let price_differs = |a: Option<f64>, b: Option<f64>| match (a, b) {
+        (Some(a), Some(b)) => (a - b).abs() > config.price_tolerance,
+        (a, b) => a.is_some() != b.is_some(),
+    };
+    check("instrument", a.instrument != b.instrument, a.instrument.to_string(), b.instrument.to_string());
+    check("size", (a.size - b.size).abs() > config.size_tolerance, a.size.to_string(), b.size.to_string());
+    check("entry_index", a.entry_index != b.entry_index, a.entry_index.to_string(), b.entry_index.to_string());
+    check("entry_price", price_differs(Some(a.entry_price), Some(b.entry_price)), a.entry_price.to_string(), b.entry_price.to_string());
+    check("exit_index", a.exit_index != b.exit_index, format!("{:?}", a.exit_index), format!("{:?}", b.exit_index));
+    check("exit_price", price_differs(a.exit_price, b.exit_price), format!("{:?}", a.exit_price), format!("{:?}", b.exit_price));
+    check("exit_tag", a.exit_tag != b.exit_tag, format!("{:?}", a.exit_tag), format!("{:?}", b.exit_tag));
+    found
+}
+
+/// compare closed trades and equity curves of two runs
+pub fn diff_runs(trades_a: &[Trade], equity_a: &[f64], trades_b: &[Trade], equity_b: &[f64], config: &DiffConfig) -> ResultDiff {
+    let mut mismatched_trades = 0;
+    let mut listed = Vec::new();
+    for (position, (a, b)) in ordered(trades_a).into_iter().zip(ordered(trades_b)).enumerate() {
+        let mismatches = trade_mismatches(position, a, b, config);
+        if !mismatches.is_empty() {
+            mismatched_trades += 1;
+            let room = config.max_listed.saturating_sub(listed.len());
+            listed.extend(mismatches.into_iter().take(room));
+        }
+    }
+
+    let mut first_equity_divergence = None;
+    let mut max_equity_diff = 0.0;
+    let mut max_equity_diff_index = 0;
+    for (i, (a, b)) in equity_a.iter().zip(equity_b.iter()).enumerate() {
+        let diff = (a - b).abs();
+        if diff > config.equity_tolerance && first_equity_divergence.is_none() {
+            first_equity_divergence = Some(i);
+        }
+        if diff > max_equity_diff {
+            max_equity_diff = diff;
+            max_equity_diff_index = i;
+        }
+    }
+
+    ResultDiff {
+        num_trades_a: trades_a.len(),
+        num_trades_b: trades_b.len(),
+        mismatched_trades,
+        trade_mismatches: listed,
+        pnl_a: trades_a.iter().map(|t| t.pnl()).sum(),
+        pnl_b: trades_b.iter().map(|t| t.pnl()).sum(),
+        equity_len_a: equity_a.len(),
+        equity_len_b: equity_b.len(),
+        first_equity_divergence,
+        max_equity_diff,
+        max_equity_diff_index,
+        final_equity_a: equity_a.last().copied().unwrap_or(0.0),
+        final_equity_b: equity_b.last().copied().unwrap_or(0.0),
+    }
+}
+
+/// compare the results of two finished brokers with the default tolerances
+pub fn diff_results(a: &Broker, b: &Broker) -> ResultDiff {
+    diff_results_with_config(a, b, &DiffConfig::default())
+}
+
+pub fn diff_results_with_config(a: &Broker, b: &Broker, config: &DiffConfig) -> ResultDiff {
+    diff_runs(&a.closed_trades, &a.equity, &b.closed_trades, &b.equity, config)
+}
+
+impl fmt::Display for ResultDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n\nResult Diff:")?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<35} {}", "Result", if self.is_match() { "match" } else { "DIFFERENT" })?;
+        writeln!(f, "{:<35} {:>15} {:>15}", "", "A", "B")?;
+        writeln!(f, "{:<35} {:>15} {:>15}", "Closed Trades", self.num_trades_a, self.num_trades_b)?;
+        writeln!(f, "{:<35} {:>15.2} {:>15.2}", "Realized PnL", self.pnl_a, self.pnl_b)?;
+        writeln!(f, "{:<35} {:>15} {:>15}", "Equity Bars", self.equity_len_a, self.equity_len_b)?;
+        writeln!(f, "{:<35} {:>15.2} {:>15.2}", "Final Equity", self.final_equity_a, self.final_equity_b)?;
+        writeln!(f, "{:<35} {:>15}", "Mismatched Trades", self.mismatched_trades)?;
+        match self.first_equity_divergence {
+            Some(index) => writeln!(f, "{:<35} {:>15}", "Equity Diverges At Bar", index)?,
+            None => writeln!(f, "{:<35} {:>15}", "Equity Diverges At Bar", "-")?,
+        }
+        writeln!(f, "{:<35} {:>15.6} (bar {})", "Max Equity Difference", self.max_equity_diff, self.max_equity_diff_index)?;
+        if !self.trade_mismatches.is_empty() {
+            writeln!(f, "\nfirst differences:")?;
+            for m in self.trade_mismatches.iter() {
+                writeln!(f, "  trade {:>5} {:<12} {} vs {}", m.position, m.field, m.a, m.b)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,21 +1,50 @@
 // core backtesting engine implementation
 #[allow(unused_imports)]
 use crate::util::as_str;
+use crate::util::Rng;
+use crate::risk::{historical_var, rolling_beta, trailing_returns, BetaNeutralConfig, DrawdownGovernor, MarginMode, VarConfig, VarEstimate};
+use crate::instrument::{InstrumentSpec, SizeError};
+use crate::clock::parse_quote_time;
+use crate::source::Bar;
+use crate::quotes::SpreadModel;
+use crate::artifacts::ArtifactWriter;
+use crate::signals::{SignalExport, SignalWriter};
+use crate::rebalance::RebalanceConfig;
+use serde::{Deserialize, Serialize};
+use crate::halts::{rebase_halts, Halt, HaltKind, HaltPolicy};
+use crate::events::BacktestEvent;
+use crate::strategies::buy_and_hold::BuyAndHoldStrategy;
+#[cfg(feature = "decimal")]
+use crate::decimal::Decimal;
 #[allow(unused_imports)]
 use std::cmp::Ordering;
 
 // import chrono and the plot module
 use chrono::NaiveDateTime;
+use std::collections::HashMap;
 use crate::plot::plot_equity;
 use crate::plot::plot_equity_and_benchmark;
 use crate::plot::plot_margin_usage;
+use crate::plot::plot_exposure;
+use crate::plot::{plot_trade_histogram, plot_holding_period_histogram, plot_pnl_vs_holding};
+use crate::plot::plot_position_timeline;
+use crate::plot::{plot_ohlc, ChartMarker, Overlay};
+use crate::plot::{plot_underwater, plot_relative_underwater};
+use crate::stats::{drawdown_series, relative_drawdown_series};
 
 // define custom error for order margin check
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OrderError {
     MarginExceeded, // error if order notional exceeds available buying power
     FractionalOrderNotAllowed, // new error type for fractional orders when not using leverage
     TradeLimitExceeded, // error if new order would exceed allowed concurrent positions per side
+    ExposureCapExceeded, // error if the order would breach a per-instrument or aggregate notional cap
+    VarBudgetExceeded, // error if the order would push portfolio var above the configured budget
+    SizeBelowMinimum, // error if the order size rounds to zero or below the instrument's minimum
+    TradingHalted, // error if the instrument is halted on this bar and halt_policy is Reject
+    InCooldown, // error if an entry follows a stop-loss exit on the same side within stop_loss_cooldown bars
+    UnknownOrder, // error if no resting order has the given id (it filled, was cancelled or never existed)
+    InvalidModification, // error if a modification would flip the side of an order or resize a contingent one
 }
 
 #[derive(Clone, Debug)]
@@ -28,6 +57,70 @@ pub struct OhlcData {
     pub close: Vec<f64>,
     pub close2: Vec<f64>,
     pub volume: Option<Vec<f64>>,
+    // untradeable windows (halts, limit-up/down) by bar index, see halt_between
+    pub halts: Vec<Halt>,
+}
+
+impl OhlcData {
+    // copy of the bars in an index range
+    pub fn slice(&self, range: std::ops::Range<usize>) -> OhlcData {
+        OhlcData {
+            date: self.date[range.clone()].to_vec(),
+            open: self.open[range.clone()].to_vec(),
+            high: self.high[range.clone()].to_vec(),
+            low: self.low[range.clone()].to_vec(),
+            close: self.close[range.clone()].to_vec(),
+            close2: self.close2.get(range.clone()).map(|c| c.to_vec()).unwrap_or_default(),
+            volume: self.volume.as_ref().map(|v| v[range.clone()].to_vec()),
+            halts: rebase_halts(&self.halts, range.start, range.end),
+        }
+    }
+
+    /// mark the bars dated from `from` to `to` (both inclusive) as untradeable for one instrument, or for
+    /// both with None. returns false if no bar falls in the window.
+    pub fn halt_between(&mut self, from: &str, to: &str, instrument: Option<u8>, kind: HaltKind) -> bool {
+        let start = self.date.iter().position(|d| compare_dates(d, from) != Ordering::Less);
+        let end = self.date.iter().rposition(|d| compare_dates(d, to) != Ordering::Greater);
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => {
+                self.halts.push(Halt::new(start, end, instrument, kind));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // whether a buy (or sell) of `instrument` can't fill on bar `index`
+    pub fn is_halted(&self, index: usize, instrument: u8, buy: bool) -> bool {
+        self.halts.iter().any(|h| h.blocks(index, instrument, buy))
+    }
+
+    // bars dated between `from` and `to` (both inclusive, either open-ended with None)
+    pub fn slice_dates(&self, from: Option<&str>, to: Option<&str>) -> OhlcData {
+        let start = match from {
+            Some(from) => self.date.iter().position(|d| compare_dates(d, from) != Ordering::Less).unwrap_or(self.date.len()),
+            None => 0,
+        };
+        let end = match to {
+            Some(to) => self.date.iter().rposition(|d| compare_dates(d, to) != Ordering::Greater).map_or(0, |i| i + 1),
+            None => self.date.len(),
+        };
+        self.slice(start..end.max(start))
+    }
+}
+
+// order direction, used to filter pending orders
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Long,
+    Short,
+}
+
+impl Side {
+    // side of a signed order or trade size
+    pub fn of(size: f64) -> Side {
+        if size > 0.0 { Side::Long } else { Side::Short }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -38,13 +131,218 @@ pub struct Order {
     pub stop: Option<f64>,
     pub sl: Option<f64>,
     pub tp: Option<f64>,
-    // for contingent orders (sl/tp), parent_trade indicates which trade they relate to (by index)
-    pub parent_trade: Option<usize>,
+    // for contingent orders (sl/tp), parent_trade is the id of the trade they relate to (Trade::id), which
+    // unlike its position in `trades` doesn't change as other trades close
+    pub parent_trade: Option<u64>,
     // instrument flag: 1 = primary (using Close), 2 = hedge (using Close2)
     pub instrument: u8,
+    // free-form reason attached by the strategy (e.g. "zscore=2.3 entry"), carried onto the trade
+    pub tag: Option<String>,
+    // scale-out take-profit levels; each becomes a contingent limit order once the entry fills
+    pub tp_ladder: Vec<TakeProfitLevel>,
+    // time exit: the broker closes the resulting trade at the close of bar entry_index + n
+    pub max_holding_bars: Option<usize>,
+    // price the decision was made at, the reference for slippage; the broker fills in the price passed to
+    // new_order when None, and the trigger level for the stop losses and take profits it creates
+    pub signal_price: Option<f64>,
+    // if-touched entry: the order waits until the price touches this level from above for a buy (from
+    // below for a sell), then works as a limit order at `limit` (limit-if-touched) or fills as a market
+    // order at the touch level (market-if-touched, no limit). ignored on contingent orders.
+    pub touch: Option<f64>,
+    // when a market order fills; None follows the broker's trade_on_close setting
+    pub timing: Option<FillTiming>,
+    // commission of this order's fills; None charges the broker's commission rate
+    pub commission: Option<OrderCommission>,
+    // trailing stop loss: once the entry fills, its stop follows the best price of the trade at this
+    // distance and never moves back. starts at `sl` when that is tighter.
+    pub trailing: Option<TrailingStop>,
+    // stable id the broker assigns when it queues the order (0 before), for cancel_order and modify_order;
+    // unlike positions in `orders` it doesn't change as other orders fill
+    pub id: u64,
+    // the size is already the one to trade (e.g. the remainder to a rebalance target): it is exempt from
+    // margin and equity-ratio scaling, the drawdown governor's size_multiplier and beta-neutral sizing,
+    // and only converted to instrument 2 units and rounded to the lot step
+    pub exact_size: bool,
+}
+
+// an empty market order on the primary instrument, so literals only spell out what they set:
+// Order { size: 10.0, sl: Some(95.0), ..Default::default() }
+impl Default for Order {
+    fn default() -> Self {
+        Order {
+            size: 0.0,
+            limit: None,
+            stop: None,
+            sl: None,
+            tp: None,
+            parent_trade: None,
+            instrument: 1,
+            tag: None,
+            tp_ladder: Vec::new(),
+            max_holding_bars: None,
+            signal_price: None,
+            touch: None,
+            timing: None,
+            commission: None,
+            trailing: None,
+            id: 0,
+            exact_size: false,
+        }
+    }
+}
+
+// distance of a trailing stop from the best price reached since entry
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrailingStop {
+    Points(f64),  // in price units
+    Percent(f64), // in percent of the price, e.g. 1.5
 }
 
-#[derive(Clone)]
+impl TrailingStop {
+    // stop level for a long (below `price`) or short (above it) at this distance
+    pub fn level(&self, price: f64, long: bool) -> f64 {
+        let distance = match *self {
+            TrailingStop::Points(points) => points,
+            TrailingStop::Percent(pct) => price * pct / 100.0,
+        };
+        if long { price - distance } else { price + distance }
+    }
+
+    // the stop moved up to `price` for a long (down for a short), if that tightens it
+    pub fn trail(&self, stop: f64, price: f64, long: bool) -> f64 {
+        let level = self.level(price, long);
+        if long { stop.max(level) } else { stop.min(level) }
+    }
+}
+
+// per-order commission, e.g. maker rates (or rebates) for passive limit entries and taker rates for
+// aggressive exits in the same strategy
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrderCommission {
+    Rate(f64), // commission ratio for this order; negative values are rebates
+    Maker,     // the broker's maker_commission
+    Taker,     // the broker's commission
+}
+
+// fill timing of a single market order, overriding trade_on_close for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillTiming {
+    OnClose,  // at the official close of the bar the order was placed on (market-on-close)
+    NextOpen, // at the open of the next bar
+}
+
+// one rung of a take-profit ladder: close `fraction` of the original trade size at `price`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TakeProfitLevel {
+    pub price: f64,
+    pub fraction: f64,
+}
+
+impl TakeProfitLevel {
+    // levels at entry ± multiple * sigma in the trade's favour, e.g. &[(1.0, 0.5), (2.0, 0.5)]
+    pub fn from_sigma(entry_price: f64, sigma: f64, size: f64, levels: &[(f64, f64)]) -> Vec<TakeProfitLevel> {
+        levels.iter()
+            .map(|&(multiple, fraction)| TakeProfitLevel {
+                price: entry_price + size.signum() * multiple * sigma,
+                fraction,
+            })
+            .collect()
+    }
+}
+
+// how orders are resized when Broker::scaling_enabled is set
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScalingMode {
+    // size times equity / starting equity once equity is more than 1% above the start (the default)
+    EquityRatio,
+    // every entry uses this fraction of the available buying power (e.g. 0.2); the strategy's size only
+    // sets the side. both legs of a pair each get the fraction.
+    BuyingPower(f64),
+    // entries are sized to bring margin usage up to this target (e.g. 0.5), and rejected once it is reached
+    TargetMarginUsage(f64),
+}
+
+// random microstructure noise applied by the broker, used by robustness tests.
+// market entries are shifted by up to ± timing_jitter bars and fills by up to ± price_jitter_ticks ticks.
+#[derive(Clone, Debug)]
+pub struct Perturbation {
+    pub timing_jitter: usize,
+    pub price_jitter_ticks: u32,
+    pub tick_size: f64,
+    pub seed: u64,
+    pub rng: Rng,
+}
+
+impl Perturbation {
+    pub fn new(timing_jitter: usize, price_jitter_ticks: u32, tick_size: f64, seed: u64) -> Self {
+        Perturbation {
+            timing_jitter,
+            price_jitter_ticks,
+            tick_size,
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+
+    // the same noise from its first draw again
+    pub fn reseeded(&self) -> Self {
+        Perturbation::new(self.timing_jitter, self.price_jitter_ticks, self.tick_size, self.seed)
+    }
+}
+
+// external deposit (positive amount) or withdrawal (negative) credited to cash at the start of the
+// first bar at or after `date`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CashFlow {
+    pub date: String,
+    pub amount: f64,
+}
+
+impl CashFlow {
+    pub fn new(date: &str, amount: f64) -> Self {
+        CashFlow { date: date.to_string(), amount }
+    }
+}
+
+// accumulated trading costs and turnover of a broker, consumed by compute_stats
+#[derive(Clone, Debug, Default)]
+pub struct CostSummary {
+    pub traded_notional: f64,      // sum of |size| * price over all fills
+    pub total_commission: f64,     // net of rebates
+    pub total_rebates: f64,        // rebates received on fills with a negative commission rate
+    pub total_spread_cost: f64,
+    pub total_financing_cost: f64,
+    // slippage of fills against their reference price, per unit and summed per side (positive = cost)
+    pub num_buy_fills: usize,
+    pub num_sell_fills: usize,
+    pub buy_slippage: f64,
+    pub sell_slippage: f64,
+    pub total_slippage_cost: f64,  // slippage * |size| over all fills
+}
+
+impl CostSummary {
+    pub fn avg_buy_slippage(&self) -> f64 {
+        if self.num_buy_fills > 0 { self.buy_slippage / self.num_buy_fills as f64 } else { 0.0 }
+    }
+
+    pub fn avg_sell_slippage(&self) -> f64 {
+        if self.num_sell_fills > 0 { self.sell_slippage / self.num_sell_fills as f64 } else { 0.0 }
+    }
+}
+
+// one execution: the reference price the decision was made at and the realized fill price (after spread,
+// commission, delay and perturbation). size is signed by the side of the fill, so closing a long is a sell.
+#[derive(Clone, Debug)]
+pub struct Fill {
+    pub tick: usize,
+    pub instrument: u8,
+    pub size: f64,
+    pub reference_price: f64,
+    pub fill_price: f64,
+    pub slippage: f64, // adverse move of the fill vs the reference, in price units (positive = cost)
+}
+
+#[derive(Clone, Debug)]
 pub struct Trade {
     pub instrument: u8,
     pub size: f64,
@@ -55,16 +353,48 @@ pub struct Trade {
     // optional indices of contingent orders assigned to this trade
     pub sl_order: Option<usize>,
     pub tp_order: Option<usize>,
+    // tag of the order that opened the trade and of the order that closed it (e.g. "stop-loss")
+    pub tag: Option<String>,
+    pub exit_tag: Option<String>,
+    // copied from the opening order; enforced by Broker::close_expired_trades
+    pub max_holding_bars: Option<usize>,
+    // commission paid on the entry and exit fills of this trade (negative for a net rebate); a partial
+    // close takes its share of the entry commission with it
+    pub commission: f64,
+    // id of the order that opened the trade; the closed parts of a partially closed trade keep it
+    pub id: u64,
+}
+
+impl Default for Trade {
+    fn default() -> Self {
+        Trade {
+            instrument: 1,
+            size: 0.0,
+            entry_price: 0.0,
+            entry_index: 0,
+            exit_price: None,
+            exit_index: None,
+            sl_order: None,
+            tp_order: None,
+            tag: None,
+            exit_tag: None,
+            max_holding_bars: None,
+            commission: 0.0,
+            id: 0,
+        }
+    }
 }
 
 impl Trade {
     // compute profit or loss in cash units for this trade
     pub fn pnl(&self) -> f64 {
         if let Some(exit_price) = self.exit_price {
-            self.size * (exit_price - self.entry_price)
-        } else {
-            0.0
+            #[cfg(feature = "decimal")]
+            return ((Decimal::from_f64(exit_price) - Decimal::from_f64(self.entry_price)) * Decimal::from_f64(self.size)).to_f64();
+            #[cfg(not(feature = "decimal"))]
+            return self.size * (exit_price - self.entry_price);
         }
+        0.0
     }
     // compute percent return of this trade
     pub fn pl_pct(&self) -> f64 {
@@ -82,6 +412,23 @@ impl Trade {
     }
 }
 
+// state an order submission may change before it is rejected (exclusive liquidation, earlier legs of a
+// group); the logs are restored by truncating them to their lengths at the snapshot
+struct BookSnapshot {
+    trades: Vec<Trade>,
+    orders: Vec<Order>,
+    delayed_orders: Vec<(usize, Order)>,
+    cash: f64,
+    costs: CostSummary,
+    closed_trades: usize,
+    fills: usize,
+    cancelled_orders: usize,
+    events: usize,
+    // ids and perturbation draws used by queued legs are given back too
+    next_order_id: u64,
+    perturbation_rng: Option<Rng>,
+}
+
 // current open position can be derived from active trades
 pub struct Position;
 
@@ -109,24 +456,114 @@ pub struct Broker {
     pub cash: f64,
     pub bidask_spread: f64,
     pub commission: f64, // commission ratio (e.g. 0.001 means 0.1% fee)
+    // commission ratio of orders flagged OrderCommission::Maker; negative for a rebate. defaults to commission
+    pub maker_commission: f64,
     pub margin: f64,     // margin ratio (0 < margin <= 1)
+    // gross (default) or netted margin of offsetting long and short trades, see current_exposure
+    pub margin_mode: MarginMode,
     pub trade_on_close: bool,
     pub hedging: bool,
+    // every new entry order first closes all open trades at the current close and cancels pending orders
     pub exclusive_orders: bool,
+    // the original exclusive_orders behaviour: open trades are dropped without being closed, so their
+    // pnl is never booked. only for reproducing old results.
+    pub legacy_exclusive_orders: bool,
     pub orders: Vec<Order>,
     pub trades: Vec<Trade>,      // active trades
     pub closed_trades: Vec<Trade>,
     // equity curve per tick
     pub equity: Vec<f64>,
+    // worst-case equity within each tick (see intrabar_worst_equity), never above equity
+    pub intrabar_equity: Vec<f64>,
     pub max_margin_usage: f64, // track maximum margin usage (percentage)
     pub base_equity: f64,      // initial equity for scaling purposes
     pub scaling_enabled: bool, // flag to enable scaling
+    pub scaling_mode: ScalingMode,
     pub margin_usage_history: Vec<f64>, // track historical margin usage
+    // marked-to-market notional per bar
+    pub long_exposure_history: Vec<f64>,
+    pub short_exposure_history: Vec<f64>,
+    pub net_exposure_history: Vec<f64>,
+    // net position in units per bar, [instrument 1, instrument 2]
+    pub position_history: Vec<[f64; 2]>,
+    // financing charged per tick on gross open notional (e.g. overnight funding), 0.0 disables it
+    pub financing_rate: f64,
+    pub costs: CostSummary,
+    pub fills: Vec<Fill>,
+    // bars between signal and fill for market entry orders: a signal on bar t fills at the open of t + n.
+    // 1 (the default) is the usual next-bar fill; larger values hold orders back in delayed_orders.
+    pub execution_delay: usize,
+    pub delayed_orders: Vec<(usize, Order)>, // (release tick, order)
+    pub perturbation: Option<Perturbation>,  // None for the normal, noise-free simulation
+    // seeded random numbers for strategies (jittered entries, exploration); draw from broker.rng only,
+    // so a run is reproduced by its seed regardless of which thread or optimizer worker executes it
+    pub seed: u64,
+    pub rng: Rng,
+    // every new_order call and every rejection (tick, reason), so strategies that ignore errors stay auditable
+    pub order_attempts: usize,
+    pub rejected_orders: Vec<(usize, OrderError)>,
+    // contingent orders (stop loss, take-profit rungs) cancelled because their trade was closed, with the tick
+    pub cancelled_orders: Vec<(usize, Order)>,
+    // every order passed to new_order / new_order_group as requested, with the tick and outcome; only
+    // recorded while Some (StrategyTester turns it on)
+    pub order_log: Option<Vec<(usize, Order, Result<(), OrderError>)>>,
+    // every submission, fill, closed trade, margin call and bar in order when Some, see Backtest::events
+    pub event_log: Option<Vec<BacktestEvent>>,
+    // notional caps as multiples of equity: per instrument (e.g. 1 => 2.0) and across all instruments
+    pub exposure_caps: HashMap<u8, f64>,
+    // lot step, rounding and contract size per instrument; unset instruments use InstrumentSpec::for_margin
+    pub instrument_specs: HashMap<u8, InstrumentSpec>,
+    pub max_gross_exposure: Option<f64>,
+    // historical var of the open portfolio, estimated every bar when var_config is set
+    pub var_config: Option<VarConfig>,
+    pub var_history: Vec<VarEstimate>,
+    // optional beta-neutral constraint; net beta exposure / equity is recorded per bar while it is set
+    pub beta_neutral: Option<BetaNeutralConfig>,
+    pub net_beta_history: Vec<f64>,
+    // scheduled deposits/withdrawals, and the amount actually applied on each bar (StatsConfig::cash_flows)
+    pub cash_flows: Vec<CashFlow>,
+    pub cash_flow_history: Vec<f64>,
+    cash_flows_applied: usize,
+    // synthetic bid/ask around the ohlc prices (treated as mids); when set, buys fill at the ask, sells at
+    // the bid, stops and limits trigger on the side they fill on, and the fixed bidask_spread is not applied
+    pub quote_model: Option<SpreadModel>,
+    // orders placed while their instrument is halted (data.halts) are queued or rejected; queued orders
+    // never fill inside a halt. with gap_fills_after_halt, the first bar after a halt fills at its open.
+    pub halt_policy: HaltPolicy,
+    pub gap_fills_after_halt: bool,
+    // after a stop-loss exit, new entries on the same side of that instrument are rejected with
+    // OrderError::InCooldown on the stop-out bar and this many bars after it
+    pub stop_loss_cooldown: Option<usize>,
+    // bar of the latest stop-loss exit per (instrument, long side)
+    pub stop_outs: HashMap<(u8, bool), usize>,
+    // scales entry sizes down as equity falls from its peak; the multiplier in force after each bar is
+    // recorded in size_multiplier_history (StatsConfig::size_multipliers)
+    pub drawdown_governor: Option<DrawdownGovernor>,
+    pub size_multiplier: f64,
+    pub size_multiplier_history: Vec<f64>,
+    equity_peak: f64,
+    // id of the next queued order; ids start at 1 so 0 marks an order the broker hasn't seen
+    next_order_id: u64,
+    // decimal feature: adjusted prices are rounded to price_decimals and cash to cash_decimals
+    #[cfg(feature = "decimal")]
+    pub price_decimals: u32,
+    #[cfg(feature = "decimal")]
+    pub cash_decimals: u32,
+    half_spread: [f64; 2], // of instrument 1 and 2 on the current bar
+    // suppresses margin-call and other per-event prints (and strategy prints that check it), e.g. in sweeps
+    pub quiet: bool,
+    // absolute bar index of data[0]; only non-zero when bars are streamed in with push_bar
+    data_offset: usize,
+    // reused by process_orders to avoid per-bar allocations
+    order_buffer: Vec<Order>,
+    execution_buffer: Vec<Order>,
+    current_index: usize,
     max_concurrent_trades: usize,
 }
 
 impl Broker {
     const MARGIN_CALL_THRESHOLD: f64 = 0.90; // 90% margin usage triggers margin call
+    pub const DEFAULT_SEED: u64 = 42;
 
     pub fn new(
         data: OhlcData,
@@ -145,24 +582,262 @@ impl Broker {
             cash,
             bidask_spread,
             commission,
+            maker_commission: commission,
             margin,
+            margin_mode: MarginMode::Gross,
             trade_on_close,
             hedging,
             exclusive_orders,
+            legacy_exclusive_orders: false,
             orders: Vec::new(),
             trades: Vec::new(),
             closed_trades: Vec::new(),
             equity: vec![cash; n],
+            intrabar_equity: vec![cash; n],
             max_margin_usage: 0.0,
             base_equity: cash,
             scaling_enabled,
+            scaling_mode: ScalingMode::EquityRatio,
             margin_usage_history: vec![0.0],
+            long_exposure_history: Vec::with_capacity(n),
+            short_exposure_history: Vec::with_capacity(n),
+            net_exposure_history: Vec::with_capacity(n),
+            position_history: Vec::with_capacity(n),
+            financing_rate: 0.0,
+            costs: CostSummary::default(),
+            fills: Vec::new(),
+            execution_delay: 1,
+            delayed_orders: Vec::new(),
+            perturbation: None,
+            seed: Self::DEFAULT_SEED,
+            rng: Rng::new(Self::DEFAULT_SEED),
+            order_attempts: 0,
+            rejected_orders: Vec::new(),
+            cancelled_orders: Vec::new(),
+            order_log: None,
+            event_log: None,
+            exposure_caps: HashMap::new(),
+            instrument_specs: HashMap::new(),
+            max_gross_exposure: None,
+            var_config: None,
+            var_history: Vec::with_capacity(n),
+            beta_neutral: None,
+            net_beta_history: Vec::with_capacity(n),
+            cash_flows: Vec::new(),
+            cash_flow_history: vec![0.0; n],
+            cash_flows_applied: 0,
+            quote_model: None,
+            halt_policy: HaltPolicy::default(),
+            gap_fills_after_halt: false,
+            stop_loss_cooldown: None,
+            stop_outs: HashMap::new(),
+            drawdown_governor: None,
+            size_multiplier: 1.0,
+            size_multiplier_history: Vec::with_capacity(n),
+            equity_peak: cash,
+            next_order_id: 1,
+            #[cfg(feature = "decimal")]
+            price_decimals: 8,
+            #[cfg(feature = "decimal")]
+            cash_decimals: 2,
+            quiet: false,
+            half_spread: [0.0; 2],
+            data_offset: 0,
+            order_buffer: Vec::new(),
+            execution_buffer: Vec::new(),
+            current_index: 0,
             max_concurrent_trades: 0,
         }
     }
 
+    // configured spec of an instrument, or the account default
+    pub fn instrument_spec(&self, instrument: u8) -> InstrumentSpec {
+        self.instrument_specs.get(&instrument).cloned().unwrap_or_else(|| InstrumentSpec::for_margin(self.margin))
+    }
+
+    // notional of open trades at entry prices that counts against margin (margin usage, buying power);
+    // with MarginMode::Netted offsetting long and short trades are charged at the reduced rate
     pub fn current_exposure(&self) -> f64 {
-        self.trades.iter().map(|trade| trade.size.abs() * trade.entry_price).sum()
+        let (long, short) = self.trades.iter().fold((0.0, 0.0), |(long, short), trade| {
+            let notional = trade.size.abs() * trade.entry_price;
+            if trade.size > 0.0 { (long + notional, short) } else { (long, short + notional) }
+        });
+        self.margin_mode.margined_notional(long, short)
+    }
+
+    // long and short notional of open trades marked at the close of the given tick
+    pub fn exposure_at(&self, index: usize) -> (f64, f64) {
+        let mut long = 0.0;
+        let mut short = 0.0;
+        for trade in self.trades.iter() {
+            let price = if trade.instrument == 1 {
+                self.data.close[self.local(index)]
+            } else {
+                self.data.close2[self.local(index)]
+            };
+            if trade.size > 0.0 {
+                long += trade.size * price;
+            } else {
+                short += -trade.size * price;
+            }
+        }
+        (long, short)
+    }
+
+    // true if adding `order_notional` to `instrument` would breach its cap or the aggregate cap. open trades
+    // and the entries still waiting to fill (queued or held back by execution_delay) count towards the caps,
+    // all at the current price and contract size of their instrument
+    fn exceeds_exposure_caps(&self, instrument: u8, order_notional: f64) -> bool {
+        if self.exposure_caps.is_empty() && self.max_gross_exposure.is_none() {
+            return false;
+        }
+        let index = self.current_index.min(self.last_index());
+        let equity = self.equity.get(index).copied().unwrap_or(self.cash);
+        let pending = self.pending_orders(None, None).into_iter()
+            .filter(|order| order.parent_trade.is_none())
+            .map(|order| (order.instrument, order.size));
+        let positions: Vec<(u8, f64)> = self.trades.iter()
+            .map(|trade| (trade.instrument, trade.size))
+            .chain(pending)
+            .collect();
+        let notional_of = |&(instrument, size): &(u8, f64)| {
+            self.instrument_spec(instrument).notional(size, self.instrument_price(instrument, index))
+        };
+        let instrument_notional: f64 = positions.iter()
+            .filter(|(inst, _)| *inst == instrument)
+            .map(notional_of)
+            .sum();
+        let gross_notional: f64 = positions.iter().map(notional_of).sum();
+
+        if let Some(cap) = self.exposure_caps.get(&instrument) {
+            if instrument_notional + order_notional > cap * equity {
+                return true;
+            }
+        }
+        if let Some(cap) = self.max_gross_exposure {
+            if gross_notional + order_notional > cap * equity {
+                return true;
+            }
+        }
+        false
+    }
+
+    // var/es of the open trades at the given tick, optionally with an extra signed notional on one instrument
+    pub fn portfolio_var(&self, index: usize, extra: Option<(u8, f64)>) -> VarEstimate {
+        let config = match self.var_config.as_ref() {
+            Some(config) => config,
+            None => return VarEstimate::default(),
+        };
+        let index = index.min(self.last_index());
+        let mut notionals = [0.0, 0.0]; // instrument 1, instrument 2
+        for trade in self.trades.iter() {
+            let price = if trade.instrument == 1 { self.data.close[self.local(index)] } else { self.data.close2[self.local(index)] };
+            notionals[if trade.instrument == 1 { 0 } else { 1 }] += trade.size * price;
+        }
+        if let Some((instrument, notional)) = extra {
+            notionals[if instrument == 1 { 0 } else { 1 }] += notional;
+        }
+
+        let mut exposures = Vec::new();
+        if notionals[0] != 0.0 {
+            exposures.push((notionals[0], trailing_returns(&self.data.close, self.local(index), config.lookback)));
+        }
+        if notionals[1] != 0.0 {
+            exposures.push((notionals[1], trailing_returns(&self.data.close2, self.local(index), config.lookback)));
+        }
+        historical_var(&exposures, config.confidence)
+    }
+
+    // true if a var budget is set and the order would push var above it
+    fn exceeds_var_budget(&self, instrument: u8, signed_notional: f64) -> bool {
+        let budget_pct = match self.var_config.as_ref().and_then(|c| c.budget_pct) {
+            Some(budget_pct) => budget_pct,
+            None => return false,
+        };
+        let index = self.current_index.min(self.last_index());
+        let equity = self.equity.get(index).copied().unwrap_or(self.cash);
+        let estimate = self.portfolio_var(index, Some((instrument, signed_notional)));
+        estimate.var > budget_pct / 100.0 * equity
+    }
+
+    // price of instrument 1 or 2 at a tick
+    fn instrument_price(&self, instrument: u8, index: usize) -> f64 {
+        if instrument == 1 { self.data.close[self.local(index)] } else { self.data.close2[self.local(index)] }
+    }
+
+    // rolling beta of an instrument to the configured benchmark instrument
+    pub fn instrument_beta(&self, instrument: u8, index: usize) -> f64 {
+        let config = match self.beta_neutral.as_ref() {
+            Some(config) => config,
+            None => return 1.0,
+        };
+        if instrument == config.benchmark {
+            return 1.0;
+        }
+        let benchmark = if config.benchmark == 1 { &self.data.close } else { &self.data.close2 };
+        let prices = if instrument == 1 { &self.data.close } else { &self.data.close2 };
+        rolling_beta(prices, benchmark, self.local(index), config.lookback)
+    }
+
+    // beta-weighted notional of open trades plus pending entry orders, in account currency
+    pub fn net_beta_exposure(&self, index: usize) -> f64 {
+        let index = index.min(self.last_index());
+        let pending = self.orders.iter()
+            .chain(self.delayed_orders.iter().map(|(_, order)| order))
+            .filter(|order| order.parent_trade.is_none())
+            .map(|order| (order.instrument, order.size));
+        self.trades.iter()
+            .map(|trade| (trade.instrument, trade.size))
+            .chain(pending)
+            .map(|(instrument, size)| {
+                let notional = self.instrument_spec(instrument).notional(size, self.instrument_price(instrument, index));
+                size.signum() * notional * self.instrument_beta(instrument, index)
+            })
+            .sum()
+    }
+
+    // size for a hedge-leg entry that brings net beta back to zero when it would otherwise end up outside
+    // the band; the requested size is kept if it is already inside, or if the hedge would flip its direction
+    fn beta_neutral_size(&self, order: &Order) -> f64 {
+        let config = match self.beta_neutral.as_ref() {
+            Some(config) if order.instrument == config.hedge_instrument && order.parent_trade.is_none() => config,
+            _ => return order.size,
+        };
+        let index = self.current_index.min(self.last_index());
+        let equity = self.equity.get(index).copied().unwrap_or(self.cash);
+        let unit_beta = self.instrument_spec(order.instrument).notional(1.0, self.instrument_price(order.instrument, index))
+            * self.instrument_beta(order.instrument, index);
+        let book = self.net_beta_exposure(index);
+        if unit_beta == 0.0 || (book + order.size * unit_beta).abs() <= config.band * equity {
+            return order.size;
+        }
+        let hedge = -book / unit_beta;
+        if hedge.signum() != order.size.signum() {
+            return order.size;
+        }
+        self.instrument_spec(order.instrument).round_size(hedge).unwrap_or(order.size)
+    }
+
+    // record long, short and net exposure for the given tick
+    pub fn record_exposure(&mut self, index: usize) {
+        let (long, short) = self.exposure_at(index);
+        self.long_exposure_history.push(long);
+        self.short_exposure_history.push(short);
+        self.net_exposure_history.push(long - short);
+
+        let mut positions = [0.0; 2];
+        for trade in self.trades.iter() {
+            positions[if trade.instrument == 1 { 0 } else { 1 }] += trade.size;
+        }
+        self.position_history.push(positions);
+    }
+
+    // gross exposure (long + short notional) per recorded tick
+    pub fn gross_exposure_history(&self) -> Vec<f64> {
+        self.long_exposure_history.iter()
+            .zip(self.short_exposure_history.iter())
+            .map(|(long, short)| long + short)
+            .collect()
     }
     
     // compute price adjusted for commission and bidask spread.
@@ -170,53 +845,563 @@ impl Broker {
     // for short orders (size < 0), the adjusted price is: price * (1 - commission) - bidask_spread.
     // if size is zero, the price is unchanged.
     pub fn adjusted_price(&self, size: f64, price: f64) -> f64 {
-        // apply commission adjustment
-        let price_with_commission = price * (1.0 + size.signum() * self.commission);
+        self.adjusted_price_at(size, price, self.commission)
+    }
+
+    // commission ratio charged on the fills of an order
+    pub fn commission_rate(&self, commission: Option<OrderCommission>) -> f64 {
+        match commission {
+            Some(OrderCommission::Rate(rate)) => rate,
+            Some(OrderCommission::Maker) => self.maker_commission,
+            Some(OrderCommission::Taker) | None => self.commission,
+        }
+    }
+
+    // fill price after spread and a given commission ratio
+    pub fn adjusted_price_at(&self, size: f64, price: f64, commission: f64) -> f64 {
         // always apply bidask spread if set; note bidask spread is a fixed 0.5 usd per trade
-        if self.bidask_spread > 0.0 {
+        let spread = if self.bidask_spread > 0.0 && self.quote_model.is_none() {
             if size > 0.0 {
-                price_with_commission + self.bidask_spread
+                self.bidask_spread
             } else if size < 0.0 {
-                price_with_commission - self.bidask_spread
+                -self.bidask_spread
             } else {
-                price_with_commission
+                0.0
             }
         } else {
-            price_with_commission
+            0.0
+        };
+        // apply commission adjustment; with the decimal feature exactly, rounded to price_decimals
+        #[cfg(feature = "decimal")]
+        return (Decimal::from_f64(price) * (Decimal::ONE + Decimal::from_f64(size.signum() * commission))
+            + Decimal::from_f64(spread)).round_dp(self.price_decimals).to_f64();
+        #[cfg(not(feature = "decimal"))]
+        return price * (1.0 + size.signum() * commission) + spread;
+    }
+
+    // add a realized amount (pnl, financing, cash flow) to cash. with the decimal feature the sum is exact
+    // and rounded to cash_decimals, so millions of bookings don't drift
+    fn credit(&mut self, amount: f64) {
+        #[cfg(feature = "decimal")]
+        {
+            self.cash = (Decimal::from_f64(self.cash) + Decimal::from_f64(amount)).round_dp(self.cash_decimals).to_f64();
+        }
+        #[cfg(not(feature = "decimal"))]
+        {
+            self.cash += amount;
         }
     }
     
-    // place a new order
-    pub fn new_order(&mut self, mut order: Order, current_price: f64) -> Result<(), OrderError> {
-        // prevent fractional orders when not using leverage
-        if self.margin >= 1.0 && order.size.fract() != 0.0 {
-            return Err(OrderError::FractionalOrderNotAllowed);
+    // book the commission of a fill of `size` at the raw price and return it (negative for a rebate)
+    fn charge_commission(&mut self, size: f64, raw_price: f64, rate: f64) -> f64 {
+        let commission = size.abs() * raw_price * rate;
+        self.costs.total_commission += commission;
+        if commission < 0.0 {
+            self.costs.total_rebates -= commission;
+        }
+        commission
+    }
+
+    // record notional and spread cost of a fill at the raw (unadjusted) price, and its slippage from the
+    // reference price to the adjusted fill price. size is signed by the side of the fill.
+    fn record_fill(&mut self, tick: usize, size: f64, raw_price: f64, fill_price: f64, reference_price: f64, instrument: u8) {
+        let notional = size.abs() * raw_price;
+        self.costs.traded_notional += notional;
+        self.costs.total_spread_cost += size.abs() * match self.quote_model {
+            Some(_) => self.half_spread(instrument),
+            None => self.bidask_spread,
+        };
+        let slippage = (fill_price - reference_price) * size.signum();
+        if size > 0.0 {
+            self.costs.num_buy_fills += 1;
+            self.costs.buy_slippage += slippage;
+        } else {
+            self.costs.num_sell_fills += 1;
+            self.costs.sell_slippage += slippage;
         }
+        self.costs.total_slippage_cost += slippage * size.abs();
+        let fill = Fill { tick, instrument, size, reference_price, fill_price, slippage };
+        self.record_event(|| BacktestEvent::OrderFilled(fill.clone()));
+        self.fills.push(fill);
+    }
+
+    // half the synthetic spread of an instrument on the current bar, 0.0 without a quote_model
+    pub fn half_spread(&self, instrument: u8) -> f64 {
+        if instrument == 1 { self.half_spread[0] } else { self.half_spread[1] }
+    }
+
+    // synthetic (bid, ask) of an instrument around a mid price on the current bar
+    pub fn quote(&self, instrument: u8, mid: f64) -> (f64, f64) {
+        let half = self.half_spread(instrument);
+        (mid - half, mid + half)
+    }
+
+    // price a buy fills at (the ask) or a sell fills at (the bid) for a given mid
+    fn quote_price(&self, instrument: u8, mid: f64, buy: bool) -> f64 {
+        let (bid, ask) = self.quote(instrument, mid);
+        if buy { ask } else { bid }
+    }
+
+    // spread of each instrument for this bar
+    fn update_quotes(&mut self, index: usize) {
+        if let Some(model) = self.quote_model.as_ref() {
+            let local = self.local(index);
+            self.half_spread = [
+                model.spread(&self.data.close, local) / 2.0,
+                model.spread(&self.data.close2, local) / 2.0,
+            ];
+        }
+    }
+
+    // charge financing on the gross notional held over this tick; instruments with a rollover schedule are
+    // charged by apply_rollovers instead
+    fn apply_financing(&mut self, index: usize) {
+        if self.financing_rate == 0.0 || self.trades.is_empty() {
+            return;
+        }
+        let local = self.local(index);
+        let gross: f64 = self.trades.iter()
+            .filter(|t| self.instrument_specs.get(&t.instrument).is_none_or(|spec| spec.rollover.is_none()))
+            .map(|t| t.size.abs() * if t.instrument == 1 { self.data.close[local] } else { self.data.close2[local] })
+            .sum();
+        let cost = gross * self.financing_rate;
+        self.credit(-cost);
+        self.costs.total_financing_cost += cost;
+    }
+
+    // charge the rollovers between the previous bar and this one on the trades held through them, at the
+    // previous close, for instruments whose spec has a rollover schedule. bar dates are read as utc.
+    fn apply_rollovers(&mut self, index: usize) {
+        if index <= self.data_offset || self.trades.is_empty() || self.instrument_specs.values().all(|s| s.rollover.is_none()) {
+            return;
+        }
+        let prev = self.local(index - 1);
+        let (Some(from), Some(to)) = (parse_quote_time(&self.data.date[prev]), parse_quote_time(&self.data.date[prev + 1])) else {
+            return;
+        };
+        let mut cost = 0.0;
+        for instrument in [1u8, 2] {
+            let Some(spec) = self.instrument_specs.get(&instrument) else {
+                continue;
+            };
+            let Some(schedule) = spec.rollover.as_ref() else {
+                continue;
+            };
+            let days = schedule.days_between(from, to);
+            if days == 0.0 {
+                continue;
+            }
+            let price = if instrument == 1 { self.data.close[prev] } else { self.data.close2[prev] };
+            cost += self.trades.iter()
+                .filter(|t| t.instrument == instrument)
+                .map(|t| schedule.daily_cost(t.size, spec.notional(t.size, price)) * days)
+                .sum::<f64>();
+        }
+        self.credit(-cost);
+        self.costs.total_financing_cost += cost;
+    }
 
+    // reseed the strategy rng; call before the run
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = Rng::new(seed);
+    }
+
+    /// a broker over the same data with every setting of this one (costs, quotes, margin, sizing, risk
+    /// limits, halts, cooldown, drawdown governor, perturbation, seed and cash flows) but none of its state:
+    /// no trades, orders or history, cash back at base_equity and the rngs reseeded
+    pub fn fresh_copy(&self) -> Broker {
+        let mut broker = Broker::new(
+            self.data.clone(),
+            self.base_equity,
+            self.commission,
+            self.bidask_spread,
+            self.margin,
+            self.trade_on_close,
+            self.hedging,
+            self.exclusive_orders,
+            self.scaling_enabled,
+        );
+        broker.maker_commission = self.maker_commission;
+        broker.margin_mode = self.margin_mode;
+        broker.legacy_exclusive_orders = self.legacy_exclusive_orders;
+        broker.scaling_mode = self.scaling_mode;
+        broker.financing_rate = self.financing_rate;
+        broker.execution_delay = self.execution_delay;
+        broker.perturbation = self.perturbation.as_ref().map(Perturbation::reseeded);
+        broker.set_seed(self.seed);
+        broker.exposure_caps = self.exposure_caps.clone();
+        broker.instrument_specs = self.instrument_specs.clone();
+        broker.max_gross_exposure = self.max_gross_exposure;
+        broker.var_config = self.var_config.clone();
+        broker.beta_neutral = self.beta_neutral.clone();
+        broker.set_cash_flows(self.cash_flows.clone());
+        broker.quote_model = self.quote_model.clone();
+        broker.halt_policy = self.halt_policy;
+        broker.gap_fills_after_halt = self.gap_fills_after_halt;
+        broker.stop_loss_cooldown = self.stop_loss_cooldown;
+        broker.drawdown_governor = self.drawdown_governor.clone();
+        #[cfg(feature = "decimal")]
+        {
+            broker.price_decimals = self.price_decimals;
+            broker.cash_decimals = self.cash_decimals;
+        }
+        broker.quiet = self.quiet;
+        broker
+    }
+
+    // replace the data before a run, resizing the per-bar vectors; all other settings are kept
+    pub fn set_data(&mut self, data: OhlcData) {
+        let n = data.close.len();
+        self.data = data;
+        self.equity = vec![self.cash; n];
+        self.intrabar_equity = vec![self.cash; n];
+        self.cash_flow_history = vec![0.0; n];
+        self.cash_flows_applied = 0;
+        self.data_offset = 0;
+        self.equity_peak = self.cash;
+    }
+
+    // position of an absolute bar index in `data`
+    fn local(&self, index: usize) -> usize {
+        index - self.data_offset
+    }
+
+    // absolute index of the last bar in `data`
+    fn last_index(&self) -> usize {
+        self.data_offset + self.data.close.len().saturating_sub(1)
+    }
+
+    // absolute index of the first bar still held in `data` (0 unless streaming)
+    pub fn first_index(&self) -> usize {
+        self.data_offset
+    }
+
+    // prices by absolute bar index; strategies meant to run on streamed data read prices through these
+    // (or relative to first_index) instead of copying `data` in init
+    pub fn close_at(&self, index: usize) -> f64 {
+        self.data.close[self.local(index)]
+    }
+
+    pub fn close2_at(&self, index: usize) -> f64 {
+        self.data.close2[self.local(index)]
+    }
+
+    /// append a streamed bar; once more than 2 * window bars are held the oldest are dropped, keeping
+    /// the last `window` (amortized, so appending stays O(1)). returns the bar's absolute index.
+    pub fn push_bar(&mut self, bar: Bar, window: usize) -> usize {
+        if self.data.close.len() >= 2 * window.max(1) {
+            let drop = self.data.close.len() - window.max(1);
+            self.data.date.drain(..drop);
+            self.data.open.drain(..drop);
+            self.data.high.drain(..drop);
+            self.data.low.drain(..drop);
+            self.data.close.drain(..drop);
+            self.data.close2.drain(..drop);
+            if let Some(volume) = self.data.volume.as_mut() {
+                volume.drain(..drop);
+            }
+            self.data.halts = rebase_halts(&self.data.halts, drop, drop + self.data.close.len());
+            self.data_offset += drop;
+        }
+        if self.data.close.is_empty() && self.data_offset == 0 {
+            self.data.volume = bar.volume.map(|_| Vec::new());
+        }
+        self.data.date.push(bar.date);
+        self.data.open.push(bar.open);
+        self.data.high.push(bar.high);
+        self.data.low.push(bar.low);
+        self.data.close.push(bar.close);
+        self.data.close2.push(bar.close2);
+        if let Some(volume) = self.data.volume.as_mut() {
+            volume.push(bar.volume.unwrap_or(0.0));
+        }
+        self.last_index()
+    }
+
+    // schedule deposits/withdrawals; they are applied in date order
+    pub fn set_cash_flows(&mut self, mut flows: Vec<CashFlow>) {
+        flows.sort_by(|a, b| compare_dates(&a.date, &b.date));
+        self.cash_flows = flows;
+        self.cash_flows_applied = 0;
+    }
+
+    // credit every scheduled flow that is due on this bar to cash, before any fills
+    fn apply_cash_flows(&mut self, index: usize) {
+        let mut amount = 0.0;
+        while let Some(flow) = self.cash_flows.get(self.cash_flows_applied) {
+            if compare_dates(&flow.date, &self.data.date[self.local(index)]) == Ordering::Greater {
+                break;
+            }
+            amount += flow.amount;
+            self.cash_flows_applied += 1;
+        }
+        if amount != 0.0 {
+            self.credit(amount);
+            if self.cash_flow_history.len() <= index {
+                self.cash_flow_history.resize(index + 1, 0.0);
+            }
+            self.cash_flow_history[index] += amount;
+        }
+    }
+
+    // place a new order and return its id; rejections are recorded in rejected_orders before being returned
+    pub fn new_order(&mut self, order: Order, current_price: f64) -> Result<u64, OrderError> {
+        self.order_attempts += 1;
+        let requested = (self.order_log.is_some() || self.event_log.is_some()).then(|| order.clone());
+        let result = self.submit_order(order, current_price);
+        if let Err(e) = result {
+            self.rejected_orders.push((self.current_index, e));
+        }
+        if let Some(mut order) = requested {
+            order.id = result.unwrap_or(0);
+            let result = result.map(|_| ());
+            self.record_submission(&order, result);
+            if let Some(log) = self.order_log.as_mut() {
+                log.push((self.current_index, order, result));
+            }
+        }
+        result
+    }
+
+    /// place the legs of a multi-leg entry (e.g. both sides of a pair) as one unit: every leg is validated
+    /// and queued, and the margin check uses the combined notional of all legs. if any leg is rejected,
+    /// none of them is queued and that leg's error is returned, so a pairs entry can no longer leave an
+    /// unhedged primary leg behind. each leg is (order, current price of its instrument); returns the ids
+    /// of the legs in the same order.
+    pub fn new_order_group(&mut self, legs: Vec<(Order, f64)>) -> Result<Vec<u64>, OrderError> {
+        let requested: Option<Vec<Order>> = (self.order_log.is_some() || self.event_log.is_some())
+            .then(|| legs.iter().map(|(order, _)| order.clone()).collect());
+        let result = self.submit_order_group(legs);
+        for (n, mut order) in requested.into_iter().flatten().enumerate() {
+            order.id = result.as_ref().map_or(0, |ids| ids[n]);
+            let result = result.as_ref().map(|_| ()).map_err(|e| *e);
+            self.record_submission(&order, result);
+            if let Some(log) = self.order_log.as_mut() {
+                log.push((self.current_index, order, result));
+            }
+        }
+        result
+    }
+
+    // add an event to the event log, if one is kept
+    fn record_event(&mut self, event: impl FnOnce() -> BacktestEvent) {
+        if let Some(log) = self.event_log.as_mut() {
+            log.push(event());
+        }
+    }
+
+    fn record_submission(&mut self, order: &Order, result: Result<(), OrderError>) {
+        let index = self.current_index;
+        self.record_event(|| match result {
+            Ok(()) => BacktestEvent::OrderSubmitted { index, order: order.clone() },
+            Err(error) => BacktestEvent::OrderRejected { index, order: order.clone(), error },
+        });
+    }
+
+    // book a closed trade (or the closed part of one)
+    fn push_closed(&mut self, trade: Trade) {
+        self.record_event(|| BacktestEvent::TradeClosed(trade.clone()));
+        self.closed_trades.push(trade);
+    }
+
+    fn submit_order_group(&mut self, legs: Vec<(Order, f64)>) -> Result<Vec<u64>, OrderError> {
+        self.order_attempts += legs.len();
+        // the book before an exclusive entry liquidates it, restored if any leg is rejected
+        let liquidated = if legs.iter().any(|(order, _)| order.parent_trade.is_none()) { self.close_for_exclusive_entry() } else { None };
+        let saved = liquidated.unwrap_or_else(|| self.book_snapshot());
+        let leg_count = legs.len();
+        let mut leg_notionals = Vec::with_capacity(legs.len());
+        let mut queued_entries = 0;
+        let mut held = 0;
+        let mut ids = Vec::with_capacity(leg_count);
+        for (order, current_price) in legs {
+            // earlier legs are queued before the next is checked, so beta-neutral sizing sees them as pending
+            let checked = self.check_order(order, current_price, false).and_then(|(order, notional)| {
+                leg_notionals.push(order.size.signum() * notional);
+                // buying power is checked once for all legs together
+                if leg_notionals.len() == leg_count && self.exceeds_buying_power(&leg_notionals) {
+                    return Err(OrderError::MarginExceeded);
+                }
+                Ok((order, notional))
+            });
+            match checked {
+                Ok((order, _)) => {
+                    let delayed_before = self.delayed_orders.len();
+                    let is_entry = order.parent_trade.is_none();
+                    ids.push(self.queue_order(order));
+                    if self.delayed_orders.len() > delayed_before {
+                        held += 1;
+                    } else if is_entry {
+                        queued_entries += 1;
+                    }
+                }
+                Err(e) => {
+                    self.restore_book(saved);
+                    self.rejected_orders.push((self.current_index, e));
+                    return Err(e);
+                }
+            }
+        }
+        // legacy exclusive orders keep only the legs of this group
+        if self.exclusive_orders && self.legacy_exclusive_orders {
+            self.orders = self.orders.split_off(self.orders.len() - queued_entries);
+            self.delayed_orders = self.delayed_orders.split_off(self.delayed_orders.len() - held);
+            self.trades.clear();
+        }
+        self.update_max_margin_usage();
+        self.update_margin_usage();
+        Ok(ids)
+    }
+
+    // validate an order and queue it
+    fn submit_order(&mut self, order: Order, current_price: f64) -> Result<u64, OrderError> {
+        let saved = if order.parent_trade.is_none() { self.close_for_exclusive_entry() } else { None };
+        let (order, _) = match self.check_order(order, current_price, true) {
+            Ok(checked) => checked,
+            Err(e) => {
+                // a rejected exclusive entry doesn't flatten the book
+                if let Some(saved) = saved {
+                    self.restore_book(saved);
+                }
+                return Err(e);
+            }
+        };
+        // legacy exclusive orders: drop everything, open trades included, without booking them
+        if self.exclusive_orders && self.legacy_exclusive_orders {
+            self.orders.clear();
+            self.delayed_orders.clear();
+            self.trades.clear();
+        }
+        let id = self.queue_order(order);
+
+        // update max margin usage stat
+        self.update_max_margin_usage();
+
+        // update margin usage history
+        self.update_margin_usage();
+
+        Ok(id)
+    }
+
+    // with exclusive_orders, a new entry closes the book at the current close and cancels pending orders
+    // before it is checked, so it is sized against the freed-up buying power. returns the book as it was
+    // before, for the caller to restore if the entry is then rejected.
+    fn close_for_exclusive_entry(&mut self) -> Option<BookSnapshot> {
+        if !self.exclusive_orders || self.legacy_exclusive_orders || (self.trades.is_empty() && self.orders.is_empty() && self.delayed_orders.is_empty()) {
+            return None;
+        }
+        let saved = self.book_snapshot();
+        let index = self.current_index.min(self.last_index());
+        self.liquidate(index);
+        Some(saved)
+    }
+
+    fn book_snapshot(&self) -> BookSnapshot {
+        BookSnapshot {
+            trades: self.trades.clone(),
+            orders: self.orders.clone(),
+            delayed_orders: self.delayed_orders.clone(),
+            cash: self.cash,
+            costs: self.costs.clone(),
+            closed_trades: self.closed_trades.len(),
+            fills: self.fills.len(),
+            cancelled_orders: self.cancelled_orders.len(),
+            events: self.event_log.as_ref().map_or(0, |log| log.len()),
+            next_order_id: self.next_order_id,
+            perturbation_rng: self.perturbation.as_ref().map(|p| p.rng.clone()),
+        }
+    }
+
+    // undo everything since the snapshot: closes and fills are dropped, cancelled orders requeued, order ids
+    // and perturbation draws given back
+    fn restore_book(&mut self, saved: BookSnapshot) {
+        self.trades = saved.trades;
+        self.orders = saved.orders;
+        self.delayed_orders = saved.delayed_orders;
+        self.cash = saved.cash;
+        self.costs = saved.costs;
+        self.closed_trades.truncate(saved.closed_trades);
+        self.fills.truncate(saved.fills);
+        self.cancelled_orders.truncate(saved.cancelled_orders);
+        if let Some(log) = self.event_log.as_mut() {
+            log.truncate(saved.events);
+        }
+        self.next_order_id = saved.next_order_id;
+        if let (Some(p), Some(rng)) = (self.perturbation.as_mut(), saved.perturbation_rng) {
+            p.rng = rng;
+        }
+    }
+
+    // size an order (scaling, hedge ratio, beta neutrality, then lot rounding) and check it against buying power
+    // (unless the caller checks it for several orders together), exposure caps, var budget and trade limits.
+    // returns the adjusted order and its notional.
+    fn check_order(&self, mut order: Order, current_price: f64, check_margin: bool) -> Result<(Order, f64), OrderError> {
+        if self.halt_policy == HaltPolicy::Reject && self.halted(self.current_index, order.instrument, Self::is_buy(&order)) {
+            return Err(OrderError::TradingHalted);
+        }
+        if order.parent_trade.is_none() && self.in_cooldown(self.current_index, order.instrument, order.size > 0.0) {
+            return Err(OrderError::InCooldown);
+        }
+        // the price the strategy decided at is the reference for the fill's slippage; for an if-touched
+        // order that is its level
+        order.signal_price.get_or_insert(order.touch.unwrap_or(current_price));
+        // margin-based scaling replaces the requested size of entries before rounding
+        let scaled = order.parent_trade.is_none() && !order.exact_size;
+        if self.scaling_enabled && scaled && self.scaling_mode != ScalingMode::EquityRatio {
+            order.size = self.margin_scaled_size(&order, current_price);
+            // no buying power left for the target
+            if order.size == 0.0 {
+                return Err(OrderError::MarginExceeded);
+            }
+        }
+        // the drawdown governor shrinks entries before rounding, so they still fit the lot step
+        if scaled {
+            order.size *= self.size_multiplier;
+        }
         // if scaling is enabled, adjust order size
-        if self.scaling_enabled {
+        if self.scaling_enabled && scaled && self.scaling_mode == ScalingMode::EquityRatio {
             order.size = self.scale_order_size(order.size);
         }
         
         // adjust order size for hedge instrument (instrument 2) dynamically based on price ratio:
-        // factor = (current primary price) / (current hedge price)
-        if order.instrument == 2 {
-            let last_tick = self.equity.len().saturating_sub(1);
-            let primary_price = self.data.close[last_tick];
-            let hedge_price = self.data.close2[last_tick];
+        // factor = (current primary price) / (current hedge price). exits are already in the trade's units
+        if order.instrument == 2 && order.parent_trade.is_none() {
+            // prices of the bar being processed (the equity vector is preallocated, so not its last entry)
+            let tick = self.current_index.min(self.last_index());
+            let primary_price = self.data.close[self.local(tick)];
+            let hedge_price = self.data.close2[self.local(tick)];
             let factor = primary_price / hedge_price;
             order.size *= factor;
         }
+        if scaled {
+            order.size = self.beta_neutral_size(&order);
+        }
+        // round the final size to the instrument's lot step (whole units without leverage by default), after
+        // every adjustment, so the queued order is always a whole number of lots
+        order.size = self.instrument_spec(order.instrument).round_size(order.size).map_err(|e| match e {
+            SizeError::NotWholeLot => OrderError::FractionalOrderNotAllowed,
+            SizeError::BelowMinimum => OrderError::SizeBelowMinimum,
+        })?;
         
         // calculate order notional using current price
-        let order_notional = order.size.abs() * current_price;
-        let available = self.available_buying_power();
+        let order_notional = self.instrument_spec(order.instrument).notional(order.size, current_price);
 
-        // if order exceeds available buying power, return error
-        if order_notional > available {
+        // if order exceeds available buying power, return error; exits only reduce exposure
+        if check_margin && order.parent_trade.is_none() && self.exceeds_buying_power(&[order.size.signum() * order_notional]) {
             return Err(OrderError::MarginExceeded);
         }
         
+        // enforce notional caps on new (non-contingent) orders
+        if order.parent_trade.is_none() && self.exceeds_exposure_caps(order.instrument, order_notional) {
+            return Err(OrderError::ExposureCapExceeded);
+        }
+
+        // enforce the var budget on new (non-contingent) orders
+        if order.parent_trade.is_none() && self.exceeds_var_budget(order.instrument, order.size.signum() * order_notional) {
+            return Err(OrderError::VarBudgetExceeded);
+        }
+
         // enforce trade limit on new (non-contingent) orders; allow max 3 per side
         if order.parent_trade.is_none() {
             if order.size > 0.0 {
@@ -233,267 +1418,565 @@ impl Broker {
                 }
             }
         }
-        // clear orders if exclusive orders are enabled
-        if self.exclusive_orders {
-            self.orders.clear();
-            self.trades.clear();
+        Ok((order, order_notional))
+    }
+
+    // true if new trades with these signed notionals would add more margined exposure than the available
+    // buying power; under MarginMode::Netted an order that offsets the book adds less than its notional
+    fn exceeds_buying_power(&self, signed_notionals: &[f64]) -> bool {
+        let (long, short) = self.trades.iter()
+            .map(|trade| trade.size * trade.entry_price)
+            .chain(signed_notionals.iter().copied())
+            .fold((0.0, 0.0), |(long, short), notional| {
+                if notional > 0.0 { (long + notional, short) } else { (long, short - notional) }
+            });
+        let added = self.margin_mode.margined_notional(long, short) - self.current_exposure();
+        added > self.available_buying_power()
+    }
+
+    // give an order the next id
+    fn assign_id(&mut self, order: &mut Order) -> u64 {
+        order.id = self.next_order_id;
+        self.next_order_id += 1;
+        order.id
+    }
+
+    // append an order created by the broker (contingent exits) to the queue under a new id
+    fn push_order(&mut self, mut order: Order) {
+        self.assign_id(&mut order);
+        self.orders.push(order);
+    }
+
+    // put a checked order in the queue under a new id: contingent orders first, delayed market entries held
+    // back. returns the id.
+    fn queue_order(&mut self, mut order: Order) -> u64 {
+        let id = self.assign_id(&mut order);
+        let is_market_entry = order.parent_trade.is_none() && order.limit.is_none() && order.stop.is_none() && order.touch.is_none();
+        // jitter the entry timing if a perturbation is active; never earlier than the next bar
+        let mut delay = self.execution_delay;
+        if is_market_entry {
+            if let Some(p) = self.perturbation.as_mut() {
+                let jitter = p.timing_jitter as i64;
+                delay = (delay as i64 + p.rng.range_i64(-jitter, jitter)).max(1) as usize;
+            }
         }
         if order.parent_trade.is_some() {
             self.orders.insert(0, order);
+        } else if is_market_entry && delay > 1 {
+            // hold the order back; it joins the queue on bar current_index + delay and fills on that bar
+            let release = self.current_index + delay;
+            self.delayed_orders.push((release, order));
         } else {
             self.orders.push(order);
         }
+        id
+    }
+    
 
-        // update max margin usage stat
-        self.update_max_margin_usage();
+    // take the contingent orders of a closed trade out of `orders` and return them
+    fn drop_contingent(orders: &mut Vec<Order>, trade_id: u64) -> Vec<Order> {
+        let (dropped, kept) = std::mem::take(orders).into_iter().partition(|order| order.parent_trade == Some(trade_id));
+        *orders = kept;
+        dropped
+    }
 
-        // update margin usage history
-        self.update_margin_usage();
+    // remove an open trade together with its remaining contingent orders
+    fn remove_trade(&mut self, trade_index: usize) -> Trade {
+        let trade = self.trades.remove(trade_index);
+        let cancelled = Self::drop_contingent(&mut self.orders, trade.id);
+        self.record_cancelled(cancelled);
+        trade
+    }
 
-        Ok(())
+    // log orders cancelled by the broker on the current bar
+    fn record_cancelled(&mut self, orders: Vec<Order>) {
+        for order in orders {
+            self.cancel_at(self.current_index, order);
+        }
+    }
+
+    // book a cancelled order in cancelled_orders and the event log
+    fn cancel_at(&mut self, index: usize, order: Order) {
+        self.record_event(|| BacktestEvent::OrderCancelled { index, order: order.clone() });
+        self.cancelled_orders.push((index, order));
+    }
+
+    /// the stop loss and take-profit orders still waiting for an open trade
+    pub fn contingent_orders(&self, trade_index: usize) -> Vec<&Order> {
+        let Some(trade) = self.trades.get(trade_index) else {
+            return Vec::new();
+        };
+        self.orders.iter().filter(|o| o.parent_trade == Some(trade.id)).collect()
+    }
+
+    /// current stop level of an open trade (its stop loss, trailed if it is a trailing stop)
+    pub fn stop_level(&self, trade_index: usize) -> Option<f64> {
+        let trade = self.trades.get(trade_index)?;
+        self.orders.iter()
+            .filter(|o| o.parent_trade == Some(trade.id))
+            .find_map(|o| o.stop)
+    }
+
+    /// contingent orders whose parent id is not an open trade; always empty unless the trade list was
+    /// changed behind the broker's back
+    pub fn orphaned_orders(&self) -> Vec<&Order> {
+        self.orders.iter()
+            .filter(|o| o.parent_trade.map_or(false, |parent| self.trade(parent).is_none()))
+            .collect()
+    }
+
+    // close part of an open trade at the given tick's close; closing the full size closes the trade
+    pub fn close_partial(&mut self, trade_index: usize, size: f64, tick_index: usize) {
+        if trade_index >= self.trades.len() {
+            return;
+        }
+        let instrument = self.trades[trade_index].instrument;
+        let close = if instrument == 1 {
+            self.data.close[self.local(tick_index)]
+        } else {
+            self.data.close2[self.local(tick_index)]
+        };
+        // closing a long sells at the bid, closing a short buys at the ask
+        let raw_exit_price = self.quote_price(instrument, close, self.trades[trade_index].size < 0.0);
+        let exit_price = self.adjusted_price(-self.trades[trade_index].size, raw_exit_price);
+        let commission = self.charge_commission(size, raw_exit_price, self.commission);
+        self.reduce_trade(trade_index, size, exit_price, tick_index, None, commission);
+        self.record_fill(tick_index, -size, raw_exit_price, exit_price, close, instrument);
+    }
+
+    // book a (partial) exit of `size` (same sign as the trade) with the exit fill's commission and return
+    // true if the trade is now closed
+    fn reduce_trade(&mut self, trade_index: usize, size: f64, exit_price: f64, tick_index: usize, exit_tag: Option<String>, commission: f64) -> bool {
+        let remaining = self.trades[trade_index].size.abs() - size.abs();
+        if remaining <= 1e-9 {
+            let trade = self.remove_trade(trade_index);
+            let closed_trade = Trade {
+                exit_price: Some(exit_price),
+                exit_index: Some(tick_index),
+                exit_tag,
+                commission: trade.commission + commission,
+                ..trade
+            };
+            self.credit(closed_trade.pnl());
+            self.push_closed(closed_trade);
+            true
+        } else {
+            let trade = &mut self.trades[trade_index];
+            let closed_size = size.abs() * trade.size.signum();
+            let entry_commission = trade.commission * closed_size / trade.size;
+            trade.size -= closed_size;
+            trade.commission -= entry_commission;
+            let closed_trade = Trade {
+                size: closed_size,
+                exit_price: Some(exit_price),
+                exit_index: Some(tick_index),
+                exit_tag,
+                commission: entry_commission + commission,
+                ..trade.clone()
+            };
+            self.credit(closed_trade.pnl());
+            self.push_closed(closed_trade);
+            false
+        }
     }
-    
 
     // updated close_position method with separate trade_index and tick_index parameters
     pub fn close_position(&mut self, trade_index: usize, tick_index: usize) {
+        self.close_position_tagged(trade_index, tick_index, None);
+    }
+
+    // close_position with the exit tag of the closed trade, e.g. "time exit"
+    pub fn close_position_tagged(&mut self, trade_index: usize, tick_index: usize, exit_tag: Option<String>) {
         // check if the specified trade index is valid
         if trade_index < self.trades.len() {
-            let trade = self.trades.remove(trade_index);
+            let trade = self.remove_trade(trade_index);
             // create a closed trade using the market price from the specified tick_index
-            let raw_exit_price = if trade.instrument == 1 {
-                self.data.close[tick_index]
+            let close = if trade.instrument == 1 {
+                self.data.close[self.local(tick_index)]
             } else {
-                self.data.close2[tick_index]
+                self.data.close2[self.local(tick_index)]
             };
+            let raw_exit_price = self.quote_price(trade.instrument, close, trade.size < 0.0);
+            let exit_price = self.adjusted_price(-trade.size, raw_exit_price);
+            self.record_fill(tick_index, -trade.size, raw_exit_price, exit_price, close, trade.instrument);
+            let commission = self.charge_commission(trade.size, raw_exit_price, self.commission);
             let closed_trade = Trade {
                 size: trade.size,
                 entry_price: trade.entry_price,
                 entry_index: trade.entry_index,
-                exit_price: Some(self.adjusted_price(trade.size, raw_exit_price)),
+                exit_price: Some(exit_price),
                 exit_index: Some(tick_index),
                 sl_order: trade.sl_order,
                 tp_order: trade.tp_order,
                 instrument: trade.instrument,
+                tag: trade.tag,
+                exit_tag,
+                max_holding_bars: trade.max_holding_bars,
+                commission: trade.commission + commission,
+                id: trade.id,
             };
             // update the broker's cash balance with the profit or loss from the closed trade
-            self.cash += closed_trade.pnl();
+            self.credit(closed_trade.pnl());
             // push the closed trade into the closed_trades vector
-            self.closed_trades.push(closed_trade);
+            self.push_closed(closed_trade);
         }
     }
 
     // Revised method for closing all trades, using separate tick indices per instrument.
     // tick1 is used for instrument 1 and tick2 for instrument 2.
     pub fn close_all_trades(&mut self, tick1: usize, tick2: usize) {
-        // Extract local references to avoid borrow conflicts.
-        let close_prices = &self.data.close;
-        let close2_prices = &self.data.close2;
         let commission = self.commission;
-        let bidask_spread = self.bidask_spread;
-        let adjusted_price = |size: f64, price: f64| -> f64 {
-            let price_with_commission = price * (1.0 + size.signum() * commission);
-            if bidask_spread > 0.0 {
-                if size > 0.0 {
-                    price_with_commission + bidask_spread
-                } else if size < 0.0 {
-                    price_with_commission - bidask_spread
+        let first_closed = self.closed_trades.len();
+        // (tick, size, raw price, exit price, close, instrument)
+        let mut fills: Vec<(usize, f64, f64, f64, f64, u8)> = Vec::new();
+
+        // Partition trades by instrument; instrument 1 trades close on tick1, instrument 2 trades on tick2.
+        let (trades_inst1, trades_inst2): (Vec<Trade>, Vec<Trade>) =
+            self.trades.drain(..).partition(|trade| trade.instrument == 1);
+
+        for mut trade in trades_inst1.into_iter().chain(trades_inst2) {
+            let (tick, close) = if trade.instrument == 1 {
+                (tick1, self.data.close[self.local(tick1)])
+            } else {
+                (tick2, self.data.close2[self.local(tick2)])
+            };
+            // liquidate at the bid (longs) or ask (shorts), priced and booked like close_position so the
+            // decimal feature keeps both exact
+            let raw_exit_price = self.quote_price(trade.instrument, close, trade.size < 0.0);
+            let exit_price = self.adjusted_price(-trade.size, raw_exit_price);
+            fills.push((tick, -trade.size, raw_exit_price, exit_price, close, trade.instrument));
+            trade.commission += trade.size.abs() * raw_exit_price * commission;
+            trade.exit_price = Some(exit_price);
+            trade.exit_index = Some(tick);
+            self.credit(trade.pnl());
+            self.closed_trades.push(trade);
+        }
+
+        // Record turnover and costs of the liquidation fills.
+        for (tick, size, price, exit_price, close, instrument) in fills {
+            self.charge_commission(size, price, commission);
+            self.record_fill(tick, size, price, exit_price, close, instrument);
+        }
+        if let Some(log) = self.event_log.as_mut() {
+            log.extend(self.closed_trades[first_closed..].iter().cloned().map(BacktestEvent::TradeClosed));
+        }
+
+        // Cancel the stop losses and take profits of the closed trades; pending entries stay queued.
+        let (cancelled, entries): (Vec<Order>, Vec<Order>) =
+            self.orders.drain(..).partition(|order| order.parent_trade.is_some());
+        self.orders = entries;
+        self.record_cancelled(cancelled);
+    }
+    
+    // true if a queued order fills on a bar with this high/low; a hit stop turns the order into a
+    // market (or limit) order by clearing its stop, also when its limit is not reached on this bar
+    fn order_triggered(order: &mut Order, high: f64, low: f64) -> bool {
+        // if-touched entries wait for their level: buys for a dip to it, sells for a rally to it
+        if let (Some(touch), None) = (order.touch, order.parent_trade) {
+            let touched = if order.size > 0.0 { low <= touch } else { high >= touch };
+            if !touched {
+                return false;
+            }
+            // a touched lit order is a plain limit order from here on; mit keeps the level for its fill price
+            if order.limit.is_some() {
+                order.touch = None;
+            }
+        }
+        // check stop order condition
+        if let Some(stop_price) = order.stop {
+            let is_stop_hit = if order.parent_trade.is_some() {
+                // contingent stop loss order for an open trade:
+                // for a long trade, trigger if current low is below (or equal) to the stop loss price;
+                // for a short trade, trigger if current high is above (or equal) to the stop loss price
+                if order.size > 0.0 {
+                    low <= stop_price
+                } else {
+                    high >= stop_price
+                }
+            } else {
+                // non-contingent stop entry order:
+                // for a long stop entry, trigger when high reaches or exceeds the stop price;
+                // for a short, when low reaches or falls below the stop price.
+                if order.size > 0.0 {
+                    high >= stop_price
+                } else {
+                    low <= stop_price
+                }
+            };
+            if !is_stop_hit {
+                return false;
+            }
+            // on stop, remove the stop price to treat as market order
+            order.stop = None;
+        }
+        // if limit is set, verify limit condition
+        match order.limit {
+            Some(limit_price) => {
+                if order.parent_trade.is_some() {
+                    // contingent take profit: it sells out of a long when the high reaches the limit,
+                    // and buys back a short when the low reaches it
+                    if order.size > 0.0 {
+                        high >= limit_price
+                    } else {
+                        low <= limit_price
+                    }
+                } else if order.size > 0.0 {
+                    low < limit_price
                 } else {
-                    price_with_commission
+                    high > limit_price
                 }
-            } else {
-                price_with_commission
             }
-        };
-
-        let mut total_pnl = 0.0;
+            // market order: execute immediately using prev_close if trade_on_close, else open price
+            None => true,
+        }
+    }
 
-        // Partition trades by instrument.
-        let (mut trades_inst1, mut trades_inst2): (Vec<Trade>, Vec<Trade>) =
-            self.trades.drain(..).partition(|trade| trade.instrument == 1);
+    // whether a buy (or sell) of `instrument` can't fill on bar `index` (absolute)
+    pub fn halted(&self, index: usize, instrument: u8, buy: bool) -> bool {
+        index >= self.data_offset && self.data.is_halted(self.local(index), instrument, buy)
+    }
 
-        // Process instrument 1 trades.
-        for mut trade in trades_inst1.drain(..) {
-            let raw_exit_price = close_prices[tick1];
-            let exit_price = adjusted_price(trade.size, raw_exit_price);
-            trade.exit_price = Some(exit_price);
-            trade.exit_index = Some(tick1);
-            total_pnl += if trade.size > 0.0 {
-                (exit_price - trade.entry_price) * trade.size
-            } else {
-                (trade.entry_price - exit_price) * (-trade.size)
-            };
-            self.closed_trades.push(trade);
+    // whether entries on this side of `instrument` are still blocked on bar `index` by a stop-loss exit
+    pub fn in_cooldown(&self, index: usize, instrument: u8, long: bool) -> bool {
+        match (self.stop_loss_cooldown, self.stop_outs.get(&(instrument, long))) {
+            (Some(bars), Some(&stopped)) => index >= stopped && index <= stopped + bars,
+            _ => false,
         }
+    }
 
-        // Process instrument 2 trades.
-        for mut trade in trades_inst2.drain(..) {
-            let close2 = close2_prices[tick2];
-            let exit_price = adjusted_price(trade.size, close2);
-            trade.exit_price = Some(exit_price);
-            trade.exit_index = Some(tick2);
-            total_pnl += if trade.size > 0.0 {
-                (exit_price - trade.entry_price) * trade.size
-            } else {
-                (trade.entry_price - exit_price) * (-trade.size)
-            };
-            self.closed_trades.push(trade);
-        }
+    // whether bar `index` is the first tradeable bar after a halt on this side
+    fn reopens(&self, index: usize, instrument: u8, buy: bool) -> bool {
+        index > 0 && self.halted(index - 1, instrument, buy) && !self.halted(index, instrument, buy)
+    }
 
-        // Update cash balance.
-        self.cash += total_pnl;
+    // a contingent take profit of an instrument 1 trade whose level the bar's open already passed
+    fn opened_through_take_profit(order: &Order, open: f64) -> bool {
+        match (order.parent_trade, order.limit) {
+            (Some(_), Some(limit)) if order.instrument == 1 => {
+                if order.size > 0.0 { open >= limit } else { open <= limit }
+            }
+            _ => false,
+        }
+    }
 
-        // Cancel any pending orders.
-        self.orders.clear();
+    // whether an order buys when it fills; contingent orders carry their trade's sign, so closing a long sells
+    fn is_buy(order: &Order) -> bool {
+        if order.parent_trade.is_some() { order.size < 0.0 } else { order.size > 0.0 }
     }
-    
+
     // process orders at a given tick index based on current market prices
     pub fn process_orders(&mut self, index: usize) {
-        let open_price = self.data.open[index];
-        let high = self.data.high[index];
-        let low = self.data.low[index];
-        let prev_close = if index > 0 { self.data.close[index - 1] } else { open_price };
+        let open_price = self.data.open[self.local(index)];
+        let high = self.data.high[self.local(index)];
+        let low = self.data.low[self.local(index)];
+        let prev_close = if self.local(index) > 0 { self.data.close[self.local(index) - 1] } else { open_price };
 
         // for the hedge instrument we assume price is taken from 'Close2'
-        let hedge_price = self.data.close2[index];
-        let prev_hedge = if index > 0 { self.data.close2[index - 1] } else { hedge_price };
+        let hedge_price = self.data.close2[self.local(index)];
+        let prev_hedge = if self.local(index) > 0 { self.data.close2[self.local(index) - 1] } else { hedge_price };
 
-        let mut executed_order_indices: Vec<usize> = Vec::new();
         let reprocess_orders = false;
-        
-        // check each order in the queue
-        for (i, order) in self.orders.iter_mut().enumerate() {
-            // check stop order condition
-            if let Some(stop_price) = order.stop {
-                let is_stop_hit = if order.parent_trade.is_some() {
-                    // contingent stop loss order for an open trade:
-                    // for a long trade, trigger if current low is below (or equal) to the stop loss price;
-                    // for a short trade, trigger if current high is above (or equal) to the stop loss price
-                    if order.size > 0.0 {
-                        low <= stop_price
-                    } else {
-                        high >= stop_price
-                    }
-                } else {
-                    // non-contingent stop entry order:
-                    // for a long stop entry, trigger when high reaches or exceeds the stop price;
-                    // for a short, when low reaches or falls below the stop price.
-                    if order.size > 0.0 {
-                        high >= stop_price
-                    } else {
-                        low <= stop_price
-                    }
-                };
-                if is_stop_hit {
-                    // on stop, remove the stop price to treat as market order
-                    order.stop = None;
-                } else {
-                    continue;
-                }
+
+        // split the queue into triggered and waiting orders without cloning; both buffers are kept
+        // on the broker between bars so a steady-state run does not allocate here
+        let mut queue = std::mem::take(&mut self.order_buffer);
+        std::mem::swap(&mut queue, &mut self.orders);
+        let mut orders_to_execute = std::mem::take(&mut self.execution_buffer);
+        for mut order in queue.drain(..) {
+            // nothing fills inside a halt; the order waits, stops and limits included
+            if self.halted(index, order.instrument, Self::is_buy(&order)) {
+                self.orders.push(order);
+                continue;
             }
-            // if limit is set, verify limit condition
-            if let Some(limit_price) = order.limit {
-                let is_limit_hit = if order.size > 0.0 {
-                    low < limit_price
-                } else {
-                    high > limit_price
-                };
-                if is_limit_hit {
-                    executed_order_indices.push(i);
-                } else {
-                    continue;
-                }
+            // with a quote model, stops and limits are compared against the ask for buys and the bid for sells
+            let half = self.half_spread(order.instrument);
+            let shift = if Self::is_buy(&order) { half } else { -half };
+            // instrument 2 has no intrabar range in OhlcData: its stops and limits are checked against close2
+            let (bar_high, bar_low) = if order.instrument == 1 { (high, low) } else { (hedge_price, hedge_price) };
+            if Self::order_triggered(&mut order, bar_high + shift, bar_low + shift) {
+                orders_to_execute.push(order);
             } else {
-                // market order: execute immediately using prev_close if trade_on_close, else open price
-                executed_order_indices.push(i);
+                self.orders.push(order);
             }
         }
+        self.order_buffer = queue;
+        // when a stop loss and a take profit of a trade are both inside the bar, ohlc doesn't say which came
+        // first: the stop loss (queued first) wins, unless the bar opened through the take profit
+        orders_to_execute.sort_by_key(|order| !Self::opened_through_take_profit(order, open_price));
         
-        // clone orders to execute then remove them from order queue (process in descending order to avoid index issues)
-        let orders_to_execute: Vec<Order> = executed_order_indices.iter().map(|&i| self.orders[i].clone()).collect();
-        executed_order_indices.sort_unstable_by(|a, b| b.cmp(a));
-        for i in executed_order_indices {
-            self.orders.remove(i);
-        }
-        
-        // execute each selected order
-        for order in orders_to_execute.iter() {
+        // execute each selected order; closing a trade re-points the contingent orders still waiting here
+        orders_to_execute.reverse();
+        while let Some(mut order) = orders_to_execute.pop() {
+            let on_close = match order.timing {
+                Some(timing) => timing == FillTiming::OnClose,
+                None => self.trade_on_close,
+            };
             let exec_price = if let Some(limit_price) = order.limit {
                 limit_price
             } else {
                 if order.instrument == 1 {
-                    if self.trade_on_close { prev_close } else { open_price }
+                    if on_close { prev_close } else { open_price }
                 } else {
-                    if self.trade_on_close { prev_hedge } else { hedge_price }
+                    if on_close { prev_hedge } else { hedge_price }
+                }
+            };
+            // market-if-touched fills at its level, or at the open if the bar gapped through it
+            let exec_price = match (order.touch.take(), order.parent_trade) {
+                (Some(touch), None) if order.limit.is_none() => {
+                    if order.size > 0.0 { exec_price.min(touch) } else { exec_price.max(touch) }
                 }
+                _ => exec_price,
             };
-            let adjusted_price = self.adjusted_price(order.size, exec_price);
+            // what piled up during a halt trades at the reopening price: market orders and stops at the open,
+            // limits at the open when it gapped through them
+            let exec_price = if self.gap_fills_after_halt && self.reopens(index, order.instrument, Self::is_buy(&order)) {
+                let reopen = if order.instrument == 1 { open_price } else { hedge_price };
+                match order.limit {
+                    Some(limit) if Self::is_buy(&order) => limit.min(reopen),
+                    Some(limit) => limit.max(reopen),
+                    None => reopen,
+                }
+            } else {
+                exec_price
+            };
+            // market fills cross the synthetic spread; limit fills stay at the limit
+            let exec_price = if order.limit.is_none() {
+                self.quote_price(order.instrument, exec_price, Self::is_buy(&order))
+            } else {
+                exec_price
+            };
+            // shift the fill by a random number of ticks if a perturbation is active
+            let exec_price = match self.perturbation.as_mut() {
+                Some(p) => {
+                    let ticks = p.price_jitter_ticks as i64;
+                    exec_price + p.rng.range_i64(-ticks, ticks) as f64 * p.tick_size
+                }
+                None => exec_price,
+            };
+            let commission_rate = self.commission_rate(order.commission);
+            // priced on the side of the fill: an exit of a long sells
+            let fill_side = if order.parent_trade.is_some() { -order.size } else { order.size };
+            let adjusted_price = self.adjusted_price_at(fill_side, exec_price, commission_rate);
             
-            if let Some(parent_idx) = order.parent_trade {
-                // this is a contingent order (sl/tp)
-                if parent_idx < self.trades.len() {
-                    let trade = self.trades.remove(parent_idx);
-                    let closed_trade = Trade {
-                        size: trade.size,
-                        entry_price: trade.entry_price,
-                        entry_index: trade.entry_index,
-                        exit_price: Some(adjusted_price),
-                        exit_index: Some(index),
-                        sl_order: trade.sl_order,
-                        tp_order: trade.tp_order,
-                        instrument: trade.instrument,
-                    };
-                    // Update cash balance when closing trade 
-                    // doesnt work for some reason
-                    //oh wait i know
-                    //no wait it should work
-                    self.cash += closed_trade.pnl();
-                    self.closed_trades.push(closed_trade);
+            if let Some(parent_id) = order.parent_trade {
+                // this is a contingent order (sl/tp); ladder rungs close only part of the trade
+                if let Some(parent_idx) = self.trade_index(parent_id) {
+                    let size = order.size.abs().min(self.trades[parent_idx].size.abs()) * order.size.signum();
+                    let reference_price = order.signal_price.unwrap_or(exec_price);
+                    self.record_fill(index, -size, exec_price, adjusted_price, reference_price, order.instrument);
+                    let commission = self.charge_commission(size, exec_price, commission_rate);
+                    if order.tag.as_deref() == Some("stop-loss") {
+                        self.stop_outs.insert((order.instrument, order.size > 0.0), index);
+                    }
+                    let closed = self.reduce_trade(parent_idx, size, adjusted_price, index, order.tag.take(), commission);
+                    if closed {
+                        // the trade's other exits triggering on this bar are cancelled with it
+                        let cancelled = Self::drop_contingent(&mut orders_to_execute, parent_id);
+                        self.record_cancelled(cancelled);
+                    }
                     //println!("closed trade: {}", adjusted_price);
                 }
             } else {
                 // stand-alone order: open a new trade
+                let reference_price = order.signal_price.unwrap_or(exec_price);
+                self.record_fill(index, order.size, exec_price, adjusted_price, reference_price, order.instrument);
+                let commission = self.charge_commission(order.size, exec_price, commission_rate);
                 let trade = Trade {
                     size: order.size,
                     entry_price: adjusted_price,
                     entry_index: index,
-                    exit_price: None,
-                    exit_index: None,
-                    sl_order: None,
-                    tp_order: None,
                     instrument: order.instrument,
+                    tag: order.tag.take(),
+                    max_holding_bars: order.max_holding_bars,
+                    commission,
+                    id: order.id,
+                    ..Default::default()
                 };
                 self.trades.push(trade);
                 //println!("open trade: {}", adjusted_price);
 
                 // if a stop loss price is provided (in the 'sl' field),
                 // create a contingent stop loss order to ensure losses are capped
-                if let Some(sl_value) = order.sl {
-                    let trade_idx = self.trades.len() - 1; // index of the newly opened trade
+                // a trailing stop starts at its distance from the fill, or at `sl` if that is tighter
+                let long = order.size > 0.0;
+                let stop_loss = match (order.sl, order.trailing) {
+                    (Some(sl), Some(trailing)) => Some(trailing.trail(sl, exec_price, long)),
+                    (None, Some(trailing)) => Some(trailing.level(exec_price, long)),
+                    (sl, None) => sl,
+                };
+                if let Some(sl_value) = stop_loss {
                     let contingent_order = Order {
                         size: order.size, // same sign as the original trade
-                        limit: None,
                         // store the stop loss price in the 'stop' field for proper triggering
                         stop: Some(sl_value),
-                        sl: None,
-                        tp: order.tp, // pass through take profit if specified
-                        parent_trade: Some(trade_idx),
+                        parent_trade: Some(order.id), // the id of the newly opened trade
                         instrument: order.instrument,
+                        tag: Some("stop-loss".to_string()),
+                        signal_price: Some(sl_value),
+                        trailing: order.trailing,
+                        ..Default::default()
                     };
-                    self.orders.push(contingent_order);
+                    self.push_order(contingent_order);
+                }
+
+                // the take profit is a contingent limit order for the whole trade, queued after the stop loss
+                if let Some(tp_value) = order.tp {
+                    self.push_order(Order {
+                        size: order.size,
+                        limit: Some(tp_value),
+                        parent_trade: Some(order.id),
+                        instrument: order.instrument,
+                        tag: Some("take-profit".to_string()),
+                        signal_price: Some(tp_value),
+                        ..Default::default()
+                    });
+                }
+
+                // one contingent limit order per take-profit rung, sized off the original trade
+                let rungs = order.tp_ladder.len();
+                for (n, level) in order.tp_ladder.iter().enumerate() {
+                    self.push_order(Order {
+                        size: order.size * level.fraction,
+                        limit: Some(level.price),
+                        parent_trade: Some(order.id),
+                        instrument: order.instrument,
+                        tag: Some(format!("take-profit {}/{}", n + 1, rungs)),
+                        signal_price: Some(level.price),
+                        ..Default::default()
+                    });
                 }
             }
         }
         
+        self.execution_buffer = orders_to_execute;
+
         // if necessary, reprocess orders (for sl/tp orders that might execute in the same tick)
         if reprocess_orders {
             self.process_orders(index);
         }
+
+        // trailing stops follow this bar's best price once its stops were checked (including the reprocess
+        // above), so a stop moved here can trigger from the next bar on
+        for order in self.orders.iter_mut() {
+            if let (Some(trailing), Some(stop), Some(_)) = (order.trailing, order.stop, order.parent_trade) {
+                let long = order.size > 0.0;
+                let best = match (order.instrument, long) {
+                    (1, true) => high,
+                    (1, false) => low,
+                    _ => hedge_price,
+                };
+                let trailed = trailing.trail(stop, best, long);
+                order.stop = Some(trailed);
+                order.signal_price = Some(trailed);
+            }
+        }
     }
     
     // update equity at a given tick index; equity = cash + sum(pnl of open trades)
     pub fn update_equity(&mut self, index: usize) {
-        let current_close = self.data.close[index];
+        // every trade is marked at the close of its own instrument (close2 for hedge legs)
         let pnl_sum: f64 = self.trades.iter().map(|trade| {
+            let current_close = self.instrument_price(trade.instrument, index);
             if trade.size > 0.0 {
                 (current_close - trade.entry_price) * trade.size
             } else {
@@ -507,43 +1990,173 @@ impl Broker {
             self.equity.push(equity_value);
         }
     }
+
+    // equity with every instrument 1 trade marked at the adverse extreme of the bar: longs at the low,
+    // shorts at the high. hedge legs have no intrabar range in OhlcData and are marked at close2.
+    // all extremes are assumed to happen at once, so this is a lower bound for a hedged book.
+    pub fn intrabar_worst_equity(&self, index: usize) -> f64 {
+        let i = self.local(index);
+        let pnl_sum: f64 = self.trades.iter().map(|trade| {
+            let worst = if trade.instrument != 1 {
+                self.data.close2[i]
+            } else if trade.size > 0.0 {
+                self.data.low[i]
+            } else {
+                self.data.high[i]
+            };
+            (worst - trade.entry_price) * trade.size
+        }).sum();
+        (self.cash + pnl_sum).min(self.equity[index])
+    }
+
+    // record the worst-case equity of a tick; call after update_equity
+    pub fn update_intrabar_equity(&mut self, index: usize) {
+        let worst = self.intrabar_worst_equity(index);
+        if index < self.intrabar_equity.len() {
+            self.intrabar_equity[index] = worst;
+        } else {
+            self.intrabar_equity.push(worst);
+        }
+    }
+
+    // margin usage with equity at the bar's worst case instead of cash, so a position that was under water
+    // within the bar is caught even if it recovered by the close. infinite if the bar wiped out the account.
+    pub fn intrabar_margin_usage(&self, index: usize) -> f64 {
+        if (self.margin - 1.0).abs() < std::f64::EPSILON || self.trades.is_empty() {
+            return 0.0;
+        }
+        let worst = self.intrabar_equity.get(index).copied().unwrap_or(self.cash);
+        if worst <= 0.0 {
+            return f64::INFINITY;
+        }
+        self.current_exposure() / (worst / self.margin)
+    }
     
+    // exit of the whole book (margin call, wiped out account, exclusive entry): closes every trade and
+    // cancels all pending orders, entries included
+    fn liquidate(&mut self, index: usize) {
+        self.close_all_trades(index, index);
+        self.orders.clear();
+        self.delayed_orders.clear();
+    }
+
     // add new method to check for and handle margin calls
     fn check_margin_call(&mut self, index: usize) {
         // get current margin usage
         let usage = self.current_margin_usage();
+        let intrabar_usage = self.intrabar_margin_usage(index);
         
         // if margin usage exceeds threshold, force liquidation
-        if usage > Self::MARGIN_CALL_THRESHOLD {
-            println!("// margin call triggered at {:.2}% usage", usage * 100.0);
-            self.close_all_trades(index, index);
+        // (or did within the bar at its low/high; the liquidation is still at the close)
+        if usage > Self::MARGIN_CALL_THRESHOLD || intrabar_usage > Self::MARGIN_CALL_THRESHOLD {
+            if !self.quiet {
+                if usage > Self::MARGIN_CALL_THRESHOLD {
+                    println!("// margin call triggered at {:.2}% usage", usage * 100.0);
+                } else {
+                    println!("// intrabar margin call triggered at {:.2}% usage", intrabar_usage * 100.0);
+                }
+            }
+            let usage = usage.max(intrabar_usage);
+            self.record_event(|| BacktestEvent::MarginCall { index, usage });
+            self.liquidate(index);
             // update margin usage after liquidation
             self.update_margin_usage();
         }
     }
 
+    // close trades that have been held for their max_holding_bars, at this bar's close
+    fn close_expired_trades(&mut self, index: usize) {
+        for i in (0..self.trades.len()).rev() {
+            let expired = match self.trades[i].max_holding_bars {
+                Some(bars) => index >= self.trades[i].entry_index + bars,
+                None => false,
+            };
+            if expired {
+                self.close_position_tagged(i, index, Some("time exit".to_string()));
+            }
+        }
+    }
+
+    // move delayed orders whose release tick has been reached into the order queue
+    fn release_delayed_orders(&mut self, index: usize) {
+        if self.delayed_orders.is_empty() {
+            return;
+        }
+        // in place, keeping submission order
+        let mut i = 0;
+        while i < self.delayed_orders.len() {
+            if self.delayed_orders[i].0 <= index {
+                let (_, order) = self.delayed_orders.remove(i);
+                self.orders.push(order);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
     // modify the next() method to include margin call check
     pub fn next(&mut self, index: usize) {
         // update max_concurrent_trades if current number is higher
         self.max_concurrent_trades = self.max_concurrent_trades.max(self.trades.len());
+        self.current_index = index;
+        self.update_quotes(index);
+        self.release_delayed_orders(index);
+        self.apply_cash_flows(index);
+        // positions held through a rollover are charged before this bar's fills change them
+        self.apply_rollovers(index);
         
         self.process_orders(index);
+        self.close_expired_trades(index);
+        self.apply_financing(index);
         self.update_equity(index);
+        self.update_intrabar_equity(index);
         
         // check for margin call before equity check
         self.check_margin_call(index);
         
-        // if equity drops to zero or below, close all trades and set cash to zero
-        if self.equity[index] <= 0.0 {
-            self.close_all_trades(index, index);
+        // if equity drops to zero or below, at the close or within the bar, close all trades and set cash to zero
+        if self.equity[index] <= 0.0 || self.intrabar_equity[index] <= 0.0 {
+            self.liquidate(index);
             self.cash = 0.0;
             for t in index..self.equity.len() {
                 self.equity[t] = 0.0;
             }
+            for t in index..self.intrabar_equity.len() {
+                self.intrabar_equity[t] = 0.0;
+            }
         }
         
         // update margin usage for every tick
         self.update_margin_usage();
+        self.update_size_multiplier(index);
+
+        // track exposure after any liquidation on this tick
+        self.record_exposure(index);
+        if self.var_config.is_some() {
+            let estimate = self.portfolio_var(index, None);
+            self.var_history.push(estimate);
+        }
+        if self.beta_neutral.is_some() {
+            let equity = self.equity[index];
+            let net_beta = if equity > 0.0 { self.net_beta_exposure(index) / equity } else { 0.0 };
+            self.net_beta_history.push(net_beta);
+        }
+        let equity = self.equity[index];
+        self.record_event(|| BacktestEvent::BarProcessed { index, equity });
+    }
+
+    // drawdown governor: the size multiplier for orders placed after this bar, from equity vs its peak
+    fn update_size_multiplier(&mut self, index: usize) {
+        let equity = self.equity[index];
+        self.equity_peak = self.equity_peak.max(equity);
+        if let Some(governor) = &self.drawdown_governor {
+            let multiplier = governor.multiplier_at(equity, self.equity_peak);
+            if multiplier != self.size_multiplier && !self.quiet {
+                println!("drawdown governor: entry sizes at {:.0}% from tick {}", multiplier * 100.0, index);
+            }
+            self.size_multiplier = multiplier;
+        }
+        self.size_multiplier_history.push(self.size_multiplier);
     }
 
     // calculate available buying power given margin requirements
@@ -586,6 +2199,26 @@ impl Broker {
         }
     }
 
+    // entry size under ScalingMode::BuyingPower or TargetMarginUsage, with the sign of the requested size.
+    // instrument 2 sizes are in units of the primary, as the broker converts them by close / close2.
+    pub fn margin_scaled_size(&self, order: &Order, current_price: f64) -> f64 {
+        let target_notional = match self.scaling_mode {
+            ScalingMode::EquityRatio => return order.size,
+            ScalingMode::BuyingPower(fraction) => fraction * self.available_buying_power(),
+            ScalingMode::TargetMarginUsage(target) => target * self.cash / self.margin - self.current_exposure(),
+        };
+        let price = if order.instrument == 2 {
+            self.instrument_price(1, self.current_index.min(self.last_index()))
+        } else {
+            current_price
+        };
+        let unit_notional = self.instrument_spec(order.instrument).notional(1.0, price);
+        if unit_notional <= 0.0 {
+            return order.size;
+        }
+        order.size.signum() * target_notional.max(0.0) / unit_notional
+    }
+
     // update margin usage history whenever position changes and update max margin usage too
     pub fn update_margin_usage(&mut self) {
         let usage = self.current_margin_usage();
@@ -596,25 +2229,212 @@ impl Broker {
         self.margin_usage_history.push(usage);
     }
 
+    // read-only view of resting orders, optionally filtered by instrument and side.
+    // contingent (sl/tp) orders are included; use pending_entry_orders to skip them.
+    pub fn pending_orders(&self, instrument: Option<u8>, side: Option<Side>) -> Vec<&Order> {
+        self.orders.iter()
+            .chain(self.delayed_orders.iter().map(|(_, order)| order))
+            .filter(|order| instrument.map_or(true, |inst| order.instrument == inst))
+            .filter(|order| side.map_or(true, |s| Side::of(order.size) == s))
+            .collect()
+    }
+
+    /// position per instrument ([instrument 1, instrument 2]) the strategy is heading for: open trades plus
+    /// market entries waiting to fill (including those held back by execution_delay). limit, stop and
+    /// if-touched entries are conditional and only count once they fill.
+    pub fn target_positions(&self) -> [f64; 2] {
+        let mut targets = [0.0; 2];
+        let pending = self.orders.iter()
+            .chain(self.delayed_orders.iter().map(|(_, order)| order))
+            .filter(|order| order.parent_trade.is_none() && order.limit.is_none() && order.stop.is_none() && order.touch.is_none())
+            .map(|order| (order.instrument, order.size));
+        for (instrument, size) in self.trades.iter().map(|t| (t.instrument, t.size)).chain(pending) {
+            targets[if instrument == 1 { 0 } else { 1 }] += size;
+        }
+        targets
+    }
+
+    /// rebalancing executor: move the position of `instrument` towards `target` units at bar `index`.
+    /// the delta to target_positions is worked off in order: pending market entries against it are
+    /// cancelled, open trades against it are closed (oldest first, partially if needed) at the close, and
+    /// what is left becomes a market entry that goes through the usual checks (margin, exposure caps, lot
+    /// rounding). deltas inside the config's no-trade band are skipped. returns the units traded or cancelled.
+    pub fn rebalance(&mut self, index: usize, instrument: u8, target: f64, config: &RebalanceConfig) -> Result<f64, OrderError> {
+        let current = self.target_positions()[if instrument == 1 { 0 } else { 1 }];
+        let delta = target - current;
+        if !config.should_trade(current, delta) {
+            return Ok(0.0);
+        }
+        let mut remaining = delta;
+        let against = |order: &Order, remaining: f64| {
+            order.instrument == instrument && order.parent_trade.is_none() && order.limit.is_none() && order.stop.is_none() && order.touch.is_none()
+                && order.size.signum() != remaining.signum() && order.size.abs() <= remaining.abs() + 1e-9
+        };
+        // whole pending entries only; one larger than the delta is left to the closing and entry steps
+        let mut kept = Vec::with_capacity(self.orders.len());
+        for order in std::mem::take(&mut self.orders) {
+            if against(&order, remaining) {
+                remaining += order.size;
+                self.cancel_at(index, order);
+            } else {
+                kept.push(order);
+            }
+        }
+        self.orders = kept;
+        let mut kept = Vec::with_capacity(self.delayed_orders.len());
+        for (release, order) in std::mem::take(&mut self.delayed_orders) {
+            if against(&order, remaining) {
+                remaining += order.size;
+                self.cancel_at(index, order);
+            } else {
+                kept.push((release, order));
+            }
+        }
+        self.delayed_orders = kept;
+
+        while remaining.abs() > 1e-9 {
+            let Some(trade_index) = self.trades.iter()
+                .position(|t| t.instrument == instrument && t.size.signum() != remaining.signum()) else {
+                break;
+            };
+            let size = self.trades[trade_index].size;
+            let closed = size.abs().min(remaining.abs()) * size.signum();
+            self.close_partial(trade_index, closed, index);
+            remaining += closed;
+        }
+
+        if remaining.abs() > 1e-9 {
+            let price = if instrument == 1 { self.close_at(index) } else { self.close2_at(index) };
+            // targets are in units of their own instrument, but new_order takes instrument 2 sizes in units
+            // of the primary and converts them by close / close2
+            let size = if instrument == 1 { remaining } else { remaining * self.close2_at(index) / self.close_at(index) };
+            let order = Order {
+                size,
+                instrument,
+                tag: config.tag.clone(),
+                exact_size: true,
+                ..Default::default()
+            };
+            self.new_order(order, price)?;
+        }
+        Ok(delta)
+    }
+
+    // resting entry orders only (no contingent orders attached to open trades)
+    pub fn pending_entry_orders(&self, instrument: Option<u8>, side: Option<Side>) -> Vec<&Order> {
+        self.pending_orders(instrument, side)
+            .into_iter()
+            .filter(|order| order.parent_trade.is_none())
+            .collect()
+    }
+
+    // true if a working entry order already rests on this instrument and side
+    pub fn has_pending_entry(&self, instrument: u8, side: Side) -> bool {
+        !self.pending_entry_orders(Some(instrument), Some(side)).is_empty()
+    }
+
+    // the resting order with this id, in the queue or held back by execution_delay
+    pub fn order(&self, id: u64) -> Option<&Order> {
+        self.orders.iter().chain(self.delayed_orders.iter().map(|(_, order)| order)).find(|order| order.id == id)
+    }
+
+    fn order_mut(&mut self, id: u64) -> Option<&mut Order> {
+        self.orders.iter_mut().chain(self.delayed_orders.iter_mut().map(|(_, order)| order)).find(|order| order.id == id)
+    }
+
+    /// cancel a resting order by id and return it. cancelling a stop loss or take profit leaves its trade
+    /// open without that exit. the order is logged in cancelled_orders and the event log.
+    pub fn cancel_order(&mut self, id: u64) -> Result<Order, OrderError> {
+        let order = if let Some(position) = self.orders.iter().position(|order| order.id == id) {
+            self.orders.remove(position)
+        } else if let Some(position) = self.delayed_orders.iter().position(|(_, order)| order.id == id) {
+            self.delayed_orders.remove(position).1
+        } else {
+            return Err(OrderError::UnknownOrder);
+        };
+        self.cancel_at(self.current_index, order.clone());
+        Ok(order)
+    }
+
+    /// amend a resting order in place; None keeps the current value. `size` (in units of the order's
+    /// instrument, as queued) must keep the side and is rounded to the lot step, and an increase is checked
+    /// against buying power. contingent exits always cover their trade or rung, so only their level can
+    /// move. `limit` and `stop` move an existing level; a market order can't become a limit order this way.
+    pub fn modify_order(&mut self, id: u64, size: Option<f64>, limit: Option<f64>, stop: Option<f64>) -> Result<(), OrderError> {
+        let mut amended = self.order(id).ok_or(OrderError::UnknownOrder)?.clone();
+        if (limit.is_some() && amended.limit.is_none()) || (stop.is_some() && amended.stop.is_none()) {
+            return Err(OrderError::InvalidModification);
+        }
+        if let Some(size) = size {
+            if amended.parent_trade.is_some() || size == 0.0 || size.signum() != amended.size.signum() {
+                return Err(OrderError::InvalidModification);
+            }
+            let spec = self.instrument_spec(amended.instrument);
+            let rounded = spec.round_size(size).map_err(|e| match e {
+                SizeError::NotWholeLot => OrderError::FractionalOrderNotAllowed,
+                SizeError::BelowMinimum => OrderError::SizeBelowMinimum,
+            })?;
+            let added = rounded.abs() - amended.size.abs();
+            if added > 0.0 {
+                let price = self.instrument_price(amended.instrument, self.current_index.min(self.last_index()));
+                if self.exceeds_buying_power(&[rounded.signum() * spec.notional(added, price)]) {
+                    return Err(OrderError::MarginExceeded);
+                }
+            }
+            amended.size = rounded;
+        }
+        amended.limit = limit.or(amended.limit);
+        amended.stop = stop.or(amended.stop);
+        // the level of a contingent exit is also the reference price of its fill
+        if amended.parent_trade.is_some() {
+            amended.signal_price = amended.stop.or(amended.limit);
+        }
+        let index = self.current_index;
+        self.record_event(|| BacktestEvent::OrderModified { index, order: amended.clone() });
+        if let Some(order) = self.order_mut(id) {
+            *order = amended;
+        }
+        Ok(())
+    }
+
+    // position of the open trade with this id in `trades`; unlike the position, the id doesn't change
+    // when other trades close
+    pub fn trade_index(&self, id: u64) -> Option<usize> {
+        self.trades.iter().position(|trade| trade.id == id)
+    }
+
+    pub fn trade(&self, id: u64) -> Option<&Trade> {
+        self.trades.iter().find(|trade| trade.id == id)
+    }
+
+    // snapshot of accumulated turnover and costs
+    pub fn cost_summary(&self) -> CostSummary {
+        self.costs.clone()
+    }
+
     // add a method to print trading statistics
     pub fn print_trading_stats(&self) {
         // print max concurrent trades and current open trades
         println!("// max concurrent trades during backtest: {}", self.max_concurrent_trades);
         println!("// current open trades: {}", self.trades.len());
+        println!("// seed: {}", self.seed);
     }
 
     // new method to print a detailed log of all closed trades
     pub fn print_trade_log(&self) {
         println!("// trade log:");
         for (index, trade) in self.closed_trades.iter().enumerate() {
-            println!("trade {}: size: {}, entry: {} at tick {}, exit: {} at tick {}, pnl: {}",
+            println!("trade {}: size: {}, entry: {} at tick {}, exit: {} at tick {}, pnl: {}, commission: {}, tag: {}, exit tag: {}",
                 index,
                 trade.size,
                 trade.entry_price,
                 trade.entry_index.saturating_add(1),
                 trade.exit_price.unwrap_or(0.0),
                 trade.exit_index.unwrap_or(0).saturating_add(1),
-                trade.pnl()
+                trade.pnl(),
+                trade.commission,
+                trade.tag.as_deref().unwrap_or("-"),
+                trade.exit_tag.as_deref().unwrap_or("-")
             );
         }
     }
@@ -627,14 +2447,17 @@ impl Broker {
         let mut file = File::create(file_path)?;
         writeln!(file, "// trade log:")?;
         for (index, trade) in self.closed_trades.iter().enumerate() {
-            writeln!(file, "trade {}: size: {}, entry: {} at tick {}, exit: {} at tick {}, pnl: {}",
+            writeln!(file, "trade {}: size: {}, entry: {} at tick {}, exit: {} at tick {}, pnl: {}, commission: {}, tag: {}, exit tag: {}",
                 index,
                 trade.size,
                 trade.entry_price,
                 trade.entry_index.saturating_add(1),
                 trade.exit_price.unwrap_or(0.0),
                 trade.exit_index.unwrap_or(0).saturating_add(1),
-                trade.pnl()
+                trade.pnl(),
+                trade.commission,
+                trade.tag.as_deref().unwrap_or("-"),
+                trade.exit_tag.as_deref().unwrap_or("-")
             )?;
         }
         Ok(())
@@ -650,6 +2473,26 @@ pub trait Strategy {
 // alias for user strategies to be boxed for dynamic dispatch
 pub type StrategyRef = Box<dyn Strategy>;
 
+// console and file output of Backtest::run; quiet() for optimizer iterations or runs inside a server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Verbosity {
+    pub progress_bar: bool,
+    pub save_trade_log: bool, // trade log file at the end of run()
+    pub broker_prints: bool,  // run summary, date range, margin calls (sets Broker::quiet)
+}
+
+impl Default for Verbosity {
+    fn default() -> Self {
+        Verbosity { progress_bar: true, save_trade_log: true, broker_prints: true }
+    }
+}
+
+impl Verbosity {
+    pub fn quiet() -> Self {
+        Verbosity { progress_bar: false, save_trade_log: false, broker_prints: false }
+    }
+}
+
 // backtest struct ties together data, a broker instance and a strategy instance.
 pub struct Backtest {
     pub data: OhlcData,
@@ -662,6 +2505,14 @@ pub struct Backtest {
     pub trade_on_close: bool,
     pub hedging: bool,
     pub exclusive_orders: bool,
+    // name of this run (e.g. params::run_label("statarb", &params)); prefixed to plot filenames and shown
+    // in plot titles and legends, so runs with different settings don't overwrite each other's output
+    pub label: Option<String>,
+    // run directory for the trade log, plots and other outputs; None writes output_* files to the working directory
+    pub artifacts: Option<ArtifactWriter>,
+    pub verbosity: Verbosity,
+    // target positions written after every bar, see export_signals
+    pub signals: Option<SignalExport>,
 }
 
 impl Backtest {
@@ -699,9 +2550,85 @@ impl Backtest {
             trade_on_close,
             hedging,
             exclusive_orders,
+            label: None,
+            artifacts: None,
+            verbosity: Verbosity::default(),
+            signals: None,
+        }
+    }
+
+    pub fn set_verbosity(&mut self, verbosity: Verbosity) {
+        self.verbosity = verbosity;
+        self.broker.quiet = !verbosity.broker_prints;
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.label = Some(label.to_string());
+    }
+
+    pub fn set_artifacts(&mut self, artifacts: ArtifactWriter) {
+        self.artifacts = Some(artifacts);
+    }
+
+    /// keep an event log of the run (see events); call before run
+    pub fn record_events(&mut self) {
+        self.broker.event_log = Some(Vec::new());
+    }
+
+    /// the events of the run in the order they happened: submissions and rejections, fills, closed trades,
+    /// margin calls and processed bars. empty unless record_events was called before the run.
+    pub fn events(&self) -> impl Iterator<Item = &BacktestEvent> {
+        self.broker.event_log.iter().flatten()
+    }
+
+    /// write the target position of each instrument to `path` (through output_path) whenever it changes,
+    /// with `instruments` as the names of instrument 1 and 2, e.g. ["US500", "DJIA"]
+    pub fn export_signals(&mut self, path: &str, instruments: [&str; 2]) -> Result<(), Box<dyn std::error::Error>> {
+        let writer = SignalWriter::create(&self.output_path(path))?;
+        self.signals = Some(SignalExport { writer, instruments: instruments.map(|name| name.to_string()) });
+        Ok(())
+    }
+
+    // write the targets after the strategy has acted on bar `index`; a failed write stops the export
+    fn record_signals(&mut self, index: usize) {
+        let Some(export) = self.signals.as_mut() else {
+            return;
+        };
+        let targets = self.broker.target_positions();
+        let rows = [(export.instruments[0].as_str(), targets[0]), (export.instruments[1].as_str(), targets[1])];
+        if let Err(e) = export.writer.update(&self.data.date[index], &rows) {
+            println!("error writing signals, export stopped: {}", e);
+            self.signals = None;
+        }
+    }
+
+    // output path for this run: with an artifact directory "output_equity.png" becomes "<dir>/equity.png";
+    // otherwise it becomes "<label>_equity.png" (in the same directory) once a label is set, unchanged without one
+    pub fn output_path(&self, path: &str) -> String {
+        let file = std::path::Path::new(path);
+        let name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let short_name = name.strip_prefix("output_").unwrap_or(name);
+        match (self.artifacts.as_ref(), self.label.as_deref()) {
+            (Some(artifacts), _) => artifacts.path(short_name),
+            (None, Some(label)) => file.with_file_name(format!("{}_{}", label, short_name)).to_string_lossy().into_owned(),
+            (None, None) => path.to_string(),
         }
     }
     
+    // restrict the run to bars between `from` and `to` (inclusive, e.g. "2024-01-02 14:30:00");
+    // call before run, broker settings are kept
+    pub fn set_date_range(&mut self, from: Option<&str>, to: Option<&str>) {
+        self.data = self.data.slice_dates(from, to);
+        self.broker.set_data(self.data.clone());
+        if !self.verbosity.broker_prints {
+            return;
+        }
+        match (self.data.date.first(), self.data.date.last()) {
+            (Some(first), Some(last)) => println!("date range: {} to {} ({} bars)", first, last, self.data.close.len()),
+            _ => println!("date range: no bars between {:?} and {:?}", from, to),
+        }
+    }
+
     // run the simulation over all ticks in the provided data.
     pub fn run(&mut self) {
         use indicatif::{ProgressBar, ProgressStyle};
@@ -710,7 +2637,7 @@ impl Backtest {
         
         let n = self.data.close.len();
         
-        let pb = ProgressBar::new(n as u64);
+        let pb = if self.verbosity.progress_bar { ProgressBar::new(n as u64) } else { ProgressBar::hidden() };
         pb.set_style(ProgressStyle::default_bar()
             .template("{desc:.green} {bar:40.white} {percentage:>3}% | {pos:>7}/{len:7} [{elapsed_precise}<{eta_precise}] {msg}")
             .unwrap()
@@ -721,20 +2648,60 @@ impl Backtest {
         for index in 0..n {
             self.broker.next(index);
             self.strategy.next(&mut self.broker, index);
+            self.record_signals(index);
             pb.set_position(index as u64);
         }
         pb.finish_with_message("");
 
         // print stats after backtest completes
-        self.broker.print_trading_stats();
+        if self.verbosity.broker_prints {
+            self.broker.print_trading_stats();
+        }
         // save trade log to file instead of printing to console
-        if let Err(e) = self.broker.save_trade_log("output_trade_log.txt") {
-            println!("error saving trade log: {:?}", e);
-        } else {
-            println!("trade log successfully saved to trade_log.txt");
+        if self.verbosity.save_trade_log {
+            let trade_log_path = self.output_path("output_trade_log.txt");
+            if let Err(e) = self.broker.save_trade_log(&trade_log_path) {
+                println!("error saving trade log: {:?}", e);
+            } else if self.verbosity.broker_prints {
+                println!("trade log successfully saved to {}", trade_log_path);
+            }
         }
     }
 
+    // run the simulation without progress bar or trade log output (used by batch tools)
+    pub fn run_silent(&mut self) {
+        self.strategy.init(&mut self.broker, &self.data);
+        for index in 0..self.data.close.len() {
+            self.broker.next(index);
+            self.strategy.next(&mut self.broker, index);
+            self.record_signals(index);
+        }
+    }
+
+    // buy & hold of the primary instrument over the same data, through a fresh copy of the strategy's broker
+    // (Broker::fresh_copy: every cost, margin, sizing, halt and perturbation setting), run silently. its
+    // equity and stats are a benchmark that pays the same costs as the strategy.
+    pub fn run_buy_and_hold(&self) -> Backtest {
+        let mut benchmark = Backtest::new(
+            self.data.clone(),
+            Box::new(BuyAndHoldStrategy::new(1, 1.0)),
+            self.cash,
+            self.commission,
+            self.bidask_spread,
+            self.margin,
+            self.trade_on_close,
+            self.hedging,
+            self.exclusive_orders,
+            self.broker.scaling_enabled,
+        );
+        benchmark.set_verbosity(Verbosity::quiet());
+        benchmark.label = self.label.as_ref().map(|label| format!("{}_buy_and_hold", label));
+        benchmark.broker = self.broker.fresh_copy();
+        benchmark.broker.quiet = true;
+        benchmark.run_silent();
+        benchmark
+    }
+
     // abstraction for plotting the equity curve
     // this method converts date strings to NaiveDateTime, pairs them with equity values,
     // and calls the plot_equity function to generate the plot.
@@ -743,18 +2710,16 @@ impl Backtest {
         let equity_history: Vec<(NaiveDateTime, f64)> = self.data.date.iter()
             .zip(self.broker.equity.iter())
             .map(|(date_str, &equity)| {
-                // adjust the format string to match your data; for example: "2020-01-01 23:01:00"
-                let dt = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S")
-                    .expect("failed to parse date");
+                let dt = plot_time(date_str);
                 (dt, equity)
             })
             .collect();
 
         // call the external plotting function from plot.rs
-        plot_equity(&equity_history, output_path)
+        plot_equity(&equity_history, self.label.as_deref(), &self.output_path(output_path))
     }
 
-    pub fn plot_equity_and_benchmark(&self, benchmark: &Vec<f64>, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn plot_equity_and_benchmark(&self, benchmark: &[f64], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
         // convert to percentage changes from initial values
         let initial_equity = self.broker.equity[0];
         let initial_benchmark = benchmark[0];
@@ -762,8 +2727,7 @@ impl Backtest {
         let equity_history: Vec<(NaiveDateTime, f64)> = self.data.date.iter()
             .zip(self.broker.equity.iter())
             .map(|(date_str, &equity)| {
-                let dt = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S")
-                    .expect("failed to parse date");
+                let dt = plot_time(date_str);
                 let pct_change = (equity - initial_equity) / initial_equity * 100.0;
                 (dt, pct_change)
             })
@@ -772,27 +2736,134 @@ impl Backtest {
         let benchmark_history: Vec<(NaiveDateTime, f64)> = self.data.date.iter()
             .zip(benchmark.iter())
             .map(|(date_str, &value)| {
-                let dt = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S")
-                    .expect("failed to parse date");
+                let dt = plot_time(date_str);
                 let pct_change = (value - initial_benchmark) / initial_benchmark * 100.0;
                 (dt, pct_change)
             })
             .collect();
 
-        plot_equity_and_benchmark(&equity_history, &benchmark_history,output_path)
+        plot_equity_and_benchmark(&equity_history, &benchmark_history, self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    // drawdown below the running equity peak per bar, in percent
+    fn drawdown_history(&self) -> Vec<(NaiveDateTime, f64)> {
+        self.data.date.iter()
+            .zip(drawdown_series(&self.broker.equity))
+            .map(|(date_str, dd)| (plot_time(date_str), dd * 100.0))
+            .collect()
+    }
+
+    pub fn plot_underwater(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        plot_underwater(&self.drawdown_history(), self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    // drawdown relative to a benchmark series aligned with the data (e.g. buy & hold equity or Close)
+    pub fn plot_relative_underwater(&self, benchmark: &[f64], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let relative: Vec<(NaiveDateTime, f64)> = self.data.date.iter()
+            .zip(relative_drawdown_series(&self.broker.equity, benchmark))
+            .map(|(date_str, dd)| (plot_time(date_str), dd * 100.0))
+            .collect();
+        plot_relative_underwater(&relative, &self.drawdown_history(), self.label.as_deref(), &self.output_path(output_path))
     }
 
     pub fn plot_margin_usage(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
         let margin_usage_history: Vec<(NaiveDateTime, f64)> = self.data.date.iter()
             .zip(self.broker.margin_usage_history.iter())
             .map(|(date_str, &margin_usage)| {
-                let dt = NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S")
-                    .expect("failed to parse date");
+                let dt = plot_time(date_str);
                 (dt, margin_usage)
             })
             .collect();
 
-        plot_margin_usage(&margin_usage_history, output_path)
+        plot_margin_usage(&margin_usage_history, self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    pub fn plot_exposure(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let dates: Vec<NaiveDateTime> = self.data.date.iter()
+            .map(|date_str| {
+                plot_time(date_str)
+            })
+            .collect();
+        let long: Vec<(NaiveDateTime, f64)> = dates.iter().cloned()
+            .zip(self.broker.long_exposure_history.iter().cloned())
+            .collect();
+        let short: Vec<(NaiveDateTime, f64)> = dates.iter().cloned()
+            .zip(self.broker.short_exposure_history.iter().map(|v| -v))
+            .collect();
+        let net: Vec<(NaiveDateTime, f64)> = dates.iter().cloned()
+            .zip(self.broker.net_exposure_history.iter().cloned())
+            .collect();
+
+        plot_exposure(&long, &short, &net, self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    // equity on top, one step chart of net position per traded instrument below it
+    pub fn plot_position_timeline(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let dates: Vec<NaiveDateTime> = self.data.date.iter()
+            .map(|date_str| {
+                plot_time(date_str)
+            })
+            .collect();
+        let equity: Vec<(NaiveDateTime, f64)> = dates.iter().cloned()
+            .zip(self.broker.equity.iter().cloned())
+            .collect();
+
+        let mut positions: Vec<(String, Vec<(NaiveDateTime, f64)>)> = Vec::new();
+        for (slot, label) in [(0, "instrument 1"), (1, "instrument 2")] {
+            // skip instruments that were never traded
+            if self.broker.position_history.iter().all(|p| p[slot] == 0.0) {
+                continue;
+            }
+            let series = dates.iter().cloned()
+                .zip(self.broker.position_history.iter().map(|p| p[slot]))
+                .collect();
+            positions.push((label.to_string(), series));
+        }
+
+        plot_position_timeline(&equity, &positions, self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    // candles of bars `range` with indicator overlays and a marker at every fill of instrument 1, e.g.
+    // plot_ohlc(100..300, &[Overlay::Bollinger(20, 2.0)], "output_ohlc.png") to check a strategy's entries
+    pub fn plot_ohlc(&self, range: std::ops::Range<usize>, overlays: &[Overlay], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let markers: Vec<ChartMarker> = self.broker.fills.iter()
+            .filter(|fill| fill.instrument == 1)
+            .map(|fill| ChartMarker { index: fill.tick, price: fill.fill_price, buy: fill.size > 0.0 })
+            .collect();
+        plot_ohlc(&self.data, range, overlays, &markers, self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    // (bars held, pnl) of every closed trade
+    fn closed_trade_points(&self) -> Vec<(f64, f64)> {
+        self.broker.closed_trades.iter()
+            .filter_map(|t| t.exit_index.map(|exit| (exit.saturating_sub(t.entry_index) as f64, t.pnl())))
+            .collect()
+    }
+
+    pub fn plot_trade_histogram(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let pnls: Vec<f64> = self.closed_trade_points().iter().map(|&(_, pnl)| pnl).collect();
+        plot_trade_histogram(&pnls, 30, self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    pub fn plot_holding_periods(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let bars: Vec<f64> = self.closed_trade_points().iter().map(|&(bars, _)| bars).collect();
+        plot_holding_period_histogram(&bars, 30, self.label.as_deref(), &self.output_path(output_path))
+    }
+
+    pub fn plot_pnl_vs_holding(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        plot_pnl_vs_holding(&self.closed_trade_points(), self.label.as_deref(), &self.output_path(output_path))
     }
     
-} 
\ No newline at end of file
+} 
+// order two timestamps; falls back to string order (fine for iso dates) when either does not parse
+pub(crate) fn compare_dates(a: &str, b: &str) -> Ordering {
+    match (parse_quote_time(a), parse_quote_time(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+// bar timestamp for plotting: "%Y-%m-%d %H:%M:%S" bars or rfc3339 tick/quote times
+fn plot_time(date: &str) -> NaiveDateTime {
+    parse_quote_time(date).map(|dt| dt.naive_utc()).expect("failed to parse date")
+}
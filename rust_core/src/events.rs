@@ -0,0 +1,38 @@
+// event log of a backtest: what happened during the run in the order it happened, for analytics that need
+// more than the final trade list (fill rates, rejection reasons per bar, time from signal to fill, ...)
+use crate::engine::{Fill, Order, OrderError, Trade};
+
+#[derive(Clone, Debug)]
+pub enum BacktestEvent {
+    // an order accepted by new_order / new_order_group on bar `index`, as the strategy submitted it
+    OrderSubmitted { index: usize, order: Order },
+    OrderRejected { index: usize, order: Order, error: OrderError },
+    // a resting order cancelled on bar `index`: by cancel_order, by a rebalance, or with the trade it exits
+    OrderCancelled { index: usize, order: Order },
+    // a resting order amended by modify_order on bar `index`, as it rests after the change
+    OrderModified { index: usize, order: Order },
+    // an entry or exit fill, including stop losses, take profits, closes and liquidations
+    OrderFilled(Fill),
+    // a fully or partly closed trade, after the fill that closed it
+    TradeClosed(Trade),
+    // margin usage (at the close, or the bar's worst case) crossed the threshold; the liquidation follows
+    MarginCall { index: usize, usage: f64 },
+    // the broker finished bar `index` (fills, exits, financing, equity); the strategy runs after this
+    BarProcessed { index: usize, equity: f64 },
+}
+
+impl BacktestEvent {
+    // bar the event happened on
+    pub fn index(&self) -> usize {
+        match self {
+            BacktestEvent::OrderSubmitted { index, .. }
+            | BacktestEvent::OrderRejected { index, .. }
+            | BacktestEvent::OrderCancelled { index, .. }
+            | BacktestEvent::OrderModified { index, .. }
+            | BacktestEvent::MarginCall { index, .. }
+            | BacktestEvent::BarProcessed { index, .. } => *index,
+            BacktestEvent::OrderFilled(fill) => fill.tick,
+            BacktestEvent::TradeClosed(trade) => trade.exit_index.unwrap_or(trade.entry_index),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// step-based environment around the backtest broker, for training reinforcement-learning agents
+// against the same fills, costs and margin rules as a normal backtest
+use crate::engine::{Broker, Order, OrderError};
+
+// discrete actions map to market orders of `unit_size`; Target sets the net position directly
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Hold,
+    Buy,
+    Sell,
+    Close,
+    Target(f64), // desired net position in units of the primary instrument
+}
+
+impl Action {
+    // index mapping for agents with a discrete action space
+    pub fn from_index(index: usize) -> Action {
+        match index {
+            1 => Action::Buy,
+            2 => Action::Sell,
+            3 => Action::Close,
+            _ => Action::Hold,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    pub unit_size: f64,      // order size for Buy and Sell
+    pub window: usize,       // log returns included in each observation
+    pub max_position: f64,   // Buy/Sell/Target are clipped to +/- this many units
+    pub reward_scale: f64,   // reward = equity change * reward_scale
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            unit_size: 1.0,
+            window: 20,
+            max_position: 10.0,
+            reward_scale: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StepResult {
+    pub observation: Vec<f64>,
+    pub reward: f64,
+    pub done: bool,
+    pub rejected: bool, // the action's order was rejected by the broker (margin, caps, ...)
+}
+
+/// a signal taken on step t fills on bar t + 1, exactly like a strategy's orders in Backtest::run
+pub struct TradingEnv<F>
+where
+    F: Fn() -> Broker,
+{
+    pub config: EnvConfig,
+    pub broker: Broker,
+    pub index: usize,
+    make_broker: F,
+}
+
+impl<F> TradingEnv<F>
+where
+    F: Fn() -> Broker,
+{
+    /// `make_broker` must build a fresh, identically configured broker on every call
+    pub fn new(make_broker: F, config: EnvConfig) -> Self {
+        let broker = make_broker();
+        TradingEnv { config, broker, index: 0, make_broker }
+    }
+
+    // start a new episode on the first bar and return its observation
+    pub fn reset(&mut self) -> Vec<f64> {
+        self.broker = (self.make_broker)();
+        self.index = 0;
+        self.broker.next(0);
+        self.observation()
+    }
+
+    pub fn step(&mut self, action: Action) -> StepResult {
+        let last = self.broker.data.close.len().saturating_sub(1);
+        if self.index >= last {
+            return StepResult { observation: self.observation(), reward: 0.0, done: true, rejected: false };
+        }
+
+        let rejected = self.apply(action).is_err();
+
+        // advance one bar; orders placed above fill here
+        let previous_equity = self.broker.equity[self.index];
+        self.index += 1;
+        self.broker.next(self.index);
+        if self.index >= last {
+            // flatten on the final bar so the episode reward includes every open trade
+            self.broker.close_all_trades(self.index, self.index);
+            self.broker.update_equity(self.index);
+        }
+        let equity = self.broker.equity[self.index];
+        let reward = (equity - previous_equity) * self.config.reward_scale;
+        let done = self.index >= last || equity <= 0.0;
+
+        StepResult { observation: self.observation(), reward, done, rejected }
+    }
+
+    // net position in units of the primary instrument
+    pub fn position(&self) -> f64 {
+        self.broker.trades.iter().filter(|t| t.instrument == 1).map(|t| t.size).sum()
+    }
+
+    // trailing log returns (zero padded at the start), then position / max_position and equity / starting equity
+    pub fn observation(&self) -> Vec<f64> {
+        let close = &self.broker.data.close;
+        let mut obs = Vec::with_capacity(self.config.window + 2);
+        for k in (0..self.config.window).rev() {
+            let i = self.index as isize - k as isize;
+            let ret = if i >= 1 && close[i as usize - 1] > 0.0 {
+                (close[i as usize] / close[i as usize - 1]).ln()
+            } else {
+                0.0
+            };
+            obs.push(ret);
+        }
+        let max_position = if self.config.max_position > 0.0 { self.config.max_position } else { 1.0 };
+        obs.push(self.position() / max_position);
+        obs.push(self.broker.equity[self.index] / self.broker.base_equity);
+        obs
+    }
+
+    // translate an action into an order on the current bar
+    fn apply(&mut self, action: Action) -> Result<(), OrderError> {
+        let position = self.position();
+        let target = match action {
+            Action::Hold => return Ok(()),
+            Action::Close => return self.close_all(),
+            Action::Buy => position + self.config.unit_size,
+            Action::Sell => position - self.config.unit_size,
+            Action::Target(units) => units,
+        };
+        let target = target.clamp(-self.config.max_position, self.config.max_position);
+        let size = target - position;
+        if size == 0.0 {
+            return Ok(());
+        }
+
+        let order = Order {
+            size,
+            instrument: 1,
+            tag: Some("gym".to_string()),
+            ..Default::default()
+        };
+        let price = self.broker.data.close[self.index];
+        self.broker.new_order(order, price).map(|_| ())
+    }
+
+    // a market exit for every open trade, filled on the next bar like the other actions
+    fn close_all(&mut self) -> Result<(), OrderError> {
+        let price = self.broker.data.close[self.index];
+        let exits: Vec<Order> = self.broker.trades.iter()
+            .map(|trade| Order {
+                size: trade.size,
+                instrument: trade.instrument,
+                parent_trade: Some(trade.id),
+                tag: Some("gym close".to_string()),
+                exact_size: true,
+                ..Default::default()
+            })
+            .collect();
+        for exit in exits {
+            self.broker.new_order(exit, price)?;
+        }
+        Ok(())
+    }
+}
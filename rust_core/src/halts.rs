@@ -0,0 +1,51 @@
+// untradeable bars: trading halts and limit-up/limit-down windows attached to OhlcData. the broker keeps
+// (or rejects) orders that would fill inside a window and can fill what piled up at the reopening price.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HaltKind {
+    Halt,      // no trading at all
+    LimitUp,   // price locked at the upper limit: no sellers, so buys can't fill
+    LimitDown, // price locked at the lower limit: no buyers, so sells can't fill
+}
+
+// bars start..=end (indices into the data) of one instrument, or of all instruments when None
+#[derive(Clone, Debug, PartialEq)]
+pub struct Halt {
+    pub start: usize,
+    pub end: usize,
+    pub instrument: Option<u8>,
+    pub kind: HaltKind,
+}
+
+impl Halt {
+    pub fn new(start: usize, end: usize, instrument: Option<u8>, kind: HaltKind) -> Self {
+        Halt { start, end, instrument, kind }
+    }
+
+    // whether a buy (or sell) of `instrument` can't fill on bar `index`
+    pub fn blocks(&self, index: usize, instrument: u8, buy: bool) -> bool {
+        if index < self.start || index > self.end || self.instrument.is_some_and(|i| i != instrument) {
+            return false;
+        }
+        match self.kind {
+            HaltKind::Halt => true,
+            HaltKind::LimitUp => buy,
+            HaltKind::LimitDown => !buy,
+        }
+    }
+}
+
+// what the broker does with orders placed while their instrument is halted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HaltPolicy {
+    #[default]
+    Queue,  // accept them; they wait in the queue until trading resumes
+    Reject, // reject them with OrderError::TradingHalted
+}
+
+/// the halts overlapping bars start..end, re-indexed so `start` becomes bar 0 (for slices of the data)
+pub fn rebase_halts(halts: &[Halt], start: usize, end: usize) -> Vec<Halt> {
+    halts.iter()
+        .filter(|h| h.end >= start && h.start < end)
+        .map(|h| Halt { start: h.start.saturating_sub(start), end: h.end.min(end - 1) - start, ..h.clone() })
+        .collect()
+}
@@ -0,0 +1,90 @@
+// rolling indicators over whole series for strategies (precomputed in init) and chart overlays
+// (plot_ohlc). every output is aligned with its input: value i only uses bars up to and including i (the
+// donchian channel up to i - 1, so a close can break out of it), and is NaN until the window is full.
+use crate::util::std_dev;
+
+// an upper and lower band around a middle line
+#[derive(Clone, Debug)]
+pub struct Bands {
+    pub upper: Vec<f64>,
+    pub middle: Vec<f64>,
+    pub lower: Vec<f64>,
+}
+
+impl Bands {
+    // (upper, middle, lower) at bar i, None during the warm-up
+    pub fn at(&self, i: usize) -> Option<(f64, f64, f64)> {
+        let (upper, middle, lower) = (*self.upper.get(i)?, *self.middle.get(i)?, *self.lower.get(i)?);
+        if upper.is_finite() && middle.is_finite() && lower.is_finite() {
+            Some((upper, middle, lower))
+        } else {
+            None
+        }
+    }
+}
+
+// simple moving average over `period` bars
+pub fn sma(values: &[f64], period: usize) -> Vec<f64> {
+    rolling(values, period, |window| window.iter().sum::<f64>() / window.len() as f64)
+}
+
+// exponential moving average with alpha = 2 / (period + 1), seeded with the sma of the first `period` bars
+pub fn ema(values: &[f64], period: usize) -> Vec<f64> {
+    let mut out = vec![f64::NAN; values.len()];
+    if period == 0 || values.len() < period {
+        return out;
+    }
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut average = values[..period].iter().sum::<f64>() / period as f64;
+    out[period - 1] = average;
+    for i in period..values.len() {
+        average += alpha * (values[i] - average);
+        out[i] = average;
+    }
+    out
+}
+
+// sample standard deviation over `period` bars
+pub fn rolling_std(values: &[f64], period: usize) -> Vec<f64> {
+    rolling(values, period, std_dev)
+}
+
+// bollinger bands: the sma of `period` bars plus and minus `width` rolling standard deviations
+pub fn bollinger(values: &[f64], period: usize, width: f64) -> Bands {
+    let middle = sma(values, period);
+    let std = rolling_std(values, period);
+    Bands {
+        upper: middle.iter().zip(std.iter()).map(|(m, s)| m + width * s).collect(),
+        lower: middle.iter().zip(std.iter()).map(|(m, s)| m - width * s).collect(),
+        middle,
+    }
+}
+
+// donchian channel: highest high and lowest low of the `period` bars before bar i, and their midpoint
+pub fn donchian(high: &[f64], low: &[f64], period: usize) -> Bands {
+    let n = high.len().min(low.len());
+    let mut bands = Bands { upper: vec![f64::NAN; n], middle: vec![f64::NAN; n], lower: vec![f64::NAN; n] };
+    if period == 0 {
+        return bands;
+    }
+    for i in period..n {
+        let upper = high[i - period..i].iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let lower = low[i - period..i].iter().copied().fold(f64::INFINITY, f64::min);
+        bands.upper[i] = upper;
+        bands.lower[i] = lower;
+        bands.middle[i] = (upper + lower) / 2.0;
+    }
+    bands
+}
+
+// f over each full window ending at bar i
+fn rolling(values: &[f64], period: usize, f: impl Fn(&[f64]) -> f64) -> Vec<f64> {
+    let mut out = vec![f64::NAN; values.len()];
+    if period == 0 {
+        return out;
+    }
+    for i in period.saturating_sub(1)..values.len() {
+        out[i] = f(&values[i + 1 - period..=i]);
+    }
+    out
+}
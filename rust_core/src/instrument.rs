@@ -0,0 +1,157 @@
+// contract specifications and unit conversions for sizing orders on leveraged accounts
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+// why an order size could not be rounded; the brokers map these onto their OrderError
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeError {
+    NotWholeLot,  // not a multiple of the lot step and rounding is Reject
+    BelowMinimum, // rounded to zero or below min_size
+}
+
+// what to do with an order size that is not a multiple of the lot step
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SizeRounding {
+    Reject,  // refuse the order (FractionalOrderNotAllowed)
+    Down,    // round towards zero
+    Nearest, // round to the nearest lot
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InstrumentSpec {
+    pub contract_size: f64, // currency per unit per point of price, 1.0 for plain cfds and stocks
+    pub lot_step: f64,      // smallest size increment, e.g. 1.0 whole units, 0.1; 0.0 allows any size
+    pub min_size: f64,      // smallest absolute order size after rounding
+    pub rounding: SizeRounding,
+    // overnight financing charged at rollovers instead of the broker's per-bar financing_rate
+    pub rollover: Option<RolloverSchedule>,
+}
+
+// when and how much overnight financing a cfd is charged: once per trading day at a local time of day,
+// with one weekday charged three times to cover the weekend
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RolloverSchedule {
+    pub time: NaiveTime, // local time of the rollover, e.g. 17:00
+    pub timezone: Tz,    // e.g. chrono_tz::America::New_York, so the rollover follows daylight saving
+    pub triple_day: Option<Weekday>, // charged 3 days (e.g. Friday for index cfds, Wednesday for fx)
+    // annual rates on notional (benchmark plus or minus the markup); negative short rates are paid out
+    pub long_rate: f64,
+    pub short_rate: f64,
+    pub day_count: f64, // days per year the rates are quoted on, 360 or 365
+}
+
+impl RolloverSchedule {
+    /// 17:00 New York, triple on Friday, on a 360 day year
+    pub fn index_cfd(long_rate: f64, short_rate: f64) -> Self {
+        RolloverSchedule {
+            time: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            timezone: chrono_tz::America::New_York,
+            triple_day: Some(Weekday::Fri),
+            long_rate,
+            short_rate,
+            day_count: 360.0,
+        }
+    }
+
+    /// days of financing charged for the rollovers in (from, to]: one per weekday rollover, three on
+    /// triple_day, none on saturdays and sundays
+    pub fn days_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> f64 {
+        if to <= from {
+            return 0.0;
+        }
+        let first = from.with_timezone(&self.timezone).date_naive();
+        let last = to.with_timezone(&self.timezone).date_naive();
+        let mut days = 0.0;
+        for day in first.iter_days().take_while(|d| *d <= last) {
+            if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+                continue;
+            }
+            // a local time repeated by a daylight saving change counts once, one skipped by it not at all
+            let Some(rollover) = self.timezone.from_local_datetime(&day.and_time(self.time)).earliest() else {
+                continue;
+            };
+            let rollover = rollover.with_timezone(&Utc);
+            if rollover > from && rollover <= to {
+                days += if self.triple_day == Some(day.weekday()) { 3.0 } else { 1.0 };
+            }
+        }
+        days
+    }
+
+    // financing of one rollover day on a position of `units` with this notional
+    pub fn daily_cost(&self, units: f64, notional: f64) -> f64 {
+        let rate = if units > 0.0 { self.long_rate } else { self.short_rate };
+        notional * rate / self.day_count
+    }
+}
+
+impl Default for InstrumentSpec {
+    fn default() -> Self {
+        InstrumentSpec {
+            contract_size: 1.0,
+            lot_step: 1.0,
+            min_size: 0.0,
+            rounding: SizeRounding::Reject,
+            rollover: None,
+        }
+    }
+}
+
+impl InstrumentSpec {
+    // spec used when none is configured: whole units without leverage (margin >= 1), any size with leverage
+    pub fn for_margin(margin: f64) -> Self {
+        if margin >= 1.0 {
+            InstrumentSpec::default()
+        } else {
+            InstrumentSpec { lot_step: 0.0, ..InstrumentSpec::default() }
+        }
+    }
+
+    // absolute notional value of a position
+    pub fn notional(&self, units: f64, price: f64) -> f64 {
+        units.abs() * price * self.contract_size
+    }
+
+    // unsigned units worth `notional` at `price` (before lot rounding)
+    pub fn units_for_notional(&self, notional: f64, price: f64) -> f64 {
+        if price <= 0.0 || self.contract_size <= 0.0 {
+            return 0.0;
+        }
+        notional.abs() / (price * self.contract_size)
+    }
+
+    // cash that has to be posted for a position at the given account margin ratio
+    pub fn margin_requirement(&self, units: f64, price: f64, margin: f64) -> f64 {
+        self.notional(units, price) * margin
+    }
+
+    // unsigned units that `margin_cash` can carry at the given margin ratio (before lot rounding)
+    pub fn units_for_margin(&self, margin_cash: f64, price: f64, margin: f64) -> f64 {
+        if margin <= 0.0 {
+            return 0.0;
+        }
+        self.units_for_notional(margin_cash / margin, price)
+    }
+
+    // apply the lot step and minimum size to a signed order size
+    pub fn round_size(&self, size: f64) -> Result<f64, SizeError> {
+        let rounded = if self.lot_step > 0.0 {
+            let lots = size / self.lot_step;
+            // tolerate float noise such as 0.30000000000000004 / 0.1
+            let whole = (lots - lots.round()).abs() < 1e-9;
+            match self.rounding {
+                _ if whole => lots.round() * self.lot_step,
+                SizeRounding::Reject => return Err(SizeError::NotWholeLot),
+                SizeRounding::Down => lots.trunc() * self.lot_step,
+                SizeRounding::Nearest => lots.round() * self.lot_step,
+            }
+        } else {
+            size
+        };
+        if (rounded == 0.0 && size != 0.0) || (size != 0.0 && rounded.abs() < self.min_size) {
+            return Err(SizeError::BelowMinimum);
+        }
+        Ok(rounded)
+    }
+}
@@ -8,3 +8,28 @@ pub mod position;
 pub mod plot;
 pub use plot::plot_equity; 
 pub mod data_handler;
+pub mod screening;
+pub mod analysis;
+pub mod robustness;
+pub mod diagnostics;
+pub mod clock;
+pub mod console;
+pub mod risk;
+pub mod optimize;
+pub mod params;
+pub mod manifest;
+pub mod source;
+pub mod gym;
+pub mod instrument;
+pub mod quotes;
+pub mod crossval;
+pub mod artifacts;
+pub mod testing;
+pub mod diff;
+pub mod signals;
+pub mod rebalance;
+pub mod halts;
+pub mod events;
+pub mod indicators;
+#[cfg(feature = "decimal")]
+pub mod decimal;
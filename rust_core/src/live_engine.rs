@@ -5,14 +5,26 @@ use crate::util::as_str;
 use std::cmp::Ordering;
 use serde::{Serialize, Deserialize};
 use tokio::sync::mpsc::UnboundedReceiver;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+pub use crate::engine::Side;
+pub use crate::engine::TrailingStop;
+use crate::clock::{parse_quote_time, system_clock, ClockRef, TestClock};
+use crate::console::ConsoleReporter;
+use crate::risk::{historical_var, trailing_returns, DrawdownGovernor, MarginMode, VarConfig, VarEstimate};
+use crate::instrument::{InstrumentSpec, SizeError};
+use crate::position::PositionManager;
+use crate::signals::SignalWriter;
 
 // Define custom error for order margin check.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OrderError {
     MarginExceeded, // error if order notional exceeds available buying power
     FractionalOrderNotAllowed, // error for fractional orders when not using leverage
     TradeLimitExceeded, // error if new order would exceed allowed concurrent positions per side
+    ExposureCapExceeded, // error if the order would breach a per-instrument or aggregate notional cap
+    VarBudgetExceeded, // error if the order would push portfolio var above the configured budget
+    SizeBelowMinimum, // error if the order size rounds to zero or below the instrument's minimum
 }
 
 /// A single tick snapshot for one instrument.
@@ -29,10 +41,33 @@ pub struct TickSnapshot {
 pub struct LiveData {
     pub ticks: Vec<TickSnapshot>,
     pub current: HashMap<String, TickSnapshot>,
+    // stream events that arrived with these ticks (gaps, resyncs)
+    #[serde(default)]
+    pub events: Vec<StreamEvent>,
+}
+
+/// messages `expected` up to (not including) `received` of a subscription's sequence never arrived
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataGap {
+    pub instrument: String,
+    pub expected: u64,
+    pub received: u64,
+}
+
+impl DataGap {
+    pub fn missed(&self) -> u64 {
+        self.received.saturating_sub(self.expected)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StreamEvent {
+    DataGap(DataGap),  // updates were lost; the instrument's quote is not trusted until it is resynced
+    Resynced(String),  // a fresh snapshot of the instrument arrived after a gap
 }
 
 /// Order now uses a String to identify the instrument.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Order {
     // positive size indicates a long order, negative a short
     pub size: f64,
@@ -43,10 +78,217 @@ pub struct Order {
     // for contingent orders (sl/tp), parent_trade indicates which trade they relate to (by index)
     pub parent_trade: Option<usize>,
     pub instrument: String,
+    // free-form reason attached by the strategy, carried onto the trade
+    pub tag: Option<String>,
+    // mid price when the strategy submitted the order; filled in by the broker when None
+    pub signal_mid: Option<f64>,
+    // if-touched entry: waits until the ask touches this level from above for a buy (the bid from below for
+    // a sell), then works as a limit order at `limit` (limit-if-touched) or fills at the current quote
+    // (market-if-touched, no limit). ignored on contingent orders.
+    pub touch: Option<f64>,
+    // time exit: the broker closes the resulting trade once it has been open this long
+    pub max_holding_duration: Option<chrono::Duration>,
+    // trailing stop loss: the stop follows the best exit quote of the trade at this distance, as in the
+    // backtest broker
+    pub trailing: Option<TrailingStop>,
+}
+
+// spread and slippage of one executed order
+#[derive(Clone, Debug)]
+pub struct FillRecord {
+    pub tick: usize,
+    pub instrument: String,
+    pub size: f64,
+    pub fill_price: f64,
+    pub spread: f64,     // ask - bid at the fill
+    pub signal_mid: f64, // mid when the order was submitted
+    pub slippage: f64,   // adverse move of the fill vs signal mid, in price units (positive = cost)
+}
+
+// execution quality over all fills of a session
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ExecutionQuality {
+    pub num_fills: usize,
+    pub avg_spread: f64,
+    pub max_spread: f64,
+    pub avg_slippage: f64,
+    pub total_slippage_cost: f64, // slippage * size summed over fills, in cash
+}
+
+impl ExecutionQuality {
+    pub fn from_fills(fills: &[FillRecord]) -> Self {
+        if fills.is_empty() {
+            return ExecutionQuality::default();
+        }
+        let n = fills.len() as f64;
+        ExecutionQuality {
+            num_fills: fills.len(),
+            avg_spread: fills.iter().map(|f| f.spread).sum::<f64>() / n,
+            max_spread: fills.iter().map(|f| f.spread).fold(0.0, f64::max),
+            avg_slippage: fills.iter().map(|f| f.slippage).sum::<f64>() / n,
+            total_slippage_cost: fills.iter().map(|f| f.slippage * f.size.abs()).sum(),
+        }
+    }
+}
+
+impl std::fmt::Display for ExecutionQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "\n\nExecution Quality:")?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<35} {:>15}", "Fills", self.num_fills)?;
+        writeln!(f, "{:<35} {:>15.4}", "Avg Spread at Fill", self.avg_spread)?;
+        writeln!(f, "{:<35} {:>15.4}", "Max Spread at Fill", self.max_spread)?;
+        writeln!(f, "{:<35} {:>15.4}", "Avg Slippage vs Signal Mid", self.avg_slippage)?;
+        writeln!(f, "{:<35} {:>15.2}", "Total Slippage Cost", self.total_slippage_cost)?;
+        write!(f, "====================")
+    }
+}
+
+/// manual supervision of a live session, sent over the command channel (REST or console)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum LiveCommand {
+    Pause,  // stop calling the strategy; broker-side stops, expiries and margin calls keep running
+    Resume,
+    CloseTrade { trade: usize }, // index into broker.trades
+    CloseAll,
+    // market order (or limit/stop order) tagged "manual"; positive size buys, negative sells
+    SubmitOrder {
+        instrument: String,
+        size: f64,
+        #[serde(default)]
+        limit: Option<f64>,
+        #[serde(default)]
+        stop: Option<f64>,
+        #[serde(default)]
+        sl: Option<f64>,
+        #[serde(default)]
+        tp: Option<f64>,
+    },
+}
+
+impl std::str::FromStr for LiveCommand {
+    type Err = String;
+
+    // console syntax: pause | resume | close <trade> | close all | buy <instrument> <size> | sell <instrument> <size>
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let size = |raw: &str| raw.parse::<f64>().map_err(|_| format!("size '{}' is not a number", raw));
+        let order = |instrument: &str, size: f64| LiveCommand::SubmitOrder {
+            instrument: instrument.to_string(), size, limit: None, stop: None, sl: None, tp: None,
+        };
+        match words.as_slice() {
+            ["pause"] => Ok(LiveCommand::Pause),
+            ["resume"] => Ok(LiveCommand::Resume),
+            ["close", "all"] => Ok(LiveCommand::CloseAll),
+            ["close", trade] => trade.parse::<usize>()
+                .map(|trade| LiveCommand::CloseTrade { trade })
+                .map_err(|_| format!("trade '{}' is not an index", trade)),
+            ["buy", instrument, raw] => Ok(order(instrument, size(raw)?.abs())),
+            ["sell", instrument, raw] => Ok(order(instrument, -size(raw)?.abs())),
+            _ => Err(format!("unknown command '{}', expected pause, resume, close <trade>, close all, buy/sell <instrument> <size>", s.trim())),
+        }
+    }
+}
+
+/// what the live broker does with an entry order rejected with MarginExceeded
+#[derive(Clone, Debug, PartialEq)]
+pub enum RetryPolicy {
+    // resubmit at once with the size multiplied by `factor` (0.5 halves it), at most `max_attempts` times;
+    // new_order returns Ok with the reduced size queued
+    ShrinkSize { factor: f64, max_attempts: usize },
+    // keep the order and resubmit it unchanged every `ticks` ticks, at most `max_attempts` times;
+    // new_order still returns MarginExceeded
+    WaitTicks { ticks: usize, max_attempts: usize },
+}
+
+// counters of the retry queue over a session
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RetryStats {
+    pub retried: usize,   // resubmissions after a margin rejection
+    pub succeeded: usize, // orders placed on a retry
+    pub abandoned: usize, // orders given up after max_attempts
+    pub pending: usize,   // orders waiting in the queue
+}
+
+// an order waiting for its next resubmission
+#[derive(Clone, Debug)]
+struct PendingRetry {
+    order: Order,
+    attempts: usize,
+    due_tick: usize,
+}
+
+/// machine-readable state of the live session after a tick, for dashboards and the chart server
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LiveStatsSnapshot {
+    pub tick: usize,
+    pub time: String, // broker clock, rfc3339
+    pub cash: f64,
+    pub equity: f64,
+    pub margin_usage: f64, // fraction of equity, 0.25 = 25%
+    pub max_margin_usage: f64,
+    pub open_trades: Vec<OpenTradeStats>,
+    pub closed_trades: usize,
+    pub realized_pnl: f64,
+    pub last_prices: BTreeMap<String, TickSnapshot>,
+    pub execution: ExecutionQuality,
+    pub var: Option<f64>, // only with a var_config
+    pub es: Option<f64>,
+    pub rejected_orders: usize,
+    pub retries: RetryStats,
+    pub size_multiplier: f64, // drawdown governor, 1.0 = full size
+    // strategy vs broker positions, only for strategies that report a PositionManager
+    pub reconciliation: Option<PositionReconciliation>,
+}
+
+// what the strategy believes it holds (its PositionManager counts) next to what the broker holds.
+// pending entries count for either side: a strategy may register a position when it submits the order
+// or when it fills, so its count matches anywhere between the open trades and open trades plus pending.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PositionReconciliation {
+    pub strategy_longs: usize,
+    pub strategy_shorts: usize,
+    pub broker_longs: usize,  // open trades
+    pub broker_shorts: usize,
+    pub pending_longs: usize, // entry orders not filled yet
+    pub pending_shorts: usize,
+    pub mismatch_since: Option<usize>, // first tick of the current mismatch, None while they agree
+}
+
+impl PositionReconciliation {
+    pub fn new(positions: &PositionManager, trades: &[Trade], orders: &[Order]) -> Self {
+        let entries = orders.iter().filter(|o| o.parent_trade.is_none());
+        PositionReconciliation {
+            strategy_longs: positions.open_longs,
+            strategy_shorts: positions.open_shorts,
+            broker_longs: trades.iter().filter(|t| t.size > 0.0).count(),
+            broker_shorts: trades.iter().filter(|t| t.size < 0.0).count(),
+            pending_longs: entries.clone().filter(|o| o.size > 0.0).count(),
+            pending_shorts: entries.filter(|o| o.size < 0.0).count(),
+            mismatch_since: None,
+        }
+    }
+
+    pub fn is_mismatch(&self) -> bool {
+        let outside = |believed: usize, open: usize, pending: usize| believed < open || believed > open + pending;
+        outside(self.strategy_longs, self.broker_longs, self.pending_longs)
+            || outside(self.strategy_shorts, self.broker_shorts, self.pending_shorts)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OpenTradeStats {
+    pub instrument: String,
+    pub size: f64,
+    pub entry_price: f64,
+    pub entry_index: usize,
+    pub unrealized_pnl: f64,
+    pub tag: Option<String>,
 }
 
 /// Trade now uses a String to identify the instrument.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Trade {
     pub instrument: String,
     pub size: f64,
@@ -57,6 +299,11 @@ pub struct Trade {
     // optional indices of contingent orders assigned to this trade
     pub sl_order: Option<usize>,
     pub tp_order: Option<usize>,
+    // tag of the order that opened the trade and of the order that closed it
+    pub tag: Option<String>,
+    pub exit_tag: Option<String>,
+    // broker clock time after which the trade is closed (entry time + max_holding_duration)
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl Trade {
@@ -110,9 +357,14 @@ pub struct LiveBroker {
     pub live_data: LiveData,
     pub live_cash: f64,
     pub live_margin: f64,     // margin ratio (0 < margin <= 1)
+    // gross (default) or netted margin of offsetting long and short trades, as in the backtest broker
+    pub margin_mode: MarginMode,
     pub live_trade_on_close: bool,
     pub live_hedging: bool,
+    // every new entry order first closes all open trades at the current quotes and cancels pending orders
     pub live_exclusive_orders: bool,
+    // the original behaviour: open trades are dropped without being closed and their pnl is never booked
+    pub legacy_exclusive_orders: bool,
     pub orders: Vec<Order>,
     pub trades: Vec<Trade>,      // active trades
     pub closed_trades: Vec<Trade>,
@@ -122,6 +374,38 @@ pub struct LiveBroker {
     pub live_base_equity: f64,      // initial equity for scaling purposes
     pub live_scaling_enabled: bool, // flag to enable scaling
     pub live_margin_usage_history: Vec<f64>, // track historical margin usage
+    pub live_order_attempts: usize,
+    pub live_rejected_orders: Vec<(usize, OrderError)>, // (tick, reason)
+    // stop losses cancelled because their trade was closed another way, with the tick
+    pub cancelled_orders: Vec<(usize, Order)>,
+    pub fills: Vec<FillRecord>,
+    // notional caps as multiples of equity: per instrument (e.g. "US500" => 2.0) and across all instruments
+    pub exposure_caps: HashMap<String, f64>,
+    // lot step, rounding and contract size per instrument; unset instruments use InstrumentSpec::for_margin
+    pub instrument_specs: HashMap<String, InstrumentSpec>,
+    pub max_gross_exposure: Option<f64>,
+    // tick-to-tick historical var of the open trades; None disables the estimate and the budget rule
+    pub var_config: Option<VarConfig>,
+    // scales entry sizes down as equity falls from its peak, as in the backtest broker; the multiplier
+    // after each equity update is recorded in size_multiplier_history
+    pub drawdown_governor: Option<DrawdownGovernor>,
+    pub size_multiplier: f64,
+    pub size_multiplier_history: Vec<f64>,
+    live_equity_peak: f64,
+    // time source for quote staleness; replace with a TestClock for deterministic runs
+    pub clock: ClockRef,
+    // quotes older than this are considered stale and orders on them are held; None disables the check
+    pub max_quote_age: Option<chrono::Duration>,
+    // console output level and how often the stats summary is printed
+    pub reporter: ConsoleReporter,
+    // retry of entry orders rejected for margin; None drops them as before
+    pub retry_policy: Option<RetryPolicy>,
+    retry_queue: Vec<PendingRetry>,
+    retries: RetryStats,
+    // sequence gaps seen on the stream (tick, gap)
+    pub data_gaps: Vec<(usize, DataGap)>,
+    // instruments with a gap that no snapshot has repaired yet; their quotes count as stale
+    pub awaiting_snapshot: HashSet<String>,
     max_live_concurrent_trades: usize,
 }
 
@@ -142,9 +426,11 @@ impl LiveBroker {
             live_data,
             live_cash,
             live_margin,
+            margin_mode: MarginMode::Gross,
             live_trade_on_close,
             live_hedging,
             live_exclusive_orders,
+            legacy_exclusive_orders: false,
             orders: Vec::new(),
             trades: Vec::new(),
             closed_trades: Vec::new(),
@@ -153,27 +439,199 @@ impl LiveBroker {
             live_base_equity: live_cash,
             live_scaling_enabled,
             live_margin_usage_history: vec![0.0],
+            live_order_attempts: 0,
+            live_rejected_orders: Vec::new(),
+            cancelled_orders: Vec::new(),
+            fills: Vec::new(),
+            exposure_caps: HashMap::new(),
+            instrument_specs: HashMap::new(),
+            max_gross_exposure: None,
+            var_config: None,
+            drawdown_governor: None,
+            size_multiplier: 1.0,
+            size_multiplier_history: Vec::new(),
+            live_equity_peak: live_cash,
+            clock: system_clock(),
+            max_quote_age: None,
+            reporter: ConsoleReporter::default(),
+            retry_policy: None,
+            retry_queue: Vec::new(),
+            retries: RetryStats::default(),
+            data_gaps: Vec::new(),
+            awaiting_snapshot: HashSet::new(),
             max_live_concurrent_trades: 0,
         }
     }
 
-    // new_order: place a new order into the live orders queue
-    pub fn new_order(&mut self, mut order: Order, current_price: f64) -> Result<(), OrderError> {
-        // check fractional orders if no leverage
-        if self.live_margin >= 1.0 && order.size.fract() != 0.0 {
-            return Err(OrderError::FractionalOrderNotAllowed);
+    // new_order: place a new order into the live orders queue; rejections are recorded.
+    pub fn new_order(&mut self, order: Order, current_price: f64) -> Result<(), OrderError> {
+        self.live_order_attempts += 1;
+        let retry = (order.parent_trade.is_none() && self.retry_policy.is_some()).then(|| order.clone());
+        let mut result = self.submit_order(order, current_price);
+        let tick = self.live_data.ticks.len().saturating_sub(1);
+        if let (Err(OrderError::MarginExceeded), Some(order)) = (result, retry) {
+            match self.retry_policy.clone() {
+                Some(RetryPolicy::ShrinkSize { factor, max_attempts }) => {
+                    result = self.retry_smaller(order, current_price, factor, max_attempts);
+                }
+                Some(RetryPolicy::WaitTicks { ticks, max_attempts }) if max_attempts > 0 => {
+                    self.reporter.info(format_args!("order on {} queued for retry in {} ticks", order.instrument, ticks));
+                    self.retry_queue.push(PendingRetry { order, attempts: 0, due_tick: tick + ticks.max(1) });
+                }
+                _ => {}
+            }
+        }
+        if let Err(e) = result {
+            self.live_rejected_orders.push((tick, e));
+        }
+        result
+    }
+
+    // resubmit with a smaller size until it fits or the attempts run out
+    fn retry_smaller(&mut self, mut order: Order, current_price: f64, factor: f64, max_attempts: usize) -> Result<(), OrderError> {
+        let mut result = Err(OrderError::MarginExceeded);
+        for _ in 0..max_attempts {
+            order.size *= factor;
+            self.retries.retried += 1;
+            result = self.submit_order(order.clone(), current_price);
+            match result {
+                Ok(()) => {
+                    self.retries.succeeded += 1;
+                    self.reporter.info(format_args!("order on {} placed at reduced size {}", order.instrument, order.size));
+                    return result;
+                }
+                Err(OrderError::MarginExceeded) => continue,
+                Err(_) => break, // e.g. the size rounded below the minimum
+            }
+        }
+        self.retries.abandoned += 1;
+        result
+    }
+
+    // resubmit queued orders that are due; called at the start of every tick
+    fn process_retries(&mut self, index: usize) {
+        let (policy_ticks, max_attempts) = match self.retry_policy {
+            Some(RetryPolicy::WaitTicks { ticks, max_attempts }) => (ticks.max(1), max_attempts),
+            _ => {
+                self.retry_queue.clear();
+                return;
+            }
+        };
+        let (due, waiting): (Vec<PendingRetry>, Vec<PendingRetry>) = std::mem::take(&mut self.retry_queue)
+            .into_iter()
+            .partition(|retry| retry.due_tick <= index);
+        self.retry_queue = waiting;
+        for mut retry in due {
+            // price a resubmission like a strategy would: buy at the ask, sell at the bid
+            let price = match self.live_data.current.get(&retry.order.instrument) {
+                Some(tick) if retry.order.size > 0.0 => tick.ask,
+                Some(tick) => tick.bid,
+                None => {
+                    self.retry_queue.push(PendingRetry { due_tick: index + policy_ticks, ..retry });
+                    continue;
+                }
+            };
+            retry.attempts += 1;
+            self.retries.retried += 1;
+            match self.submit_order(retry.order.clone(), price) {
+                Ok(()) => {
+                    self.retries.succeeded += 1;
+                    self.reporter.info(format_args!("queued order on {} placed after {} retries", retry.order.instrument, retry.attempts));
+                }
+                Err(OrderError::MarginExceeded) if retry.attempts < max_attempts => {
+                    self.retry_queue.push(PendingRetry { due_tick: index + policy_ticks, ..retry });
+                }
+                Err(e) => {
+                    self.retries.abandoned += 1;
+                    self.live_rejected_orders.push((index, e));
+                    self.reporter.warn(format_args!("queued order on {} abandoned: {:?}", retry.order.instrument, e));
+                }
+            }
+        }
+    }
+
+    // retry counters, with the number of orders still queued
+    pub fn retry_stats(&self) -> RetryStats {
+        RetryStats { pending: self.retry_queue.len(), ..self.retries.clone() }
+    }
+
+    // submit_order: validate an order and queue it. with exclusive orders, an entry is checked against the
+    // book that closing every trade would leave, and the book is only liquidated (pending orders and
+    // retries cancelled) once the entry is accepted.
+    fn submit_order(&mut self, order: Order, current_price: f64) -> Result<(), OrderError> {
+        if !self.live_exclusive_orders || self.legacy_exclusive_orders || order.parent_trade.is_some() {
+            let order = self.check_order(order, current_price)?;
+            self.queue_order(order);
+            return Ok(());
+        }
+        let order = self.check_after_liquidation(order, current_price)?;
+        let tick = self.live_data.ticks.len().saturating_sub(1);
+        self.liquidate(tick);
+        self.retry_queue.clear();
+        self.queue_order(order);
+        Ok(())
+    }
+
+    // check_order against the book after an exclusive liquidation: trades with a quote are taken out and
+    // their open pnl counted as realized, trades without one stay open. nothing is closed here.
+    fn check_after_liquidation(&mut self, order: Order, current_price: f64) -> Result<Order, OrderError> {
+        let realized: f64 = self.trades.iter()
+            .filter(|t| self.live_data.current.contains_key(&t.instrument))
+            .map(|t| self.unrealized_pnl(t))
+            .sum();
+        let kept: Vec<Trade> = self.trades.iter()
+            .filter(|t| !self.live_data.current.contains_key(&t.instrument))
+            .cloned()
+            .collect();
+        let trades = std::mem::replace(&mut self.trades, kept);
+        let cash = self.live_cash;
+        self.live_cash += realized;
+        let checked = self.check_order(order, current_price);
+        self.trades = trades;
+        self.live_cash = cash;
+        checked
+    }
+
+    // size an order (drawdown governor, scaling, then lot rounding) and check it against buying power, exposure
+    // caps, var budget and trade limits; returns the adjusted order
+    fn check_order(&self, mut order: Order, current_price: f64) -> Result<Order, OrderError> {
+        // the drawdown governor shrinks entries before rounding, so they still fit the lot step
+        if order.parent_trade.is_none() {
+            order.size *= self.size_multiplier;
+        }
+        // remember the mid at signal time for slippage stats
+        if order.signal_mid.is_none() {
+            order.signal_mid = self.live_data.current.get(&order.instrument).map(|t| (t.ask + t.bid) / 2.0);
         }
         // scale order size if scaling is enabled
         if self.live_scaling_enabled {
             order.size = self.scale_order_size(order.size);
         }
+        // round the final size to the instrument's lot step (whole units without leverage by default)
+        order.size = self.instrument_spec(&order.instrument).round_size(order.size).map_err(|e| match e {
+            SizeError::NotWholeLot => OrderError::FractionalOrderNotAllowed,
+            SizeError::BelowMinimum => OrderError::SizeBelowMinimum,
+        })?;
       
         // check for sufficient buying power
-        let order_notional = order.size.abs() * current_price;
-        let available = self.available_buying_power();
-        if order_notional > available {
+        let order_notional = self.instrument_spec(&order.instrument).notional(order.size, current_price);
+        if self.margined_increase(order.size.signum() * order_notional) > self.available_buying_power() {
             return Err(OrderError::MarginExceeded);
         }
+        // enforce notional caps for non-contingent orders
+        if order.parent_trade.is_none() && self.exceeds_exposure_caps(&order.instrument, order_notional) {
+            return Err(OrderError::ExposureCapExceeded);
+        }
+        // enforce the var budget for non-contingent orders
+        if order.parent_trade.is_none() {
+            if let Some(budget_pct) = self.var_config.as_ref().and_then(|c| c.budget_pct) {
+                let equity = *self.live_equity.last().unwrap_or(&self.live_cash);
+                let estimate = self.live_var(Some((&order.instrument, order.size.signum() * order_notional)));
+                if estimate.var > budget_pct / 100.0 * equity {
+                    return Err(OrderError::VarBudgetExceeded);
+                }
+            }
+        }
         // enforce trade limits (max three open trades per side) for non-contingent orders
         if order.parent_trade.is_none() {
             if order.size > 0.0 {
@@ -188,8 +646,13 @@ impl LiveBroker {
                 }
             }
         }
-        // if exclusive orders are enabled, clear any existing orders and trades
-        if self.live_exclusive_orders {
+        Ok(order)
+    }
+
+    // put a checked order in the queue, contingent orders first
+    fn queue_order(&mut self, order: Order) {
+        // legacy exclusive orders: clear any existing orders and trades without booking them
+        if self.live_exclusive_orders && self.legacy_exclusive_orders {
             self.orders.clear();
             self.trades.clear();
         }
@@ -200,20 +663,104 @@ impl LiveBroker {
         }
         self.update_max_margin_usage();
         self.update_margin_usage();
-        Ok(())
+    }
+
+    // true if adding `order_notional` to `instrument` would breach its cap or the aggregate cap
+    fn exceeds_exposure_caps(&self, instrument: &str, order_notional: f64) -> bool {
+        if self.exposure_caps.is_empty() && self.max_gross_exposure.is_none() {
+            return false;
+        }
+        let equity = *self.live_equity.last().unwrap_or(&self.live_cash);
+        // mark open trades at the current mid, falling back to the entry price without a quote
+        let notional_of = |trade: &Trade| {
+            let price = self.live_data.current.get(&trade.instrument)
+                .map(|t| (t.ask + t.bid) / 2.0)
+                .unwrap_or(trade.entry_price);
+            trade.size.abs() * price
+        };
+        let instrument_notional: f64 = self.trades.iter()
+            .filter(|t| t.instrument == instrument)
+            .map(notional_of)
+            .sum();
+        let gross_notional: f64 = self.trades.iter().map(notional_of).sum();
+
+        if let Some(cap) = self.exposure_caps.get(instrument) {
+            if instrument_notional + order_notional > cap * equity {
+                return true;
+            }
+        }
+        if let Some(cap) = self.max_gross_exposure {
+            if gross_notional + order_notional > cap * equity {
+                return true;
+            }
+        }
+        false
+    }
+
+    // var/es of the open trades over recent tick-to-tick mid returns, optionally with an extra
+    // signed notional on one instrument
+    pub fn live_var(&self, extra: Option<(&str, f64)>) -> VarEstimate {
+        let config = match self.var_config.as_ref() {
+            Some(config) => config,
+            None => return VarEstimate::default(),
+        };
+        let mut notionals: HashMap<String, f64> = HashMap::new();
+        for trade in self.trades.iter() {
+            let price = self.live_data.current.get(&trade.instrument)
+                .map(|t| (t.ask + t.bid) / 2.0)
+                .unwrap_or(trade.entry_price);
+            *notionals.entry(trade.instrument.clone()).or_insert(0.0) += trade.size * price;
+        }
+        if let Some((instrument, notional)) = extra {
+            *notionals.entry(instrument.to_string()).or_insert(0.0) += notional;
+        }
+
+        let exposures: Vec<(f64, Vec<f64>)> = notionals.iter()
+            .filter(|(_, notional)| **notional != 0.0)
+            .map(|(instrument, notional)| {
+                let mids: Vec<f64> = self.live_data.ticks.iter()
+                    .filter(|t| &t.instrument == instrument)
+                    .map(|t| (t.ask + t.bid) / 2.0)
+                    .collect();
+                (*notional, trailing_returns(&mids, mids.len().saturating_sub(1), config.lookback))
+            })
+            .collect();
+        historical_var(&exposures, config.confidence)
     }
 
     // process_orders: check and execute orders using current live bid and ask prices.
     // For each order, we look up the current snapshot by instrument.
-    pub fn process_orders(&mut self, _index: usize) {
+    pub fn process_orders(&mut self, index: usize) {
         let mut executed_order_indices: Vec<usize> = Vec::new();
+        // instruments whose last quote is too old to trade on
+        let stale: Vec<String> = self.live_data.current.keys()
+            .filter(|instrument| self.is_quote_stale(instrument))
+            .cloned()
+            .collect();
 
         for (i, order) in self.orders.iter_mut().enumerate() {
+            if stale.contains(&order.instrument) {
+                continue;
+            }
             // Look up current snapshot for the order's instrument.
             if let Some(current_tick) = self.live_data.current.get(&order.instrument) {
                 let current_ask = current_tick.ask;
                 let current_bid = current_tick.bid;
 
+                // Handle if-touched entries; once touched they are plain limit or market orders.
+                if let (Some(touch), None) = (order.touch, order.parent_trade) {
+                    let touched = if order.size > 0.0 { current_ask <= touch } else { current_bid >= touch };
+                    if touched {
+                        order.touch = None;
+                    } else {
+                        continue;
+                    }
+                }
+                // Trailing stops follow the quote they exit on (bid for longs, ask for shorts) before the check.
+                if let (Some(trailing), Some(stop), Some(_)) = (order.trailing, order.stop, order.parent_trade) {
+                    let long = order.size > 0.0;
+                    order.stop = Some(trailing.trail(stop, if long { current_bid } else { current_ask }, long));
+                }
                 // Handle stop orders.
                 if let Some(stop_price) = order.stop {
                     let is_stop_hit = if order.parent_trade.is_some() {
@@ -259,52 +806,85 @@ impl LiveBroker {
         }
 
         // Clone orders to execute and remove them from the queue in descending order.
-        let orders_to_execute: Vec<Order> = executed_order_indices.iter().map(|&i| self.orders[i].clone()).collect();
+        let mut orders_to_execute: Vec<Order> = executed_order_indices.iter().map(|&i| self.orders[i].clone()).collect();
         executed_order_indices.sort_unstable_by(|a, b| b.cmp(a));
         for i in executed_order_indices {
             self.orders.remove(i);
         }
 
-        for order in orders_to_execute.iter() {
+        orders_to_execute.reverse();
+        while let Some(order) = orders_to_execute.pop() {
+            // a triggered stop loss closes its trade; the trade's other triggered exits are cancelled with it
+            if let Some(parent) = order.parent_trade {
+                if parent < self.trades.len() && self.live_data.current.contains_key(&order.instrument) {
+                    let cancelled = Self::reindex_contingent(&mut orders_to_execute, parent);
+                    self.record_cancelled(index, cancelled);
+                    self.close_position(parent, index);
+                    if let Some(closed) = self.closed_trades.last_mut() {
+                        closed.exit_tag = order.tag.clone();
+                    }
+                }
+                continue;
+            }
             // Get the current snapshot for this order.
             if let Some(current_tick) = self.live_data.current.get(&order.instrument) {
-                let entry_price = if order.size > 0.0 { current_tick.bid } else { current_tick.ask };
+                // buys fill at the ask, sells at the bid
+                let entry_price = if order.size > 0.0 { current_tick.ask } else { current_tick.bid };
+
+                let mid = (current_tick.ask + current_tick.bid) / 2.0;
+                let signal_mid = order.signal_mid.unwrap_or(mid);
+                self.fills.push(FillRecord {
+                    tick: index,
+                    instrument: order.instrument.clone(),
+                    size: order.size,
+                    fill_price: entry_price,
+                    spread: current_tick.ask - current_tick.bid,
+                    signal_mid,
+                    slippage: (entry_price - signal_mid) * order.size.signum(),
+                });
 
                 let trade = Trade {
                     size: order.size,
                     entry_price,
-                    entry_index: 0, // For live trading you may record a tick counter or timestamp.
-                    exit_price: None,
-                    exit_index: None,
-                    sl_order: None,
-                    tp_order: None,
+                    entry_index: index, // tick the order filled on
                     instrument: order.instrument.clone(),
+                    tag: order.tag.clone(),
+                    expires_at: order.max_holding_duration.map(|d| self.clock.now() + d),
+                    ..Default::default()
                 };
                 self.trades.push(trade);
 
                 if order.size > 0.0 {
-                    println!("open long on {}: {}", order.instrument, entry_price);
+                    self.reporter.info(format_args!("open long on {}: {}", order.instrument, entry_price));
                 } else {
-                    println!("open short on {}: {}", order.instrument, entry_price);
+                    self.reporter.info(format_args!("open short on {}: {}", order.instrument, entry_price));
                 }
 
-                // If a stop loss is provided, create a contingent order.
-                if let Some(sl_value) = order.sl {
+                // If a stop loss is provided, create a contingent order; a trailing stop starts at its
+                // distance from the fill, or at `sl` if that is tighter.
+                let long = order.size > 0.0;
+                let stop_loss = match (order.sl, order.trailing) {
+                    (Some(sl), Some(trailing)) => Some(trailing.trail(sl, entry_price, long)),
+                    (None, Some(trailing)) => Some(trailing.level(entry_price, long)),
+                    (sl, None) => sl,
+                };
+                if let Some(sl_value) = stop_loss {
                     let trade_idx = self.trades.len() - 1; // index of new trade
                     let contingent_order = Order {
                         size: order.size,
-                        limit: None,
                         stop: Some(sl_value),
-                        sl: None,
                         tp: order.tp,
                         parent_trade: Some(trade_idx),
                         instrument: order.instrument.clone(),
+                        tag: Some("stop-loss".to_string()),
+                        trailing: order.trailing,
+                        ..Default::default()
                     };
                     self.orders.push(contingent_order);
                     if order.size > 0.0 {
-                        println!("{} long stop loss set at: {}", order.instrument, sl_value);
+                        self.reporter.info(format_args!("{} long stop loss set at: {}", order.instrument, sl_value));
                     } else {
-                        println!("{} short stop loss set at: {}", order.instrument, sl_value);
+                        self.reporter.info(format_args!("{} short stop loss set at: {}", order.instrument, sl_value));
                     }
                 }
             }
@@ -314,88 +894,149 @@ impl LiveBroker {
     // update_equity: recalc live equity = live_cash + pnl from open trades.
     // For each trade, we look up the latest price from the current snapshot.
     pub fn update_equity(&mut self, _index: usize) {
-        let pnl_sum: f64 = self.trades.iter().map(|trade| {
-            if let Some(current_tick) = self.live_data.current.get(&trade.instrument) {
-                if trade.size > 0.0 {
-                    (current_tick.ask - trade.entry_price) * trade.size
-                } else {
-                    (trade.entry_price - current_tick.bid) * (-trade.size)
-                }
-            } else {
-                0.0
-            }
-        }).sum();
+        let pnl_sum: f64 = self.trades.iter().map(|trade| self.unrealized_pnl(trade)).sum();
         let equity_value = self.live_cash + pnl_sum;
         self.live_equity.push(equity_value);
+        self.live_equity_peak = self.live_equity_peak.max(equity_value);
+        if let Some(governor) = &self.drawdown_governor {
+            let multiplier = governor.multiplier_at(equity_value, self.live_equity_peak);
+            if multiplier != self.size_multiplier {
+                self.reporter.warn(format_args!("drawdown governor: entry sizes at {:.0}%", multiplier * 100.0));
+            }
+            self.size_multiplier = multiplier;
+        }
+        self.size_multiplier_history.push(self.size_multiplier);
     }
 
-    // close_position: close one open trade using the current live prices.
-    pub fn close_position(&mut self, trade_index: usize, _index: usize) {
-        if trade_index >= self.trades.len() {
+    // open pnl of a trade at the current quote of its instrument, 0 without a quote
+    pub fn unrealized_pnl(&self, trade: &Trade) -> f64 {
+        if let Some(current_tick) = self.live_data.current.get(&trade.instrument) {
+            // marked at the price the trade would close at: the bid for longs, the ask for shorts
+            if trade.size > 0.0 {
+                (current_tick.bid - trade.entry_price) * trade.size
+            } else {
+                (trade.entry_price - current_tick.ask) * (-trade.size)
+            }
+        } else {
+            0.0
+        }
+    }
+
+    // drop contingent orders of a removed trade and shift the parent indices of the others;
+    // returns the dropped orders
+    fn reindex_contingent(orders: &mut Vec<Order>, removed: usize) -> Vec<Order> {
+        let mut cancelled = Vec::new();
+        let mut i = 0;
+        while i < orders.len() {
+            match orders[i].parent_trade {
+                Some(parent) if parent == removed => {
+                    cancelled.push(orders.remove(i));
+                    continue;
+                }
+                Some(parent) if parent > removed => orders[i].parent_trade = Some(parent - 1),
+                _ => {}
+            }
+            i += 1;
+        }
+        cancelled
+    }
+
+    fn record_cancelled(&mut self, index: usize, orders: Vec<Order>) {
+        self.cancelled_orders.extend(orders.into_iter().map(|order| (index, order)));
+    }
+
+    /// the stop loss still waiting for an open trade
+    pub fn contingent_orders(&self, trade_index: usize) -> Vec<&Order> {
+        self.orders.iter().filter(|o| o.parent_trade == Some(trade_index)).collect()
+    }
+
+    /// current stop level of an open trade (its stop loss, trailed if it is a trailing stop)
+    pub fn stop_level(&self, trade_index: usize) -> Option<f64> {
+        self.orders.iter()
+            .filter(|o| o.parent_trade == Some(trade_index))
+            .find_map(|o| o.stop)
+    }
+
+    // close_position: close one open trade using the current live prices; its stop loss is cancelled.
+    // without a quote for the instrument the trade stays open.
+    pub fn close_position(&mut self, trade_index: usize, index: usize) {
+        if trade_index >= self.trades.len() || !self.live_data.current.contains_key(&self.trades[trade_index].instrument) {
             return;
         }
         let trade = self.trades.remove(trade_index);
+        let cancelled = Self::reindex_contingent(&mut self.orders, trade_index);
+        self.record_cancelled(index, cancelled);
         if let Some(current_tick) = self.live_data.current.get(&trade.instrument) {
-            let exit_price = if trade.size > 0.0 { current_tick.ask } else { current_tick.bid };
+            let exit_price = if trade.size > 0.0 { current_tick.bid } else { current_tick.ask };
             let closed_trade = Trade {
                 size: trade.size,
                 entry_price: trade.entry_price,
                 entry_index: trade.entry_index,
                 exit_price: Some(exit_price),
-                exit_index: Some(0),
+                exit_index: Some(index),
                 sl_order: trade.sl_order,
                 tp_order: trade.tp_order,
                 instrument: trade.instrument.clone(),
+                tag: trade.tag.clone(),
+                expires_at: trade.expires_at,
+                ..Default::default()
             };
             self.live_cash += closed_trade.pnl();
             self.closed_trades.push(closed_trade);
             if trade.size > 0.0 {
-                println!("closed long on {}: {}", trade.instrument, exit_price);
+                self.reporter.info(format_args!("closed long on {}: {}", trade.instrument, exit_price));
             } else {
-                println!("closed short on {}: {}", trade.instrument, exit_price);
-            }
-        }
-    }
-
-    // close_all_trades: liquidate all open trades at current live prices.
-    pub fn close_all_trades(&mut self, _index: usize) {
-        let mut total_pnl = 0.0;
-        let trades: Vec<_> = self.trades.drain(..).collect();
-        for trade in trades {
-            if let Some(current_tick) = self.live_data.current.get(&trade.instrument) {
-                let exit_price = if trade.size > 0.0 { current_tick.ask } else { current_tick.bid };
-                let closed_trade = Trade {
-                    size: trade.size,
-                    entry_price: trade.entry_price,
-                    entry_index: trade.entry_index,
-                    exit_price: Some(exit_price),
-                    exit_index: Some(0),
-                    sl_order: trade.sl_order,
-                    tp_order: trade.tp_order,
-                    instrument: trade.instrument.clone(),
-                };
-                total_pnl += closed_trade.pnl();
-                self.closed_trades.push(closed_trade);
-                if trade.size > 0.0 {
-                    println!("closed long on {}: {}", trade.instrument, exit_price);
-                } else {
-                    println!("closed short on {}: {}", trade.instrument, exit_price);
-                }
+                self.reporter.info(format_args!("closed short on {}: {}", trade.instrument, exit_price));
             }
         }
-        self.live_cash += total_pnl;
+    }
+
+    // close_all_trades: liquidate all open trades at current live prices and cancel their stop losses;
+    // pending entries stay queued, and trades on instruments without a quote stay open.
+    pub fn close_all_trades(&mut self, index: usize) {
+        let mut i = 0;
+        while i < self.trades.len() {
+            if self.live_data.current.contains_key(&self.trades[i].instrument) {
+                self.close_position(i, index);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    // forced exit of the whole book (margin call, wiped out account): closes every trade and cancels all
+    // pending orders, entries included
+    fn liquidate(&mut self, index: usize) {
+        self.close_all_trades(index);
         self.orders.clear();
     }
 
+    // close trades whose holding period has elapsed on the broker clock
+    fn close_expired_trades(&mut self, index: usize) {
+        let now = self.clock.now();
+        for i in (0..self.trades.len()).rev() {
+            let expired = self.trades[i].expires_at.map(|t| now >= t).unwrap_or(false);
+            // wait for a quote so the trade is not dropped without an exit price
+            if expired && self.live_data.current.contains_key(&self.trades[i].instrument) {
+                self.close_position(i, index);
+                if let Some(closed) = self.closed_trades.last_mut() {
+                    closed.exit_tag = Some("time exit".to_string());
+                }
+            }
+        }
+    }
+
     // next: process one tick of live data.
     // In a backtest this could be called for each new tick, but here we assume that current prices come from the `current` snapshot.
     pub fn next(&mut self, index: usize) {
         self.max_live_concurrent_trades = self.max_live_concurrent_trades.max(self.trades.len());
+        self.process_retries(index);
         self.process_orders(index);
+        self.close_expired_trades(index);
         self.update_equity(index);
         self.check_margin_call(index);
         if *self.live_equity.last().unwrap_or(&self.live_cash) <= 0.0 {
-            self.close_all_trades(index);
+            self.liquidate(index);
             self.live_cash = 0.0;
             // Reset the equity history.
             self.live_equity.push(0.0);
@@ -407,8 +1048,8 @@ impl LiveBroker {
     fn check_margin_call(&mut self, index: usize) {
         let usage = self.current_margin_usage();
         if usage > Self::MARGIN_CALL_THRESHOLD {
-            println!("// margin call triggered at {:.2}% usage", usage * 100.0);
-            self.close_all_trades(index);
+            self.reporter.warn(format_args!("margin call triggered at {:.2}% usage", usage * 100.0));
+            self.liquidate(index);
             self.update_margin_usage();
         }
     }
@@ -417,8 +1058,30 @@ impl LiveBroker {
         (self.live_cash / self.live_margin) - self.current_exposure()
     }
 
+    // notional of open trades at entry prices that counts against margin, see MarginMode
     pub fn current_exposure(&self) -> f64 {
-        self.trades.iter().map(|trade| trade.size.abs() * trade.entry_price).sum()
+        self.margined_notional(0.0)
+    }
+
+    // margined notional of the open trades plus a new trade with this signed notional
+    fn margined_notional(&self, signed_notional: f64) -> f64 {
+        let (long, short) = self.trades.iter()
+            .map(|trade| trade.size * trade.entry_price)
+            .chain(std::iter::once(signed_notional))
+            .fold((0.0, 0.0), |(long, short), notional| {
+                if notional > 0.0 { (long + notional, short) } else { (long, short - notional) }
+            });
+        self.margin_mode.margined_notional(long, short)
+    }
+
+    // margined exposure a new trade adds; less than its notional if it offsets the book under MarginMode::Netted
+    fn margined_increase(&self, signed_notional: f64) -> f64 {
+        self.margined_notional(signed_notional) - self.current_exposure()
+    }
+
+    // configured spec of an instrument, or the account default
+    pub fn instrument_spec(&self, instrument: &str) -> InstrumentSpec {
+        self.instrument_specs.get(instrument).cloned().unwrap_or_else(|| InstrumentSpec::for_margin(self.live_margin))
     }
 
     pub fn current_margin_usage(&self) -> f64 {
@@ -453,34 +1116,222 @@ impl LiveBroker {
         self.live_margin_usage_history.push(usage);
     }
 
+    // read-only view of resting orders, optionally filtered by instrument and side.
+    pub fn pending_orders(&self, instrument: Option<&str>, side: Option<Side>) -> Vec<&Order> {
+        self.orders.iter()
+            .filter(|order| instrument.map_or(true, |inst| order.instrument == inst))
+            .filter(|order| side.map_or(true, |s| Side::of(order.size) == s))
+            .collect()
+    }
+
+    // resting entry orders only (no contingent orders attached to open trades)
+    pub fn pending_entry_orders(&self, instrument: Option<&str>, side: Option<Side>) -> Vec<&Order> {
+        self.pending_orders(instrument, side)
+            .into_iter()
+            .filter(|order| order.parent_trade.is_none())
+            .collect()
+    }
+
+    /// position per instrument the strategy is heading for: open trades plus resting market entries.
+    /// every quoted instrument is listed, so flat ones show up as 0.0.
+    pub fn target_positions(&self) -> BTreeMap<String, f64> {
+        let mut targets: BTreeMap<String, f64> = self.live_data.current.keys().map(|i| (i.clone(), 0.0)).collect();
+        let pending = self.orders.iter()
+            .filter(|order| order.parent_trade.is_none() && order.limit.is_none() && order.stop.is_none() && order.touch.is_none())
+            .map(|order| (&order.instrument, order.size));
+        for (instrument, size) in self.trades.iter().map(|t| (&t.instrument, t.size)).chain(pending) {
+            *targets.entry(instrument.clone()).or_insert(0.0) += size;
+        }
+        targets
+    }
+
+    // true if a working entry order already rests on this instrument and side
+    pub fn has_pending_entry(&self, instrument: &str, side: Side) -> bool {
+        !self.pending_entry_orders(Some(instrument), Some(side)).is_empty()
+    }
+
+    // age of the latest quote for an instrument according to the broker clock
+    pub fn quote_age(&self, instrument: &str) -> Option<chrono::Duration> {
+        let tick = self.live_data.current.get(instrument)?;
+        let quote_time = parse_quote_time(&tick.date)?;
+        Some(self.clock.now() - quote_time)
+    }
+
+    // true while a sequence gap on the instrument is unrepaired, or if max_quote_age is set and the
+    // latest quote is older than it (or missing)
+    pub fn is_quote_stale(&self, instrument: &str) -> bool {
+        if self.awaiting_snapshot.contains(instrument) {
+            return true;
+        }
+        match self.max_quote_age {
+            None => false,
+            Some(max_age) => {
+                if !self.live_data.current.contains_key(instrument) {
+                    return true;
+                }
+                // quotes without a parseable timestamp cannot be aged
+                self.quote_age(instrument).map(|age| age > max_age).unwrap_or(false)
+            }
+        }
+    }
+
+    // record a gap or resync reported by the stream
+    pub fn apply_stream_event(&mut self, tick: usize, event: &StreamEvent) {
+        match event {
+            StreamEvent::DataGap(gap) => {
+                self.reporter.warn(format_args!("data gap on {}: {} message(s) missed, holding orders until resynced", gap.instrument, gap.missed()));
+                self.awaiting_snapshot.insert(gap.instrument.clone());
+                self.data_gaps.push((tick, gap.clone()));
+            }
+            StreamEvent::Resynced(instrument) => {
+                if self.awaiting_snapshot.remove(instrument) {
+                    self.reporter.info(format_args!("{} resynced from snapshot", instrument));
+                }
+            }
+        }
+    }
+
+    // state of the session after `tick` as one serializable value
+    pub fn live_stats(&self, tick: usize) -> LiveStatsSnapshot {
+        let estimate = self.var_config.as_ref().map(|_| self.live_var(None));
+        LiveStatsSnapshot {
+            tick,
+            time: self.clock.now().to_rfc3339(),
+            cash: self.live_cash,
+            equity: *self.live_equity.last().unwrap_or(&self.live_cash),
+            margin_usage: self.current_margin_usage(),
+            max_margin_usage: self.live_max_margin_usage,
+            open_trades: self.trades.iter().map(|trade| OpenTradeStats {
+                instrument: trade.instrument.clone(),
+                size: trade.size,
+                entry_price: trade.entry_price,
+                entry_index: trade.entry_index,
+                unrealized_pnl: self.unrealized_pnl(trade),
+                tag: trade.tag.clone(),
+            }).collect(),
+            closed_trades: self.closed_trades.len(),
+            realized_pnl: self.closed_trades.iter().map(|trade| trade.pnl()).sum(),
+            last_prices: self.live_data.current.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            execution: self.execution_quality(),
+            var: estimate.map(|e| e.var),
+            es: estimate.map(|e| e.es),
+            rejected_orders: self.live_rejected_orders.len(),
+            retries: self.retry_stats(),
+            size_multiplier: self.size_multiplier,
+            reconciliation: None,
+        }
+    }
+
     // new method to print basic live trading stats in one console line.
     pub fn print_live_stats(&self, tick: usize) {
+        let stats = self.live_stats(tick);
+        let var = match (self.var_config.as_ref(), stats.var, stats.es) {
+            (Some(config), Some(var), Some(es)) => format!(" | var({:.0}%): {:.2} | es: {:.2}", config.confidence * 100.0, var, es),
+            _ => String::new(),
+        };
         println!(
-            "\n tick: {} | cash: {:.2} | open trades: {} | closed trades: {} | equity: {:.2} | margin usage: {:.2}% \n",
-            tick,
-            self.live_cash,
-            self.trades.len(),
-            self.closed_trades.len(),
-            self.live_equity.last().unwrap_or(&self.live_cash),
-            self.current_margin_usage() * 100.0
+            "\n tick: {} | cash: {:.2} | open trades: {} | closed trades: {} | equity: {:.2} | margin usage: {:.2}% | avg spread: {:.4} | avg slippage: {:.4}{} \n",
+            stats.tick,
+            stats.cash,
+            stats.open_trades.len(),
+            stats.closed_trades,
+            stats.equity,
+            stats.margin_usage * 100.0,
+            stats.execution.avg_spread,
+            stats.execution.avg_slippage,
+            var
         );
     }
+
+    pub fn execution_quality(&self) -> ExecutionQuality {
+        ExecutionQuality::from_fills(&self.fills)
+    }
 }
 
 /// Strategy trait remains similar.
 pub trait LiveStrategy {
     fn init(&mut self, broker: &mut LiveBroker, data: &LiveData);
     fn next(&mut self, broker: &mut LiveBroker, index: usize);
+    // called once after init with the backfilled history so lookback buffers start full
+    fn warm_up(&mut self, _history: &[TickSnapshot]) {}
+    // called for every stream event after the broker has recorded it, before the ticks that came with it
+    fn on_stream_event(&mut self, _broker: &mut LiveBroker, _event: &StreamEvent) {}
+    // strategy-specific (series id, value) pairs, e.g. live model hit rate; published with the other series
+    fn metrics(&self) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+    // the strategy's own position bookkeeping, reconciled against the broker's trades after every batch
+    fn positions(&self) -> Option<&PositionManager> {
+        None
+    }
 }
 
 pub type LiveStrategyRef = Box<dyn LiveStrategy>;
 
+/// throttle of the equity callbacks: they fire once `interval` has passed on the broker clock or `ticks`
+/// ticks have been processed since the last call, whichever comes first (both None fires after every
+/// batch of ticks). equity in between is folded into an EquityBatch, so no extreme is lost.
+#[derive(Clone, Debug, Default)]
+pub struct EquityThrottle {
+    pub interval: Option<chrono::Duration>,
+    pub ticks: Option<usize>,
+}
+
+// equity over the ticks since the last equity callback
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EquityBatch {
+    pub ticks: usize,
+    pub first: f64,
+    pub min: f64,
+    pub max: f64,
+    pub last: f64,
+}
+
+impl EquityBatch {
+    fn new(equity: f64) -> Self {
+        EquityBatch { ticks: 1, first: equity, min: equity, max: equity, last: equity }
+    }
+
+    fn push(&mut self, equity: f64) {
+        self.ticks += 1;
+        self.min = self.min.min(equity);
+        self.max = self.max.max(equity);
+        self.last = equity;
+    }
+}
+
 /// The backtest driver.
 pub struct LiveBacktest {
     pub data: LiveData,
     pub broker: LiveBroker,
     pub strategy: LiveStrategyRef,
     equity_callback: Option<Box<dyn Fn(f64) + Send + Sync>>,
+    // called with min/max/last equity since the previous call, on the same schedule as equity_callback
+    equity_batch_callback: Option<Box<dyn Fn(&EquityBatch) + Send + Sync>>,
+    pub equity_throttle: EquityThrottle,
+    equity_batch: Option<EquityBatch>,
+    last_equity_call: Option<chrono::DateTime<chrono::Utc>>,
+    // called with (series id, value) for margin usage, each instrument's mid price and the strategy's metrics
+    series_callback: Option<Box<dyn Fn(&str, f64) + Send + Sync>>,
+    // called in order with the session stats after every batch of ticks
+    stats_callbacks: Vec<Box<dyn Fn(&LiveStatsSnapshot) + Send + Sync>>,
+    // called with every new fill and the quote time of its tick, after the batch it happened in
+    fill_callback: Option<Box<dyn Fn(&FillRecord, &str) + Send + Sync>>,
+    fills_reported: usize,
+    // latest strategy vs broker reconciliation, None if the strategy reports no positions
+    reconciliation: Option<PositionReconciliation>,
+    // target positions written after every tick, see export_signals
+    signals: Option<SignalWriter>,
+    // manual commands, handled between batches of ticks
+    commands: Option<UnboundedReceiver<LiveCommand>>,
+    // while paused the strategy is not called; the broker keeps processing orders and exits
+    pub paused: bool,
+}
+
+// next input of the run loop
+enum RunInput {
+    Data(Option<LiveData>),
+    Command(Option<LiveCommand>),
 }
 
 impl LiveBacktest {
@@ -508,6 +1359,18 @@ impl LiveBacktest {
             broker,
             strategy: live_strategy,
             equity_callback: None,
+            equity_batch_callback: None,
+            equity_throttle: EquityThrottle::default(),
+            equity_batch: None,
+            last_equity_call: None,
+            series_callback: None,
+            stats_callbacks: Vec::new(),
+            fill_callback: None,
+            fills_reported: 0,
+            reconciliation: None,
+            signals: None,
+            commands: None,
+            paused: false,
         }
     }
 
@@ -518,36 +1381,340 @@ impl LiveBacktest {
         self.equity_callback = Some(Box::new(callback));
     }
 
+    // e.g. for candles that keep the highs and lows of throttled equity
+    pub fn set_equity_batch_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&EquityBatch) + Send + Sync + 'static,
+    {
+        self.equity_batch_callback = Some(Box::new(callback));
+    }
+
+    // call the equity callbacks at most every `interval` and/or every `ticks` ticks
+    pub fn set_equity_throttle(&mut self, interval: Option<chrono::Duration>, ticks: Option<usize>) {
+        self.equity_throttle = EquityThrottle { interval, ticks };
+    }
+
+    fn current_equity(&self) -> f64 {
+        *self.broker.live_equity.last().unwrap_or(&self.broker.live_cash)
+    }
+
+    // whether the equity gathered since the last call is due; the first batch always is
+    fn equity_due(&self) -> bool {
+        let Some(batch) = self.equity_batch.as_ref() else {
+            return false;
+        };
+        let throttle = &self.equity_throttle;
+        let by_time = match (throttle.interval, self.last_equity_call) {
+            (Some(interval), Some(last)) => self.broker.clock.now() - last >= interval,
+            (_, None) => true,
+            (None, Some(_)) => false,
+        };
+        let by_ticks = throttle.ticks.is_some_and(|n| batch.ticks >= n);
+        let every_batch = throttle.interval.is_none() && throttle.ticks.is_none();
+        by_time || by_ticks || every_batch
+    }
+
+    // hand the gathered equity to the callbacks and start a new batch
+    fn flush_equity(&mut self) {
+        let Some(batch) = self.equity_batch.take() else {
+            return;
+        };
+        self.last_equity_call = Some(self.broker.clock.now());
+        if let Some(ref callback) = self.equity_callback {
+            callback(batch.last);
+        }
+        if let Some(ref callback) = self.equity_batch_callback {
+            callback(&batch);
+        }
+    }
+
+    /// write the target position of each instrument to `path` whenever it changes, at the quote time of the
+    /// tick that changed it, e.g. for an external execution system
+    pub fn export_signals(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.signals = Some(SignalWriter::create(path)?);
+        Ok(())
+    }
+
+    // write the targets after `tick` has been processed; a failed write stops the export
+    fn record_signals(&mut self, tick: usize) {
+        let Some(writer) = self.signals.as_mut() else {
+            return;
+        };
+        let time = self.broker.live_data.ticks.get(tick).map(|t| t.date.clone()).unwrap_or_default();
+        let targets = self.broker.target_positions();
+        let rows: Vec<(&str, f64)> = targets.iter().map(|(instrument, target)| (instrument.as_str(), *target)).collect();
+        if let Err(e) = writer.update(&time, &rows) {
+            self.broker.reporter.warn(format!("error writing signals, export stopped: {}", e));
+            self.signals = None;
+        }
+    }
+
+    // replace the broker's time source, e.g. with a TestClock in tests
+    pub fn set_clock(&mut self, clock: ClockRef) {
+        self.broker.clock = clock;
+    }
+
+    pub fn set_series_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&str, f64) + Send + Sync + 'static,
+    {
+        self.series_callback = Some(Box::new(callback));
+    }
+
+    // replaces any stats callbacks set before
+    pub fn set_stats_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&LiveStatsSnapshot) + Send + Sync + 'static,
+    {
+        self.stats_callbacks = vec![Box::new(callback)];
+    }
+
+    // another consumer of the stats (e.g. a telemetry sink next to the chart server)
+    pub fn add_stats_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&LiveStatsSnapshot) + Send + Sync + 'static,
+    {
+        self.stats_callbacks.push(Box::new(callback));
+    }
+
+    pub fn set_fill_callback<F>(&mut self, callback: F)
+    where
+        F: Fn(&FillRecord, &str) + Send + Sync + 'static,
+    {
+        self.fill_callback = Some(Box::new(callback));
+    }
+
+    pub fn reconciliation(&self) -> Option<&PositionReconciliation> {
+        self.reconciliation.as_ref()
+    }
+
+    // compare the strategy's positions with the broker's after `tick`, warning when they start to disagree
+    // and when they agree again
+    fn reconcile(&mut self, tick: usize) {
+        let Some(positions) = self.strategy.positions() else {
+            self.reconciliation = None;
+            return;
+        };
+        let mut current = PositionReconciliation::new(positions, &self.broker.trades, &self.broker.orders);
+        let since = self.reconciliation.as_ref().and_then(|r| r.mismatch_since);
+        if current.is_mismatch() {
+            current.mismatch_since = Some(since.unwrap_or(tick));
+            if since.is_none() {
+                self.broker.reporter.warn(format_args!(
+                    "position mismatch at tick {}: strategy {} long / {} short, broker {} long / {} short ({} / {} pending)",
+                    tick, current.strategy_longs, current.strategy_shorts, current.broker_longs,
+                    current.broker_shorts, current.pending_longs, current.pending_shorts
+                ));
+            }
+        } else if let Some(since) = since {
+            self.broker.reporter.info(format_args!("positions reconciled at tick {} (mismatched since tick {})", tick, since));
+        }
+        self.reconciliation = Some(current);
+    }
+
+    // accept LiveCommands on this channel while running
+    pub fn set_command_channel(&mut self, commands: UnboundedReceiver<LiveCommand>) {
+        self.commands = Some(commands);
+    }
+
+    // apply one manual command at `tick`; the outcome is reported on the console
+    pub fn apply_command(&mut self, command: LiveCommand, tick: usize) -> Result<(), String> {
+        let result = match &command {
+            LiveCommand::Pause => {
+                self.paused = true;
+                Ok(())
+            }
+            LiveCommand::Resume => {
+                self.paused = false;
+                Ok(())
+            }
+            LiveCommand::CloseTrade { trade } => {
+                match self.broker.trades.get(*trade) {
+                    None => Err(format!("no open trade {}", trade)),
+                    Some(open) if !self.broker.live_data.current.contains_key(&open.instrument) => {
+                        Err(format!("no quote for {}", open.instrument))
+                    }
+                    Some(_) => {
+                        self.broker.close_position(*trade, tick);
+                        Ok(())
+                    }
+                }
+            }
+            LiveCommand::CloseAll => {
+                self.broker.close_all_trades(tick);
+                Ok(())
+            }
+            LiveCommand::SubmitOrder { instrument, size, limit, stop, sl, tp } => {
+                match self.broker.live_data.current.get(instrument) {
+                    None => Err(format!("no quote for {}", instrument)),
+                    Some(quote) => {
+                        let price = if *size > 0.0 { quote.ask } else { quote.bid };
+                        let order = Order {
+                            size: *size,
+                            limit: *limit,
+                            stop: *stop,
+                            sl: *sl,
+                            tp: *tp,
+                            instrument: instrument.clone(),
+                            tag: Some("manual".to_string()),
+                            ..Default::default()
+                        };
+                        self.broker.new_order(order, price).map_err(|e| format!("order rejected: {:?}", e))
+                    }
+                }
+            }
+        };
+        match &result {
+            Ok(()) => self.broker.reporter.info(format_args!("command {:?} applied", command)),
+            Err(e) => self.broker.reporter.warn(format_args!("command {:?} failed: {}", command, e)),
+        }
+        result
+    }
+
+    // Prepend historical ticks (oldest first) to the live history; they are handed to the
+    // strategy's warm_up at the start of run but never traded on.
+    pub fn preload_history(&mut self, history: Vec<TickSnapshot>) {
+        if history.is_empty() {
+            return;
+        }
+        let mut ticks = history;
+        ticks.extend(self.broker.live_data.ticks.drain(..));
+        for tick_snapshot in ticks.iter() {
+            self.broker.live_data.current.insert(tick_snapshot.instrument.clone(), tick_snapshot.clone());
+        }
+        self.broker.live_data.ticks = ticks;
+        self.data = self.broker.live_data.clone();
+    }
+
     // The run method now expects incoming LiveData (hybrid type).
     // For each incoming snapshot, we append its ticks to our history and update the current snapshot.
     pub async fn run(&mut self, mut rx: UnboundedReceiver<LiveData>) {
         // init strategy with initial live data
         self.strategy.init(&mut self.broker, &self.data);
+        self.strategy.warm_up(&self.broker.live_data.ticks);
         let mut tick: usize = self.broker.live_data.ticks.len();
-        while let Some(new_data) = rx.recv().await {
-            // Append incoming ticks to the history.
-            self.broker.live_data.ticks.extend(new_data.ticks.iter().cloned());
-            // Update the current snapshot for each tick.
-            for tick_snapshot in new_data.ticks.iter() {
-                self.broker
-                    .live_data
-                    .current
-                    .insert(tick_snapshot.instrument.clone(), tick_snapshot.clone());
-            }
-            // Determine the new tick count.
-            let new_tick_count = self.broker.live_data.ticks.len();
-            // Process each newly appended tick.
-            for _ in tick..new_tick_count {
+        loop {
+            let input = match self.commands.as_mut() {
+                Some(commands) => tokio::select! {
+                    data = rx.recv() => RunInput::Data(data),
+                    command = commands.recv() => RunInput::Command(command),
+                },
+                None => RunInput::Data(rx.recv().await),
+            };
+            let new_data = match input {
+                RunInput::Data(Some(new_data)) => new_data,
+                RunInput::Data(None) => break,
+                RunInput::Command(Some(command)) => {
+                    let _ = self.apply_command(command, tick.saturating_sub(1));
+                    continue;
+                }
+                RunInput::Command(None) => {
+                    self.commands = None;
+                    continue;
+                }
+            };
+            tick = self.process_data(new_data, tick);
+        }
+
+        // equity still held back by the throttle
+        self.flush_equity();
+
+        // end-of-session report
+        println!("{}", self.broker.execution_quality());
+        if self.broker.retry_policy.is_some() {
+            let retries = self.broker.retry_stats();
+            println!("order retries: {} resubmitted, {} placed, {} abandoned, {} still queued",
+                retries.retried, retries.succeeded, retries.abandoned, retries.pending);
+        }
+        for (series_id, value) in self.strategy.metrics() {
+            println!("{:<25} {:>10.4}", series_id, value);
+        }
+    }
+
+    // apply one batch of stream data: events first, then strategy and broker for every new tick, then the
+    // callbacks; returns the index of the next tick
+    fn process_data(&mut self, new_data: LiveData, mut tick: usize) -> usize {
+        for event in new_data.events.iter() {
+            self.broker.apply_stream_event(tick, event);
+            self.strategy.on_stream_event(&mut self.broker, event);
+        }
+        // Append incoming ticks to the history.
+        self.broker.live_data.ticks.extend(new_data.ticks.iter().cloned());
+        // Determine the new tick count.
+        let new_tick_count = self.broker.live_data.ticks.len();
+        // Process each newly appended tick; the current snapshot only moves up to the tick being processed,
+        // so a batch of quotes doesn't let the strategy or the broker see the later ones early.
+        for _ in tick..new_tick_count {
+            let tick_snapshot = self.broker.live_data.ticks[tick].clone();
+            self.broker.live_data.current.insert(tick_snapshot.instrument.clone(), tick_snapshot);
+            if !self.paused {
                 self.strategy.next(&mut self.broker, tick);
-                self.broker.next(tick);
+            }
+            self.broker.next(tick);
+            self.record_signals(tick);
+            if self.broker.reporter.summary_due(self.broker.clock.now()) {
                 self.broker.print_live_stats(tick);
-                tick += 1;
             }
+            let equity = self.current_equity();
+            match self.equity_batch.as_mut() {
+                Some(batch) => batch.push(equity),
+                None => self.equity_batch = Some(EquityBatch::new(equity)),
+            }
+            tick += 1;
+        }
+        self.reconcile(tick.saturating_sub(1));
+
+        if self.equity_due() {
+            self.flush_equity();
+        }
 
-            if let Some(ref callback) = self.equity_callback {
-                let current_equity = *self.broker.live_equity.last().unwrap_or(&self.broker.live_cash);
-                callback(current_equity);
+        if let Some(ref callback) = self.series_callback {
+            callback("margin_usage", self.broker.current_margin_usage());
+            for (instrument, snapshot) in self.broker.live_data.current.iter() {
+                callback(instrument, (snapshot.ask + snapshot.bid) / 2.0);
+            }
+            for (series_id, value) in self.strategy.metrics() {
+                callback(&series_id, value);
+            }
+            if let Some(reconciliation) = self.reconciliation.as_ref() {
+                callback("position_mismatch", if reconciliation.is_mismatch() { 1.0 } else { 0.0 });
+            }
+        }
+
+        if !self.stats_callbacks.is_empty() {
+            let mut stats = self.broker.live_stats(tick.saturating_sub(1));
+            stats.reconciliation = self.reconciliation.clone();
+            for callback in self.stats_callbacks.iter() {
+                callback(&stats);
+            }
+        }
+
+        if let Some(ref callback) = self.fill_callback {
+            for fill in self.broker.fills[self.fills_reported..].iter() {
+                let time = self.broker.live_data.ticks.get(fill.tick).map(|t| t.date.as_str()).unwrap_or("");
+                callback(fill, time);
+            }
+        }
+        self.fills_reported = self.broker.fills.len();
+        tick
+    }
+
+    /// replay recorded quotes through the strategy and broker one tick at a time, without a stream.
+    /// `clock` becomes the broker clock and is set to each quote's time before it is processed, so quote
+    /// ages and holding durations follow the recording instead of the wall clock.
+    pub fn replay(&mut self, ticks: &[TickSnapshot], clock: Arc<TestClock>) {
+        self.set_clock(clock.clone());
+        self.strategy.init(&mut self.broker, &self.data);
+        self.strategy.warm_up(&self.broker.live_data.ticks);
+        let mut tick = self.broker.live_data.ticks.len();
+        for snapshot in ticks.iter() {
+            if let Some(time) = parse_quote_time(&snapshot.date) {
+                clock.set(time);
             }
+            let mut current = HashMap::new();
+            current.insert(snapshot.instrument.clone(), snapshot.clone());
+            tick = self.process_data(LiveData { ticks: vec![snapshot.clone()], current, events: Vec::new() }, tick);
         }
+        self.flush_equity();
     }
 }
@@ -0,0 +1,166 @@
+// run manifests: everything needed to reproduce a backtest, stored as json next to its results
+use crate::data_handler::handle_ohlc;
+use crate::engine::{Backtest, CashFlow, StrategyRef};
+use crate::instrument::InstrumentSpec;
+use crate::risk::MarginMode;
+use crate::params::Parameterized;
+use crate::strategies::simple_strategy::SimpleStrategy;
+use crate::strategies::sma::SmaStrategy;
+use crate::strategies::statarb_spread::StatArbSpreadStrategy;
+use crate::util::content_hash;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BrokerSettings {
+    pub cash: f64,
+    pub commission: f64,
+    // None in manifests written before it was captured: the broker default (the taker commission)
+    #[serde(default)]
+    pub maker_commission: Option<f64>,
+    pub bidask_spread: f64,
+    pub margin: f64,
+    #[serde(default)]
+    pub margin_mode: MarginMode,
+    #[serde(default)]
+    pub instrument_specs: BTreeMap<u8, InstrumentSpec>,
+    pub trade_on_close: bool,
+    pub hedging: bool,
+    pub exclusive_orders: bool,
+    #[serde(default)]
+    pub legacy_exclusive_orders: bool,
+    pub scaling_enabled: bool,
+    pub execution_delay: usize,
+    pub financing_rate: f64,
+    #[serde(default)]
+    pub cash_flows: Vec<CashFlow>,
+}
+
+/// strategy, data and settings of one run. broker settings beyond `BrokerSettings` (exposure caps, var
+/// budget, drawdown governor, quote model, ...) are not captured and have to be applied again by the caller.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RunManifest {
+    pub engine_version: String,
+    pub created: String,
+    pub strategy: String,
+    pub params: BTreeMap<String, f64>,
+    pub data_path: String,
+    pub data_hash: String, // fnv-1a of the csv bytes, hex
+    pub first_date: Option<String>,
+    pub last_date: Option<String>,
+    pub seed: u64,
+    pub broker: BrokerSettings,
+}
+
+// hex content hash of a file
+pub fn file_hash(path: &str) -> Result<String, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    Ok(format!("{:016x}", content_hash(&bytes)))
+}
+
+impl RunManifest {
+    /// describe a configured backtest; `strategy` must be a name known to `strategy_from_name`
+    /// and `params` its parameter values (e.g. `Parameterized::param_values`)
+    pub fn capture(backtest: &Backtest, strategy: &str, params: Vec<(String, f64)>, data_path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(RunManifest {
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            created: chrono::Utc::now().to_rfc3339(),
+            strategy: strategy.to_string(),
+            params: params.into_iter().collect(),
+            data_path: data_path.to_string(),
+            data_hash: file_hash(data_path)?,
+            first_date: backtest.data.date.first().cloned(),
+            last_date: backtest.data.date.last().cloned(),
+            seed: backtest.broker.seed,
+            broker: BrokerSettings {
+                cash: backtest.cash,
+                commission: backtest.commission,
+                maker_commission: Some(backtest.broker.maker_commission),
+                bidask_spread: backtest.bidask_spread,
+                margin: backtest.margin,
+                margin_mode: backtest.broker.margin_mode,
+                instrument_specs: backtest.broker.instrument_specs.iter().map(|(k, v)| (*k, v.clone())).collect(),
+                trade_on_close: backtest.trade_on_close,
+                hedging: backtest.hedging,
+                exclusive_orders: backtest.exclusive_orders,
+                legacy_exclusive_orders: backtest.broker.legacy_exclusive_orders,
+                scaling_enabled: backtest.broker.scaling_enabled,
+                execution_delay: backtest.broker.execution_delay,
+                financing_rate: backtest.broker.financing_rate,
+                cash_flows: backtest.broker.cash_flows.clone(),
+            },
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+fn configured<S>(mut strategy: S, params: &BTreeMap<String, f64>) -> Result<StrategyRef, Box<dyn Error>>
+where
+    S: crate::engine::Strategy + Parameterized + 'static,
+{
+    for (name, value) in params.iter() {
+        strategy.apply_param(name, *value)?;
+    }
+    Ok(Box::new(strategy))
+}
+
+// build a strategy by its manifest name with the given parameters
+pub fn strategy_from_name(name: &str, params: &BTreeMap<String, f64>) -> Result<StrategyRef, Box<dyn Error>> {
+    match name {
+        "statarb_spread" => configured(StatArbSpreadStrategy::new(), params),
+        "sma" => configured(SmaStrategy::new(), params),
+        "simple" => Ok(Box::new(SimpleStrategy::new())),
+        _ => Err(format!("unknown strategy in manifest: {}", name).into()),
+    }
+}
+
+impl Backtest {
+    /// rebuild the backtest described by a manifest; fails if the data file changed since it was written
+    pub fn from_manifest(path: &str) -> Result<Backtest, Box<dyn Error>> {
+        let manifest = RunManifest::load(path)?;
+        if manifest.engine_version != env!("CARGO_PKG_VERSION") {
+            eprintln!("warning: manifest written by engine {}, running {}", manifest.engine_version, env!("CARGO_PKG_VERSION"));
+        }
+        let hash = file_hash(&manifest.data_path)?;
+        if hash != manifest.data_hash {
+            return Err(format!("data file {} changed since the manifest was written (hash {} != {})",
+                manifest.data_path, hash, manifest.data_hash).into());
+        }
+
+        let data = handle_ohlc(&manifest.data_path)?.slice_dates(manifest.first_date.as_deref(), manifest.last_date.as_deref());
+        let strategy = strategy_from_name(&manifest.strategy, &manifest.params)?;
+        let settings = &manifest.broker;
+        let mut backtest = Backtest::new(
+            data,
+            strategy,
+            settings.cash,
+            settings.commission,
+            settings.bidask_spread,
+            settings.margin,
+            settings.trade_on_close,
+            settings.hedging,
+            settings.exclusive_orders,
+            settings.scaling_enabled,
+        );
+        backtest.broker.execution_delay = settings.execution_delay;
+        backtest.broker.financing_rate = settings.financing_rate;
+        backtest.broker.legacy_exclusive_orders = settings.legacy_exclusive_orders;
+        if let Some(maker_commission) = settings.maker_commission {
+            backtest.broker.maker_commission = maker_commission;
+        }
+        backtest.broker.margin_mode = settings.margin_mode;
+        backtest.broker.instrument_specs = settings.instrument_specs.iter().map(|(k, v)| (*k, v.clone())).collect();
+        backtest.broker.set_cash_flows(settings.cash_flows.clone());
+        backtest.broker.set_seed(manifest.seed);
+        Ok(backtest)
+    }
+}
@@ -0,0 +1,326 @@
+// parameter sweeps: run a backtest over a 2d grid of strategy parameters
+use crate::engine::{Backtest, Strategy, StrategyRef};
+use crate::params::{ParamError, Parameterized};
+use crate::plot::plot_parameter_heatmap;
+use crate::robustness::{outcome, RunOutcome};
+use crate::stats::StatsFormat;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+#[derive(Clone, Debug)]
+pub struct GridResult {
+    pub param1_name: String,
+    pub param1: Vec<f64>,
+    pub param2_name: String,
+    pub param2: Vec<f64>,
+    // outcomes[i][j] is the run with (param1[i], param2[j])
+    pub outcomes: Vec<Vec<RunOutcome>>,
+}
+
+/// run one backtest per (p1, p2) pair; `make_backtest` must build a fresh backtest
+/// (including a fresh strategy instance) configured with the given parameters.
+pub fn run_grid_2d<F>(
+    make_backtest: F,
+    param1_name: &str,
+    param1: &[f64],
+    param2_name: &str,
+    param2: &[f64],
+    risk_free_rate: f64,
+) -> GridResult
+where
+    F: Fn(f64, f64) -> Backtest,
+{
+    let outcomes = param1.iter()
+        .map(|&p1| {
+            param2.iter()
+                .map(|&p2| {
+                    let mut backtest = make_backtest(p1, p2);
+                    backtest.run_silent();
+                    outcome(&backtest, Some(backtest.broker.seed), risk_free_rate)
+                })
+                .collect()
+        })
+        .collect();
+
+    GridResult {
+        param1_name: param1_name.to_string(),
+        param1: param1.to_vec(),
+        param2_name: param2_name.to_string(),
+        param2: param2.to_vec(),
+        outcomes,
+    }
+}
+
+/// destination of sweep results, written one row per run as soon as it finishes, so a long sweep can be
+/// watched with external tools while it runs and keeps its completed runs if it crashes
+pub trait ResultSink {
+    fn record(&mut self, param1: f64, param2: f64, outcome: &RunOutcome) -> Result<(), Box<dyn Error>>;
+}
+
+// csv with one row per run, flushed after every row
+pub struct CsvResultSink {
+    writer: csv::Writer<File>,
+}
+
+impl CsvResultSink {
+    /// create (or truncate) `path`; the first two columns are named after the swept parameters
+    pub fn create(path: &str, param1_name: &str, param2_name: &str) -> Result<Self, Box<dyn Error>> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record([param1_name, param2_name, "seed", "return_pct", "sharpe_ratio", "max_drawdown_pct", "num_trades", "finished_at"])?;
+        writer.flush()?;
+        Ok(CsvResultSink { writer })
+    }
+}
+
+impl ResultSink for CsvResultSink {
+    fn record(&mut self, param1: f64, param2: f64, outcome: &RunOutcome) -> Result<(), Box<dyn Error>> {
+        self.writer.write_record([
+            param1.to_string(),
+            param2.to_string(),
+            outcome.seed.map(|s| s.to_string()).unwrap_or_default(),
+            outcome.return_pct.to_string(),
+            outcome.sharpe_ratio.to_string(),
+            outcome.max_drawdown_pct.to_string(),
+            outcome.num_trades.to_string(),
+            chrono::Utc::now().to_rfc3339(),
+        ])?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// sqlite table with one row per run (only with the "sqlite" feature). every insert commits on its own,
+// and the database runs in wal mode so other processes can query it during the sweep.
+#[cfg(feature = "sqlite")]
+pub struct SqliteResultSink {
+    conn: rusqlite::Connection,
+    table: String,
+    param1_name: String,
+    param2_name: String,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteResultSink {
+    /// open (or create) the database at `path` and create `table` if it does not exist; rows of earlier
+    /// sweeps in the same table are kept
+    pub fn open(path: &str, table: &str, param1_name: &str, param2_name: &str) -> Result<Self, Box<dyn Error>> {
+        if table.is_empty() || !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("invalid table name '{}'", table).into());
+        }
+        let conn = rusqlite::Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY,
+                param1 TEXT NOT NULL,
+                value1 REAL NOT NULL,
+                param2 TEXT NOT NULL,
+                value2 REAL NOT NULL,
+                seed INTEGER,
+                return_pct REAL,
+                sharpe_ratio REAL,
+                max_drawdown_pct REAL,
+                num_trades INTEGER,
+                finished_at TEXT NOT NULL
+            )",
+            table
+        ))?;
+        Ok(SqliteResultSink {
+            conn,
+            table: table.to_string(),
+            param1_name: param1_name.to_string(),
+            param2_name: param2_name.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl ResultSink for SqliteResultSink {
+    fn record(&mut self, param1: f64, param2: f64, outcome: &RunOutcome) -> Result<(), Box<dyn Error>> {
+        self.conn.execute(
+            &format!(
+                "INSERT INTO {} (param1, value1, param2, value2, seed, return_pct, sharpe_ratio, max_drawdown_pct, num_trades, finished_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                self.table
+            ),
+            rusqlite::params![
+                self.param1_name,
+                param1,
+                self.param2_name,
+                param2,
+                outcome.seed.map(|s| s as i64),
+                outcome.return_pct,
+                outcome.sharpe_ratio,
+                outcome.max_drawdown_pct,
+                outcome.num_trades as i64,
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// run_grid_2d on `threads` worker threads (0 uses every available core). every run is handed to `sink`
+/// as soon as it finishes, in completion order; a failed write is reported and the sweep continues.
+/// `make_backtest` is called on the worker threads, so each backtest is built and dropped there.
+pub fn run_grid_2d_parallel<F>(
+    make_backtest: F,
+    param1_name: &str,
+    param1: &[f64],
+    param2_name: &str,
+    param2: &[f64],
+    risk_free_rate: f64,
+    threads: usize,
+    mut sink: Option<&mut dyn ResultSink>,
+) -> GridResult
+where
+    F: Fn(f64, f64) -> Backtest + Sync,
+{
+    let jobs: Vec<(usize, usize)> = (0..param1.len())
+        .flat_map(|i| (0..param2.len()).map(move |j| (i, j)))
+        .collect();
+    let threads = if threads == 0 {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    } else {
+        threads
+    };
+    let threads = threads.min(jobs.len()).max(1);
+
+    let mut outcomes: Vec<Vec<Option<RunOutcome>>> = vec![vec![None; param2.len()]; param1.len()];
+    let next_job = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            let tx = tx.clone();
+            let (jobs, next_job, make_backtest) = (&jobs, &next_job, &make_backtest);
+            scope.spawn(move || {
+                // workers pull the next grid point until none is left
+                while let Some(&(i, j)) = jobs.get(next_job.fetch_add(1, Ordering::Relaxed)) {
+                    let mut backtest = make_backtest(param1[i], param2[j]);
+                    backtest.run_silent();
+                    let result = outcome(&backtest, Some(backtest.broker.seed), risk_free_rate);
+                    if tx.send((i, j, result)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for (i, j, result) in rx {
+            if let Some(sink) = sink.as_mut() {
+                if let Err(e) = sink.record(param1[i], param2[j], &result) {
+                    eprintln!("failed to write sweep result ({} = {}, {} = {}): {}", param1_name, param1[i], param2_name, param2[j], e);
+                }
+            }
+            outcomes[i][j] = Some(result);
+        }
+    });
+
+    GridResult {
+        param1_name: param1_name.to_string(),
+        param1: param1.to_vec(),
+        param2_name: param2_name.to_string(),
+        param2: param2.to_vec(),
+        // every job sent its outcome unless a worker panicked, which the scope has re-raised by now
+        outcomes: outcomes.into_iter().map(|row| row.into_iter().map(|o| o.expect("missing grid run")).collect()).collect(),
+    }
+}
+
+/// sweep two declared parameters of a strategy over the ranges from its `param_specs`.
+/// `make_strategy` builds a default instance, `make_backtest` wraps a configured strategy in a fresh backtest.
+pub fn run_param_grid<S, M, B>(
+    make_strategy: M,
+    make_backtest: B,
+    param1: &str,
+    param2: &str,
+    risk_free_rate: f64,
+) -> Result<GridResult, ParamError>
+where
+    S: Strategy + Parameterized + 'static,
+    M: Fn() -> S,
+    B: Fn(StrategyRef) -> Backtest,
+{
+    let template = make_strategy();
+    let spec1 = template.param_spec(param1).ok_or_else(|| ParamError::Unknown(param1.to_string()))?;
+    let spec2 = template.param_spec(param2).ok_or_else(|| ParamError::Unknown(param2.to_string()))?;
+
+    Ok(run_grid_2d(
+        |p1, p2| {
+            let mut strategy = make_strategy();
+            strategy.set_param(param1, p1);
+            strategy.set_param(param2, p2);
+            make_backtest(Box::new(strategy))
+        },
+        param1,
+        &spec1.values(),
+        param2,
+        &spec2.values(),
+        risk_free_rate,
+    ))
+}
+
+impl GridResult {
+    pub fn sharpe_grid(&self) -> Vec<Vec<f64>> {
+        self.outcomes.iter().map(|row| row.iter().map(|o| o.sharpe_ratio).collect()).collect()
+    }
+
+    pub fn return_grid(&self) -> Vec<Vec<f64>> {
+        self.outcomes.iter().map(|row| row.iter().map(|o| o.return_pct).collect()).collect()
+    }
+
+    // (p1, p2, outcome) of the run with the highest sharpe
+    pub fn best(&self) -> Option<(f64, f64, &RunOutcome)> {
+        self.outcomes.iter().enumerate()
+            .flat_map(|(i, row)| row.iter().enumerate().map(move |(j, o)| (i, j, o)))
+            .filter(|(_, _, o)| o.sharpe_ratio.is_finite())
+            .max_by(|a, b| a.2.sharpe_ratio.partial_cmp(&b.2.sharpe_ratio).unwrap())
+            .map(|(i, j, o)| (self.param1[i], self.param2[j], o))
+    }
+
+    // one summary line per run, e.g. "lookback = 20, zscore_threshold = 1.5 | return 3.21% | sharpe 1.05 | ..."
+    pub fn sweep_log(&self, format: &StatsFormat) -> Vec<String> {
+        self.param1.iter().zip(self.outcomes.iter())
+            .flat_map(|(p1, row)| {
+                self.param2.iter().zip(row.iter()).map(move |(p2, o)| {
+                    format!("{} = {}, {} = {} | {}", self.param1_name, p1, self.param2_name, p2, o.summary_line(format))
+                })
+            })
+            .collect()
+    }
+
+    pub fn plot_sharpe_heatmap(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        plot_parameter_heatmap(&self.param1_name, &self.param1, &self.param2_name, &self.param2, &self.sharpe_grid(), "sharpe ratio", output_path)
+    }
+
+    pub fn plot_return_heatmap(&self, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        plot_parameter_heatmap(&self.param1_name, &self.param1, &self.param2_name, &self.param2, &self.return_grid(), "return [%]", output_path)
+    }
+}
+
+impl fmt::Display for GridResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n\nParameter Grid ({} x {}, sharpe ratio):", self.param1_name, self.param2_name)?;
+        writeln!(f, "====================")?;
+        write!(f, "{:>10}", "")?;
+        for p2 in self.param2.iter() {
+            write!(f, " {:>8}", p2)?;
+        }
+        writeln!(f)?;
+        for (p1, row) in self.param1.iter().zip(self.outcomes.iter()) {
+            write!(f, "{:>10}", p1)?;
+            for o in row.iter() {
+                write!(f, " {:>8.2}", o.sharpe_ratio)?;
+            }
+            writeln!(f)?;
+        }
+        if let Some((p1, p2, best)) = self.best() {
+            writeln!(f, "best: {} = {}, {} = {} (sharpe {:.2}, return {:.2}%)",
+                self.param1_name, p1, self.param2_name, p2, best.sharpe_ratio, best.return_pct)?;
+        }
+        write!(f, "====================")
+    }
+}
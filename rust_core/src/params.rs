@@ -0,0 +1,122 @@
+// tunable strategy parameters declared by the strategy itself, so sweeps don't need per-strategy glue
+use std::fmt;
+
+#[derive(Clone, Debug)]
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+    pub integer: bool, // swept values are whole numbers (periods, lookbacks)
+}
+
+impl ParamSpec {
+    pub fn float(name: &'static str, default: f64, min: f64, max: f64, step: f64) -> Self {
+        ParamSpec { name, default, min, max, step, integer: false }
+    }
+
+    pub fn integer(name: &'static str, default: usize, min: usize, max: usize, step: usize) -> Self {
+        ParamSpec { name, default: default as f64, min: min as f64, max: max as f64, step: step as f64, integer: true }
+    }
+
+    // every value from min to max (inclusive) in steps of `step`
+    pub fn values(&self) -> Vec<f64> {
+        if self.step <= 0.0 || self.max < self.min {
+            return vec![self.default];
+        }
+        let count = ((self.max - self.min) / self.step + 1e-9).floor() as usize + 1;
+        (0..count)
+            .map(|k| {
+                let value = self.min + k as f64 * self.step;
+                if self.integer { value.round() } else { value }
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        value >= self.min && value <= self.max && (!self.integer || value.fract() == 0.0)
+    }
+}
+
+impl fmt::Display for ParamSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:<20} default {:<10} range {} to {} step {}{}",
+            self.name, self.default, self.min, self.max, self.step, if self.integer { " (integer)" } else { "" })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamError {
+    Unknown(String),
+    OutOfRange(String, f64),
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::Unknown(name) => write!(f, "unknown parameter: {}", name),
+            ParamError::OutOfRange(name, value) => write!(f, "value {} out of range for parameter {}", value, name),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+/// run label from a strategy name and its parameter values, e.g. "statarb_lookback20_zscore_threshold1.5";
+/// characters that don't belong in a filename are replaced with '-'
+pub fn run_label(name: &str, params: &[(String, f64)]) -> String {
+    let mut label = name.to_string();
+    for (param, value) in params.iter() {
+        label.push_str(&format!("_{}{}", param, value));
+    }
+    label.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') { c } else { '-' })
+        .collect()
+}
+
+/// strategies that expose their tunable parameters; `param_specs` lists them with ranges and steps,
+/// `set_param` applies one value (checked against the spec before it is called by `apply_param`),
+/// `get_param` reads it back for manifests and reports
+pub trait Parameterized {
+    fn param_specs(&self) -> Vec<ParamSpec>;
+    fn set_param(&mut self, name: &str, value: f64);
+    fn get_param(&self, name: &str) -> Option<f64>;
+
+    // current value of every declared parameter
+    fn param_values(&self) -> Vec<(String, f64)> {
+        self.param_specs().iter()
+            .filter_map(|spec| self.get_param(spec.name).map(|value| (spec.name.to_string(), value)))
+            .collect()
+    }
+
+    fn param_spec(&self, name: &str) -> Option<ParamSpec> {
+        self.param_specs().into_iter().find(|spec| spec.name == name)
+    }
+
+    // validated set_param
+    fn apply_param(&mut self, name: &str, value: f64) -> Result<(), ParamError> {
+        let spec = self.param_spec(name).ok_or_else(|| ParamError::Unknown(name.to_string()))?;
+        if !spec.contains(value) {
+            return Err(ParamError::OutOfRange(name.to_string(), value));
+        }
+        self.set_param(name, value);
+        Ok(())
+    }
+
+    // apply "name=value" pairs, e.g. from command line arguments
+    fn apply_args(&mut self, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+        for arg in args {
+            let (name, value) = arg.split_once('=').ok_or_else(|| format!("expected name=value, got {}", arg))?;
+            self.apply_param(name.trim(), value.trim().parse::<f64>()?)?;
+        }
+        Ok(())
+    }
+
+    fn print_params(&self) {
+        println!("Parameters:");
+        for spec in self.param_specs() {
+            println!("  {}", spec);
+        }
+    }
+}
@@ -1,9 +1,51 @@
 use plotters::prelude::*;
 use chrono::NaiveDateTime;
+use crate::engine::OhlcData;
+use crate::indicators::{bollinger, ema, sma};
+
+// caption of a chart: the run label in front of the chart name, e.g. "statarb_lookback20 - trade pnl distribution"
+fn caption(name: &str, label: Option<&str>) -> String {
+    match label {
+        Some(label) => format!("{} - {}", label, name),
+        None => name.to_string(),
+    }
+}
+
+/// resample irregular (time, value) points, e.g. live equity, onto a fixed `interval` grid with
+/// last-observation-carried-forward: every grid time gets the latest value observed at or before it.
+/// the grid starts at the first point rounded down to the interval and ends at the last point; points
+/// must be sorted by time.
+pub fn resample_locf(data: &[(NaiveDateTime, f64)], interval: chrono::Duration) -> Vec<(NaiveDateTime, f64)> {
+    let step = interval.num_milliseconds();
+    if data.is_empty() || step <= 0 {
+        return data.to_vec();
+    }
+    let first = data[0].0.and_utc().timestamp_millis();
+    let last = data[data.len() - 1].0.and_utc().timestamp_millis();
+
+    let mut resampled = Vec::new();
+    let mut i = 0;
+    let mut t = first - first.rem_euclid(step);
+    while t <= last {
+        // advance to the last observation at or before t
+        while i + 1 < data.len() && data[i + 1].0.and_utc().timestamp_millis() <= t {
+            i += 1;
+        }
+        // grid times before the first observation have nothing to carry forward
+        if data[i].0.and_utc().timestamp_millis() <= t {
+            if let Some(time) = chrono::DateTime::from_timestamp_millis(t) {
+                resampled.push((time.naive_utc(), data[i].1));
+            }
+        }
+        t += step;
+    }
+    resampled
+}
 
 /// function plot_equity that plots equity values as a function of time
-/// it takes a slice of (naivedatetime, equity_value) tuples and an output file path
-pub fn plot_equity(data: &[(NaiveDateTime, f64)], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// it takes a slice of (naivedatetime, equity_value) tuples, an optional run label for the title and legend,
+/// and an output file path
+pub fn plot_equity(data: &[(NaiveDateTime, f64)], label: Option<&str>, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
     // determine the minimum and maximum dates for the x-axis
     let start_date = data.first().unwrap().0;
     let end_date = data.last().unwrap().0;
@@ -20,7 +62,11 @@ pub fn plot_equity(data: &[(NaiveDateTime, f64)], output_path: &str) -> Result<(
     root_area.fill(&WHITE)?;
 
     // build the chart object with axis labels and margins, using timestamp range for x-axis
-    let mut chart = ChartBuilder::on(&root_area)
+    let mut builder = ChartBuilder::on(&root_area);
+    if label.is_some() {
+        builder.caption(caption("equity", label), ("sans-serif", 20));
+    }
+    let mut chart = builder
         .margin(10)
         .x_label_area_size(40)
         .y_label_area_size(50)
@@ -42,7 +88,7 @@ pub fn plot_equity(data: &[(NaiveDateTime, f64)], output_path: &str) -> Result<(
         data.iter().map(|&(time, equity)| (time.and_utc().timestamp(), equity)),
         &BLUE,
     ))?
-    .label("equity")
+    .label(label.unwrap_or("equity"))
     .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
 
     // configure and draw the legend for clarity
@@ -57,6 +103,7 @@ pub fn plot_equity(data: &[(NaiveDateTime, f64)], output_path: &str) -> Result<(
 pub fn plot_equity_and_benchmark(
     equity: &[(NaiveDateTime, f64)],
     benchmark: &[(NaiveDateTime, f64)],
+    label: Option<&str>,
     output_path: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // compute the starting and ending dates for equity
@@ -95,7 +142,11 @@ pub fn plot_equity_and_benchmark(
     root_area.fill(&WHITE)?;
 
     // build the chart with the computed x and y ranges
-    let mut chart = ChartBuilder::on(&root_area)
+    let mut builder = ChartBuilder::on(&root_area);
+    if label.is_some() {
+        builder.caption(caption("equity vs benchmark [%]", label), ("sans-serif", 20));
+    }
+    let mut chart = builder
         .margin(10)
         .x_label_area_size(40)
         .y_label_area_size(50)
@@ -119,7 +170,7 @@ pub fn plot_equity_and_benchmark(
             equity.iter().map(|&(time, value)| (time.and_utc().timestamp(), value)),
             &BLUE,
         ))?
-        .label("equity")
+        .label(label.unwrap_or("equity"))
         .legend(|(x, y)| {
             // create a legend entry for equity
             PathElement::new(vec![(x, y), (x + 20, y)], &BLUE)
@@ -146,7 +197,7 @@ pub fn plot_equity_and_benchmark(
     Ok(())
 }
 
-pub fn plot_margin_usage(data: &[(NaiveDateTime, f64)], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+pub fn plot_margin_usage(data: &[(NaiveDateTime, f64)], label: Option<&str>, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
     // determine the minimum and maximum dates for the x-axis
     let start_date = data.first().unwrap().0;
     let end_date = data.last().unwrap().0;
@@ -172,7 +223,11 @@ pub fn plot_margin_usage(data: &[(NaiveDateTime, f64)], output_path: &str) -> Re
     root_area.fill(&WHITE)?;
 
     // build the chart object with axis labels and margins, using timestamp range for x-axis
-    let mut chart = ChartBuilder::on(&root_area)
+    let mut builder = ChartBuilder::on(&root_area);
+    if label.is_some() {
+        builder.caption(caption("margin usage", label), ("sans-serif", 20));
+    }
+    let mut chart = builder
         .margin(10)
         .x_label_area_size(40)
         .y_label_area_size(50)
@@ -200,3 +255,643 @@ pub fn plot_margin_usage(data: &[(NaiveDateTime, f64)], output_path: &str) -> Re
     // return ok to satisfy the function result type
     Ok(())
 }
+
+// (legend name, points, color) of one line in a chart
+type NamedSeries<'a> = (&'a str, &'a [(NaiveDateTime, f64)], RGBColor);
+
+// underwater chart of one or more drawdown series in percent (0 at a new high, negative below it);
+// the first series is filled down from zero, the others are drawn as lines on top
+fn draw_underwater(
+    series: &[NamedSeries],
+    name: &str,
+    label: Option<&str>,
+    output_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some((_, first, _)) = series.first() else {
+        return Ok(());
+    };
+    if first.is_empty() {
+        return Ok(());
+    }
+    let start_ts = first.first().unwrap().0.and_utc().timestamp();
+    let end_ts = first.last().unwrap().0.and_utc().timestamp();
+
+    // the y-axis runs from the deepest drawdown (with a little room) up to zero
+    let deepest = series.iter()
+        .flat_map(|(_, data, _)| data.iter().map(|&(_, v)| v))
+        .fold(0.0, f64::min);
+    let y_lower = if deepest < 0.0 { deepest * 1.05 } else { -1.0 };
+
+    let root_area = BitMapBackend::new(output_path, (800, 600)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root_area)
+        .caption(caption(name, label), ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(start_ts..end_ts, y_lower..0.0)?;
+    chart.configure_mesh()
+        .x_label_formatter(&|x| {
+            chrono::DateTime::from_timestamp(*x, 0)
+                .map(|dt| dt.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        })
+        .x_labels(5)
+        .y_labels(5)
+        .draw()?;
+
+    for (i, &(series_name, data, color)) in series.iter().enumerate() {
+        let points = data.iter().map(|&(time, value)| (time.and_utc().timestamp(), value));
+        if i == 0 {
+            chart.draw_series(AreaSeries::new(points, 0.0, color.mix(0.3)).border_style(color))?
+        } else {
+            chart.draw_series(LineSeries::new(points, &color))?
+        }
+        .label(series_name)
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &color));
+    }
+    chart.configure_series_labels()
+        .border_style(&BLACK)
+        .draw()?;
+    Ok(())
+}
+
+/// drawdown [%] below the running equity peak over time
+pub fn plot_underwater(drawdown: &[(NaiveDateTime, f64)], label: Option<&str>, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    draw_underwater(&[("drawdown", drawdown, RED)], "underwater [%]", label, output_path)
+}
+
+/// drawdown [%] of the strategy relative to a benchmark (filled), with the strategy's own drawdown as a line
+pub fn plot_relative_underwater(
+    relative: &[(NaiveDateTime, f64)],
+    drawdown: &[(NaiveDateTime, f64)],
+    label: Option<&str>,
+    output_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    draw_underwater(
+        &[("vs benchmark", relative, MAGENTA), ("absolute", drawdown, RED)],
+        "drawdown vs benchmark [%]",
+        label,
+        output_path,
+    )
+}
+
+/// function plot_correlation_heatmap that renders a square correlation matrix
+/// cells are colored from red (-1) through white (0) to blue (+1)
+pub fn plot_correlation_heatmap(labels: &[String], matrix: &[Vec<f64>], output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let n = labels.len();
+    if n == 0 {
+        return Ok(());
+    }
+
+    // create a drawing area for the plot
+    let root_area = BitMapBackend::new(output_path, (800, 800)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    // build the chart with one unit per symbol on both axes
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(10)
+        .caption("return correlation", ("sans-serif", 20))
+        .x_label_area_size(60)
+        .y_label_area_size(80)
+        .build_cartesian_2d(0..n as i32, 0..n as i32)?;
+
+    // label the axes with symbol names instead of indices
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_labels(n)
+        .y_labels(n)
+        .x_label_formatter(&|x| labels.get(*x as usize).cloned().unwrap_or_default())
+        .y_label_formatter(&|y| labels.get(*y as usize).cloned().unwrap_or_default())
+        .draw()?;
+
+    // map a correlation value to a diverging color
+    let color = |value: f64| {
+        let v = value.clamp(-1.0, 1.0);
+        let fade = ((1.0 - v.abs()) * 255.0) as u8;
+        if v >= 0.0 {
+            RGBColor(fade, fade, 255)
+        } else {
+            RGBColor(255, fade, fade)
+        }
+    };
+
+    // draw one filled rectangle per matrix cell
+    chart.draw_series(
+        (0..n).flat_map(|i| (0..n).map(move |j| (i, j))).map(|(i, j)| {
+            Rectangle::new(
+                [(j as i32, i as i32), (j as i32 + 1, i as i32 + 1)],
+                color(matrix[i][j]).filled(),
+            )
+        }),
+    )?;
+
+    // return ok upon successful completion
+    Ok(())
+}
+
+/// function plot_exposure that plots long, short (drawn negative) and net notional over time
+pub fn plot_exposure(
+    long: &[(NaiveDateTime, f64)],
+    short: &[(NaiveDateTime, f64)],
+    net: &[(NaiveDateTime, f64)],
+    label: Option<&str>,
+    output_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if long.is_empty() {
+        return Ok(());
+    }
+    // determine the minimum and maximum dates for the x-axis
+    let start_ts = long.first().unwrap().0.and_utc().timestamp();
+    let end_ts = long.last().unwrap().0.and_utc().timestamp();
+
+    // take the union of the y-axis ranges of all three series
+    let all_values = long.iter().chain(short.iter()).chain(net.iter()).map(|&(_, v)| v);
+    let (mut min_value, mut max_value) = all_values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    if (max_value - min_value).abs() < std::f64::EPSILON {
+        // constant data; add padding
+        min_value -= 1.0;
+        max_value += 1.0;
+    }
+
+    // create a drawing area for the plot
+    let root_area = BitMapBackend::new(output_path, (800, 600)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    // build the chart object with axis labels and margins, using timestamp range for x-axis
+    let mut builder = ChartBuilder::on(&root_area);
+    if label.is_some() {
+        builder.caption(caption("exposure", label), ("sans-serif", 20));
+    }
+    let mut chart = builder
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(70)
+        .build_cartesian_2d(start_ts..end_ts, min_value..max_value)?;
+
+    // configure the mesh for the chart and add a custom x-axis label formatter
+    chart.configure_mesh()
+        .x_label_formatter(&|x| {
+            // convert timestamp to datetime
+            let dt = NaiveDateTime::from_timestamp(*x, 0);
+            dt.format("%Y-%m-%d").to_string()
+        })
+        .x_labels(5)
+        .y_labels(5)
+        .draw()?;
+
+    // draw each exposure series with its own color
+    for (series, name, color) in [(long, "long", GREEN), (short, "short", RED), (net, "net", BLUE)] {
+        chart.draw_series(LineSeries::new(
+            series.iter().map(|&(time, value)| (time.and_utc().timestamp(), value)),
+            &color,
+        ))?
+        .label(name)
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &color));
+    }
+
+    // configure and draw the legend for clarity
+    chart.configure_series_labels()
+        .border_style(&BLACK)
+        .draw()?;
+
+    // return ok upon successful completion
+    Ok(())
+}
+
+// bucket values into `bins` equal-width bins; returns (bin start, bin width, counts)
+fn histogram_bins(values: &[f64], bins: usize) -> (f64, f64, Vec<usize>) {
+    let bins = bins.max(1);
+    let min_value = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max_value = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let (start, width) = if (max_value - min_value).abs() < std::f64::EPSILON {
+        // constant data; spread the bins over one unit around the value
+        (min_value - 0.5, 1.0 / bins as f64)
+    } else {
+        (min_value, (max_value - min_value) / bins as f64)
+    };
+
+    let mut counts = vec![0usize; bins];
+    for &v in values {
+        let bin = (((v - start) / width) as usize).min(bins - 1);
+        counts[bin] += 1;
+    }
+    (start, width, counts)
+}
+
+// shared drawing for the trade histograms
+fn draw_histogram(values: &[f64], bins: usize, caption: &str, x_desc: &str, color: RGBColor, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let (start, width, counts) = histogram_bins(values, bins);
+    let end = start + width * counts.len() as f64;
+    let max_count = counts.iter().cloned().max().unwrap_or(0).max(1);
+
+    // create a drawing area for the plot
+    let root_area = BitMapBackend::new(output_path, (800, 600)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    // build the chart with the value range on x and the trade count on y
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(10)
+        .caption(caption, ("sans-serif", 20))
+        .x_label_area_size(40)
+        .y_label_area_size(50)
+        .build_cartesian_2d(start..end, 0.0..(max_count as f64 * 1.1))?;
+
+    chart.configure_mesh()
+        .x_desc(x_desc)
+        .y_desc("trades")
+        .x_labels(8)
+        .y_labels(5)
+        .draw()?;
+
+    // draw one filled bar per bin
+    chart.draw_series(counts.iter().enumerate().map(|(i, &count)| {
+        let left = start + width * i as f64;
+        Rectangle::new([(left, 0.0), (left + width, count as f64)], color.filled())
+    }))?;
+
+    // return ok upon successful completion
+    Ok(())
+}
+
+/// function plot_trade_histogram that plots the distribution of closed trade pnl
+/// bars left of zero are losers, right of zero winners
+pub fn plot_trade_histogram(pnls: &[f64], bins: usize, label: Option<&str>, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    draw_histogram(pnls, bins, &caption("trade pnl distribution", label), "pnl", RGBColor(70, 110, 200), output_path)
+}
+
+/// function plot_holding_period_histogram that plots the distribution of holding periods in bars
+pub fn plot_holding_period_histogram(holding_bars: &[f64], bins: usize, label: Option<&str>, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    draw_histogram(holding_bars, bins, &caption("holding period distribution", label), "bars held", RGBColor(120, 120, 120), output_path)
+}
+
+/// function plot_pnl_vs_holding that scatters trade pnl against holding period
+/// it takes a slice of (bars held, pnl) tuples; winners are drawn green, losers red
+pub fn plot_pnl_vs_holding(points: &[(f64, f64)], label: Option<&str>, output_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if points.is_empty() {
+        return Ok(());
+    }
+    // determine the axis ranges, padding constant data
+    let max_bars = points.iter().map(|&(bars, _)| bars).fold(0.0, f64::max).max(1.0);
+    let mut min_pnl = points.iter().map(|&(_, pnl)| pnl).fold(f64::INFINITY, f64::min).min(0.0);
+    let mut max_pnl = points.iter().map(|&(_, pnl)| pnl).fold(f64::NEG_INFINITY, f64::max).max(0.0);
+    if (max_pnl - min_pnl).abs() < std::f64::EPSILON {
+        min_pnl -= 1.0;
+        max_pnl += 1.0;
+    }
+
+    // create a drawing area for the plot
+    let root_area = BitMapBackend::new(output_path, (800, 600)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(10)
+        .caption(caption("pnl vs holding period", label), ("sans-serif", 20))
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(0.0..max_bars * 1.05, min_pnl..max_pnl)?;
+
+    chart.configure_mesh()
+        .x_desc("bars held")
+        .y_desc("pnl")
+        .x_labels(8)
+        .y_labels(5)
+        .draw()?;
+
+    // zero line to separate winners from losers
+    chart.draw_series(LineSeries::new(vec![(0.0, 0.0), (max_bars * 1.05, 0.0)], &BLACK))?;
+
+    // draw one dot per trade
+    chart.draw_series(points.iter().map(|&(bars, pnl)| {
+        let color = if pnl >= 0.0 { GREEN } else { RED };
+        Circle::new((bars, pnl), 3, color.filled())
+    }))?;
+
+    // return ok upon successful completion
+    Ok(())
+}
+
+/// function plot_parameter_heatmap that renders a metric over a 2d parameter grid
+/// values[i][j] belongs to (param1[i], param2[j]); cells are colored from red (worst) to green (best)
+pub fn plot_parameter_heatmap(
+    param1_name: &str,
+    param1: &[f64],
+    param2_name: &str,
+    param2: &[f64],
+    values: &[Vec<f64>],
+    metric: &str,
+    output_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (n1, n2) = (param1.len(), param2.len());
+    if n1 == 0 || n2 == 0 {
+        return Ok(());
+    }
+
+    // color scale over the finite values only
+    let finite = values.iter().flatten().cloned().filter(|v| v.is_finite());
+    let (min_value, max_value) = finite.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+    let range = if max_value > min_value { max_value - min_value } else { 1.0 };
+    let color = |value: f64| {
+        if !value.is_finite() {
+            return RGBColor(200, 200, 200);
+        }
+        let t = ((value - min_value) / range).clamp(0.0, 1.0);
+        RGBColor(((1.0 - t) * 220.0) as u8, (t * 180.0) as u8, 60)
+    };
+
+    // create a drawing area for the plot
+    let root_area = BitMapBackend::new(output_path, (900, 800)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+
+    // one unit per grid value on both axes, param1 on y and param2 on x
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(10)
+        .caption(format!("{} by {} x {}", metric, param1_name, param2_name), ("sans-serif", 20))
+        .x_label_area_size(50)
+        .y_label_area_size(70)
+        .build_cartesian_2d(0..n2 as i32, 0..n1 as i32)?;
+
+    // label the axes with parameter values instead of indices
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_desc(param2_name)
+        .y_desc(param1_name)
+        .x_labels(n2.min(20))
+        .y_labels(n1.min(20))
+        .x_label_formatter(&|x| param2.get(*x as usize).map(|v| format!("{}", v)).unwrap_or_default())
+        .y_label_formatter(&|y| param1.get(*y as usize).map(|v| format!("{}", v)).unwrap_or_default())
+        .draw()?;
+
+    // draw one filled rectangle per grid cell
+    chart.draw_series(
+        (0..n1).flat_map(|i| (0..n2).map(move |j| (i, j))).map(|(i, j)| {
+            let value = values.get(i).and_then(|row| row.get(j)).cloned().unwrap_or(f64::NAN);
+            Rectangle::new(
+                [(j as i32, i as i32), (j as i32 + 1, i as i32 + 1)],
+                color(value).filled(),
+            )
+        }),
+    )?;
+
+    // print the value inside each cell when the grid is small enough to read
+    if n1 * n2 <= 400 {
+        chart.draw_series(
+            (0..n1).flat_map(|i| (0..n2).map(move |j| (i, j))).map(|(i, j)| {
+                let value = values.get(i).and_then(|row| row.get(j)).cloned().unwrap_or(f64::NAN);
+                Text::new(format!("{:.2}", value), (j as i32, i as i32 + 1), ("sans-serif", 12).into_font())
+            }),
+        )?;
+    }
+
+    // return ok upon successful completion
+    Ok(())
+}
+
+/// function plot_position_timeline that draws the equity curve with one position panel per instrument below it
+/// positions are drawn as step charts, long regions shaded green and short regions red; all panels share the time axis
+pub fn plot_position_timeline(
+    equity: &[(NaiveDateTime, f64)],
+    positions: &[(String, Vec<(NaiveDateTime, f64)>)],
+    label: Option<&str>,
+    output_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if equity.is_empty() {
+        return Ok(());
+    }
+    // determine the shared x-axis range
+    let start_ts = equity.first().unwrap().0.and_utc().timestamp();
+    let end_ts = equity.last().unwrap().0.and_utc().timestamp();
+
+    // create a drawing area: equity gets the top half, the position panels split the rest
+    let height = 600 + 200 * positions.len() as u32;
+    let root_area = BitMapBackend::new(output_path, (1000, height)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+    let (equity_area, position_area) = root_area.split_vertically(400);
+
+    // equity panel
+    let min_equity = equity.iter().map(|&(_, v)| v).fold(f64::INFINITY, f64::min);
+    let max_equity = equity.iter().map(|&(_, v)| v).fold(f64::NEG_INFINITY, f64::max);
+    let (min_equity, max_equity) = if (max_equity - min_equity).abs() < std::f64::EPSILON {
+        (min_equity - 1.0, max_equity + 1.0)
+    } else {
+        (min_equity, max_equity)
+    };
+    let mut chart = ChartBuilder::on(&equity_area)
+        .margin(10)
+        .caption(caption("equity and positions", label), ("sans-serif", 20))
+        .x_label_area_size(30)
+        .y_label_area_size(70)
+        .build_cartesian_2d(start_ts..end_ts, min_equity..max_equity)?;
+    chart.configure_mesh()
+        .x_label_formatter(&|x| {
+            chrono::DateTime::from_timestamp(*x, 0)
+                .map(|dt| dt.format("%Y-%m-%d").to_string())
+                .unwrap_or_default()
+        })
+        .x_labels(5)
+        .y_labels(5)
+        .draw()?;
+    chart.draw_series(LineSeries::new(
+        equity.iter().map(|&(time, value)| (time.and_utc().timestamp(), value)),
+        &BLUE,
+    ))?;
+
+    // one panel per instrument
+    let panels = position_area.split_evenly((positions.len().max(1), 1));
+    for ((instrument, series), panel) in positions.iter().zip(panels.iter()) {
+        if series.is_empty() {
+            continue;
+        }
+        // turn the per-bar positions into a step line: hold each value until the next bar
+        let mut steps: Vec<(i64, f64)> = Vec::with_capacity(series.len() * 2);
+        for (i, &(time, value)) in series.iter().enumerate() {
+            let ts = time.and_utc().timestamp();
+            if i > 0 {
+                steps.push((ts, series[i - 1].1));
+            }
+            steps.push((ts, value));
+        }
+
+        let max_abs = series.iter().map(|&(_, v)| v.abs()).fold(0.0, f64::max).max(1.0);
+        let mut chart = ChartBuilder::on(panel)
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(70)
+            .build_cartesian_2d(start_ts..end_ts, -max_abs * 1.1..max_abs * 1.1)?;
+        chart.configure_mesh()
+            .x_label_formatter(&|x| {
+                chrono::DateTime::from_timestamp(*x, 0)
+                    .map(|dt| dt.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default()
+            })
+            .x_labels(5)
+            .y_labels(3)
+            .y_desc(instrument.as_str())
+            .draw()?;
+
+        // shade long and short regions separately, then draw the step line on top
+        chart.draw_series(AreaSeries::new(
+            steps.iter().map(|&(ts, v)| (ts, v.max(0.0))),
+            0.0,
+            GREEN.mix(0.3),
+        ))?;
+        chart.draw_series(AreaSeries::new(
+            steps.iter().map(|&(ts, v)| (ts, v.min(0.0))),
+            0.0,
+            RED.mix(0.3),
+        ))?;
+        chart.draw_series(LineSeries::new(steps.iter().cloned(), &BLACK))?;
+    }
+
+    // return ok upon successful completion
+    Ok(())
+}
+
+/// indicator drawn over the candles of plot_ohlc. it is computed on the closes of the whole data set, so
+/// the first bars of the range already have values when there is history before it.
+#[derive(Clone, Debug)]
+pub enum Overlay {
+    Sma(usize),
+    Ema(usize),
+    Bollinger(usize, f64),    // period and band width in standard deviations
+    Series(String, Vec<f64>), // any series aligned with the bars, e.g. a strategy's thresholds
+}
+
+impl Overlay {
+    pub fn label(&self) -> String {
+        match self {
+            Overlay::Sma(period) => format!("sma {}", period),
+            Overlay::Ema(period) => format!("ema {}", period),
+            Overlay::Bollinger(period, width) => format!("bollinger {} {}", period, width),
+            Overlay::Series(name, _) => name.clone(),
+        }
+    }
+
+    // the lines of the overlay (three for bollinger bands), aligned with the bars; NaN where undefined
+    pub fn lines(&self, close: &[f64]) -> Vec<Vec<f64>> {
+        match self {
+            Overlay::Sma(period) => vec![sma(close, *period)],
+            Overlay::Ema(period) => vec![ema(close, *period)],
+            Overlay::Bollinger(period, width) => {
+                let bands = bollinger(close, *period, *width);
+                vec![bands.middle, bands.upper, bands.lower]
+            }
+            Overlay::Series(_, values) => vec![values.clone()],
+        }
+    }
+}
+
+/// a buy (triangle up) or sell (triangle down) marker at a bar and price, e.g. a fill or a signal
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartMarker {
+    pub index: usize,
+    pub price: f64,
+    pub buy: bool,
+}
+
+// contiguous runs of finite values of `values` over `range`, as (bar, value) points
+fn finite_runs(values: &[f64], range: std::ops::Range<usize>) -> Vec<Vec<(i64, f64)>> {
+    let mut runs: Vec<Vec<(i64, f64)>> = Vec::new();
+    let mut run = Vec::new();
+    for i in range {
+        match values.get(i) {
+            Some(v) if v.is_finite() => run.push((i as i64, *v)),
+            _ if !run.is_empty() => runs.push(std::mem::take(&mut run)),
+            _ => {}
+        }
+    }
+    if !run.is_empty() {
+        runs.push(run);
+    }
+    runs
+}
+
+/// function plot_ohlc that draws the candles of bars `range` (green up, red down) with indicator overlays
+/// and buy/sell markers on top. the x-axis counts bars, so gaps overnight and on weekends take no space,
+/// and is labeled with the bar dates.
+pub fn plot_ohlc(
+    data: &OhlcData,
+    range: std::ops::Range<usize>,
+    overlays: &[Overlay],
+    markers: &[ChartMarker],
+    label: Option<&str>,
+    output_path: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let end = range.end.min(data.close.len());
+    let start = range.start.min(end);
+    if start == end {
+        return Ok(());
+    }
+    let lines: Vec<(String, Vec<Vec<f64>>)> = overlays.iter().map(|o| (o.label(), o.lines(&data.close))).collect();
+    let markers: Vec<&ChartMarker> = markers.iter().filter(|m| m.index >= start && m.index < end).collect();
+
+    // y-axis range covering the candles, the overlays and the markers, with a little room around them
+    let mut min_price = data.low[start..end].iter().copied().fold(f64::INFINITY, f64::min);
+    let mut max_price = data.high[start..end].iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let overlay_values = lines.iter().flat_map(|(_, lines)| lines.iter()).flat_map(|line| line[start..end.min(line.len())].iter());
+    for &value in overlay_values.chain(markers.iter().map(|m| &m.price)).filter(|v| v.is_finite()) {
+        min_price = min_price.min(value);
+        max_price = max_price.max(value);
+    }
+    let pad = ((max_price - min_price) * 0.05).max(1e-9);
+
+    let root_area = BitMapBackend::new(output_path, (1200, 700)).into_drawing_area();
+    root_area.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(10)
+        .caption(caption("ohlc", label), ("sans-serif", 20))
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(start as i64 - 1..end as i64, min_price - pad..max_price + pad)?;
+    chart.configure_mesh()
+        .x_label_formatter(&|x| data.date.get(*x as usize).cloned().unwrap_or_default())
+        .x_labels(5)
+        .y_labels(8)
+        .draw()?;
+
+    // candle bodies get most of the space a bar has on the x-axis
+    let width = (1100 / (end - start) as u32 * 7 / 10).clamp(1, 15);
+    chart.draw_series((start..end).map(|i| {
+        CandleStick::new(i as i64, data.open[i], data.high[i], data.low[i], data.close[i], GREEN.filled(), RED.filled(), width)
+    }))?;
+
+    let palette = [BLUE, MAGENTA, CYAN, BLACK, RGBColor(255, 140, 0)];
+    for (n, (name, overlay_lines)) in lines.iter().enumerate() {
+        let color = palette[n % palette.len()];
+        for (k, line) in overlay_lines.iter().enumerate() {
+            // the outer bollinger bands are drawn lighter than the middle line
+            let style = if k == 0 { color.stroke_width(2) } else { color.mix(0.5).stroke_width(1) };
+            for (r, run) in finite_runs(line, start..end).into_iter().enumerate() {
+                let series = chart.draw_series(LineSeries::new(run, style))?;
+                if k == 0 && r == 0 {
+                    series.label(name.as_str())
+                        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+                }
+            }
+        }
+    }
+
+    // markers are drawn in pixels around their point, so they keep their size at any zoom
+    chart.draw_series(markers.iter().map(|m| {
+        let (shape, color) = if m.buy {
+            (vec![(0, -6), (-5, 4), (5, 4)], GREEN)
+        } else {
+            (vec![(0, 6), (-5, -4), (5, -4)], RED)
+        };
+        EmptyElement::at((m.index as i64, m.price))
+            + Polygon::new(shape, color.filled())
+    }))?;
+
+    if !lines.is_empty() {
+        chart.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+
+    // return ok upon successful completion
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// synthetic bid/ask quotes for bar backtests: ohlc prices are treated as mids and a spread model
+// puts the bid and ask around them, so fills follow the live broker's rules (buy at the ask, sell at
+// the bid, stops and limits compared against the side they fill on)
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpreadModel {
+    Fixed(f64),        // constant spread in price units
+    Proportional(f64), // spread as a fraction of the mid, e.g. 0.0002 = 2 bps
+    // spread widening with volatility: max(min_spread, multiple * stdev of the last `lookback`
+    // bar-to-bar price changes); bars before the lookback is filled use min_spread
+    Volatility { min_spread: f64, multiple: f64, lookback: usize },
+}
+
+impl SpreadModel {
+    /// full spread at bar `index` of `prices` (closes of the instrument). only bars before `index`
+    /// are used, so the spread at a bar's open does not look at its close.
+    pub fn spread(&self, prices: &[f64], index: usize) -> f64 {
+        match self {
+            SpreadModel::Fixed(spread) => *spread,
+            SpreadModel::Proportional(ratio) => {
+                let mid = prices.get(index).or(prices.last()).copied().unwrap_or(0.0);
+                ratio * mid.abs()
+            }
+            SpreadModel::Volatility { min_spread, multiple, lookback } => {
+                if *lookback < 2 || index < lookback + 1 || index > prices.len() {
+                    return *min_spread;
+                }
+                let changes: Vec<f64> = prices[index - lookback - 1..index].windows(2).map(|w| w[1] - w[0]).collect();
+                let n = changes.len() as f64;
+                let mean = changes.iter().sum::<f64>() / n;
+                let var = changes.iter().map(|c| (c - mean).powi(2)).sum::<f64>() / (n - 1.0);
+                (multiple * var.sqrt()).max(*min_spread)
+            }
+        }
+    }
+
+    // (bid, ask) around a mid price
+    pub fn quote(&self, prices: &[f64], index: usize, mid: f64) -> (f64, f64) {
+        let half = self.spread(prices, index) / 2.0;
+        (mid - half, mid + half)
+    }
+}
@@ -0,0 +1,79 @@
+// target-position strategies: instead of managing orders, next returns the position each instrument
+// should hold and Broker::rebalance trades the difference, e.g. for portfolio-style strategies that
+// compute weights or hedge ratios every bar
+use crate::engine::{Broker, OhlcData, Strategy};
+
+#[derive(Clone, Debug)]
+pub struct RebalanceConfig {
+    // deltas below this many units are not traded
+    pub min_trade_size: f64,
+    // no-trade band: deltas below this fraction of the current position are not traded, so small drifts
+    // in the target don't pay commission and spread every bar. 0.0 trades every change.
+    pub min_trade_fraction: f64,
+    // tag of the orders the executor submits
+    pub tag: Option<String>,
+}
+
+impl Default for RebalanceConfig {
+    fn default() -> Self {
+        RebalanceConfig {
+            min_trade_size: 1e-9,
+            min_trade_fraction: 0.0,
+            tag: Some("rebalance".to_string()),
+        }
+    }
+}
+
+impl RebalanceConfig {
+    // whether a delta from `current` is worth trading; going flat or reversing always is
+    pub fn should_trade(&self, current: f64, delta: f64) -> bool {
+        let target = current + delta;
+        if delta.abs() < self.min_trade_size {
+            return false;
+        }
+        if target.abs() <= 1e-9 || target.signum() != current.signum() {
+            return true;
+        }
+        delta.abs() >= self.min_trade_fraction * current.abs()
+    }
+}
+
+// a strategy that states targets instead of placing orders
+pub trait TargetStrategy {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+    // (instrument, target units, signed) for bar `index`; instruments left out keep their position
+    fn targets(&mut self, broker: &Broker, index: usize) -> Vec<(u8, f64)>;
+}
+
+// runs a TargetStrategy as a regular Strategy, so it works with Backtest, the optimizer and StrategyTester
+pub struct TargetPositionStrategy<T: TargetStrategy> {
+    pub inner: T,
+    pub config: RebalanceConfig,
+}
+
+impl<T: TargetStrategy> TargetPositionStrategy<T> {
+    pub fn new(inner: T) -> Self {
+        TargetPositionStrategy { inner, config: RebalanceConfig::default() }
+    }
+
+    pub fn with_config(inner: T, config: RebalanceConfig) -> Self {
+        TargetPositionStrategy { inner, config }
+    }
+}
+
+impl<T: TargetStrategy> Strategy for TargetPositionStrategy<T> {
+    fn init(&mut self, broker: &mut Broker, data: &OhlcData) {
+        self.inner.init(broker, data);
+    }
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        for (instrument, target) in self.inner.targets(broker, index) {
+            // rejections are recorded in broker.rejected_orders; the next bar tries again
+            if let Err(e) = broker.rebalance(index, instrument, target, &self.config) {
+                if !broker.quiet {
+                    println!("rebalance of instrument {} to {} rejected: {:?}", instrument, target, e);
+                }
+            }
+        }
+    }
+}
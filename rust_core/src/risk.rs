@@ -0,0 +1,166 @@
+// historical-simulation value at risk for open portfolios, in account currency
+use serde::{Deserialize, Serialize};
+
+// settings for the broker's var estimate and optional var budget
+#[derive(Clone, Debug)]
+pub struct VarConfig {
+    pub lookback: usize,         // number of past returns used as scenarios
+    pub confidence: f64,         // e.g. 0.95 or 0.99
+    pub budget_pct: Option<f64>, // reject orders that would push var above this % of equity
+}
+
+impl Default for VarConfig {
+    fn default() -> Self {
+        VarConfig {
+            lookback: 250,
+            confidence: 0.95,
+            budget_pct: None,
+        }
+    }
+}
+
+// one-period var and expected shortfall as positive loss amounts
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VarEstimate {
+    pub var: f64,
+    pub es: f64,
+}
+
+/// revalue the portfolio under each historical return scenario and read var/es off the loss tail.
+/// `exposures` holds the signed notional of each position and that instrument's recent returns
+/// (oldest first); scenarios are aligned on the most recent returns.
+pub fn historical_var(exposures: &[(f64, Vec<f64>)], confidence: f64) -> VarEstimate {
+    let scenarios = exposures.iter().map(|(_, r)| r.len()).min().unwrap_or(0);
+    if scenarios == 0 {
+        return VarEstimate::default();
+    }
+
+    let mut pnl: Vec<f64> = (0..scenarios)
+        .map(|s| {
+            exposures.iter()
+                .map(|(notional, returns)| notional * returns[returns.len() - scenarios + s])
+                .sum()
+        })
+        .collect();
+    pnl.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    // the worst (1 - confidence) share of scenarios forms the tail
+    let tail = (((1.0 - confidence) * scenarios as f64).ceil() as usize).clamp(1, scenarios);
+    let var = (-pnl[tail - 1]).max(0.0);
+    let es = (-pnl[..tail].iter().sum::<f64>() / tail as f64).max(0.0);
+    VarEstimate { var, es }
+}
+
+// simple returns of the last `lookback` periods ending at `index` (inclusive)
+pub fn trailing_returns(prices: &[f64], index: usize, lookback: usize) -> Vec<f64> {
+    if prices.is_empty() {
+        return Vec::new();
+    }
+    let end = index.min(prices.len() - 1);
+    let start = end.saturating_sub(lookback);
+    prices[start..=end].windows(2)
+        .map(|w| if w[0] > 0.0 { w[1] / w[0] - 1.0 } else { 0.0 })
+        .collect()
+}
+
+// how open trades count against margin
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MarginMode {
+    Gross, // every trade at its full notional, so a hedged long/short pair uses twice the margin of one leg
+    // long and short notional offset each other: the matched part is charged at offset_rate (0.0 fully
+    // netted, 1.0 same as gross) and only the unmatched remainder at the full rate. meant for correlated
+    // legs such as two stock indices.
+    Netted { offset_rate: f64 },
+}
+
+impl Default for MarginMode {
+    fn default() -> Self {
+        MarginMode::Gross
+    }
+}
+
+impl MarginMode {
+    // notional that counts against margin for the given long and short notional
+    pub fn margined_notional(&self, long: f64, short: f64) -> f64 {
+        match *self {
+            MarginMode::Gross => long + short,
+            MarginMode::Netted { offset_rate } => {
+                let matched = long.min(short);
+                (long + short - 2.0 * matched) + 2.0 * matched * offset_rate.clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+// keeps the portfolio's net beta to a benchmark instrument inside a band by resizing hedge-leg entries
+#[derive(Clone, Debug)]
+pub struct BetaNeutralConfig {
+    pub benchmark: u8,        // instrument whose returns define beta (1 = close, 2 = close2)
+    pub hedge_instrument: u8, // new entries on this instrument are resized to offset the rest of the book
+    pub lookback: usize,      // returns used for the rolling beta estimate
+    pub band: f64,            // tolerated |net beta exposure| as a fraction of equity
+}
+
+impl Default for BetaNeutralConfig {
+    fn default() -> Self {
+        BetaNeutralConfig {
+            benchmark: 1,
+            hedge_instrument: 2,
+            lookback: 250,
+            band: 0.05,
+        }
+    }
+}
+
+// beta of an instrument to a benchmark over the last `lookback` returns ending at `index`;
+// 1.0 until there are enough returns to estimate it
+pub fn rolling_beta(prices: &[f64], benchmark: &[f64], index: usize, lookback: usize) -> f64 {
+    let asset = trailing_returns(prices, index, lookback);
+    let market = trailing_returns(benchmark, index, lookback);
+    let n = asset.len().min(market.len());
+    if n < 2 {
+        return 1.0;
+    }
+    let (asset, market) = (&asset[asset.len() - n..], &market[market.len() - n..]);
+    let mean_a = asset.iter().sum::<f64>() / n as f64;
+    let mean_m = market.iter().sum::<f64>() / n as f64;
+    let cov: f64 = asset.iter().zip(market.iter()).map(|(a, m)| (a - mean_a) * (m - mean_m)).sum();
+    let var: f64 = market.iter().map(|m| (m - mean_m).powi(2)).sum();
+    if var == 0.0 { 1.0 } else { cov / var }
+}
+
+// drawdown circuit breaker: new entries are scaled down as equity falls from its peak and scaled back up as
+// it recovers, e.g. full size until -10%, half size until -20% and a quarter beyond
+#[derive(Clone, Debug)]
+pub struct DrawdownGovernor {
+    // (drawdown in %, size multiplier from that drawdown on), sorted by drawdown
+    pub steps: Vec<(f64, f64)>,
+}
+
+impl DrawdownGovernor {
+    pub fn new(mut steps: Vec<(f64, f64)>) -> Self {
+        steps.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        DrawdownGovernor { steps }
+    }
+
+    // full size, half size past first_pct and a quarter past second_pct, e.g. halving(10.0, 20.0)
+    pub fn halving(first_pct: f64, second_pct: f64) -> Self {
+        DrawdownGovernor::new(vec![(first_pct, 0.5), (second_pct, 0.25)])
+    }
+
+    // size multiplier at a drawdown of `drawdown_pct` (positive, e.g. 12.5 for -12.5%)
+    pub fn multiplier(&self, drawdown_pct: f64) -> f64 {
+        self.steps.iter()
+            .take_while(|(threshold, _)| drawdown_pct >= *threshold)
+            .last()
+            .map_or(1.0, |&(_, multiplier)| multiplier)
+    }
+
+    // multiplier for equity relative to its running peak
+    pub fn multiplier_at(&self, equity: f64, peak: f64) -> f64 {
+        if peak <= 0.0 {
+            return 1.0;
+        }
+        self.multiplier(((peak - equity) / peak * 100.0).max(0.0))
+    }
+}
@@ -0,0 +1,232 @@
+// robustness testing: re-run a backtest under random microstructure noise
+use crate::engine::{Backtest, Perturbation};
+use crate::stats::{compute_stats, StatsFormat};
+// defined in stats, re-exported so robustness::Distribution keeps working
+pub use crate::stats::Distribution;
+use std::fmt;
+
+// summary of one (possibly perturbed) run
+#[derive(Clone, Debug)]
+pub struct RunOutcome {
+    pub seed: Option<u64>, // None for the unperturbed robustness baseline
+    pub return_pct: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown_pct: f64,
+    pub num_trades: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct RobustnessReport {
+    pub baseline: RunOutcome,
+    pub runs: Vec<RunOutcome>,
+    pub return_dist: Distribution,
+    pub sharpe_dist: Distribution,
+    pub drawdown_dist: Distribution,
+    pub pct_profitable_runs: f64,
+    // true if the baseline is profitable but the typical perturbed run is not
+    pub edge_vanishes: bool,
+}
+
+// noise parameters for a robustness test
+#[derive(Clone, Debug)]
+pub struct RobustnessConfig {
+    pub runs: usize,
+    pub timing_jitter: usize,    // ± bars on market entries
+    pub price_jitter_ticks: u32, // ± ticks on every fill
+    pub tick_size: f64,
+    pub seed: u64,               // run i uses seed + i, for both the noise and the strategy rng (broker.rng)
+    pub risk_free_rate: f64,
+}
+
+impl Default for RobustnessConfig {
+    fn default() -> Self {
+        RobustnessConfig {
+            runs: 100,
+            timing_jitter: 1,
+            price_jitter_ticks: 1,
+            tick_size: 0.25,
+            seed: 42,
+            risk_free_rate: 0.0,
+        }
+    }
+}
+
+impl RunOutcome {
+    // one line per run for sweep logs, the compact form of Stats::summary_line
+    pub fn summary_line(&self, format: &StatsFormat) -> String {
+        format!("return {} | sharpe {:.2} | max dd {} | trades {}",
+            format.pct(self.return_pct),
+            self.sharpe_ratio,
+            format.pct(self.max_drawdown_pct),
+            self.num_trades)
+    }
+}
+
+pub(crate) fn outcome(backtest: &Backtest, seed: Option<u64>, risk_free_rate: f64) -> RunOutcome {
+    let stats = compute_stats(
+        &backtest.broker.closed_trades,
+        &backtest.broker.equity,
+        &backtest.data,
+        risk_free_rate,
+        backtest.broker.max_margin_usage,
+        &backtest.broker.gross_exposure_history(),
+        &backtest.broker.cost_summary(),
+    );
+    RunOutcome {
+        seed,
+        return_pct: stats.return_pct,
+        sharpe_ratio: stats.sharpe_ratio,
+        max_drawdown_pct: stats.max_drawdown_pct,
+        num_trades: stats.num_trades,
+    }
+}
+
+/// run a baseline plus `config.runs` perturbed backtests; `make_backtest` must build a fresh,
+/// identically configured backtest (including a fresh strategy instance) on every call.
+pub fn run_robustness<F>(make_backtest: F, config: &RobustnessConfig) -> RobustnessReport
+where
+    F: Fn() -> Backtest,
+{
+    let mut baseline_bt = make_backtest();
+    baseline_bt.run_silent();
+    let baseline = outcome(&baseline_bt, None, config.risk_free_rate);
+
+    let mut runs = Vec::with_capacity(config.runs);
+    for i in 0..config.runs {
+        let seed = config.seed.wrapping_add(i as u64);
+        let mut backtest = make_backtest();
+        backtest.broker.set_seed(seed);
+        backtest.broker.perturbation = Some(Perturbation::new(
+            config.timing_jitter,
+            config.price_jitter_ticks,
+            config.tick_size,
+            seed,
+        ));
+        backtest.run_silent();
+        runs.push(outcome(&backtest, Some(seed), config.risk_free_rate));
+    }
+
+    let returns: Vec<f64> = runs.iter().map(|r| r.return_pct).collect();
+    let sharpes: Vec<f64> = runs.iter().map(|r| r.sharpe_ratio).collect();
+    let drawdowns: Vec<f64> = runs.iter().map(|r| r.max_drawdown_pct).collect();
+    let profitable = runs.iter().filter(|r| r.return_pct > 0.0).count();
+    let pct_profitable_runs = if runs.is_empty() { 0.0 } else { profitable as f64 / runs.len() as f64 * 100.0 };
+    let return_dist = Distribution::from_values(&returns);
+    let edge_vanishes = baseline.return_pct > 0.0 && (return_dist.median <= 0.0 || pct_profitable_runs < 50.0);
+
+    RobustnessReport {
+        baseline,
+        runs,
+        return_dist,
+        sharpe_dist: Distribution::from_values(&sharpes),
+        drawdown_dist: Distribution::from_values(&drawdowns),
+        pct_profitable_runs,
+        edge_vanishes,
+    }
+}
+
+impl fmt::Display for RobustnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n\nRobustness Test ({} perturbed runs):", self.runs.len())?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<25} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}", "Metric", "Baseline", "Mean", "P5", "Median", "P95", "Std")?;
+        let rows = [
+            ("Total Return [%]", self.baseline.return_pct, &self.return_dist),
+            ("Sharpe Ratio", self.baseline.sharpe_ratio, &self.sharpe_dist),
+            ("Max Drawdown [%]", self.baseline.max_drawdown_pct, &self.drawdown_dist),
+        ];
+        for (label, base, dist) in rows.iter() {
+            writeln!(f, "{:<25} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+                label, base, dist.mean, dist.p5, dist.median, dist.p95, dist.std)?;
+        }
+        writeln!(f, "{:<25} {:>10.2}", "Profitable Runs [%]", self.pct_profitable_runs)?;
+        if self.edge_vanishes {
+            writeln!(f, "warning: edge vanishes under microstructure noise")?;
+        }
+        write!(f, "====================")
+    }
+}
+
+// seed batch: the same backtest under different rng seeds, without any market noise, to measure how much of
+// a stochastic strategy's result (randomized entries, dropout, exploration) is down to its seed
+#[derive(Clone, Debug)]
+pub struct SeedBatchConfig {
+    pub runs: usize,
+    pub seed: u64, // run i uses seed + i for the strategy rng (broker.rng)
+    pub risk_free_rate: f64,
+}
+
+impl Default for SeedBatchConfig {
+    fn default() -> Self {
+        SeedBatchConfig {
+            runs: 20,
+            seed: 42,
+            risk_free_rate: 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SeedBatchReport {
+    pub runs: Vec<RunOutcome>,
+    pub return_dist: Distribution,
+    pub sharpe_dist: Distribution,
+    pub drawdown_dist: Distribution,
+    pub trades_dist: Distribution,
+}
+
+impl SeedBatchReport {
+    // the runs with the lowest and highest return, e.g. to replay the extremes with set_seed
+    pub fn worst_and_best(&self) -> Option<(&RunOutcome, &RunOutcome)> {
+        let by_return = |a: &&RunOutcome, b: &&RunOutcome| a.return_pct.partial_cmp(&b.return_pct).unwrap_or(std::cmp::Ordering::Equal);
+        Some((self.runs.iter().min_by(by_return)?, self.runs.iter().max_by(by_return)?))
+    }
+}
+
+/// run `config.runs` backtests that differ only in their rng seed; `make_backtest` must build a fresh,
+/// identically configured backtest (including a fresh strategy instance) on every call.
+pub fn run_seed_batch<F>(make_backtest: F, config: &SeedBatchConfig) -> SeedBatchReport
+where
+    F: Fn() -> Backtest,
+{
+    let mut runs = Vec::with_capacity(config.runs);
+    for i in 0..config.runs {
+        let seed = config.seed.wrapping_add(i as u64);
+        let mut backtest = make_backtest();
+        backtest.broker.set_seed(seed);
+        backtest.run_silent();
+        runs.push(outcome(&backtest, Some(seed), config.risk_free_rate));
+    }
+
+    let values = |f: fn(&RunOutcome) -> f64| Distribution::from_values(&runs.iter().map(f).collect::<Vec<f64>>());
+    SeedBatchReport {
+        return_dist: values(|r| r.return_pct),
+        sharpe_dist: values(|r| r.sharpe_ratio),
+        drawdown_dist: values(|r| r.max_drawdown_pct),
+        trades_dist: values(|r| r.num_trades as f64),
+        runs,
+    }
+}
+
+impl fmt::Display for SeedBatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n\nSeed Batch ({} runs):", self.runs.len())?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<25} {:>10} {:>10} {:>10} {:>10} {:>10}", "Metric", "Mean", "Std", "P5", "Median", "P95")?;
+        let rows = [
+            ("Total Return [%]", &self.return_dist),
+            ("Sharpe Ratio", &self.sharpe_dist),
+            ("Max Drawdown [%]", &self.drawdown_dist),
+            ("Total Trades", &self.trades_dist),
+        ];
+        for (label, dist) in rows.iter() {
+            writeln!(f, "{:<25} {:>10.2} {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+                label, dist.mean, dist.std, dist.p5, dist.median, dist.p95)?;
+        }
+        if let Some((worst, best)) = self.worst_and_best() {
+            writeln!(f, "{:<25} {:>10.2} (seed {})", "Worst Return [%]", worst.return_pct, worst.seed.unwrap_or_default())?;
+            writeln!(f, "{:<25} {:>10.2} (seed {})", "Best Return [%]", best.return_pct, best.seed.unwrap_or_default())?;
+        }
+        write!(f, "====================")
+    }
+}
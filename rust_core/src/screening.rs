@@ -0,0 +1,262 @@
+// universe screening for portfolio strategies
+use crate::clock::parse_quote_time;
+use crate::data_handler::handle_ohlc_dir;
+use crate::engine::{compare_dates, OhlcData};
+use crate::util::{correlation, log_returns, std_dev};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+
+// filters applied to every symbol over the trailing lookback window.
+// a filter set to None is skipped.
+#[derive(Clone, Debug)]
+pub struct ScreenCriteria {
+    pub lookback: usize,                 // number of bars used to compute the metrics
+    pub min_avg_volume: Option<f64>,     // minimum average traded notional (close * volume) per bar
+    pub min_volatility: Option<f64>,     // minimum per-bar std of log returns
+    pub max_volatility: Option<f64>,     // maximum per-bar std of log returns
+    pub min_correlation: Option<f64>,    // minimum return correlation to the benchmark
+    pub max_correlation: Option<f64>,    // maximum return correlation to the benchmark
+    pub max_symbols: Option<usize>,      // keep at most this many symbols after filtering
+}
+
+impl Default for ScreenCriteria {
+    fn default() -> Self {
+        ScreenCriteria {
+            lookback: 100,
+            min_avg_volume: None,
+            min_volatility: None,
+            max_volatility: None,
+            min_correlation: None,
+            max_correlation: None,
+            max_symbols: None,
+        }
+    }
+}
+
+// metrics computed for one symbol at one screening point
+#[derive(Clone, Debug)]
+pub struct SymbolMetrics {
+    pub symbol: String,
+    pub avg_notional_volume: Option<f64>, // None if the symbol has no volume data
+    pub volatility: f64,
+    pub correlation: Option<f64>,         // None if no benchmark was supplied or no bar shares a date with it
+    pub selected: bool,
+}
+
+// compute screening metrics for a symbol over the bars (end - lookback, end]; None if it has fewer than
+// two bars up to `end` (e.g. an empty csv or a symbol listed on that bar), so there is no return to measure
+pub fn compute_symbol_metrics(
+    symbol: &str,
+    data: &OhlcData,
+    benchmark: Option<&OhlcData>,
+    end: usize,
+    lookback: usize,
+) -> Option<SymbolMetrics> {
+    if data.close.len() < 2 || end == 0 {
+        return None;
+    }
+    let end = end.min(data.close.len() - 1);
+    let start = end.saturating_sub(lookback);
+    let closes = &data.close[start..=end];
+    let returns = log_returns(closes);
+
+    let avg_notional_volume = data.volume.as_ref().map(|volume| {
+        let n = (end - start + 1) as f64;
+        (start..=end).map(|i| volume[i] * data.close[i]).sum::<f64>() / n
+    });
+
+    let correlation = benchmark.and_then(|bench| {
+        let (returns, bench_returns) = date_aligned_returns(data, start, end, bench);
+        (returns.len() >= 2).then(|| correlation(&returns, &bench_returns))
+    });
+
+    Some(SymbolMetrics {
+        symbol: symbol.to_string(),
+        avg_notional_volume,
+        volatility: std_dev(&returns),
+        correlation,
+        selected: false,
+    })
+}
+
+// log returns of a symbol over its bars (start, end] and of the benchmark over the same intervals, joined
+// on the date: a bar without a benchmark bar at its date is skipped, so the return over a gap or around a
+// missing benchmark day spans the same days on both sides. dates are compared as parsed times, so the two
+// files may use different formats and the benchmark needn't be sorted.
+pub(crate) fn date_aligned_returns(data: &OhlcData, start: usize, end: usize, benchmark: &OhlcData) -> (Vec<f64>, Vec<f64>) {
+    let log_return = |from: f64, to: f64| if from > 0.0 && to > 0.0 { (to / from).ln() } else { 0.0 };
+    let bench_bars: HashMap<Result<DateTime<Utc>, &str>, usize> = benchmark.date.iter()
+        .enumerate()
+        .map(|(j, date)| (date_key(date), j))
+        .collect();
+    let mut returns = Vec::new();
+    let mut bench_returns = Vec::new();
+    // (symbol close, benchmark close) of the last bar found in the benchmark
+    let mut previous: Option<(f64, f64)> = None;
+    for i in start..=end {
+        let Some(&j) = bench_bars.get(&date_key(&data.date[i])) else {
+            continue;
+        };
+        let (close, bench_close) = (data.close[i], benchmark.close[j]);
+        if let Some((prev_close, prev_bench)) = previous {
+            returns.push(log_return(prev_close, close));
+            bench_returns.push(log_return(prev_bench, bench_close));
+        }
+        previous = Some((close, bench_close));
+    }
+    (returns, bench_returns)
+}
+
+// join key of a bar date: its parsed time, or the raw string if parse_quote_time can't read it
+fn date_key(date: &str) -> Result<DateTime<Utc>, &str> {
+    parse_quote_time(date).ok_or(date)
+}
+
+// last bar of a symbol dated at or before `date`, None if it has no bar by then (e.g. not yet listed)
+fn last_bar_at(data: &OhlcData, date: &str) -> Option<usize> {
+    data.date.iter().rposition(|d| compare_dates(d, date) != Ordering::Greater)
+}
+
+impl ScreenCriteria {
+    // check whether a symbol's metrics pass every configured filter
+    pub fn passes(&self, metrics: &SymbolMetrics) -> bool {
+        if let Some(min_volume) = self.min_avg_volume {
+            // symbols without volume data cannot prove liquidity
+            match metrics.avg_notional_volume {
+                Some(v) if v >= min_volume => {}
+                _ => return false,
+            }
+        }
+        if let Some(min_vol) = self.min_volatility {
+            if metrics.volatility < min_vol {
+                return false;
+            }
+        }
+        if let Some(max_vol) = self.max_volatility {
+            if metrics.volatility > max_vol {
+                return false;
+            }
+        }
+        if let Some(corr) = metrics.correlation {
+            if self.min_correlation.map(|min| corr < min).unwrap_or(false) {
+                return false;
+            }
+            if self.max_correlation.map(|max| corr > max).unwrap_or(false) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// screener holds the candidate symbols and re-evaluates the universe on a fixed bar schedule.
+// portfolio strategies call `update` from `next` with the bar's index and date and read `universe`
+// afterwards. every symbol is screened on its own bars up to that date, whatever its listing date or gaps.
+pub struct Screener {
+    pub symbols: Vec<(String, OhlcData)>,
+    pub benchmark: Option<OhlcData>, // e.g. an index csv; correlations are computed on shared dates
+    pub criteria: ScreenCriteria,
+    pub rebalance_every: usize,            // re-screen every n bars (0 = only once)
+    pub universe: Vec<String>,             // currently selected symbols
+    pub history: Vec<(usize, Vec<String>)>, // bar index and universe at each re-evaluation
+    last_screen: Option<usize>,
+}
+
+impl Screener {
+    pub fn new(
+        symbols: Vec<(String, OhlcData)>,
+        benchmark: Option<OhlcData>,
+        criteria: ScreenCriteria,
+        rebalance_every: usize,
+    ) -> Self {
+        Screener {
+            symbols,
+            benchmark,
+            criteria,
+            rebalance_every,
+            universe: Vec::new(),
+            history: Vec::new(),
+            last_screen: None,
+        }
+    }
+
+    // build a screener from a directory of symbol csvs
+    pub fn from_dir(
+        dir: &str,
+        benchmark: Option<OhlcData>,
+        criteria: ScreenCriteria,
+        rebalance_every: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        let symbols = handle_ohlc_dir(dir)?;
+        Ok(Screener::new(symbols, benchmark, criteria, rebalance_every))
+    }
+
+    // compute metrics for every symbol at its last bar at or before `date` and mark the selected ones;
+    // symbols with fewer than two bars by then are left out
+    pub fn screen(&self, date: &str) -> Vec<SymbolMetrics> {
+        let benchmark = self.benchmark.as_ref();
+        let mut metrics: Vec<SymbolMetrics> = self.symbols.iter()
+            .filter_map(|(symbol, data)| {
+                let end = last_bar_at(data, date)?;
+                compute_symbol_metrics(symbol, data, benchmark, end, self.criteria.lookback)
+            })
+            .collect();
+
+        let mut passing: Vec<usize> = (0..metrics.len())
+            .filter(|&i| self.criteria.passes(&metrics[i]))
+            .collect();
+
+        // rank by liquidity when available, otherwise by volatility, and cut to max_symbols
+        if let Some(max_symbols) = self.criteria.max_symbols {
+            passing.sort_by(|&a, &b| {
+                let key_a = metrics[a].avg_notional_volume.unwrap_or(metrics[a].volatility);
+                let key_b = metrics[b].avg_notional_volume.unwrap_or(metrics[b].volatility);
+                key_b.partial_cmp(&key_a).unwrap_or(std::cmp::Ordering::Equal)
+            });
+            passing.truncate(max_symbols);
+        }
+        for i in passing {
+            metrics[i].selected = true;
+        }
+        metrics
+    }
+
+    // re-screen at the bar `index` dated `date` if the schedule is due; returns true if the universe was
+    // re-evaluated
+    pub fn update(&mut self, index: usize, date: &str) -> bool {
+        let due = match self.last_screen {
+            None => index >= self.criteria.lookback,
+            Some(last) => self.rebalance_every > 0 && index >= last + self.rebalance_every,
+        };
+        if !due {
+            return false;
+        }
+        self.universe = self.screen(date)
+            .into_iter()
+            .filter(|m| m.selected)
+            .map(|m| m.symbol)
+            .collect();
+        self.history.push((index, self.universe.clone()));
+        self.last_screen = Some(index);
+        true
+    }
+
+    // look up the data for a symbol in the candidate set
+    pub fn data(&self, symbol: &str) -> Option<&OhlcData> {
+        self.symbols.iter().find(|(s, _)| s == symbol).map(|(_, data)| data)
+    }
+
+    // write the universe history to csv: one row per re-evaluation
+    pub fn save_universe(&self, file_path: &str) -> std::io::Result<()> {
+        use std::fs::File;
+        use std::io::Write;
+        let mut file = File::create(file_path)?;
+        writeln!(file, "index,symbols")?;
+        for (index, universe) in self.history.iter() {
+            writeln!(file, "{},{}", index, universe.join(";"))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+// signal export: the target position of every instrument whenever it changes, as a chronological csv
+// (time, instrument, target_position), so a run can feed an external execution system or oms instead of
+// relying on the simulated fills
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signal {
+    pub time: String,
+    pub instrument: String,
+    pub target_position: f64, // signed units, 0.0 = flat
+}
+
+// changes smaller than this are rounding noise, not a new target
+const TARGET_TOLERANCE: f64 = 1e-9;
+
+pub struct SignalWriter {
+    writer: csv::Writer<File>,
+    last: HashMap<String, f64>,
+    pub signals_written: usize,
+}
+
+impl SignalWriter {
+    /// create (or truncate) `path` with the header time,instrument,target_position
+    pub fn create(path: &str) -> Result<Self, Box<dyn Error>> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(["time", "instrument", "target_position"])?;
+        writer.flush()?;
+        Ok(SignalWriter { writer, last: HashMap::new(), signals_written: 0 })
+    }
+
+    /// write a row for every instrument whose target differs from the last one written for it (an
+    /// instrument starts flat, so nothing is written until it is first traded). rows are flushed right away,
+    /// so an external system can tail the file. returns the number of rows written.
+    pub fn update(&mut self, time: &str, targets: &[(&str, f64)]) -> Result<usize, Box<dyn Error>> {
+        let mut written = 0;
+        for &(instrument, target) in targets {
+            let last = self.last.get(instrument).copied().unwrap_or(0.0);
+            if (target - last).abs() <= TARGET_TOLERANCE {
+                continue;
+            }
+            self.writer.write_record([time, instrument, &target.to_string()])?;
+            self.last.insert(instrument.to_string(), target);
+            written += 1;
+        }
+        if written > 0 {
+            self.writer.flush()?;
+            self.signals_written += written;
+        }
+        Ok(written)
+    }
+}
+
+// signal export of a backtest: the writer and the names written for instrument 1 and 2
+pub struct SignalExport {
+    pub writer: SignalWriter,
+    pub instruments: [String; 2],
+}
+
+/// read a signal file back, e.g. to compare the signals of two runs
+pub fn load_signals(path: &str) -> Result<Vec<Signal>, Box<dyn Error>> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let mut signals = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let field = |i: usize| record.get(i).ok_or_else(|| format!("missing column {} in {}", i + 1, path));
+        signals.push(Signal {
+            time: field(0)?.to_string(),
+            instrument: field(1)?.to_string(),
+            target_position: field(2)?.parse()?,
+        });
+    }
+    Ok(signals)
+}
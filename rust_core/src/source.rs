@@ -0,0 +1,141 @@
+// streamed bar input for backtests over data sets that do not fit in memory: bars are read one at a
+// time and the broker only keeps a window of recent bars (see Broker::push_bar)
+use crate::engine::{Broker, OhlcData, Strategy};
+use csv::{Reader, ReaderBuilder, StringRecord};
+use std::error::Error;
+use std::fs::File;
+
+#[derive(Clone, Debug)]
+pub struct Bar {
+    pub date: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub close2: f64,
+    pub volume: Option<f64>,
+}
+
+// yields bars in time order; any Iterator<Item = Bar> is a source
+pub trait BarSource {
+    fn next_bar(&mut self) -> Result<Option<Bar>, Box<dyn Error>>;
+}
+
+impl<I: Iterator<Item = Bar>> BarSource for I {
+    fn next_bar(&mut self) -> Result<Option<Bar>, Box<dyn Error>> {
+        Ok(self.next())
+    }
+}
+
+// bars of an in-memory data set, e.g. to check a streaming strategy against a normal backtest
+pub fn bars(data: &OhlcData) -> impl Iterator<Item = Bar> + '_ {
+    (0..data.close.len()).map(move |i| Bar {
+        date: data.date[i].clone(),
+        open: data.open[i],
+        high: data.high[i],
+        low: data.low[i],
+        close: data.close[i],
+        close2: data.close2.get(i).copied().unwrap_or(0.0),
+        volume: data.volume.as_ref().map(|v| v[i]),
+    })
+}
+
+/// reads an ohlc csv (same columns as handle_ohlc) row by row. blank close2 fields carry the last
+/// close2 forward for up to `max_staleness` rows; rows beyond that, and rows before the first close2,
+/// are skipped. files without a close2 column read close2 as 0.0.
+pub struct CsvBarSource {
+    reader: Reader<File>,
+    record: StringRecord,
+    has_close2: bool,
+    max_staleness: usize,
+    last_close2: Option<f64>,
+    stale: usize,
+    pub skipped: usize, // rows skipped for a missing close2
+}
+
+impl CsvBarSource {
+    pub fn open(path: &str, max_staleness: usize) -> Result<Self, Box<dyn Error>> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(true)
+            .from_path(path)?;
+        let has_close2 = reader.headers()?.len() > 5;
+        Ok(CsvBarSource {
+            reader,
+            record: StringRecord::new(),
+            has_close2,
+            max_staleness,
+            last_close2: None,
+            stale: 0,
+            skipped: 0,
+        })
+    }
+
+    fn close2(&mut self) -> Result<Option<f64>, Box<dyn Error>> {
+        if !self.has_close2 {
+            return Ok(Some(0.0));
+        }
+        match self.record.get(5) {
+            Some(field) if !field.trim().is_empty() => {
+                let value = field.parse::<f64>()?;
+                self.last_close2 = Some(value);
+                self.stale = 0;
+                Ok(Some(value))
+            }
+            _ => {
+                self.stale += 1;
+                Ok(if self.stale <= self.max_staleness { self.last_close2 } else { None })
+            }
+        }
+    }
+}
+
+impl BarSource for CsvBarSource {
+    fn next_bar(&mut self) -> Result<Option<Bar>, Box<dyn Error>> {
+        while self.reader.read_record(&mut self.record)? {
+            let close2 = match self.close2()? {
+                Some(close2) => close2,
+                None => {
+                    self.skipped += 1;
+                    continue;
+                }
+            };
+            let volume = match self.record.get(6) {
+                Some(field) if !field.trim().is_empty() => Some(field.parse::<f64>()?),
+                _ => None,
+            };
+            return Ok(Some(Bar {
+                date: self.record[0].to_string(),
+                open: self.record[1].parse::<f64>()?,
+                high: self.record[2].parse::<f64>()?,
+                low: self.record[3].parse::<f64>()?,
+                close: self.record[4].parse::<f64>()?,
+                close2,
+                volume,
+            }));
+        }
+        Ok(None)
+    }
+}
+
+/// run a strategy over a bar source, keeping only the last `window` bars in `broker.data`.
+/// `broker` should be created with empty data; the strategy's init sees empty data, so it has to
+/// compute its indicators incrementally in next and read prices with broker.close_at(index)
+/// (indices are absolute, bars before broker.first_index() are gone). per-bar results such as the
+/// equity curve and exposure histories are still recorded for every bar. returns the number of bars.
+pub fn run_streaming(
+    broker: &mut Broker,
+    strategy: &mut dyn Strategy,
+    source: &mut dyn BarSource,
+    window: usize,
+) -> Result<usize, Box<dyn Error>> {
+    let empty = broker.data.slice(0..0);
+    strategy.init(broker, &empty);
+    let mut bars = 0;
+    while let Some(bar) = source.next_bar()? {
+        let index = broker.push_bar(bar, window);
+        broker.next(index);
+        strategy.next(broker, index);
+        bars += 1;
+    }
+    Ok(bars)
+}
@@ -1,8 +1,10 @@
 // module for computing performance statistics
 
-use crate::engine::{OhlcData, Trade};
+use crate::engine::{CostSummary, OhlcData, Trade};
+use crate::clock::parse_quote_time;
 use std::fmt;
-use chrono::NaiveDateTime;
+use chrono::{Datelike, NaiveDateTime, Weekday};
+use serde::{Deserialize, Serialize};
 
 /// compute geometric mean from a slice; if any value is <= 0, return 0.0
 pub fn geometric_mean(returns: &[f64]) -> f64 {
@@ -14,7 +16,7 @@ pub fn geometric_mean(returns: &[f64]) -> f64 {
     (sum_logs / n).exp() - 1.0
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Stats {
     // tick index of start and end of simulation
     pub start: usize,
@@ -29,6 +31,8 @@ pub struct Stats {
     pub sharpe_ratio: f64,
     pub calmar_ratio: f64,
     pub max_drawdown_pct: f64,
+    // deepest fall from a closing peak to a bar's worst-case equity (lows for longs, highs for shorts)
+    pub max_intrabar_drawdown_pct: f64,
     // number of trades executed
     pub num_trades: usize,
     pub win_rate_pct: f64,
@@ -45,6 +49,280 @@ pub struct Stats {
     pub beta: f64,
     // new field for maximum margin usage (percentage)
     pub max_margin_usage: f64,
+    // gross exposure divided by equity
+    pub avg_gross_leverage: f64,
+    pub max_gross_leverage: f64,
+    // turnover
+    pub traded_notional: f64,
+    pub turnover: f64,            // traded notional divided by average equity
+    pub num_round_trips: usize,
+    pub avg_holding_bars: f64,
+    // cost attribution
+    pub total_commission: f64,    // net of rebates
+    pub total_rebates: f64,
+    pub total_spread_cost: f64,
+    pub total_financing_cost: f64,
+    pub gross_pnl: f64,           // net pnl plus all costs
+    pub commission_pct_of_gross: f64,
+    pub spread_pct_of_gross: f64,
+    pub financing_pct_of_gross: f64,
+    // execution: fill price vs the price at decision time, per unit (positive = cost)
+    pub avg_buy_slippage: f64,
+    pub avg_sell_slippage: f64,
+    pub total_slippage_cost: f64,
+    // drawdown governor: share of bars with reduced entry sizes and the smallest multiplier applied
+    pub de_risked_time_pct: f64,
+    pub min_size_multiplier: f64,
+    // largest drawdowns, deepest first
+    pub top_drawdowns: Vec<Drawdown>,
+}
+
+// one peak-to-recovery drawdown episode
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Drawdown {
+    pub start_index: usize,            // peak before the decline
+    pub trough_index: usize,
+    pub recovery_index: Option<usize>, // first tick back at the peak, None if not recovered
+    pub start_date: String,
+    pub trough_date: String,
+    pub recovery_date: Option<String>,
+    pub depth_pct: f64,                // negative, e.g. -12.5
+}
+
+impl Drawdown {
+    // ticks from peak to recovery (or to the end of the data if not recovered)
+    pub fn duration(&self, last_index: usize) -> usize {
+        self.recovery_index.unwrap_or(last_index) - self.start_index
+    }
+}
+
+/// the `n` deepest drawdown episodes of an equity curve
+pub fn top_drawdowns(equity: &[f64], dates: &[String], n: usize) -> Vec<Drawdown> {
+    let mut episodes: Vec<Drawdown> = Vec::new();
+    if equity.is_empty() {
+        return episodes;
+    }
+    let date_at = |i: usize| dates.get(i).cloned().unwrap_or_default();
+    let mut peak_index = 0;
+    let mut trough_index = 0;
+    let mut in_drawdown = false;
+
+    for (i, &value) in equity.iter().enumerate() {
+        let peak = equity[peak_index];
+        if value >= peak {
+            if in_drawdown {
+                episodes.push(Drawdown {
+                    start_index: peak_index,
+                    trough_index,
+                    recovery_index: Some(i),
+                    start_date: date_at(peak_index),
+                    trough_date: date_at(trough_index),
+                    recovery_date: Some(date_at(i)),
+                    depth_pct: (equity[trough_index] - peak) / peak * 100.0,
+                });
+                in_drawdown = false;
+            }
+            peak_index = i;
+        } else if !in_drawdown {
+            in_drawdown = true;
+            trough_index = i;
+        } else if value < equity[trough_index] {
+            trough_index = i;
+        }
+    }
+    if in_drawdown {
+        let peak = equity[peak_index];
+        episodes.push(Drawdown {
+            start_index: peak_index,
+            trough_index,
+            recovery_index: None,
+            start_date: date_at(peak_index),
+            trough_date: date_at(trough_index),
+            recovery_date: None,
+            depth_pct: (equity[trough_index] - peak) / peak * 100.0,
+        });
+    }
+
+    episodes.sort_by(|a, b| a.depth_pct.partial_cmp(&b.depth_pct).unwrap_or(std::cmp::Ordering::Equal));
+    episodes.truncate(n);
+    episodes
+}
+
+// how many return periods make up a year, used to annualize volatility
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Annualization {
+    Auto,              // infer from the average time between observations (calendar time)
+    TradingDays(f64),  // e.g. 252 trading days times the observed bars per day
+    CalendarDays(f64), // e.g. 365 days times the observed bars per day
+    Continuous,        // 24/7 markets (crypto): a calendar year divided by the median bar length
+    Periods(f64),      // explicit periods per year
+    ElapsedTime,       // irregular observations (ticks, quote pseudo-bars): every return is weighted by
+                       // the time it took, and the annual return uses the exact elapsed time
+}
+
+// price series buy & hold, alpha and beta are measured against
+#[derive(Clone, Debug, Default)]
+pub enum Benchmark {
+    #[default]
+    Close,              // primary instrument (ohlc.close)
+    Close2,             // hedge instrument (ohlc.close2)
+    External(Vec<f64>), // any series aligned to the backtest dates, e.g. from data_handler::load_benchmark
+    None,
+}
+
+impl Benchmark {
+    // the benchmark prices for a data set, None if disabled or unusable (empty, starting at zero, or an
+    // external series with a different number of bars than the data)
+    pub fn series<'a>(&'a self, ohlc: &'a OhlcData) -> Option<&'a [f64]> {
+        let series: &[f64] = match self {
+            Benchmark::Close => &ohlc.close,
+            Benchmark::Close2 => &ohlc.close2,
+            Benchmark::External(values) => values,
+            Benchmark::None => return None,
+        };
+        if series.len() != ohlc.close.len() {
+            return None;
+        }
+        match series.first() {
+            Some(&first) if first != 0.0 => Some(series),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StatsConfig {
+    pub annualization: Annualization,
+    // for Auto: ignore gaps spanning a weekend and count only weekday time in the year,
+    // so market-hours-only data is not annualized as if it traded on weekends
+    pub exclude_weekends: bool,
+    pub benchmark: Benchmark,
+    // external deposits (+) / withdrawals (-) per bar, e.g. Broker::cash_flow_history; when any is non-zero,
+    // returns, volatility and drawdowns are time-weighted so contributions don't count as performance
+    pub cash_flows: Vec<f64>,
+    // worst-case equity per bar, e.g. Broker::intrabar_equity; empty to report the close-based drawdown
+    // as max_intrabar_drawdown_pct
+    pub intrabar_equity: Vec<f64>,
+    // entry size multiplier per bar, e.g. Broker::size_multiplier_history; empty without a drawdown governor
+    pub size_multipliers: Vec<f64>,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        StatsConfig {
+            annualization: Annualization::Auto,
+            exclude_weekends: false,
+            benchmark: Benchmark::Close,
+            cash_flows: Vec::new(),
+            intrabar_equity: Vec::new(),
+            size_multipliers: Vec::new(),
+        }
+    }
+}
+
+// "%Y-%m-%d %H:%M:%S" as in the csv data, or rfc3339 as in live quotes
+fn parse_date(date: &str) -> NaiveDateTime {
+    parse_quote_time(date)
+        .map(|dt| dt.naive_utc())
+        .unwrap_or_else(|| panic!("unrecognised date format: {}", date))
+}
+
+// average number of observations per distinct calendar date
+fn bars_per_day(dates: &[NaiveDateTime]) -> f64 {
+    let mut days: Vec<chrono::NaiveDate> = dates.iter().map(|d| d.date()).collect();
+    days.dedup();
+    if days.is_empty() { 1.0 } else { dates.len() as f64 / days.len() as f64 }
+}
+
+// true if the interval between two observations contains a saturday or sunday
+fn spans_weekend(d0: &NaiveDateTime, d1: &NaiveDateTime) -> bool {
+    let days = (d1.date() - d0.date()).num_days();
+    if days >= 7 {
+        return true;
+    }
+    (0..=days.max(0)).any(|k| {
+        let day = d0.date() + chrono::Duration::days(k);
+        matches!(day.weekday(), Weekday::Sat | Weekday::Sun)
+    })
+}
+
+/// number of return periods per year for the given observation dates
+pub fn periods_per_year(dates: &[String], config: &StatsConfig) -> f64 {
+    let parsed: Vec<NaiveDateTime> = dates.iter().map(|d| parse_date(d)).collect();
+    let seconds_per_year = 365.0 * 24.0 * 3600.0; // number of seconds in a calendar year
+    match config.annualization {
+        Annualization::Periods(n) => n,
+        Annualization::TradingDays(days) | Annualization::CalendarDays(days) => days * bars_per_day(&parsed),
+        Annualization::Continuous => {
+            let mut deltas: Vec<f64> = parsed.windows(2).map(|w| (w[1] - w[0]).num_seconds() as f64).collect();
+            deltas.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let median = deltas.get(deltas.len() / 2).copied().unwrap_or(seconds_per_year);
+            seconds_per_year / median
+        }
+        Annualization::Auto | Annualization::ElapsedTime => {
+            // Instead of assuming 252 trading days, compute the actual number of periods per year.
+            // We use the dates to calculate the average time delta between observations.
+            let deltas: Vec<f64> = parsed.windows(2)
+                .filter(|w| !(config.exclude_weekends && spans_weekend(&w[0], &w[1])))
+                .map(|w| (w[1] - w[0]).num_seconds() as f64)
+                .collect();
+            let avg_dt = deltas.iter().sum::<f64>() / deltas.len() as f64;
+            let year = if config.exclude_weekends { seconds_per_year * 5.0 / 7.0 } else { seconds_per_year };
+            year / avg_dt
+        }
+    }
+}
+
+/// annualized volatility of returns over irregular intervals: the variance per second is the sum of
+/// squared (drift-adjusted) returns divided by the total elapsed time, so a return over a long gap
+/// counts as much as the many small ones it replaces. returns[i] spans dates[i]..dates[i + 1].
+pub fn elapsed_time_volatility(returns: &[f64], dates: &[String]) -> f64 {
+    let parsed: Vec<NaiveDateTime> = dates.iter().map(|d| parse_date(d)).collect();
+    let elapsed: Vec<f64> = parsed.windows(2)
+        .map(|w| ((w[1] - w[0]).num_milliseconds() as f64 / 1000.0).max(0.0))
+        .collect();
+    let n = returns.len().min(elapsed.len());
+    let total: f64 = elapsed[..n].iter().sum();
+    if n < 2 || total <= 0.0 {
+        return 0.0;
+    }
+    let drift = returns[..n].iter().sum::<f64>() / total;
+    let variance_per_second = returns[..n].iter()
+        .zip(elapsed[..n].iter())
+        .map(|(r, dt)| (r - drift * dt).powi(2))
+        .sum::<f64>() / total;
+    (variance_per_second * 365.0 * 24.0 * 3600.0).sqrt()
+}
+
+/// drawdown of every bar below the running peak of `equity`, as a fraction (0.0 at a new high,
+/// -0.1 when 10% below the peak)
+pub fn drawdown_series(equity: &[f64]) -> Vec<f64> {
+    let mut peak = f64::NEG_INFINITY;
+    equity.iter()
+        .map(|&val| {
+            peak = peak.max(val);
+            if peak > 0.0 { (val - peak) / peak } else { 0.0 }
+        })
+        .collect()
+}
+
+/// drawdown of the strategy relative to a benchmark: the drawdown of equity / benchmark, both rebased to
+/// their first bar, so it shows how far the strategy has fallen behind its best showing against the
+/// benchmark. series of different lengths are cut to the shorter one.
+pub fn relative_drawdown_series(equity: &[f64], benchmark: &[f64]) -> Vec<f64> {
+    let (Some(&equity0), Some(&benchmark0)) = (equity.first(), benchmark.first()) else {
+        return Vec::new();
+    };
+    let relative: Vec<f64> = equity.iter().zip(benchmark.iter())
+        .map(|(&e, &b)| {
+            if benchmark0 != 0.0 && b != 0.0 && equity0 != 0.0 {
+                (e / equity0) / (b / benchmark0)
+            } else {
+                1.0
+            }
+        })
+        .collect();
+    drawdown_series(&relative)
 }
 
 fn max_drawdown(equity: &[f64]) -> f64 {
@@ -63,6 +341,19 @@ fn max_drawdown(equity: &[f64]) -> f64 {
     max_dd
 }
 
+// deepest drop from the running peak of `equity` to the worst-case value of a later (or the same) bar
+fn max_intrabar_drawdown(equity: &[f64], worst: &[f64]) -> f64 {
+    let mut peak = equity[0];
+    let mut max_dd: f64 = 0.0;
+    for (&val, &low) in equity.iter().zip(worst.iter()) {
+        peak = peak.max(val);
+        if peak > 0.0 {
+            max_dd = max_dd.min((low.min(val) - peak) / peak);
+        }
+    }
+    max_dd
+}
+
 fn compute_beta(equity: &[f64], market_prices: &[f64]) -> f64 {
     let mut equity_returns = Vec::with_capacity(equity.len() - 1);
     let mut market_returns = Vec::with_capacity(market_prices.len() - 1);
@@ -116,35 +407,186 @@ pub fn compute_stats(
     equity: &[f64],
     ohlc: &OhlcData,
     risk_free_rate: f64,
-    max_margin_usage: f64
+    max_margin_usage: f64,
+    gross_exposure: &[f64],
+    costs: &CostSummary,
+) -> Stats {
+    compute_stats_with_config(
+        trades,
+        equity,
+        ohlc,
+        risk_free_rate,
+        max_margin_usage,
+        gross_exposure,
+        costs,
+        &StatsConfig::default(),
+    )
+}
+
+/// compute_stats with explicit annualization and calendar assumptions
+pub fn compute_stats_with_config(
+    trades: &[Trade],
+    equity: &[f64],
+    ohlc: &OhlcData,
+    risk_free_rate: f64,
+    max_margin_usage: f64,
+    gross_exposure: &[f64],
+    costs: &CostSummary,
+    config: &StatsConfig,
+) -> Stats {
+    stats_from_equity(
+        trades,
+        equity,
+        &ohlc.date,
+        config.benchmark.series(ohlc),
+        risk_free_rate,
+        max_margin_usage,
+        gross_exposure,
+        costs,
+        config,
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StatsError {
+    NoReturns,
+    DateCount(usize, usize), // returns, dates
+    InvalidDate(String),
+    SpanTooShort, // less than a day from start to end, or a single return without its start date
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsError::NoReturns => write!(f, "no returns to compute stats for"),
+            StatsError::DateCount(returns, dates) => write!(
+                f, "{} returns need {} dates (one per return plus an optional start date), got {}", returns, returns + 1, dates
+            ),
+            StatsError::InvalidDate(date) => write!(f, "unrecognised date format: {}", date),
+            StatsError::SpanTooShort => write!(f, "the returns span less than a day, too short to annualize"),
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+/// compute stats for an externally produced return series (live session equity, combined portfolios, ...).
+/// dates[0] is the start and dates[i + 1] the end of returns[i]; if dates has the same length as returns,
+/// dates[i] is the end of returns[i] and the start is taken one spacing (dates[1] - dates[0]) before
+/// dates[0]. the returns have to span at least a day to be annualized. trade, cost and benchmark figures
+/// are reported as zero.
+pub fn compute_stats_from_returns(returns: &[f64], dates: &[String], risk_free_rate: f64) -> Result<Stats, StatsError> {
+    if returns.is_empty() {
+        return Err(StatsError::NoReturns);
+    }
+    if let Some(date) = dates.iter().find(|date| parse_quote_time(date).is_none()) {
+        return Err(StatsError::InvalidDate(date.clone()));
+    }
+    let mut dates = dates.to_vec();
+    if dates.len() == returns.len() {
+        if dates.len() < 2 {
+            return Err(StatsError::SpanTooShort);
+        }
+        let (first, second) = (parse_date(&dates[0]), parse_date(&dates[1]));
+        dates.insert(0, (first - (second - first)).format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+    if dates.len() != returns.len() + 1 {
+        return Err(StatsError::DateCount(returns.len(), dates.len()));
+    }
+    if (parse_date(&dates[returns.len()]) - parse_date(&dates[0])).num_days() < 1 {
+        return Err(StatsError::SpanTooShort);
+    }
+
+    let mut equity = Vec::with_capacity(returns.len() + 1);
+    equity.push(1.0);
+    for r in returns.iter() {
+        let last = *equity.last().unwrap();
+        equity.push(last * (1.0 + r));
+    }
+    Ok(stats_from_equity(
+        &[],
+        &equity,
+        &dates,
+        None,
+        risk_free_rate,
+        0.0,
+        &[],
+        &CostSummary::default(),
+        &StatsConfig::default(),
+    ))
+}
+
+/// time-weighted performance index of an equity curve with external cash flows: each flow is taken to
+/// arrive at the start of its bar, so index[t] = index[t - 1] * equity[t] / (equity[t - 1] + flows[t]).
+/// the index starts at equity[0] so it reads like an equity curve without the contributions.
+pub fn time_weighted_index(equity: &[f64], flows: &[f64]) -> Vec<f64> {
+    let mut index = Vec::with_capacity(equity.len());
+    if equity.is_empty() {
+        return index;
+    }
+    index.push(equity[0]);
+    for t in 1..equity.len() {
+        let base = equity[t - 1] + flows.get(t).copied().unwrap_or(0.0);
+        let growth = if base > 0.0 { equity[t] / base } else { 1.0 };
+        index.push(index[t - 1] * growth);
+    }
+    index
+}
+
+// shared implementation; benchmark prices (if any) drive buy & hold, alpha and beta
+fn stats_from_equity(
+    trades: &[Trade],
+    equity: &[f64],
+    dates: &[String],
+    benchmark: Option<&[f64]>,
+    risk_free_rate: f64,
+    max_margin_usage: f64,
+    gross_exposure: &[f64],
+    costs: &CostSummary,
+    config: &StatsConfig,
 ) -> Stats {
     let start = 0;
-    let start_date = ohlc.date[start].clone();
+    let start_date = dates[start].clone();
     let end = equity.len() - 1;
-    let end_date = ohlc.date[end].clone();
+    let end_date = dates[end].clone();
     let duration = end - start;
 
     let equity_final = equity[end];
-    let return_pct = (equity_final - equity[0]) / equity[0] * 100.0;
-    let buy_hold_return_pct =
-        (ohlc.close[ohlc.close.len() - 1] - ohlc.close[0]) / ohlc.close[0] * 100.0;
+    let net_cash_flow: f64 = config.cash_flows.iter().sum();
+    // return-based figures use the time-weighted index when there are cash flows, the raw curve otherwise
+    let twr_index;
+    let performance: &[f64] = if config.cash_flows.iter().any(|f| *f != 0.0) {
+        twr_index = time_weighted_index(equity, &config.cash_flows);
+        &twr_index
+    } else {
+        equity
+    };
+    let return_pct = (performance[end] - performance[0]) / performance[0] * 100.0;
+    let buy_hold_return_pct = match benchmark {
+        Some(close) => (close[close.len() - 1] - close[0]) / close[0] * 100.0,
+        None => 0.0,
+    };
 
     // store original string dates
     let start_date_str = start_date.clone();
     let end_date_str = end_date.clone();
     
     // calculate number of years more accurately using actual dates
-    let start_date_parsed = NaiveDateTime::parse_from_str(&start_date, "%Y-%m-%d %H:%M:%S").unwrap();
-    let end_date_parsed = NaiveDateTime::parse_from_str(&end_date, "%Y-%m-%d %H:%M:%S").unwrap();
+    let start_date_parsed = parse_date(&start_date);
+    let end_date_parsed = parse_date(&end_date);
     let days = (end_date_parsed - start_date_parsed).num_days() as f64;
-    let years = days / 365.0;  // use calendar days for year fraction
+    let years = if config.annualization == Annualization::ElapsedTime {
+        (end_date_parsed - start_date_parsed).num_milliseconds() as f64 / 1000.0 / (365.0 * 24.0 * 3600.0)
+    } else {
+        days / 365.0  // use calendar days for year fraction
+    };
     
     // calculate annualized return
     let return_ann_pct = ((1.0 + return_pct / 100.0).powf(1.0 / years) - 1.0) * 100.0;
     
     // --- Compute period returns for volatility ---
     // (Note: each return corresponds to the time between two consecutive equity observations)
-    let period_returns: Vec<f64> = equity
+    let period_returns: Vec<f64> = performance
         .windows(2)
         .map(|w| (w[1] - w[0]) / w[0])
         .collect();
@@ -168,21 +610,25 @@ pub fn compute_stats(
         0.0
     };
 
-    // Instead of assuming 252 trading days, compute the actual number of periods per year.
-    // We use the OHLC dates to calculate the average time delta between observations.
-    let mut total_seconds = 0.0;
-    for window in ohlc.date.windows(2) {
-        let d0 = NaiveDateTime::parse_from_str(&window[0], "%Y-%m-%d %H:%M:%S").unwrap();
-        let d1 = NaiveDateTime::parse_from_str(&window[1], "%Y-%m-%d %H:%M:%S").unwrap();
-        total_seconds += (d1 - d0).num_seconds() as f64;
-    }
-    let avg_dt = total_seconds / (ohlc.date.len() as f64 - 1.0);
-    let seconds_per_year = 365.0 * 24.0 * 3600.0; // number of seconds in a calendar year
-    let periods_per_year = seconds_per_year / avg_dt;
+    let periods_per_year = periods_per_year(dates, config);
 
-    let volatility_ann_pct: f64 = std_return * periods_per_year.sqrt() * 100.0;
+    let volatility_ann_pct: f64 = if config.annualization == Annualization::ElapsedTime {
+        elapsed_time_volatility(&period_returns, dates) * 100.0
+    } else {
+        std_return * periods_per_year.sqrt() * 100.0
+    };
     
-    let max_dd = max_drawdown(equity) * 100.0;
+    let max_dd = max_drawdown(performance) * 100.0;
+    let max_intrabar_dd = if config.intrabar_equity.len() == equity.len() {
+        // worst cases scaled onto the performance curve, so cash flows are handled like in max_dd
+        let worst: Vec<f64> = performance.iter()
+            .zip(equity.iter().zip(config.intrabar_equity.iter()))
+            .map(|(p, (e, w))| if *e > 0.0 { p * w / e } else { 0.0 })
+            .collect();
+        max_intrabar_drawdown(performance, &worst) * 100.0
+    } else {
+        max_dd
+    };
     let num_trades = trades.len();
     let num_wins = trades.iter().filter(|t| t.pnl() > 0.0).count();
     let win_rate_pct = if num_trades > 0 {
@@ -264,10 +710,49 @@ pub fn compute_stats(
         .min_by(|a, b| a.partial_cmp(b).unwrap())
         .unwrap_or(0.0);
 
-    let alpha = return_pct - buy_hold_return_pct;
-    let beta = compute_beta(equity, &ohlc.close);
+    let alpha = if benchmark.is_some() { return_pct - buy_hold_return_pct } else { 0.0 };
+    let beta = benchmark.map(|close| compute_beta(performance, close)).unwrap_or(0.0);
     let alpha_risk_adjusted = (return_pct - risk_free_rate * 100.0) - beta *(buy_hold_return_pct - risk_free_rate * 100.0);
 
+    // gross leverage per tick; ticks with non-positive equity are skipped
+    let leverage: Vec<f64> = gross_exposure.iter()
+        .zip(equity.iter())
+        .filter(|(_, &eq)| eq > 0.0)
+        .map(|(&gross, &eq)| gross / eq)
+        .collect();
+    let avg_gross_leverage = if !leverage.is_empty() {
+        leverage.iter().sum::<f64>() / leverage.len() as f64
+    } else {
+        0.0
+    };
+    let max_gross_leverage = leverage.iter().cloned().fold(0.0, f64::max);
+
+    // turnover: traded notional relative to the average equity over the run
+    let avg_equity = equity.iter().sum::<f64>() / equity.len() as f64;
+    let turnover = if avg_equity > 0.0 { costs.traded_notional / avg_equity } else { 0.0 };
+    let num_round_trips = trades.iter().filter(|t| t.exit_index.is_some()).count();
+    let avg_holding_bars = if num_round_trips > 0 {
+        trades.iter()
+            .filter_map(|t| t.exit_index.map(|exit| exit.saturating_sub(t.entry_index) as f64))
+            .sum::<f64>() / num_round_trips as f64
+    } else {
+        0.0
+    };
+
+    // cost attribution: how much of the pnl before costs went to each bucket
+    let total_costs = costs.total_commission + costs.total_spread_cost + costs.total_financing_cost;
+    let gross_pnl = (equity_final - equity[0] - net_cash_flow) + total_costs;
+    let pct_of_gross = |cost: f64| if gross_pnl.abs() > 0.0 { cost / gross_pnl.abs() * 100.0 } else { 0.0 };
+
+    // time spent de-risked by the drawdown governor
+    let multipliers = &config.size_multipliers;
+    let de_risked_time_pct = if !multipliers.is_empty() {
+        multipliers.iter().filter(|&&m| m < 1.0).count() as f64 / multipliers.len() as f64 * 100.0
+    } else {
+        0.0
+    };
+    let min_size_multiplier = multipliers.iter().cloned().fold(1.0, f64::min);
+
 
     Stats {
         start,
@@ -287,6 +772,7 @@ pub fn compute_stats(
         avg_win,
         avg_loss,
         max_drawdown_pct: max_dd,
+        max_intrabar_drawdown_pct: max_intrabar_dd,
         num_trades,
         win_rate_pct,
         best_trade,
@@ -295,38 +781,638 @@ pub fn compute_stats(
         alpha,
         beta,
         max_margin_usage,
+        avg_gross_leverage,
+        max_gross_leverage,
+        traded_notional: costs.traded_notional,
+        turnover,
+        num_round_trips,
+        avg_holding_bars,
+        total_commission: costs.total_commission,
+        total_rebates: costs.total_rebates,
+        total_spread_cost: costs.total_spread_cost,
+        total_financing_cost: costs.total_financing_cost,
+        gross_pnl,
+        commission_pct_of_gross: pct_of_gross(costs.total_commission),
+        spread_pct_of_gross: pct_of_gross(costs.total_spread_cost),
+        financing_pct_of_gross: pct_of_gross(costs.total_financing_cost),
+        avg_buy_slippage: costs.avg_buy_slippage(),
+        avg_sell_slippage: costs.avg_sell_slippage(),
+        total_slippage_cost: costs.total_slippage_cost,
+        de_risked_time_pct,
+        min_size_multiplier,
+        top_drawdowns: top_drawdowns(performance, dates, 5),
     }
 }
 
 impl fmt::Display for Stats {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display(&StatsFormat::default()))
+    }
+}
+
+// number formatting of the stats display and reports. the default keeps the plain "1234567.89" style;
+// StatsFormat::us / ::eu group thousands, and the currency goes into labels and compact summaries
+#[derive(Clone, Debug)]
+pub struct StatsFormat {
+    pub currency: String,                  // account currency symbol or code, e.g. "$", "€", "DKK"
+    pub thousands_separator: Option<char>, // None prints 1234567.89
+    pub decimal_separator: char,
+    pub money_precision: usize,            // decimals of cash amounts
+    pub pct_precision: usize,              // decimals of percentages
+}
+
+impl Default for StatsFormat {
+    fn default() -> Self {
+        StatsFormat {
+            currency: "$".to_string(),
+            thousands_separator: None,
+            decimal_separator: '.',
+            money_precision: 2,
+            pct_precision: 2,
+        }
+    }
+}
+
+impl StatsFormat {
+    // 1,234,567.89
+    pub fn us(currency: &str) -> Self {
+        StatsFormat { currency: currency.to_string(), thousands_separator: Some(','), ..StatsFormat::default() }
+    }
+
+    // 1.234.567,89
+    pub fn eu(currency: &str) -> Self {
+        StatsFormat {
+            currency: currency.to_string(),
+            thousands_separator: Some('.'),
+            decimal_separator: ',',
+            ..StatsFormat::default()
+        }
+    }
+
+    pub fn number(&self, value: f64, precision: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let text = format!("{:.*}", precision, value.abs());
+        let (int_part, frac_part) = match text.split_once('.') {
+            Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+            None => (text.as_str(), None),
+        };
+        let mut out = String::with_capacity(text.len() + int_part.len() / 3 + 1);
+        if value < 0.0 {
+            out.push('-');
+        }
+        for (i, c) in int_part.chars().enumerate() {
+            if i > 0 && (int_part.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    out.push(separator);
+                }
+            }
+            out.push(c);
+        }
+        if let Some(frac_part) = frac_part {
+            out.push(self.decimal_separator);
+            out.push_str(frac_part);
+        }
+        out
+    }
+
+    // cash amount with the currency: "$1,234.50" / "-$1,234.50" for symbols, "1.234,50 DKK" for codes
+    pub fn money(&self, value: f64) -> String {
+        let amount = self.number(value.abs(), self.money_precision);
+        let sign = if value < 0.0 { "-" } else { "" };
+        let currency = self.currency.trim();
+        if currency.chars().count() > 1 && currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            format!("{}{} {}", sign, amount, currency)
+        } else {
+            format!("{}{}{}", sign, currency, amount)
+        }
+    }
+
+    pub fn pct(&self, value: f64) -> String {
+        format!("{}%", self.number(value, self.pct_precision))
+    }
+}
+
+// Stats printed with a StatsFormat, see Stats::display
+pub struct StatsDisplay<'a> {
+    stats: &'a Stats,
+    format: &'a StatsFormat,
+}
+
+// the key stats of a run next to a benchmark run, see Stats::compare
+pub struct StatsComparison<'a> {
+    stats: &'a Stats,
+    benchmark: &'a Stats,
+    benchmark_name: &'a str,
+    format: &'a StatsFormat,
+}
+
+impl Stats {
+    pub fn display<'a>(&'a self, format: &'a StatsFormat) -> StatsDisplay<'a> {
+        StatsDisplay { stats: self, format }
+    }
+
+    /// side by side table of this run and a benchmark run (e.g. Backtest::run_buy_and_hold), with the
+    /// difference strategy minus benchmark
+    pub fn compare<'a>(&'a self, benchmark: &'a Stats, benchmark_name: &'a str, format: &'a StatsFormat) -> StatsComparison<'a> {
+        StatsComparison { stats: self, benchmark, benchmark_name, format }
+    }
+
+    /// the stats as a json object (top_drawdowns included); nan and infinite ratios become null
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// column names matching to_csv_row
+    pub fn csv_header() -> String {
+        Stats::default().csv_fields().iter().map(|(name, _)| *name).collect::<Vec<_>>().join(",")
+    }
+
+    /// the scalar stats as one csv row (top_drawdowns are left out), to append to a results file
+    /// written with csv_header
+    pub fn to_csv_row(&self) -> String {
+        self.csv_fields().into_iter().map(|(_, value)| value).collect::<Vec<_>>().join(",")
+    }
+
+    // (column, value) pairs in field order
+    fn csv_fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("start", self.start.to_string()),
+            ("end", self.end.to_string()),
+            ("duration", self.duration.to_string()),
+            ("exposure_time_pct", self.exposure_time_pct.to_string()),
+            ("equity_final", self.equity_final.to_string()),
+            ("return_pct", self.return_pct.to_string()),
+            ("buy_hold_return_pct", self.buy_hold_return_pct.to_string()),
+            ("return_ann_pct", self.return_ann_pct.to_string()),
+            ("volatility_ann_pct", self.volatility_ann_pct.to_string()),
+            ("sharpe_ratio", self.sharpe_ratio.to_string()),
+            ("calmar_ratio", self.calmar_ratio.to_string()),
+            ("max_drawdown_pct", self.max_drawdown_pct.to_string()),
+            ("max_intrabar_drawdown_pct", self.max_intrabar_drawdown_pct.to_string()),
+            ("num_trades", self.num_trades.to_string()),
+            ("win_rate_pct", self.win_rate_pct.to_string()),
+            ("best_trade", self.best_trade.to_string()),
+            ("worst_trade", self.worst_trade.to_string()),
+            ("start_date", self.start_date.clone()),
+            ("end_date", self.end_date.clone()),
+            ("profit_factor", self.profit_factor.to_string()),
+            ("avg_win", self.avg_win.to_string()),
+            ("avg_loss", self.avg_loss.to_string()),
+            ("alpha_risk_adjusted", self.alpha_risk_adjusted.to_string()),
+            ("alpha", self.alpha.to_string()),
+            ("beta", self.beta.to_string()),
+            ("max_margin_usage", self.max_margin_usage.to_string()),
+            ("avg_gross_leverage", self.avg_gross_leverage.to_string()),
+            ("max_gross_leverage", self.max_gross_leverage.to_string()),
+            ("traded_notional", self.traded_notional.to_string()),
+            ("turnover", self.turnover.to_string()),
+            ("num_round_trips", self.num_round_trips.to_string()),
+            ("avg_holding_bars", self.avg_holding_bars.to_string()),
+            ("total_commission", self.total_commission.to_string()),
+            ("total_rebates", self.total_rebates.to_string()),
+            ("total_spread_cost", self.total_spread_cost.to_string()),
+            ("total_financing_cost", self.total_financing_cost.to_string()),
+            ("gross_pnl", self.gross_pnl.to_string()),
+            ("commission_pct_of_gross", self.commission_pct_of_gross.to_string()),
+            ("spread_pct_of_gross", self.spread_pct_of_gross.to_string()),
+            ("financing_pct_of_gross", self.financing_pct_of_gross.to_string()),
+            ("avg_buy_slippage", self.avg_buy_slippage.to_string()),
+            ("avg_sell_slippage", self.avg_sell_slippage.to_string()),
+            ("total_slippage_cost", self.total_slippage_cost.to_string()),
+            ("de_risked_time_pct", self.de_risked_time_pct.to_string()),
+            ("min_size_multiplier", self.min_size_multiplier.to_string()),
+        ]
+    }
+
+    // one line for sweep and optimizer logs, e.g.
+    // "return 12.34% | ann 8.10% | sharpe 1.23 | max dd -5.67% | trades 42 | win 55.00% | equity $112,345.67"
+    pub fn summary_line(&self, format: &StatsFormat) -> String {
+        format!("return {} | ann {} | sharpe {:.2} | max dd {} | trades {} | win {} | equity {}",
+            format.pct(self.return_pct),
+            format.pct(self.return_ann_pct),
+            self.sharpe_ratio,
+            format.pct(self.max_drawdown_pct),
+            self.num_trades,
+            format.pct(self.win_rate_pct),
+            format.money(self.equity_final))
+    }
+}
+
+impl fmt::Display for StatsDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stats = self.stats;
+        let format = self.format;
+        let cur = format.currency.trim();
+        let money = |value: f64| format.number(value, format.money_precision);
+        let pct = |value: f64| format.number(value, format.pct_precision);
         writeln!(f, "\n\nBacktest Statistics:")?;
         writeln!(f, "====================")?;
         
         // format each stat with consistent spacing (35 chars for the label)
-        writeln!(f, "{:<35} {:>15}", "Start Date", self.start_date)?;
-        writeln!(f, "{:<35} {:>15}", "End Date", self.end_date)?;
-        writeln!(f, "{:<35} {:>15.2}", "Exposure Time [%]", self.exposure_time_pct)?;
-        writeln!(f, "{:<35} {:>15.2}", "Total Return [%]", self.return_pct)?;
-        writeln!(f, "{:<35} {:>15.2}", "Buy & Hold Return [%]", self.buy_hold_return_pct)?;
-        writeln!(f, "{:<35} {:>15.2}", "Equity Final [$]", self.equity_final)?;
-        writeln!(f, "{:<35} {:>15.2}", "Sharpe Ratio", self.sharpe_ratio)?;
-        writeln!(f, "{:<35} {:>15.2}", "Max Drawdown [%]", self.max_drawdown_pct)?;
-        writeln!(f, "{:<35} {:>15.2}", "Profit Factor", self.profit_factor)?;
-        writeln!(f, "{:<35} {:>15}", "Total Trades", self.num_trades)?;
-        writeln!(f, "{:<35} {:>15.2}", "Win Rate [%]", self.win_rate_pct)?;
-        writeln!(f, "{:<35} {:>15.2}", "Best Trade [$]", self.best_trade)?;
-        writeln!(f, "{:<35} {:>15.2}", "Worst Trade [$]", self.worst_trade)?;
-        writeln!(f, "{:<35} {:>15.2}", "Avg. Win [$]", self.avg_win)?;
-        writeln!(f, "{:<35} {:>15.2}", "Avg. Loss [$]", self.avg_loss)?;
-        writeln!(f, "{:<35} {:>15.2}", "Beta", self.beta)?;
-        writeln!(f, "{:<35} {:>15.2}", "Alpha [%]", self.alpha)?;
-        writeln!(f, "{:<35} {:>15.2}", "Alpha Risk Adjusted [%]", self.alpha_risk_adjusted)?;
-        writeln!(f, "{:<35} {:>15.2}", "Return Ann [%]", self.return_ann_pct)?;
-        writeln!(f, "{:<35} {:>15.2}", "Volatility Ann [%]", self.volatility_ann_pct)?;
-        writeln!(f, "{:<35} {:>15.2}", "Max Margin Usage [%]", self.max_margin_usage * 100.0)?;
+        writeln!(f, "{:<35} {:>15}", "Start Date", stats.start_date)?;
+        writeln!(f, "{:<35} {:>15}", "End Date", stats.end_date)?;
+        writeln!(f, "{:<35} {:>15}", "Exposure Time [%]", pct(stats.exposure_time_pct))?;
+        writeln!(f, "{:<35} {:>15}", "Total Return [%]", pct(stats.return_pct))?;
+        writeln!(f, "{:<35} {:>15}", "Buy & Hold Return [%]", pct(stats.buy_hold_return_pct))?;
+        writeln!(f, "{:<35} {:>15}", format!("Equity Final [{}]", cur), money(stats.equity_final))?;
+        writeln!(f, "{:<35} {:>15.2}", "Sharpe Ratio", stats.sharpe_ratio)?;
+        writeln!(f, "{:<35} {:>15}", "Max Drawdown [%]", pct(stats.max_drawdown_pct))?;
+        writeln!(f, "{:<35} {:>15}", "Max Intrabar Drawdown [%]", pct(stats.max_intrabar_drawdown_pct))?;
+        writeln!(f, "{:<35} {:>15.2}", "Profit Factor", stats.profit_factor)?;
+        writeln!(f, "{:<35} {:>15}", "Total Trades", stats.num_trades)?;
+        writeln!(f, "{:<35} {:>15}", "Win Rate [%]", pct(stats.win_rate_pct))?;
+        writeln!(f, "{:<35} {:>15}", format!("Best Trade [{}]", cur), money(stats.best_trade))?;
+        writeln!(f, "{:<35} {:>15}", format!("Worst Trade [{}]", cur), money(stats.worst_trade))?;
+        writeln!(f, "{:<35} {:>15}", format!("Avg. Win [{}]", cur), money(stats.avg_win))?;
+        writeln!(f, "{:<35} {:>15}", format!("Avg. Loss [{}]", cur), money(stats.avg_loss))?;
+        writeln!(f, "{:<35} {:>15.2}", "Beta", stats.beta)?;
+        writeln!(f, "{:<35} {:>15}", "Alpha [%]", pct(stats.alpha))?;
+        writeln!(f, "{:<35} {:>15}", "Alpha Risk Adjusted [%]", pct(stats.alpha_risk_adjusted))?;
+        writeln!(f, "{:<35} {:>15}", "Return Ann [%]", pct(stats.return_ann_pct))?;
+        writeln!(f, "{:<35} {:>15}", "Volatility Ann [%]", pct(stats.volatility_ann_pct))?;
+        writeln!(f, "{:<35} {:>15}", "Max Margin Usage [%]", pct(stats.max_margin_usage * 100.0))?;
+        writeln!(f, "{:<35} {:>15.2}", "Avg. Gross Leverage", stats.avg_gross_leverage)?;
+        writeln!(f, "{:<35} {:>15.2}", "Max Gross Leverage", stats.max_gross_leverage)?;
+        writeln!(f, "{:<35} {:>15}", format!("Traded Notional [{}]", cur), money(stats.traded_notional))?;
+        writeln!(f, "{:<35} {:>15.2}", "Turnover [x]", stats.turnover)?;
+        writeln!(f, "{:<35} {:>15}", "Round Trips", stats.num_round_trips)?;
+        writeln!(f, "{:<35} {:>15.2}", "Avg. Holding Period [bars]", stats.avg_holding_bars)?;
+
+        writeln!(f, "\nCost Attribution:")?;
+        writeln!(f, "--------------------")?;
+        writeln!(f, "{:<35} {:>15}", format!("Gross PnL (before costs) [{}]", cur), money(stats.gross_pnl))?;
+        writeln!(f, "{:<35} {:>15}", format!("Commissions [{}]", cur), money(stats.total_commission))?;
+        writeln!(f, "{:<35} {:>15}", "Commissions [% of gross]", pct(stats.commission_pct_of_gross))?;
+        if stats.total_rebates > 0.0 {
+            writeln!(f, "{:<35} {:>15}", format!("Rebates Received [{}]", cur), money(stats.total_rebates))?;
+        }
+        writeln!(f, "{:<35} {:>15}", format!("Spread Cost [{}]", cur), money(stats.total_spread_cost))?;
+        writeln!(f, "{:<35} {:>15}", "Spread Cost [% of gross]", pct(stats.spread_pct_of_gross))?;
+        writeln!(f, "{:<35} {:>15}", format!("Financing Cost [{}]", cur), money(stats.total_financing_cost))?;
+        writeln!(f, "{:<35} {:>15}", "Financing Cost [% of gross]", pct(stats.financing_pct_of_gross))?;
+
+        writeln!(f, "\nExecution:")?;
+        writeln!(f, "--------------------")?;
+        writeln!(f, "{:<35} {:>15.4}", "Avg. Buy Slippage [price]", stats.avg_buy_slippage)?;
+        writeln!(f, "{:<35} {:>15.4}", "Avg. Sell Slippage [price]", stats.avg_sell_slippage)?;
+        writeln!(f, "{:<35} {:>15}", format!("Slippage Cost [{}]", cur), money(stats.total_slippage_cost))?;
+        if stats.de_risked_time_pct > 0.0 {
+            writeln!(f, "{:<35} {:>15}", "De-risked Time [%]", pct(stats.de_risked_time_pct))?;
+            writeln!(f, "{:<35} {:>15.2}", "Min. Size Multiplier", stats.min_size_multiplier)?;
+        }
+
+        if !stats.top_drawdowns.is_empty() {
+            writeln!(f, "\nTop Drawdowns:")?;
+            writeln!(f, "--------------------")?;
+            writeln!(f, "{:<4} {:>10} {:>21} {:>21} {:>21} {:>10}", "#", "Depth [%]", "Peak", "Trough", "Recovery", "Bars")?;
+            for (i, dd) in stats.top_drawdowns.iter().enumerate() {
+                writeln!(f, "{:<4} {:>10} {:>21} {:>21} {:>21} {:>10}",
+                    i + 1,
+                    pct(dd.depth_pct),
+                    dd.start_date,
+                    dd.trough_date,
+                    dd.recovery_date.as_deref().unwrap_or("-"),
+                    dd.duration(stats.end))?;
+            }
+        }
        
  
         write!(f, "====================")
     }
 }
+
+impl fmt::Display for StatsComparison<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (a, b) = (self.stats, self.benchmark);
+        let format = self.format;
+        let cur = format.currency.trim();
+        let money = |value: f64| format.number(value, format.money_precision);
+        let pct = |value: f64| format.number(value, format.pct_precision);
+        let ratio = |value: f64| format.number(value, 2);
+        writeln!(f, "\n\nStrategy vs {}:", self.benchmark_name)?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<35} {:>15} {:>15} {:>15}", "", "Strategy", self.benchmark_name, "Difference")?;
+        let rows: Vec<(String, f64, f64, &dyn Fn(f64) -> String)> = vec![
+            ("Total Return [%]".to_string(), a.return_pct, b.return_pct, &pct),
+            ("Return Ann [%]".to_string(), a.return_ann_pct, b.return_ann_pct, &pct),
+            ("Volatility Ann [%]".to_string(), a.volatility_ann_pct, b.volatility_ann_pct, &pct),
+            ("Sharpe Ratio".to_string(), a.sharpe_ratio, b.sharpe_ratio, &ratio),
+            ("Calmar Ratio".to_string(), a.calmar_ratio, b.calmar_ratio, &ratio),
+            ("Max Drawdown [%]".to_string(), a.max_drawdown_pct, b.max_drawdown_pct, &pct),
+            ("Max Intrabar Drawdown [%]".to_string(), a.max_intrabar_drawdown_pct, b.max_intrabar_drawdown_pct, &pct),
+            (format!("Equity Final [{}]", cur), a.equity_final, b.equity_final, &money),
+            ("Exposure Time [%]".to_string(), a.exposure_time_pct, b.exposure_time_pct, &pct),
+            (format!("Commissions [{}]", cur), a.total_commission, b.total_commission, &money),
+            (format!("Spread Cost [{}]", cur), a.total_spread_cost, b.total_spread_cost, &money),
+            (format!("Financing Cost [{}]", cur), a.total_financing_cost, b.total_financing_cost, &money),
+            (format!("Slippage Cost [{}]", cur), a.total_slippage_cost, b.total_slippage_cost, &money),
+        ];
+        for (name, value, benchmark, fmt_value) in rows {
+            writeln!(f, "{:<35} {:>15} {:>15} {:>15}", name, fmt_value(value), fmt_value(benchmark), fmt_value(value - benchmark))?;
+        }
+        write!(f, "====================")
+    }
+}
+
+// performance of closed trades sharing the same tag
+#[derive(Debug, Clone)]
+pub struct TagStats {
+    pub tag: String,
+    pub num_trades: usize,
+    pub win_rate_pct: f64,
+    pub total_pnl: f64,
+    pub avg_pnl: f64,
+}
+
+// reduce a tag to its signal type by dropping "key=value" tokens,
+// so "zscore=2.31 long entry" and "zscore=1.87 long entry" fall into the same group
+pub fn tag_group(tag: &str) -> String {
+    let group: Vec<&str> = tag.split_whitespace().filter(|token| !token.contains('=')).collect();
+    if group.is_empty() {
+        tag.to_string()
+    } else {
+        group.join(" ")
+    }
+}
+
+fn group_trades<F>(trades: &[Trade], key: F) -> Vec<TagStats>
+where
+    F: Fn(&Trade) -> Option<&String>,
+{
+    use std::collections::BTreeMap;
+    let mut groups: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for trade in trades.iter() {
+        let group = key(trade).map(|tag| tag_group(tag)).unwrap_or_else(|| "untagged".to_string());
+        groups.entry(group).or_default().push(trade.pnl());
+    }
+    groups.into_iter()
+        .map(|(tag, pnls)| {
+            let num_trades = pnls.len();
+            let wins = pnls.iter().filter(|&&p| p > 0.0).count();
+            let total_pnl: f64 = pnls.iter().sum();
+            TagStats {
+                tag,
+                num_trades,
+                win_rate_pct: wins as f64 / num_trades as f64 * 100.0,
+                total_pnl,
+                avg_pnl: total_pnl / num_trades as f64,
+            }
+        })
+        .collect()
+}
+
+/// group closed trades by the tag of their entry order
+pub fn stats_by_tag(trades: &[Trade]) -> Vec<TagStats> {
+    group_trades(trades, |t| t.tag.as_ref())
+}
+
+/// group closed trades by the tag of the order that closed them (e.g. "stop-loss")
+pub fn stats_by_exit_tag(trades: &[Trade]) -> Vec<TagStats> {
+    group_trades(trades, |t| t.exit_tag.as_ref())
+}
+
+// print a per-tag performance table
+pub fn print_tag_stats(title: &str, tag_stats: &[TagStats]) {
+    println!("\n{}:", title);
+    println!("{:<30} {:>8} {:>12} {:>15} {:>12}", "Tag", "Trades", "Win Rate [%]", "Total PnL [$]", "Avg PnL [$]");
+    for ts in tag_stats.iter() {
+        println!("{:<30} {:>8} {:>12.2} {:>15.2} {:>12.2}", ts.tag, ts.num_trades, ts.win_rate_pct, ts.total_pnl, ts.avg_pnl);
+    }
+}
+
+// distribution summary of a sample (e.g. one metric across runs)
+#[derive(Clone, Debug)]
+pub struct Distribution {
+    pub mean: f64,
+    pub std: f64,
+    pub p5: f64,
+    pub median: f64,
+    pub p95: f64,
+}
+
+impl Distribution {
+    pub fn from_values(values: &[f64]) -> Self {
+        let mut sorted: Vec<f64> = values.iter().cloned().filter(|v| v.is_finite()).collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        if sorted.is_empty() {
+            return Distribution { mean: 0.0, std: 0.0, p5: 0.0, median: 0.0, p95: 0.0 };
+        }
+        let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+        Distribution {
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            std: crate::util::std_dev(&sorted),
+            p5: percentile(0.05),
+            median: percentile(0.5),
+            p95: percentile(0.95),
+        }
+    }
+}
+
+// settings for the risk-of-ruin estimates
+#[derive(Clone, Debug)]
+pub struct RuinConfig {
+    pub ruin_pct: f64,            // loss from the starting equity that counts as ruin, e.g. 50.0
+    pub drawdown_pcts: Vec<f64>,  // drawdown levels to estimate probabilities for
+    pub horizon: usize,           // trades per simulated path, 0 = as many as the backtest had
+    pub simulations: usize,
+    pub seed: u64,
+}
+
+impl Default for RuinConfig {
+    fn default() -> Self {
+        RuinConfig {
+            ruin_pct: 50.0,
+            drawdown_pcts: vec![10.0, 20.0, 30.0],
+            horizon: 0,
+            simulations: 10_000,
+            seed: 42,
+        }
+    }
+}
+
+// probability of one drawdown level, analytic and simulated
+#[derive(Clone, Debug)]
+pub struct DrawdownProbability {
+    pub level_pct: f64,
+    pub analytic: f64, // equity ever falls level_pct below the start (no horizon), NaN with fewer than two trades
+    pub simulated: f64, // a peak-to-trough drawdown of at least level_pct within the horizon
+}
+
+#[derive(Clone, Debug)]
+pub struct RuinReport {
+    pub num_trades: usize,
+    pub win_rate_pct: f64,
+    pub payoff_ratio: f64,        // average win / average loss
+    pub mean_trade_return_pct: f64, // per trade, relative to equity at entry
+    pub longest_win_streak: usize,
+    pub longest_loss_streak: usize,
+    pub horizon: usize,
+    pub ruin_pct: f64,
+    pub analytic_ruin: f64, // NaN with fewer than two trades (no edge estimate)
+    pub simulated_ruin: f64,
+    pub drawdowns: Vec<DrawdownProbability>,
+    pub simulated_loss_streak: Distribution, // longest losing streak per simulated path
+    pub simulated_final_return: Distribution, // compounded return per path, in percent
+}
+
+// longest runs of winning and losing trades
+fn longest_streaks(returns: &[f64]) -> (usize, usize) {
+    let (mut wins, mut losses, mut max_wins, mut max_losses) = (0, 0, 0, 0);
+    for &r in returns.iter() {
+        if r > 0.0 {
+            wins += 1;
+            losses = 0;
+        } else if r < 0.0 {
+            losses += 1;
+            wins = 0;
+        } else {
+            wins = 0;
+            losses = 0;
+        }
+        max_wins = max_wins.max(wins);
+        max_losses = max_losses.max(losses);
+    }
+    (max_wins, max_losses)
+}
+
+// drift-diffusion approximation of the probability that log equity, drifting by `mu` per trade with
+// variance `var`, ever falls `depth` below its start: exp(-2 mu depth / var), 1.0 without a positive edge
+// and NaN without an estimate of mu. a total loss (mu of -inf) reaches every depth, 100% included;
+// otherwise log equity never gets there
+fn analytic_ruin(mu: f64, var: f64, loss_pct: f64) -> f64 {
+    if mu.is_nan() {
+        return f64::NAN;
+    }
+    if mu == f64::NEG_INFINITY {
+        return 1.0;
+    }
+    if loss_pct >= 100.0 {
+        return 0.0;
+    }
+    let depth = -(1.0 - loss_pct / 100.0).ln();
+    if mu <= 0.0 {
+        1.0
+    } else if var <= 0.0 {
+        0.0
+    } else {
+        (-2.0 * mu * depth / var).exp().min(1.0)
+    }
+}
+
+/// risk of ruin and drawdown probabilities from the closed trades of a run. every trade's pnl is taken
+/// relative to the equity at its entry (`equity` is the broker's equity curve), and paths of `horizon`
+/// trades are bootstrapped from those returns and compounded one after another, so overlapping trades
+/// are treated as sequential bets. the analytic figures use the mean and variance of the log returns.
+pub fn risk_of_ruin(trades: &[Trade], equity: &[f64], config: &RuinConfig) -> RuinReport {
+    let start_equity = equity.first().copied().unwrap_or(0.0);
+    let returns: Vec<f64> = trades.iter()
+        .filter(|t| t.exit_price.is_some())
+        .map(|t| {
+            let base = equity.get(t.entry_index).copied().unwrap_or(start_equity);
+            if base > 0.0 { t.pnl() / base } else { 0.0 }
+        })
+        .collect();
+    let horizon = if config.horizon > 0 { config.horizon } else { returns.len() };
+
+    let wins: Vec<f64> = returns.iter().cloned().filter(|&r| r > 0.0).collect();
+    let losses: Vec<f64> = returns.iter().cloned().filter(|&r| r < 0.0).collect();
+    let avg_win = if wins.is_empty() { 0.0 } else { wins.iter().sum::<f64>() / wins.len() as f64 };
+    let avg_loss = if losses.is_empty() { 0.0 } else { losses.iter().sum::<f64>() / losses.len() as f64 };
+    let (longest_win_streak, longest_loss_streak) = longest_streaks(&returns);
+
+    // a trade losing the whole equity is ruin on its own; fewer than two trades give no edge estimate
+    let log_returns: Vec<f64> = returns.iter().filter(|&&r| r > -1.0).map(|r| r.ln_1p()).collect();
+    let (mu, var) = if log_returns.len() < returns.len() {
+        (f64::NEG_INFINITY, 0.0)
+    } else if log_returns.len() < 2 {
+        (f64::NAN, f64::NAN)
+    } else {
+        let n = log_returns.len() as f64;
+        let mu = log_returns.iter().sum::<f64>() / n;
+        (mu, log_returns.iter().map(|r| (r - mu).powi(2)).sum::<f64>() / (n - 1.0))
+    };
+
+    // bootstrap paths: ruin is a loss of ruin_pct from the start, drawdowns are measured from the running peak.
+    // a ruined path is still followed to the horizon, so deeper drawdown levels are counted as well
+    let mut rng = crate::util::Rng::new(config.seed);
+    let ruin_level = 1.0 - config.ruin_pct / 100.0;
+    let mut ruined = 0usize;
+    let mut drawdown_hits = vec![0usize; config.drawdown_pcts.len()];
+    let mut loss_streaks = Vec::with_capacity(config.simulations);
+    let mut final_returns = Vec::with_capacity(config.simulations);
+    if !returns.is_empty() && horizon > 0 {
+        for _ in 0..config.simulations {
+            let (mut value, mut peak, mut max_dd) = (1.0_f64, 1.0_f64, 0.0_f64);
+            let (mut streak, mut max_streak) = (0usize, 0usize);
+            let mut path_ruined = false;
+            for _ in 0..horizon {
+                let r = returns[(rng.next_u64() % returns.len() as u64) as usize];
+                value = (value * (1.0 + r)).max(0.0);
+                peak = peak.max(value);
+                max_dd = max_dd.max((1.0 - value / peak) * 100.0);
+                streak = if r < 0.0 { streak + 1 } else { 0 };
+                max_streak = max_streak.max(streak);
+                path_ruined |= value <= ruin_level;
+            }
+            if path_ruined {
+                ruined += 1;
+            }
+            for (hits, &level) in drawdown_hits.iter_mut().zip(config.drawdown_pcts.iter()) {
+                if max_dd >= level {
+                    *hits += 1;
+                }
+            }
+            loss_streaks.push(max_streak as f64);
+            final_returns.push((value - 1.0) * 100.0);
+        }
+    }
+    let share = |count: usize| if loss_streaks.is_empty() { 0.0 } else { count as f64 / loss_streaks.len() as f64 };
+
+    RuinReport {
+        num_trades: returns.len(),
+        win_rate_pct: if returns.is_empty() { 0.0 } else { wins.len() as f64 / returns.len() as f64 * 100.0 },
+        payoff_ratio: if avg_loss < 0.0 { avg_win / -avg_loss } else { f64::INFINITY },
+        mean_trade_return_pct: if returns.is_empty() { 0.0 } else { returns.iter().sum::<f64>() / returns.len() as f64 * 100.0 },
+        longest_win_streak,
+        longest_loss_streak,
+        horizon,
+        ruin_pct: config.ruin_pct,
+        analytic_ruin: analytic_ruin(mu, var, config.ruin_pct),
+        simulated_ruin: share(ruined),
+        drawdowns: config.drawdown_pcts.iter().zip(drawdown_hits.iter())
+            .map(|(&level, &hits)| DrawdownProbability {
+                level_pct: level,
+                analytic: analytic_ruin(mu, var, level),
+                simulated: share(hits),
+            })
+            .collect(),
+        simulated_loss_streak: Distribution::from_values(&loss_streaks),
+        simulated_final_return: Distribution::from_values(&final_returns),
+    }
+}
+
+impl fmt::Display for RuinReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "\n\nRisk of Ruin ({} trades per path):", self.horizon)?;
+        writeln!(f, "====================")?;
+        writeln!(f, "{:<35} {:>15}", "Trades", self.num_trades)?;
+        writeln!(f, "{:<35} {:>15.2}", "Win Rate [%]", self.win_rate_pct)?;
+        writeln!(f, "{:<35} {:>15.2}", "Payoff Ratio", self.payoff_ratio)?;
+        writeln!(f, "{:<35} {:>15.4}", "Mean Trade Return [%]", self.mean_trade_return_pct)?;
+        writeln!(f, "{:<35} {:>15}", "Longest Win Streak", self.longest_win_streak)?;
+        writeln!(f, "{:<35} {:>15}", "Longest Loss Streak", self.longest_loss_streak)?;
+        writeln!(f, "{:<35} {:>15.2}", "Sim. Loss Streak (median)", self.simulated_loss_streak.median)?;
+        writeln!(f, "{:<35} {:>15.2}", "Sim. Loss Streak (p95)", self.simulated_loss_streak.p95)?;
+        writeln!(f, "{:<35} {:>15.2}", "Sim. Return [%] (p5)", self.simulated_final_return.p5)?;
+        writeln!(f, "{:<35} {:>15.2}", "Sim. Return [%] (median)", self.simulated_final_return.median)?;
+        writeln!(f, "{:<35} {:>15.2}", format!("Ruin ({:.0}% loss) analytic [%]", self.ruin_pct), self.analytic_ruin * 100.0)?;
+        writeln!(f, "{:<35} {:>15.2}", format!("Ruin ({:.0}% loss) simulated [%]", self.ruin_pct), self.simulated_ruin * 100.0)?;
+        for dd in self.drawdowns.iter() {
+            writeln!(f, "{:<35} {:>15.2}", format!("{:.0}% Loss analytic [%]", dd.level_pct), dd.analytic * 100.0)?;
+            writeln!(f, "{:<35} {:>15.2}", format!("{:.0}% Drawdown simulated [%]", dd.level_pct), dd.simulated * 100.0)?;
+        }
+        write!(f, "====================")
+    }
+}
@@ -0,0 +1,141 @@
+use crate::engine::{Broker, OhlcData, Strategy};
+use crate::position::PositionManager;
+use crate::params::{ParamSpec, Parameterized};
+use crate::strategies::statarb_spread::{push_spread, spread_entry, spread_zscore};
+
+// StatArbSpreadStrategy with an entry threshold that follows the regime: instead of a fixed z-score, a
+// trade needs a |z| above the `quantile` of the |z| values seen over the last `regime_lookback` bars, so
+// calm markets trade smaller deviations and volatile ones wait for larger ones
+pub struct AdaptiveStatArbSpreadStrategy {
+    pub size: f64,
+    pub lookback: usize,
+    pub regime_lookback: usize,
+    pub quantile: f64,
+    // bounds of the adaptive threshold; zscore_threshold is used until regime_lookback values are in
+    pub min_threshold: f64,
+    pub max_threshold: f64,
+    pub zscore_threshold: f64,
+    // positions are closed once |z| falls below this fraction of the current threshold
+    pub exit_fraction: f64,
+    pub stop_loss: f64,
+    pub bidask_spread: f64,
+    pub spread: Vec<f64>,
+    pub abs_zscores: Vec<f64>,
+    pub close: Vec<f64>,
+    // threshold in force on each processed bar, for plots and analysis
+    pub thresholds: Vec<f64>,
+
+    pub positions: PositionManager,
+}
+
+impl AdaptiveStatArbSpreadStrategy {
+    pub fn new() -> Self {
+        AdaptiveStatArbSpreadStrategy {
+            size: 20.0,
+            lookback: 10,
+            regime_lookback: 250,
+            quantile: 0.8,
+            min_threshold: 0.8,
+            max_threshold: 3.0,
+            zscore_threshold: 1.2,
+            exit_fraction: 0.5,
+            stop_loss: 5.0 * 0.0075,
+            bidask_spread: 0.5,
+            spread: Vec::new(),
+            abs_zscores: Vec::new(),
+            close: Vec::new(),
+            thresholds: Vec::new(),
+            positions: PositionManager::new(10),
+        }
+    }
+
+    // entry threshold from the |z| history (before the current bar's z is added)
+    pub fn current_threshold(&self) -> f64 {
+        if self.abs_zscores.len() < self.regime_lookback {
+            return self.zscore_threshold;
+        }
+        let mut sorted = self.abs_zscores.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let rank = ((sorted.len() - 1) as f64 * self.quantile.clamp(0.0, 1.0)).round() as usize;
+        sorted[rank].clamp(self.min_threshold, self.max_threshold)
+    }
+}
+
+impl Parameterized for AdaptiveStatArbSpreadStrategy {
+    fn param_specs(&self) -> Vec<ParamSpec> {
+        vec![
+            ParamSpec::float("size", 20.0, 5.0, 50.0, 5.0),
+            ParamSpec::integer("lookback", 10, 5, 100, 5),
+            ParamSpec::integer("regime_lookback", 250, 50, 1000, 50),
+            ParamSpec::float("quantile", 0.8, 0.5, 0.99, 0.05),
+            ParamSpec::float("stop_loss", 5.0 * 0.0075, 0.0075, 0.15, 0.0075),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f64) {
+        match name {
+            "size" => self.size = value,
+            "lookback" => self.lookback = value as usize,
+            "regime_lookback" => self.regime_lookback = value as usize,
+            "quantile" => self.quantile = value,
+            "stop_loss" => self.stop_loss = value,
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f64> {
+        match name {
+            "size" => Some(self.size),
+            "lookback" => Some(self.lookback as f64),
+            "regime_lookback" => Some(self.regime_lookback as f64),
+            "quantile" => Some(self.quantile),
+            "stop_loss" => Some(self.stop_loss),
+            _ => None,
+        }
+    }
+}
+
+impl Strategy for AdaptiveStatArbSpreadStrategy {
+    fn init(&mut self, _broker: &mut Broker, data: &OhlcData) {
+        self.close = data.close.clone();
+    }
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        if index < self.lookback || index >= self.close.len() {
+            return;
+        }
+
+        // stop losses filled on this bar close trades behind the strategy's back
+        for trade in broker.closed_trades.iter().rev().take_while(|t| t.exit_index == Some(index)) {
+            if trade.exit_tag.as_deref() == Some("stop-loss") {
+                self.positions.close_position(trade.size);
+            }
+        }
+
+        push_spread(&mut self.spread, self.close[index].ln(), self.lookback);
+        let zscore = spread_zscore(&self.spread);
+        if !zscore.is_finite() {
+            return;
+        }
+        let threshold = self.current_threshold();
+        self.thresholds.push(threshold);
+        push_spread(&mut self.abs_zscores, zscore.abs(), self.regime_lookback);
+        let price = self.close[index];
+
+        // short when zscore is high (overvalued), long when it is low (undervalued)
+        if self.positions.can_open_short() && zscore > threshold {
+            let order = spread_entry(-self.size, price, self.stop_loss + self.bidask_spread, zscore);
+            if broker.new_order(order, price).is_ok() {
+                self.positions.register_position(-self.size);
+            }
+        } else if self.positions.can_open_long() && zscore < -threshold {
+            let order = spread_entry(self.size, price, self.stop_loss + self.bidask_spread, zscore);
+            if broker.new_order(order, price).is_ok() {
+                self.positions.register_position(self.size);
+            }
+        } else if zscore.abs() < threshold * self.exit_fraction {
+            broker.close_all_trades(index, index);
+            self.positions.reset();
+        }
+    }
+}
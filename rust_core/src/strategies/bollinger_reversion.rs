@@ -0,0 +1,101 @@
+use crate::engine::{Broker, OhlcData, Order, Strategy};
+use crate::indicators::{bollinger, Bands};
+use crate::params::{ParamSpec, Parameterized};
+
+// mean reversion on bollinger bands: buy a close below the lower band, sell one above the upper band, and
+// exit when the close is back at the middle band. the stop loss sits `stop_width` standard deviations
+// beyond the entry. one position at a time, on instrument 1.
+pub struct BollingerReversionStrategy {
+    pub size: f64,
+    pub period: usize,
+    pub width: f64,      // band distance from the middle in standard deviations
+    pub stop_width: f64, // stop distance from the entry close in standard deviations, 0.0 = no stop
+    pub bands: Option<Bands>,
+    close: Vec<f64>,
+}
+
+impl BollingerReversionStrategy {
+    pub fn new() -> Self {
+        BollingerReversionStrategy {
+            size: 10.0,
+            period: 20,
+            width: 2.0,
+            stop_width: 1.5,
+            bands: None,
+            close: Vec::new(),
+        }
+    }
+
+    fn entry(&self, size: f64, price: f64, std: f64) -> Order {
+        let sl = (self.stop_width > 0.0).then(|| price - size.signum() * self.stop_width * std);
+        Order {
+            size,
+            sl,
+            instrument: 1,
+            tag: Some(if size > 0.0 { "bollinger lower" } else { "bollinger upper" }.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+impl Parameterized for BollingerReversionStrategy {
+    fn param_specs(&self) -> Vec<ParamSpec> {
+        vec![
+            ParamSpec::float("size", 10.0, 1.0, 100.0, 1.0),
+            ParamSpec::integer("period", 20, 5, 100, 5),
+            ParamSpec::float("width", 2.0, 1.0, 3.0, 0.25),
+            ParamSpec::float("stop_width", 1.5, 0.0, 4.0, 0.5),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f64) {
+        match name {
+            "size" => self.size = value,
+            "period" => self.period = value as usize,
+            "width" => self.width = value,
+            "stop_width" => self.stop_width = value,
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f64> {
+        match name {
+            "size" => Some(self.size),
+            "period" => Some(self.period as f64),
+            "width" => Some(self.width),
+            "stop_width" => Some(self.stop_width),
+            _ => None,
+        }
+    }
+}
+
+impl Strategy for BollingerReversionStrategy {
+    fn init(&mut self, _broker: &mut Broker, data: &OhlcData) {
+        self.close = data.close.clone();
+        self.bands = Some(bollinger(&data.close, self.period, self.width));
+    }
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        let Some((upper, middle, lower)) = self.bands.as_ref().and_then(|bands| bands.at(index)) else {
+            return;
+        };
+        let price = self.close[index];
+        let position: f64 = broker.trades.iter().filter(|t| t.instrument == 1).map(|t| t.size).sum();
+
+        // back at the mean: take the reversion
+        if (position > 0.0 && price >= middle) || (position < 0.0 && price <= middle) {
+            broker.close_all_trades(index, index);
+            return;
+        }
+        if position != 0.0 || !broker.pending_entry_orders(Some(1), None).is_empty() {
+            return;
+        }
+        let std = (upper - middle) / self.width;
+        // rejected entries are recorded in broker.rejected_orders; the next signal tries again
+        if price < lower {
+            let _ = broker.new_order(self.entry(self.size, price, std), price);
+        } else if price > upper {
+            let _ = broker.new_order(self.entry(-self.size, price, std), price);
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::engine::{Broker, OhlcData, Order, Strategy};
+use crate::instrument::{InstrumentSpec, SizeRounding};
+
+// buys on the first bar and holds until the last, where the position is closed so the exit pays the
+// same costs as the entry. used by Backtest::run_buy_and_hold as a cost-consistent benchmark.
+pub struct BuyAndHoldStrategy {
+    pub instrument: u8, // 1 = close, 2 = close2
+    pub leverage: f64,  // notional as a multiple of cash, 1.0 = fully invested without leverage
+    entered: bool,
+}
+
+impl BuyAndHoldStrategy {
+    pub fn new(instrument: u8, leverage: f64) -> Self {
+        BuyAndHoldStrategy { instrument, leverage, entered: false }
+    }
+}
+
+impl Strategy for BuyAndHoldStrategy {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {
+        self.entered = false;
+    }
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        let last = broker.data.close.len() - 1;
+        if index == last {
+            while !broker.trades.is_empty() {
+                broker.close_position(0, index);
+            }
+            return;
+        }
+        if self.entered {
+            return;
+        }
+        // sized on the primary price: the broker converts instrument 2 orders by close / close2
+        let price = broker.data.close[index];
+        let notional = (broker.cash * self.leverage).min(broker.available_buying_power());
+        if price <= 0.0 || notional <= 0.0 {
+            return;
+        }
+        // whole lots only, rounded down so the order still fits the notional
+        let spec = broker.instrument_spec(self.instrument);
+        let units = spec.units_for_notional(notional, price);
+        let size = match (InstrumentSpec { rounding: SizeRounding::Down, ..spec }).round_size(units) {
+            Ok(size) => size,
+            Err(_) => return,
+        };
+        let order = Order {
+            size,
+            instrument: self.instrument,
+            tag: Some("buy and hold".to_string()),
+            ..Default::default()
+        };
+        // a rejected order (e.g. below the minimum lot) is retried on the next bar
+        self.entered = broker.new_order(order, price).is_ok();
+    }
+}
@@ -0,0 +1,156 @@
+use crate::engine::{Broker, OhlcData};
+use crate::params::{ParamSpec, Parameterized};
+use crate::rebalance::TargetStrategy;
+
+// return over bars (index - skip - lookback, index - skip], leaving out the most recent `skip` bars (the
+// short-term reversal window). None until there is enough history.
+pub fn trailing_return(prices: &[f64], index: usize, lookback: usize, skip: usize) -> Option<f64> {
+    let end = index.checked_sub(skip)?;
+    let start = end.checked_sub(lookback)?;
+    let (first, last) = (*prices.get(start)?, *prices.get(end)?);
+    if first <= 0.0 || lookback == 0 {
+        return None;
+    }
+    Some(last / first - 1.0)
+}
+
+// weights of a long-short momentum book over any number of symbols: the top_k returns get +1/top_k, the
+// bottom_k get -1/bottom_k and the rest 0. symbols without a return (None) are left out of the ranking,
+// and the books never overlap: with fewer symbols than top_k + bottom_k both sides shrink equally.
+pub fn momentum_book(returns: &[Option<f64>], top_k: usize, bottom_k: usize) -> Vec<f64> {
+    let mut ranked: Vec<usize> = (0..returns.len()).filter(|&i| returns[i].is_some_and(f64::is_finite)).collect();
+    // best first; ties keep their universe order
+    ranked.sort_by(|&a, &b| returns[b].partial_cmp(&returns[a]).unwrap_or(std::cmp::Ordering::Equal));
+    let (mut top_k, mut bottom_k) = (top_k, bottom_k);
+    while top_k + bottom_k > ranked.len() {
+        if top_k >= bottom_k { top_k -= 1 } else { bottom_k -= 1 }
+    }
+    let mut weights = vec![0.0; returns.len()];
+    for &i in &ranked[..top_k] {
+        weights[i] = 1.0 / top_k as f64;
+    }
+    for &i in &ranked[ranked.len() - bottom_k..] {
+        weights[i] = -1.0 / bottom_k as f64;
+    }
+    weights
+}
+
+// reference cross-sectional momentum strategy: on the first bar of every month, rank the instruments by
+// their trailing return, go long the top_k and short the bottom_k with equal notional per name, and hold
+// the book until the next month. the backtest broker trades close (instrument 1) and close2 (instrument 2),
+// so its universe is those two and the book is one long-short pair; momentum_book ranks any number of
+// symbols, e.g. a Screener's universe. run it wrapped in TargetPositionStrategy, so each rebalance only
+// trades the difference to the current book.
+pub struct CrossSectionalMomentumStrategy {
+    pub lookback: usize, // bars of trailing return used for the ranking
+    pub skip: usize,     // most recent bars left out of the return, 0 = none
+    pub top_k: usize,
+    pub bottom_k: usize,
+    // gross notional of the book as a multiple of equity, split equally between the names held
+    pub gross_leverage: f64,
+    // bar and target units (instrument, signed units) of every rebalance
+    pub rebalances: Vec<(usize, Vec<(u8, f64)>)>,
+    close: Vec<f64>,
+    close2: Vec<f64>,
+    date: Vec<String>,
+    last_month: Option<String>,
+}
+
+impl CrossSectionalMomentumStrategy {
+    pub fn new() -> Self {
+        CrossSectionalMomentumStrategy {
+            lookback: 120,
+            skip: 5,
+            top_k: 1,
+            bottom_k: 1,
+            gross_leverage: 1.0,
+            rebalances: Vec::new(),
+            close: Vec::new(),
+            close2: Vec::new(),
+            date: Vec::new(),
+            last_month: None,
+        }
+    }
+
+    // "yyyy-mm" of a bar's date
+    fn month(&self, index: usize) -> Option<String> {
+        self.date.get(index).and_then(|date| date.get(..7)).map(str::to_string)
+    }
+}
+
+impl Parameterized for CrossSectionalMomentumStrategy {
+    fn param_specs(&self) -> Vec<ParamSpec> {
+        vec![
+            ParamSpec::integer("lookback", 120, 20, 250, 20),
+            ParamSpec::integer("skip", 5, 0, 20, 5),
+            ParamSpec::float("gross_leverage", 1.0, 0.5, 2.0, 0.5),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f64) {
+        match name {
+            "lookback" => self.lookback = value as usize,
+            "skip" => self.skip = value as usize,
+            "gross_leverage" => self.gross_leverage = value,
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f64> {
+        match name {
+            "lookback" => Some(self.lookback as f64),
+            "skip" => Some(self.skip as f64),
+            "gross_leverage" => Some(self.gross_leverage),
+            _ => None,
+        }
+    }
+}
+
+impl TargetStrategy for CrossSectionalMomentumStrategy {
+    fn init(&mut self, _broker: &mut Broker, data: &OhlcData) {
+        self.close = data.close.clone();
+        self.close2 = data.close2.clone();
+        self.date = data.date.clone();
+        self.last_month = None;
+        self.rebalances.clear();
+    }
+
+    fn targets(&mut self, broker: &Broker, index: usize) -> Vec<(u8, f64)> {
+        let month = self.month(index);
+        if month.is_none() || month == self.last_month {
+            return Vec::new();
+        }
+        let returns = [
+            trailing_return(&self.close, index, self.lookback, self.skip),
+            trailing_return(&self.close2, index, self.lookback, self.skip),
+        ];
+        let weights = momentum_book(&returns, self.top_k, self.bottom_k);
+        let names = weights.iter().filter(|w| **w != 0.0).count();
+        if names == 0 {
+            return Vec::new();
+        }
+        self.last_month = month;
+
+        let equity = broker.equity.get(index).copied().unwrap_or(broker.cash);
+        let gross = self.gross_leverage * equity.max(0.0);
+        // each name held gets an equal share of the gross notional, in units of its own instrument
+        let per_name = gross / names as f64;
+        let targets: Vec<(u8, f64)> = [(1u8, self.close[index]), (2u8, self.close2[index])]
+            .iter()
+            .zip(weights.iter())
+            .map(|(&(instrument, price), &weight)| {
+                if weight == 0.0 {
+                    return (instrument, 0.0);
+                }
+                let spec = broker.instrument_spec(instrument);
+                let mut units = spec.units_for_notional(per_name, price);
+                if spec.lot_step > 0.0 {
+                    units = (units / spec.lot_step).floor() * spec.lot_step;
+                }
+                (instrument, weight.signum() * units)
+            })
+            .collect();
+        self.rebalances.push((index, targets.clone()));
+        targets
+    }
+}
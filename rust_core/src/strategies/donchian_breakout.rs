@@ -0,0 +1,100 @@
+use crate::engine::{Broker, OhlcData, Order, Strategy};
+use crate::indicators::{donchian, Bands};
+use crate::params::{ParamSpec, Parameterized};
+
+// trend following on donchian channels: buy a close above the highest high of the last `entry_period`
+// bars, sell one below the lowest low, and exit on a close through the opposite side of the shorter
+// `exit_period` channel (turtle-style). one position at a time, on instrument 1.
+pub struct DonchianBreakoutStrategy {
+    pub size: f64,
+    pub entry_period: usize,
+    pub exit_period: usize,
+    pub entry_channel: Option<Bands>,
+    pub exit_channel: Option<Bands>,
+    close: Vec<f64>,
+}
+
+impl DonchianBreakoutStrategy {
+    pub fn new() -> Self {
+        DonchianBreakoutStrategy {
+            size: 10.0,
+            entry_period: 20,
+            exit_period: 10,
+            entry_channel: None,
+            exit_channel: None,
+            close: Vec::new(),
+        }
+    }
+
+    fn entry(size: f64) -> Order {
+        Order {
+            size,
+            instrument: 1,
+            tag: Some(if size > 0.0 { "donchian high" } else { "donchian low" }.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+impl Parameterized for DonchianBreakoutStrategy {
+    fn param_specs(&self) -> Vec<ParamSpec> {
+        vec![
+            ParamSpec::float("size", 10.0, 1.0, 100.0, 1.0),
+            ParamSpec::integer("entry_period", 20, 5, 100, 5),
+            ParamSpec::integer("exit_period", 10, 5, 50, 5),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f64) {
+        match name {
+            "size" => self.size = value,
+            "entry_period" => self.entry_period = value as usize,
+            "exit_period" => self.exit_period = value as usize,
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f64> {
+        match name {
+            "size" => Some(self.size),
+            "entry_period" => Some(self.entry_period as f64),
+            "exit_period" => Some(self.exit_period as f64),
+            _ => None,
+        }
+    }
+}
+
+impl Strategy for DonchianBreakoutStrategy {
+    fn init(&mut self, _broker: &mut Broker, data: &OhlcData) {
+        self.close = data.close.clone();
+        self.entry_channel = Some(donchian(&data.high, &data.low, self.entry_period));
+        self.exit_channel = Some(donchian(&data.high, &data.low, self.exit_period));
+    }
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        let price = self.close[index];
+        let position: f64 = broker.trades.iter().filter(|t| t.instrument == 1).map(|t| t.size).sum();
+
+        if position != 0.0 {
+            let Some((exit_high, _, exit_low)) = self.exit_channel.as_ref().and_then(|c| c.at(index)) else {
+                return;
+            };
+            if (position > 0.0 && price < exit_low) || (position < 0.0 && price > exit_high) {
+                broker.close_all_trades(index, index);
+            }
+            return;
+        }
+        if !broker.pending_entry_orders(Some(1), None).is_empty() {
+            return;
+        }
+        let Some((high, _, low)) = self.entry_channel.as_ref().and_then(|c| c.at(index)) else {
+            return;
+        };
+        // rejected entries are recorded in broker.rejected_orders; the next signal tries again
+        if price > high {
+            let _ = broker.new_order(Self::entry(self.size), price);
+        } else if price < low {
+            let _ = broker.new_order(Self::entry(-self.size), price);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use crate::live_engine::{LiveBroker, LiveData, Order, LiveStrategy, TickSnapshot};
+use crate::position::PositionManager;
+use rust_ml::inference::NeuralNet;
+pub use rust_ml::inference::{InferenceConfig, InferenceDevice};
+use rust_ml::scaler::{scale_input, try_load_scaler_params, ScalerParams};
+use rust_ml::drift::DriftMonitor;
+use rust_ml::tracking::PredictionTracker;
+pub use rust_ml::drift::{DriftAction, DriftConfig};
+
+// class indices of the model output, as mapped in training/FNNtrain.py
+const SIGNAL_BUY: i64 = 0;
+const SIGNAL_SELL: i64 = 2;
+
+pub struct LiveMLStatArbSpreadStrategy {
+    pub size: f64,
+    pub lookback: usize,
+    pub return_lag: usize,       // bars over which each leg's return is taken
+    pub zscore_threshold: f64,   // fallback rule when no model is loaded
+    pub stop_loss: f64,
+    pub take_profit: f64,
+    pub instrument1: String,
+    pub instrument2: String,
+    pub mid1: Vec<f64>,
+    pub mid2: Vec<f64>,
+    pub spread: Vec<f64>,
+    pub positions: PositionManager,
+    // None if the model or scaler could not be loaded; the strategy then trades the zscore directly
+    model: Option<(NeuralNet, ScalerParams)>,
+    // watches live inputs against the scaler's training distribution; only set when a model is loaded
+    drift: Option<DriftMonitor>,
+    // scores each signal against the instrument 1 return `horizon` ticks later
+    pub tracker: PredictionTracker,
+}
+
+impl LiveMLStatArbSpreadStrategy {
+    pub fn new() -> Self {
+        LiveMLStatArbSpreadStrategy::with_inference(InferenceConfig::default())
+    }
+
+    pub fn with_inference(inference: InferenceConfig) -> Self {
+        LiveMLStatArbSpreadStrategy::with_model("src/models/model.pt", "src/models/scaler_params.json", &inference)
+    }
+
+    // device name as accepted by InferenceDevice ("auto", "cpu", "cuda:0", ...) and intra-op threads (0 = libtorch default)
+    pub fn from_settings(device: &str, threads: usize) -> Self {
+        let device = device.parse::<InferenceDevice>().unwrap_or_else(|e| {
+            eprintln!("{}, using auto", e);
+            InferenceDevice::Auto
+        });
+        LiveMLStatArbSpreadStrategy::with_inference(InferenceConfig {
+            device,
+            intra_op_threads: if threads > 0 { Some(threads as i32) } else { None },
+            ..InferenceConfig::default()
+        })
+    }
+
+    // paths are relative to the rust_ml crate
+    pub fn with_model(model_path: &str, scaler_path: &str, inference: &InferenceConfig) -> Self {
+        let model = match (NeuralNet::new(model_path, inference), try_load_scaler_params(scaler_path)) {
+            (Ok(net), Ok(scaler)) => {
+                println!("ml model loaded on {:?}", net.device());
+                Some((net, scaler))
+            }
+            (Err(e), _) => {
+                eprintln!("ml model unavailable ({}), falling back to zscore signals", e);
+                None
+            }
+            (_, Err(e)) => {
+                eprintln!("ml scaler unavailable ({}), falling back to zscore signals", e);
+                None
+            }
+        };
+
+        LiveMLStatArbSpreadStrategy {
+            size: 50.0,
+            lookback: 20,
+            return_lag: 2,
+            zscore_threshold: 1.0,
+            stop_loss: 50.0 * 0.0075,
+            take_profit: 50.0 * 0.015,
+            instrument1: "US500".to_string(),
+            instrument2: "DJIA".to_string(),
+            mid1: Vec::new(),
+            mid2: Vec::new(),
+            spread: Vec::new(),
+            positions: PositionManager::new(4),
+            tracker: PredictionTracker::new(20, 500),
+            drift: model.as_ref().map(|(_, scaler)| DriftMonitor::new(scaler, DriftConfig::default())),
+            model,
+        }
+    }
+
+    // replace the default drift thresholds, e.g. to pause entries instead of only warning
+    pub fn with_drift(mut self, config: DriftConfig) -> Self {
+        if let Some((_, scaler)) = self.model.as_ref() {
+            self.drift = Some(DriftMonitor::new(scaler, config));
+        }
+        self
+    }
+
+    // record a pair of mids and extend the spread series; returns the feature vector
+    // [spread, spread mean, spread std, zscore] once a full lookback is available
+    fn update_features(&mut self, mid1: f64, mid2: f64) -> Option<[f64; 4]> {
+        self.mid1.push(mid1);
+        self.mid2.push(mid2);
+        if self.mid1.len() > self.return_lag + 1 {
+            self.mid1.remove(0);
+            self.mid2.remove(0);
+        }
+        if self.mid1.len() <= self.return_lag {
+            return None;
+        }
+
+        // same spread as in training: difference of the two legs' returns over return_lag bars
+        let spread = mid1 / self.mid1[0] - mid2 / self.mid2[0];
+        self.spread.push(spread);
+        if self.spread.len() > self.lookback {
+            self.spread.remove(0);
+        }
+        if self.spread.len() < self.lookback {
+            return None;
+        }
+
+        let mean = self.spread.iter().sum::<f64>() / self.spread.len() as f64;
+        let std = (self.spread.iter()
+            .map(|x| (x - mean).powi(2))
+            .sum::<f64>() / ((self.spread.len() - 1) as f64))
+            .sqrt();
+        if std == 0.0 {
+            return None;
+        }
+        Some([spread, mean, std, (spread - mean) / std])
+    }
+
+    // model class for the features, or the zscore rule used to label the training data
+    fn signal(&mut self, features: &[f64; 4]) -> i64 {
+        let zscore = features[3];
+        let fallback = if zscore > self.zscore_threshold {
+            SIGNAL_SELL
+        } else if zscore < -self.zscore_threshold {
+            SIGNAL_BUY
+        } else {
+            1
+        };
+
+        let (net, scaler) = match self.model.as_ref() {
+            Some(model) => model,
+            None => return fallback,
+        };
+        let input: Vec<f32> = features.iter().map(|&x| x as f32).collect();
+        if let Some(drift) = self.drift.as_mut() {
+            drift.update(&input);
+            if drift.should_pause() {
+                return 1;
+            }
+        }
+        match net.predict(&scale_input(&input, scaler)) {
+            Ok(output) => output.argmax(-1, false).int64_value(&[0]),
+            Err(e) => {
+                eprintln!("ml inference failed ({}), using zscore signal", e);
+                fallback
+            }
+        }
+    }
+}
+
+impl LiveStrategy for LiveMLStatArbSpreadStrategy {
+    fn init(&mut self, _broker: &mut LiveBroker, _data: &LiveData) {
+        // nothing to do; strategy will use broker's live data directly
+    }
+
+    fn warm_up(&mut self, history: &[TickSnapshot]) {
+        // replay backfilled ticks, pairing each instrument 1 tick with the latest instrument 2 mid
+        let mut last_mid2: Option<f64> = None;
+        for tick in history.iter() {
+            let mid = (tick.ask + tick.bid) / 2.0;
+            if tick.instrument == self.instrument2 {
+                last_mid2 = Some(mid);
+            } else if tick.instrument == self.instrument1 {
+                if let Some(mid2) = last_mid2 {
+                    self.update_features(mid, mid2);
+                }
+            }
+        }
+    }
+
+    fn metrics(&self) -> Vec<(String, f64)> {
+        let metrics = self.tracker.metrics();
+        vec![
+            ("model_hit_rate".to_string(), metrics.hit_rate),
+            ("model_ic".to_string(), metrics.ic),
+        ]
+    }
+
+    fn positions(&self) -> Option<&PositionManager> {
+        Some(&self.positions)
+    }
+
+    fn next(&mut self, broker: &mut LiveBroker, index: usize) {
+        // both legs are needed to build the spread
+        let (entry1, entry2) = match (
+            broker.live_data.current.get(&self.instrument1),
+            broker.live_data.current.get(&self.instrument2),
+        ) {
+            (Some(e1), Some(e2)) => (e1, e2),
+            _ => return,
+        };
+        let current_ask = entry1.ask;
+        let current_bid = entry1.bid;
+        let mid2 = (entry2.ask + entry2.bid) / 2.0;
+
+        let features = match self.update_features((current_ask + current_bid) / 2.0, mid2) {
+            Some(features) => features,
+            None => return,
+        };
+        let signal = self.signal(&features);
+
+        // score the signal against the realized move so a decaying model shows up live
+        let mid1 = (current_ask + current_bid) / 2.0;
+        self.tracker.update(index, mid1);
+        let score = match signal {
+            SIGNAL_BUY => 1.0,
+            SIGNAL_SELL => -1.0,
+            _ => 0.0,
+        };
+        self.tracker.record(index, score, mid1);
+
+        if signal == SIGNAL_SELL && broker.current_margin_usage() < 0.65 {
+            let order = Order {
+                size: -self.size,
+                sl: Some(current_ask + self.stop_loss),
+                tp: Some(current_bid - self.take_profit),
+                instrument: self.instrument1.clone(),
+                tag: Some(format!("ml sell zscore={:.2}", features[3])),
+                ..Default::default()
+            };
+            if let Err(e) = broker.new_order(order, current_ask) {
+                broker.reporter.warn(format_args!("order rejected: {:?}", e));
+            } else {
+                self.positions.register_position(-self.size);
+            }
+        } else if signal == SIGNAL_BUY && broker.current_margin_usage() < 0.65 {
+            let order = Order {
+                size: self.size,
+                sl: Some(current_bid - self.stop_loss),
+                tp: Some(current_ask + self.take_profit),
+                instrument: self.instrument1.clone(),
+                tag: Some(format!("ml buy zscore={:.2}", features[3])),
+                ..Default::default()
+            };
+            if let Err(e) = broker.new_order(order, current_bid) {
+                broker.reporter.warn(format_args!("order rejected: {:?}", e));
+            } else {
+                self.positions.register_position(self.size);
+            }
+        } else if features[3].abs() < self.zscore_threshold / 2.0 && !self.positions.is_empty() {
+            // spread has reverted; flatten
+            broker.close_all_trades(index);
+        }
+
+        // release positions whose trades were closed by sl/tp on this tick
+        for trade in broker.closed_trades.iter().skip(broker.closed_trades.len().saturating_sub(1)) {
+            if trade.exit_index == Some(index) {
+                self.positions.close_position(trade.size);
+            }
+        }
+    }
+}
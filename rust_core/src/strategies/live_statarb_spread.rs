@@ -1,4 +1,4 @@
-use crate::live_engine::{LiveBroker, LiveData, Order, LiveStrategy};
+use crate::live_engine::{LiveBroker, LiveData, Order, LiveStrategy, TickSnapshot};
 use crate::position::PositionManager;
 
 pub struct LiveStatArbSpreadStrategy {
@@ -32,6 +32,20 @@ impl LiveStrategy for LiveStatArbSpreadStrategy {
         // nothing to do; strategy will use broker's live data directly
     }
 
+    fn warm_up(&mut self, history: &[TickSnapshot]) {
+        // seed the spread window with backfilled mids so signals start immediately
+        for tick in history.iter().filter(|t| t.instrument == "US500") {
+            self.spread.push(((tick.ask + tick.bid) / 2.0).ln());
+            if self.spread.len() > 10 {
+                self.spread.remove(0);
+            }
+        }
+    }
+
+    fn positions(&self) -> Option<&PositionManager> {
+        Some(&self.positions)
+    }
+
 
     fn next(&mut self, broker: &mut LiveBroker, index: usize) {
         // get live data and copy price values to avoid borrow conflicts
@@ -47,8 +61,7 @@ impl LiveStrategy for LiveStatArbSpreadStrategy {
         let current_ask = entry.ask;
         let current_bid = entry.bid;
 
-        println!("instrument - Uic: {}", instrument);
-        println!("current_ask: {}, current_bid: {}", current_ask, current_bid);
+        broker.reporter.debug(format_args!("{}: ask {}, bid {}", instrument, current_ask, current_bid));
         
         // calculate current spread using local prices
         //let current_log_spread = current_ask.ln() - current_bid.ln();
@@ -78,11 +91,9 @@ impl LiveStrategy for LiveStatArbSpreadStrategy {
             let order = Order {
                 size: -self.size,
                 sl: Some(current_ask + self.stop_loss),
-                tp: None,
-                limit: None,
-                stop: None,
-                parent_trade: None,
                 instrument: "US500".to_string(),
+                tag: Some(format!("zscore={:.2} short entry", zscore)),
+                ..Default::default()
             };
             if let Err(_e) = broker.new_order(order, current_ask) {
                 // error handling (e.g., print warning)
@@ -95,11 +106,9 @@ impl LiveStrategy for LiveStatArbSpreadStrategy {
             let order = Order {
                 size: self.size,
                 sl: Some(current_bid - self.stop_loss),
-                tp: None,
-                limit: None,
-                stop: None,
-                parent_trade: None,
                 instrument: "US500".to_string(),
+                tag: Some(format!("zscore={:.2} long entry", zscore)),
+                ..Default::default()
             };  
             if let Err(_e) = broker.new_order(order, current_bid) {
                 // error handling (e.g., print warning)
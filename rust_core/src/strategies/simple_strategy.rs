@@ -20,22 +20,23 @@ impl Strategy for SimpleStrategy {
         if broker.trades.is_empty() {
             let order = Order {
                 size: size,
-                limit: None,
-                stop: None,
-                sl: None,
-                tp: None,
-                parent_trade: None,
                 instrument: 1,
+                tag: Some("buy and hold".to_string()),
+                ..Default::default()
             };
             if let Err(_e) = broker.new_order(order, broker.data.close[index]) {
                 // handle error - for example, you could print a warning or skip the order
                 // (error: margin_exceeded)
             }
-            println!("Buy at {}", broker.data.close[index]); 
+            if !broker.quiet {
+                println!("Buy at {}", broker.data.close[index]);
+            }
         } else if index == broker.data.close.len() - 1 {   
             // we're at the last candle, close all positions
             broker.close_position(0, index);
-            println!("Sell at {}", broker.data.close[index]);
+            if !broker.quiet {
+                println!("Sell at {}", broker.data.close[index]);
+            }
         }
     }
 }
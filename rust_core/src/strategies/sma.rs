@@ -1,4 +1,5 @@
-use crate::engine::{Broker, OhlcData, Order, Strategy, Trade};
+use crate::engine::{Broker, OhlcData, Order, Strategy};
+use crate::params::{ParamSpec, Parameterized};
 
 
 pub struct SmaStrategy {
@@ -17,6 +18,31 @@ impl SmaStrategy {
     }
 }
 
+impl Parameterized for SmaStrategy {
+    fn param_specs(&self) -> Vec<ParamSpec> {
+        vec![
+            ParamSpec::integer("sma_period", 10, 5, 50, 5),
+            ParamSpec::integer("sma_period_2", 20, 20, 200, 20),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f64) {
+        match name {
+            "sma_period" => self.sma_period = value as usize,
+            "sma_period_2" => self.sma_period_2 = value as usize,
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f64> {
+        match name {
+            "sma_period" => Some(self.sma_period as f64),
+            "sma_period_2" => Some(self.sma_period_2 as f64),
+            _ => None,
+        }
+    }
+}
+
 impl Strategy for SmaStrategy {
     fn init(&mut self, _broker: &mut Broker, data: &OhlcData) {
         self.close = data.close.clone();
@@ -46,33 +72,24 @@ impl Strategy for SmaStrategy {
             // bullish cross: only buy when the difference switches from non-positive to positive
             let order = Order {
                 size: 30.0,
-                tp: None,
-                sl: None,
-                limit: None,
-                stop: None,
-                parent_trade: None,
                 instrument: 1,
+                tag: Some("sma bullish cross".to_string()),
+                ..Default::default()
             };
             if let Err(_e) = broker.new_order(order, price) {
                 // handle error - for example, you could print a warning or skip the order
                 // (error: margin_exceeded)
             }
-            println!("Buy at {}", self.close[index]);
+            if !broker.quiet {
+                println!("Buy at {}", self.close[index]);
+            }
 
         } else if prev_diff >= 0.0 && curr_diff < 0.0 && broker.trades.len() > 0 {
-            let trade = broker.trades.remove(0);
-            let closed_trade = Trade {
-                size: trade.size,
-                entry_price: trade.entry_price,
-                entry_index: trade.entry_index,
-                exit_price: Some(self.close[index]),
-                exit_index: Some(index),
-                sl_order: trade.sl_order,
-                tp_order: trade.tp_order,
-                instrument: trade.instrument,
-            };
-            broker.closed_trades.push(closed_trade);
-            println!("Closed at {}", self.close[index]);
+            // closed through the broker so the exit fill, commission and pnl are booked
+            broker.close_position_tagged(0, index, Some("sma bearish cross".to_string()));
+            if !broker.quiet {
+                println!("Closed at {}", self.close[index]);
+            }
  
         } 
 
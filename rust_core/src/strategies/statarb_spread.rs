@@ -1,5 +1,6 @@
 use crate::engine::{Broker, OhlcData, Order, Strategy};
 use crate::position::PositionManager;
+use crate::params::{ParamSpec, Parameterized};
 
 pub struct StatArbSpreadStrategy {
     pub size: f64,
@@ -32,6 +33,70 @@ impl StatArbSpreadStrategy {
     }
 }
 
+// spread machinery shared with the other spread strategies (see adaptive_statarb_spread)
+
+// add the latest spread value to a rolling window of at most `lookback` values
+pub fn push_spread(spread: &mut Vec<f64>, value: f64, lookback: usize) {
+    spread.push(value);
+    if spread.len() > lookback {
+        spread.remove(0);
+    }
+}
+
+// z-score of the latest value in the window against the window's mean and sample standard deviation
+pub fn spread_zscore(spread: &[f64]) -> f64 {
+    let current_spread = spread[spread.len() - 1];
+    let spread_mean = spread.iter().sum::<f64>() / spread.len() as f64;
+    let spread_std = (spread.iter()
+        .map(|x| (x - spread_mean).powi(2))
+        .sum::<f64>() / ((spread.len() - 1) as f64))
+        .sqrt();
+    (current_spread - spread_mean) / spread_std
+}
+
+// market entry on instrument 1 with its stop loss `stop_distance` away, tagged with the entry z-score
+pub fn spread_entry(size: f64, price: f64, stop_distance: f64, zscore: f64) -> Order {
+    let side = if size > 0.0 { "long" } else { "short" };
+    Order {
+        size,
+        sl: Some(price - size.signum() * stop_distance),
+        instrument: 1,
+        tag: Some(format!("zscore={:.2} {} entry", zscore, side)),
+        ..Default::default()
+    }
+}
+
+impl Parameterized for StatArbSpreadStrategy {
+    fn param_specs(&self) -> Vec<ParamSpec> {
+        vec![
+            ParamSpec::float("size", 20.0, 5.0, 50.0, 5.0),
+            ParamSpec::integer("lookback", 10, 5, 100, 5),
+            ParamSpec::float("zscore_threshold", 1.2, 0.5, 3.0, 0.1),
+            ParamSpec::float("stop_loss", 5.0 * 0.0075, 0.0075, 0.15, 0.0075),
+        ]
+    }
+
+    fn set_param(&mut self, name: &str, value: f64) {
+        match name {
+            "size" => self.size = value,
+            "lookback" => self.lookback = value as usize,
+            "zscore_threshold" => self.zscore_threshold = value,
+            "stop_loss" => self.stop_loss = value,
+            _ => {}
+        }
+    }
+
+    fn get_param(&self, name: &str) -> Option<f64> {
+        match name {
+            "size" => Some(self.size),
+            "lookback" => Some(self.lookback as f64),
+            "zscore_threshold" => Some(self.zscore_threshold),
+            "stop_loss" => Some(self.stop_loss),
+            _ => None,
+        }
+    }
+}
+
 impl Strategy for StatArbSpreadStrategy {
     fn init(&mut self, _broker: &mut Broker, data: &OhlcData) {
         self.close = data.close.clone();
@@ -43,31 +108,14 @@ impl Strategy for StatArbSpreadStrategy {
         }
 
         let current_spread = self.calculate_log_spread(index);
-        self.spread.push(current_spread);
-        if self.spread.len() > self.lookback {
-            self.spread.remove(0);
-        }
-
-        let spread_mean = self.spread.iter().sum::<f64>() / self.spread.len() as f64;
-        let spread_std = (self.spread.iter()
-            .map(|x| (x - spread_mean).powi(2))
-            .sum::<f64>() / ((self.spread.len() - 1) as f64))
-            .sqrt();
-        let zscore = (current_spread - spread_mean) / spread_std;
+        push_spread(&mut self.spread, current_spread, self.lookback);
+        let zscore = spread_zscore(&self.spread);
         let price = self.close[index];
 
 
         // short when zscore is high (overvalued)
         if self.positions.can_open_short() && zscore > self.zscore_threshold {
-            let order = Order {
-                size: -self.size,
-                sl: Some(price + (self.stop_loss + self.bidask_spread)),
-                tp: None,
-                limit: None,
-                stop: None,
-                parent_trade: None,
-                instrument: 1,
-            };
+            let order = spread_entry(-self.size, price, self.stop_loss + self.bidask_spread, zscore);
             if let Err(_e) = broker.new_order(order, price) {
                 // handle error - for example, you could print a warning or skip the order
                 // (error: margin_exceeded)
@@ -77,15 +125,7 @@ impl Strategy for StatArbSpreadStrategy {
         }
         // long when zscore is low (undervalued)
         else if self.positions.can_open_long() && zscore < -self.zscore_threshold {
-            let order = Order {
-                size: self.size,
-                sl: Some(price - (self.stop_loss + self.bidask_spread)),
-                tp: None,
-                limit: None,
-                stop: None,
-                parent_trade: None,
-                instrument: 1,
-            };  
+            let order = spread_entry(self.size, price, self.stop_loss + self.bidask_spread, zscore);
             if let Err(_e) = broker.new_order(order, price) {
                 // handle error - for example, you could print a warning or skip the order
                 // (error: margin_exceeded)
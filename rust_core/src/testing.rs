@@ -0,0 +1,194 @@
+// unit-test harness for strategies: scripted bars are fed one at a time through the strategy and a broker
+// that logs every order the strategy submits, so tests can assert on the orders of each bar (side, size,
+// stop loss, ...) without running and scoring a full backtest
+use crate::engine::{Broker, OhlcData, Order, OrderError, Side, Strategy};
+
+// one order as the strategy requested it (before lot rounding and scaling), with the bar and the outcome
+#[derive(Clone, Debug)]
+pub struct SubmittedOrder {
+    pub index: usize,
+    pub order: Order,
+    pub result: Result<(), OrderError>,
+}
+
+impl SubmittedOrder {
+    pub fn side(&self) -> Side {
+        Side::of(self.order.size)
+    }
+
+    pub fn accepted(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+// what a test expects of one submitted order; fields left at None match anything
+#[derive(Clone, Debug, Default)]
+pub struct ExpectedOrder {
+    pub side: Option<Side>,
+    pub size: Option<f64>, // absolute size
+    pub sl: Option<f64>,
+    pub instrument: Option<u8>,
+    pub tag_prefix: Option<String>,
+    pub accepted: Option<bool>,
+}
+
+// sizes and prices compare with a small relative tolerance, so computed stops match literal values
+fn approx_eq(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+}
+
+impl ExpectedOrder {
+    pub fn buy() -> Self {
+        ExpectedOrder { side: Some(Side::Long), ..ExpectedOrder::default() }
+    }
+
+    pub fn sell() -> Self {
+        ExpectedOrder { side: Some(Side::Short), ..ExpectedOrder::default() }
+    }
+
+    pub fn size(mut self, size: f64) -> Self {
+        self.size = Some(size.abs());
+        self
+    }
+
+    pub fn sl(mut self, sl: f64) -> Self {
+        self.sl = Some(sl);
+        self
+    }
+
+    pub fn instrument(mut self, instrument: u8) -> Self {
+        self.instrument = Some(instrument);
+        self
+    }
+
+    pub fn tag_prefix(mut self, prefix: &str) -> Self {
+        self.tag_prefix = Some(prefix.to_string());
+        self
+    }
+
+    pub fn accepted(mut self) -> Self {
+        self.accepted = Some(true);
+        self
+    }
+
+    pub fn rejected(mut self) -> Self {
+        self.accepted = Some(false);
+        self
+    }
+
+    pub fn matches(&self, submitted: &SubmittedOrder) -> bool {
+        let order = &submitted.order;
+        self.side.is_none_or(|side| side == submitted.side())
+            && self.size.is_none_or(|size| approx_eq(size, order.size.abs()))
+            && self.sl.is_none_or(|sl| order.sl.is_some_and(|actual| approx_eq(sl, actual)))
+            && self.instrument.is_none_or(|instrument| instrument == order.instrument)
+            && self.tag_prefix.as_ref().is_none_or(|prefix| order.tag.as_deref().is_some_and(|tag| tag.starts_with(prefix.as_str())))
+            && self.accepted.is_none_or(|accepted| accepted == submitted.accepted())
+    }
+}
+
+/// bars from a list of closes (open = high = low = close = close2), one minute apart
+pub fn scripted_bars(closes: &[f64]) -> OhlcData {
+    scripted_pair(closes, closes)
+}
+
+/// bars of a primary and a hedge instrument from their closes, one minute apart
+pub fn scripted_pair(closes: &[f64], closes2: &[f64]) -> OhlcData {
+    assert_eq!(closes.len(), closes2.len(), "need one close2 per close");
+    let start = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(9, 0, 0).unwrap();
+    OhlcData {
+        date: (0..closes.len())
+            .map(|i| (start + chrono::Duration::minutes(i as i64)).format("%Y-%m-%d %H:%M:%S").to_string())
+            .collect(),
+        open: closes.to_vec(),
+        high: closes.to_vec(),
+        low: closes.to_vec(),
+        close: closes.to_vec(),
+        close2: closes2.to_vec(),
+        volume: None,
+        halts: Vec::new(),
+    }
+}
+
+pub struct StrategyTester<S: Strategy> {
+    pub strategy: S,
+    pub broker: Broker,
+    next_index: usize,
+    initialized: bool,
+}
+
+impl<S: Strategy> StrategyTester<S> {
+    /// a broker with 100,000 cash, no costs or leverage and hedging on; change `tester.broker` before the
+    /// first step for other settings
+    pub fn new(strategy: S, data: OhlcData) -> Self {
+        let mut broker = Broker::new(data, 100_000.0, 0.0, 0.0, 1.0, false, true, false, false);
+        broker.quiet = true;
+        Self::with_broker(strategy, broker)
+    }
+
+    /// run against a configured broker (its data are the scripted bars)
+    pub fn with_broker(strategy: S, mut broker: Broker) -> Self {
+        broker.order_log = Some(Vec::new());
+        StrategyTester { strategy, broker, next_index: 0, initialized: false }
+    }
+
+    // index of the bar the next step processes
+    pub fn next_index(&self) -> usize {
+        self.next_index
+    }
+
+    /// process the next bar as Backtest::run does (broker first, then the strategy; init before the
+    /// first bar) and return the orders the strategy submitted on it
+    pub fn step(&mut self) -> Vec<SubmittedOrder> {
+        assert!(self.next_index < self.broker.data.close.len(), "no scripted bar left at index {}", self.next_index);
+        if !self.initialized {
+            let data = self.broker.data.clone();
+            self.strategy.init(&mut self.broker, &data);
+            self.initialized = true;
+        }
+        let index = self.next_index;
+        self.broker.next(index);
+        self.strategy.next(&mut self.broker, index);
+        self.next_index += 1;
+        self.orders_at(index)
+    }
+
+    /// step through bar `index` (inclusive)
+    pub fn run_to(&mut self, index: usize) {
+        while self.next_index <= index {
+            self.step();
+        }
+    }
+
+    pub fn run_all(&mut self) {
+        let n = self.broker.data.close.len();
+        if n > 0 {
+            self.run_to(n - 1);
+        }
+    }
+
+    // every submitted order so far, in submission order
+    pub fn orders(&self) -> Vec<SubmittedOrder> {
+        self.broker.order_log.iter().flatten()
+            .map(|(index, order, result)| SubmittedOrder { index: *index, order: order.clone(), result: *result })
+            .collect()
+    }
+
+    pub fn orders_at(&self, index: usize) -> Vec<SubmittedOrder> {
+        self.orders().into_iter().filter(|o| o.index == index).collect()
+    }
+
+    /// panic unless the orders submitted on bar `index` match `expected` one to one, in order
+    pub fn assert_orders(&self, index: usize, expected: &[ExpectedOrder]) {
+        let actual = self.orders_at(index);
+        let matched = actual.len() == expected.len()
+            && actual.iter().zip(expected.iter()).all(|(order, expected)| expected.matches(order));
+        if !matched {
+            panic!("orders on bar {} did not match\n expected: {:#?}\n submitted: {:#?}", index, expected, actual);
+        }
+    }
+
+    pub fn assert_no_orders(&self, index: usize) {
+        self.assert_orders(index, &[]);
+    }
+}
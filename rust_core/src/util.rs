@@ -1,6 +1,8 @@
 // helper utility functions
 
 use std::fmt::Display;
+use crate::engine::OhlcData;
+use rust_ml::labels::{fixed_horizon_labels, triple_barrier_labels, BarrierLabel, TripleBarrier};
 
 // convert any displayable value to a string
 pub fn as_str<T: Display>(value: T) -> String {
@@ -20,4 +22,106 @@ pub fn data_period(diffs: &[f64]) -> Option<f64> {
     } else {
         Some(sorted[len/2])
     }
-}
\ No newline at end of file
+}
+
+// compute log returns from a price series; non-positive prices yield a zero return
+pub fn log_returns(prices: &[f64]) -> Vec<f64> {
+    prices.windows(2)
+        .map(|w| if w[0] > 0.0 && w[1] > 0.0 { (w[1] / w[0]).ln() } else { 0.0 })
+        .collect()
+}
+
+// sample standard deviation (n - 1); returns 0.0 for fewer than two values
+pub fn std_dev(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() as f64 - 1.0);
+    variance.sqrt()
+}
+
+// pearson correlation of two equally long series; returns 0.0 if either series is flat
+pub fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len().min(b.len());
+    if n < 2 {
+        return 0.0;
+    }
+    let mean_a = a[..n].iter().sum::<f64>() / n as f64;
+    let mean_b = b[..n].iter().sum::<f64>() / n as f64;
+    let mut cov = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for i in 0..n {
+        cov += (a[i] - mean_a) * (b[i] - mean_b);
+        var_a += (a[i] - mean_a).powi(2);
+        var_b += (b[i] - mean_b).powi(2);
+    }
+    if var_a > 0.0 && var_b > 0.0 {
+        cov / (var_a.sqrt() * var_b.sqrt())
+    } else {
+        0.0
+    }
+}
+
+// 64-bit fnv-1a hash of a byte slice; stable across platforms and compiler versions, so it can be
+// stored in manifests and caches (not for anything security related)
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+// small deterministic pseudo random number generator (splitmix64), so seeded runs are reproducible
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // uniform value in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // uniform integer in [low, high] (inclusive)
+    pub fn range_i64(&mut self, low: i64, high: i64) -> i64 {
+        if high <= low {
+            return low;
+        }
+        let span = (high - low + 1) as u64;
+        low + (self.next_u64() % span) as i64
+    }
+
+    // standard normal draw (box-muller)
+    pub fn normal(&mut self) -> f64 {
+        let u1 = self.next_f64().max(f64::MIN_POSITIVE);
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+// triple-barrier training labels for the first instrument of a backtest data set
+pub fn barrier_labels(data: &OhlcData, config: &TripleBarrier) -> Vec<Option<BarrierLabel>> {
+    triple_barrier_labels(&data.open, &data.high, &data.low, &data.close, config)
+}
+
+// fixed-horizon training labels for the first instrument of a backtest data set
+pub fn horizon_labels(data: &OhlcData, horizon: usize, threshold: f64) -> Vec<Option<i8>> {
+    fixed_horizon_labels(&data.close, horizon, threshold)
+}
@@ -0,0 +1,46 @@
+// AdaptiveStatArbSpreadStrategy: the entry threshold is a quantile of recent |z| values
+use rust_core::engine::Side;
+use rust_core::strategies::adaptive_statarb_spread::AdaptiveStatArbSpreadStrategy;
+use rust_core::testing::{scripted_bars, ExpectedOrder, StrategyTester};
+
+fn strategy(regime_lookback: usize) -> AdaptiveStatArbSpreadStrategy {
+    let mut strategy = AdaptiveStatArbSpreadStrategy::new();
+    strategy.regime_lookback = regime_lookback;
+    strategy
+}
+
+#[test]
+fn threshold_is_a_bounded_quantile_of_recent_zscores() {
+    let mut strategy = strategy(5);
+    strategy.abs_zscores = vec![0.5, 1.0];
+    // not enough history yet: the fixed threshold
+    assert_eq!(strategy.current_threshold(), 1.2);
+
+    strategy.abs_zscores = vec![2.5, 0.5, 2.0, 1.0, 1.5];
+    assert_eq!(strategy.current_threshold(), 2.0);
+    strategy.quantile = 0.0;
+    assert_eq!(strategy.current_threshold(), 0.8);
+    strategy.quantile = 1.0;
+    strategy.max_threshold = 2.2;
+    assert_eq!(strategy.current_threshold(), 2.2);
+}
+
+// forty bars of a slow zigzag, then a spike
+fn closes() -> Vec<f64> {
+    let mut closes: Vec<f64> = (0..40).map(|i| 100.0 + [0.0, 0.1, 0.3, 0.1][i % 4]).collect();
+    closes.extend_from_slice(&[104.0, 100.1, 100.1]);
+    closes
+}
+
+#[test]
+fn spike_is_shorted_once_the_regime_is_known() {
+    let mut tester = StrategyTester::new(strategy(20), scripted_bars(&closes()));
+    tester.run_to(39);
+    let threshold = *tester.strategy.thresholds.last().unwrap();
+    assert!(threshold != 1.2 && (0.8..=3.0).contains(&threshold));
+
+    let orders = tester.step();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].side(), Side::Short);
+    tester.assert_orders(40, &[ExpectedOrder::sell().size(20.0).sl(104.0 + 5.0 * 0.0075 + 0.5).tag_prefix("zscore=").accepted()]);
+}
@@ -0,0 +1,84 @@
+// indicator library and the band strategies built on it: bollinger mean reversion and donchian breakout
+use rust_core::indicators::{bollinger, donchian, sma};
+use rust_core::params::Parameterized;
+use rust_core::strategies::bollinger_reversion::BollingerReversionStrategy;
+use rust_core::strategies::donchian_breakout::DonchianBreakoutStrategy;
+use rust_core::testing::{scripted_bars, ExpectedOrder, StrategyTester};
+
+#[test]
+fn indicators_are_aligned_and_causal() {
+    let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+    let averages = sma(&values, 3);
+    assert!(averages[0].is_nan() && averages[1].is_nan());
+    assert_eq!(&averages[2..], &[2.0, 3.0, 4.0]);
+
+    // sample std of (1, 2, 3) is 1
+    let bands = bollinger(&values, 3, 2.0);
+    assert_eq!(bands.at(1), None);
+    assert_eq!(bands.at(2), Some((4.0, 2.0, 0.0)));
+
+    // the channel of bar i ends at bar i - 1, so bar 3 can close above it
+    let high = [10.0, 12.0, 11.0, 15.0];
+    let low = [9.0, 8.0, 10.0, 14.0];
+    let channel = donchian(&high, &low, 3);
+    assert_eq!(channel.at(2), None);
+    assert_eq!(channel.at(3), Some((12.0, 10.0, 8.0)));
+}
+
+#[test]
+fn bollinger_buys_the_dip_and_exits_at_the_mean() {
+    // a quiet range, a sharp dip on bar 20, then the recovery
+    let mut closes: Vec<f64> = (0..20).map(|i| 100.0 + if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+    closes.extend([95.0, 96.0, 99.0, 101.0, 100.0]);
+    let mut tester = StrategyTester::new(BollingerReversionStrategy::new(), scripted_bars(&closes));
+
+    tester.run_to(20);
+    tester.assert_orders(20, &[ExpectedOrder::buy().size(10.0).tag_prefix("bollinger lower").accepted()]);
+    tester.run_to(22);
+    assert_eq!(tester.broker.trades.len(), 1);
+    // 101 is back above the middle band
+    tester.run_to(23);
+    assert!(tester.broker.trades.is_empty());
+    assert_eq!(tester.broker.closed_trades.len(), 1);
+    assert!(tester.broker.closed_trades[0].pnl() > 0.0);
+}
+
+#[test]
+fn bollinger_stop_is_placed_in_standard_deviations() {
+    let mut strategy = BollingerReversionStrategy::new();
+    strategy.apply_args(&["stop_width=0".to_string(), "period=10".to_string()]).unwrap();
+    assert_eq!(strategy.get_param("period"), Some(10.0));
+    let mut closes: Vec<f64> = (0..10).map(|i| 100.0 + if i % 2 == 0 { 0.5 } else { -0.5 }).collect();
+    closes.extend([105.0, 105.0]);
+    let mut tester = StrategyTester::new(strategy, scripted_bars(&closes));
+    tester.run_to(10);
+    let orders = tester.orders_at(10);
+    assert_eq!(orders.len(), 1);
+    assert!(orders[0].order.size < 0.0);
+    // stop_width 0 trades without a stop loss
+    assert_eq!(orders[0].order.sl, None);
+}
+
+#[test]
+fn donchian_follows_the_breakout_and_exits_on_the_exit_channel() {
+    let mut closes = vec![100.0; 20];
+    // breakout on bar 20, a trend, then a pullback below the 10 bar low on bar 30
+    closes.extend((1..=9).map(|i| 100.0 + i as f64));
+    closes.extend([103.0, 100.5]);
+    let mut tester = StrategyTester::new(DonchianBreakoutStrategy::new(), scripted_bars(&closes));
+
+    tester.run_to(19);
+    assert!(tester.orders().is_empty());
+    tester.run_to(20);
+    tester.assert_orders(20, &[ExpectedOrder::buy().tag_prefix("donchian high").accepted()]);
+    tester.run_to(28);
+    assert_eq!(tester.broker.trades.len(), 1);
+    // 103 is still above the lowest low of the last 10 bars (100, then 101 on bar 30)
+    tester.run_to(29);
+    assert_eq!(tester.broker.trades.len(), 1);
+    tester.run_to(30);
+    assert!(tester.broker.trades.is_empty());
+    // entered at the open after the breakout bar, closed at the close of the exit signal
+    assert_eq!(tester.broker.closed_trades[0].entry_price, 102.0);
+    assert_eq!(tester.broker.closed_trades[0].exit_price, Some(100.5));
+}
@@ -0,0 +1,100 @@
+// streamed input: run_streaming over a BarSource keeps only a window of bars in the broker and gives the
+// same trades and equity as a run over the whole data set
+mod common;
+
+use common::{data, Bar};
+use rust_core::diff::diff_results;
+use rust_core::engine::{Broker, OhlcData, Order, Strategy};
+use rust_core::source::{bars, run_streaming, BarSource, CsvBarSource};
+
+// buys on bar 1 and closes on bar 6, reading prices by absolute index
+struct Scripted;
+
+impl Strategy for Scripted {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        if index == 1 {
+            let price = broker.close_at(index);
+            broker.new_order(Order { size: 10.0, instrument: 1, ..Default::default() }, price).unwrap();
+        } else if index == 6 {
+            broker.close_position(0, index);
+        }
+    }
+}
+
+// ten bars rising by 1 from 100
+fn rising() -> OhlcData {
+    let bars: Vec<Bar> = (0..10).map(|i| 100.0 + i as f64).map(|c| (c, c + 1.0, c - 1.0, c)).collect();
+    data(&bars)
+}
+
+fn in_memory() -> Broker {
+    let data = rising();
+    let mut broker = common::broker_with(data.clone());
+    let mut strategy = Scripted;
+    strategy.init(&mut broker, &data);
+    for index in 0..data.close.len() {
+        broker.next(index);
+        strategy.next(&mut broker, index);
+    }
+    broker
+}
+
+fn streamed(window: usize) -> Broker {
+    let data = rising();
+    let mut broker = common::broker(&[]);
+    let count = run_streaming(&mut broker, &mut Scripted, &mut bars(&data), window).unwrap();
+    assert_eq!(count, 10);
+    broker
+}
+
+#[test]
+fn a_streamed_run_matches_the_in_memory_run() {
+    let streamed = streamed(2);
+    let diff = diff_results(&in_memory(), &streamed);
+    assert!(diff.is_match(), "{}", diff);
+    assert_eq!(streamed.closed_trades.len(), 1);
+    assert_eq!(streamed.equity.len(), 10);
+}
+
+#[test]
+fn the_broker_keeps_a_window_of_recent_bars() {
+    let broker = streamed(2);
+    assert!(broker.data.close.len() <= 4, "{} bars held", broker.data.close.len());
+    assert!(broker.first_index() > 0);
+    // absolute indices still address the bars that are left
+    assert_eq!(broker.close_at(9), 109.0);
+    assert_eq!(broker.first_index() + broker.data.close.len(), 10);
+}
+
+fn csv(name: &str, rows: &[&str]) -> String {
+    let path = std::env::temp_dir().join(name).to_string_lossy().to_string();
+    let mut contents = String::from("date,open,high,low,close,close2\n");
+    for row in rows {
+        contents.push_str(row);
+        contents.push('\n');
+    }
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn csv_rows_carry_close2_forward_up_to_the_staleness_limit() {
+    let path = csv("rust_bt_bar_source_test.csv", &[
+        "2024-01-02,100,101,99,100,",
+        "2024-01-03,100,101,99,100,50",
+        "2024-01-04,100,101,99,101,",
+        "2024-01-05,100,101,99,102,",
+        "2024-01-06,100,101,99,103,51",
+    ]);
+    let mut source = CsvBarSource::open(&path, 1).unwrap();
+    let mut read = Vec::new();
+    while let Some(bar) = source.next_bar().unwrap() {
+        read.push((bar.close, bar.close2));
+    }
+    // the first row has no close2 yet and the second blank row in a row is too stale
+    assert_eq!(read, vec![(100.0, 50.0), (101.0, 50.0), (103.0, 51.0)]);
+    assert_eq!(source.skipped, 2);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,73 @@
+// buy & hold benchmark of the stats: an external series has to cover every bar of the backtest
+use rust_core::engine::{Backtest, Perturbation, ScalingMode};
+use rust_core::halts::HaltPolicy;
+use rust_core::stats::{compute_stats_with_config, Benchmark, Stats, StatsConfig};
+use rust_core::strategies::buy_and_hold::BuyAndHoldStrategy;
+use rust_core::testing::scripted_bars;
+
+fn stats_against(benchmark: Benchmark) -> Stats {
+    let closes: Vec<f64> = (0..50).map(|i| 100.0 + i as f64).collect();
+    let mut backtest = Backtest::new(scripted_bars(&closes), Box::new(BuyAndHoldStrategy::new(1, 1.0)), 100_000.0, 0.0, 0.0, 1.0, false, true, false, false);
+    backtest.run_silent();
+    let broker = &backtest.broker;
+    let config = StatsConfig { benchmark, ..StatsConfig::default() };
+    compute_stats_with_config(
+        &broker.closed_trades,
+        &broker.equity,
+        &backtest.data,
+        0.0,
+        broker.max_margin_usage,
+        &broker.gross_exposure_history(),
+        &broker.cost_summary(),
+        &config,
+    )
+}
+
+#[test]
+fn external_benchmark_is_used_when_aligned() {
+    let stats = stats_against(Benchmark::External((0..50).map(|i| 200.0 + 2.0 * i as f64).collect()));
+    assert!((stats.buy_hold_return_pct - 49.0).abs() < 1e-9, "{}", stats.buy_hold_return_pct);
+}
+
+#[test]
+fn short_external_benchmark_is_ignored() {
+    let series: Vec<f64> = (0..30).map(|i| 200.0 + i as f64).collect();
+    assert!(Benchmark::External(series.clone()).series(&scripted_bars(&[1.0; 50])).is_none());
+    // reported like Benchmark::None instead of indexing past the end of the series
+    let stats = stats_against(Benchmark::External(series));
+    assert_eq!(stats.buy_hold_return_pct, 0.0);
+    assert_eq!(stats.beta, 0.0);
+}
+
+#[test]
+fn buy_and_hold_holds_whole_units_without_leverage() {
+    // 100_000 / 103 is fractional, and without leverage only whole units are accepted
+    let closes: Vec<f64> = (0..50).map(|i| 103.0 + i as f64).collect();
+    let mut backtest = Backtest::new(scripted_bars(&closes), Box::new(BuyAndHoldStrategy::new(1, 1.0)), 100_000.0, 0.001, 0.0, 1.0, false, true, false, false);
+    backtest.run_silent();
+    let broker = &backtest.broker;
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.closed_trades[0].size, 970.0);
+    assert_eq!(broker.closed_trades[0].entry_index, 1);
+    assert!(*broker.equity.last().unwrap() > 100_000.0);
+}
+
+#[test]
+fn buy_and_hold_runs_with_every_setting_of_the_strategy_broker() {
+    let closes: Vec<f64> = (0..50).map(|i| 100.0 + i as f64).collect();
+    let mut backtest = Backtest::new(scripted_bars(&closes), Box::new(BuyAndHoldStrategy::new(1, 1.0)), 100_000.0, 0.001, 0.0, 1.0, false, true, false, true);
+    backtest.broker.scaling_mode = ScalingMode::BuyingPower(0.5);
+    backtest.broker.halt_policy = HaltPolicy::Reject;
+    backtest.broker.gap_fills_after_halt = true;
+    backtest.broker.perturbation = Some(Perturbation::new(1, 2, 0.01, 7));
+    backtest.run_silent();
+
+    let benchmark = backtest.run_buy_and_hold();
+    let broker = &benchmark.broker;
+    assert_eq!(broker.scaling_mode, ScalingMode::BuyingPower(0.5));
+    assert_eq!(broker.halt_policy, HaltPolicy::Reject);
+    assert!(broker.gap_fills_after_halt);
+    assert_eq!(broker.perturbation.as_ref().map(|p| (p.timing_jitter, p.price_jitter_ticks, p.seed)), Some((1, 2, 7)));
+    // the noise starts from its seed again, so the benchmark is the same however often it is run
+    assert_eq!(backtest.run_buy_and_hold().broker.equity, broker.equity);
+}
@@ -0,0 +1,96 @@
+// per-order commissions: a maker-rebated limit entry and a taker exit in the same run, with the
+// commission of every fill attributed to its trade and taken out of its pnl and cash
+mod common;
+
+use common::{Bar, FLAT};
+use rust_core::engine::{Broker, Order, OrderCommission};
+
+// (open, high, low, close) per bar, 0.1% taker commission and a 0.02% maker rebate
+fn broker(bars: &[Bar]) -> Broker {
+    let mut broker = common::broker(bars);
+    broker.commission = 0.001;
+    broker.maker_commission = -0.0002;
+    broker
+}
+
+fn order(size: f64, limit: Option<f64>, commission: Option<OrderCommission>) -> Order {
+    Order {
+        size,
+        limit,
+        instrument: 1,
+        commission,
+        ..Default::default()
+    }
+}
+
+fn close_to(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn maker_entry_earns_a_rebate_and_taker_exit_pays() {
+    let mut broker = broker(&[FLAT, FLAT, FLAT]);
+    broker.next(0);
+    broker.new_order(order(10.0, Some(100.0), Some(OrderCommission::Maker)), 100.0).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 1);
+    // a buy with a rebate fills below the limit
+    assert!(close_to(broker.trades[0].entry_price, 100.0 * (1.0 - 0.0002)));
+    assert!(close_to(broker.trades[0].commission, -0.2));
+
+    broker.close_position(0, 2);
+    let trade = &broker.closed_trades[0];
+    assert!(close_to(trade.commission, -0.2 + 1.0));
+    assert!(close_to(broker.costs.total_commission, 0.8));
+    assert!(close_to(broker.costs.total_rebates, 0.2));
+    // the exit sells below the close: a flat round trip loses exactly the net commission
+    assert!(close_to(trade.exit_price.unwrap(), 100.0 * (1.0 - 0.001)));
+    assert!(close_to(trade.pnl(), -0.8));
+    assert!(close_to(broker.cash, 100_000.0 - 0.8));
+}
+
+#[test]
+fn rate_override_replaces_the_broker_commission() {
+    let mut broker = broker(&[FLAT, FLAT]);
+    broker.next(0);
+    broker.new_order(order(10.0, None, Some(OrderCommission::Rate(0.0))), 100.0).unwrap();
+    broker.new_order(order(10.0, None, None), 100.0).unwrap();
+    broker.next(1);
+    assert!(close_to(broker.trades[0].entry_price, 100.0));
+    assert!(close_to(broker.trades[0].commission, 0.0));
+    assert!(close_to(broker.trades[1].entry_price, 100.1));
+    assert!(close_to(broker.trades[1].commission, 1.0));
+}
+
+#[test]
+fn partial_close_takes_its_share_of_the_entry_commission() {
+    let mut broker = broker(&[FLAT, FLAT, FLAT]);
+    broker.next(0);
+    broker.new_order(order(10.0, None, None), 100.0).unwrap();
+    broker.next(1);
+    broker.close_partial(0, 4.0, 2);
+    // 40% of the 1.0 entry commission plus 0.1% of 4 * 100 on the exit
+    assert!(close_to(broker.closed_trades[0].commission, 0.4 + 0.4));
+    assert!(close_to(broker.trades[0].commission, 0.6));
+    let attributed: f64 = broker.closed_trades.iter().chain(broker.trades.iter()).map(|t| t.commission).sum();
+    assert!(close_to(attributed, broker.costs.total_commission));
+    assert!(close_to(broker.closed_trades[0].pnl(), -0.8));
+    assert!(close_to(broker.cash, 100_000.0 - 0.8));
+}
+
+#[test]
+fn contingent_exits_pay_commission_on_the_exit_side() {
+    let mut broker = broker(&[FLAT, FLAT, (100.0, 101.0, 89.0, 92.0)]);
+    broker.next(0);
+    let short = Order { tp: Some(90.0), ..order(-10.0, None, None) };
+    broker.new_order(short, 100.0).unwrap();
+    broker.next(1);
+    broker.next(2);
+    let trade = &broker.closed_trades[0];
+    // the take profit buys back above its limit
+    assert!(close_to(trade.exit_price.unwrap(), 90.0 * (1.0 + 0.001)));
+    assert!(close_to(trade.commission, 1.0 + 0.9));
+    // 100 gross less the commission of both fills
+    assert!(close_to(trade.pnl(), 100.0 - 1.9));
+    assert!(close_to(broker.cash, 100_000.0 + 98.1));
+}
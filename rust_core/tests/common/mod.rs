@@ -0,0 +1,56 @@
+// fixtures shared by the broker tests; each test crate uses only some of them
+#![allow(dead_code)]
+use rust_core::engine::{Broker, OhlcData, Order};
+use rust_core::live_engine::{LiveBroker, LiveData, TickSnapshot};
+
+// (open, high, low, close) of one bar
+pub type Bar = (f64, f64, f64, f64);
+
+pub const FLAT: Bar = (100.0, 101.0, 99.0, 100.0);
+
+// one-minute bars with close2 equal to close
+pub fn data(bars: &[Bar]) -> OhlcData {
+    OhlcData {
+        date: (0..bars.len()).map(|i| format!("2024-01-02 10:{:02}:00", i)).collect(),
+        open: bars.iter().map(|b| b.0).collect(),
+        high: bars.iter().map(|b| b.1).collect(),
+        low: bars.iter().map(|b| b.2).collect(),
+        close: bars.iter().map(|b| b.3).collect(),
+        close2: bars.iter().map(|b| b.3).collect(),
+        volume: None,
+        halts: Vec::new(),
+    }
+}
+
+// hedging broker with 100k cash, no commission, spread or leverage
+pub fn broker_with(data: OhlcData) -> Broker {
+    Broker::new(data, 100_000.0, 0.0, 0.0, 1.0, false, true, false, false)
+}
+
+pub fn broker(bars: &[Bar]) -> Broker {
+    broker_with(data(bars))
+}
+
+// submit on bar 0 at its close and run the remaining bars
+pub fn run(bars: &[Bar], order: Order) -> Broker {
+    let mut broker = broker(bars);
+    broker.next(0);
+    broker.new_order(order, bars[0].3).unwrap();
+    for index in 1..bars.len() {
+        broker.next(index);
+    }
+    broker
+}
+
+// live broker with 100k cash and no leverage, before any tick
+pub fn live_broker() -> LiveBroker {
+    let data = LiveData { ticks: Vec::new(), current: Default::default(), events: Vec::new() };
+    LiveBroker::new(data, 100_000.0, 1.0, false, true, false, false)
+}
+
+// feed a live tick of one instrument
+pub fn quote(broker: &mut LiveBroker, instrument: &str, bid: f64, ask: f64) {
+    let tick = TickSnapshot { instrument: instrument.to_string(), date: "2024-01-02T10:00:00Z".to_string(), bid, ask };
+    broker.live_data.ticks.push(tick.clone());
+    broker.live_data.current.insert(instrument.to_string(), tick);
+}
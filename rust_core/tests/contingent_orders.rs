@@ -0,0 +1,156 @@
+// lifecycle of stop-loss and take-profit orders: whichever way a trade is closed, its remaining
+// contingent orders are cancelled (and logged), and the orders of other trades keep pointing at them
+mod common;
+
+use common::{broker, live_broker, quote, Bar, FLAT};
+use rust_core::engine::{Broker, Order, TakeProfitLevel};
+use rust_core::live_engine::{LiveBroker, Order as LiveOrder};
+
+fn entry(size: f64, sl: Option<f64>, tp_ladder: Vec<TakeProfitLevel>) -> Order {
+    Order {
+        size,
+        sl,
+        instrument: 1,
+        tp_ladder,
+        ..Default::default()
+    }
+}
+
+fn ladder() -> Vec<TakeProfitLevel> {
+    vec![
+        TakeProfitLevel { price: 110.0, fraction: 0.5 },
+        TakeProfitLevel { price: 120.0, fraction: 0.5 },
+    ]
+}
+
+// two longs opened on bar 1: trade 0 with a stop at 90 and a two-rung ladder, trade 1 with a stop at 80
+fn two_trades(bars: &[Bar]) -> Broker {
+    let mut broker = broker(bars);
+    broker.next(0);
+    broker.new_order(entry(10.0, Some(90.0), ladder()), 100.0).unwrap();
+    broker.new_order(entry(4.0, Some(80.0), Vec::new()), 100.0).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 2);
+    assert_eq!(broker.contingent_orders(0).len(), 3);
+    assert_eq!(broker.contingent_orders(1).len(), 1);
+    broker
+}
+
+#[test]
+fn closing_a_trade_cancels_its_children() {
+    let mut broker = two_trades(&[FLAT, FLAT, FLAT]);
+    broker.close_position(0, 1);
+
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.cancelled_orders.len(), 3);
+    assert!(broker.cancelled_orders.iter().all(|(tick, _)| *tick == 1));
+    // the other trade's stop moved down to index 0 with its trade
+    let remaining = broker.contingent_orders(0);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].stop, Some(80.0));
+    assert!(broker.orphaned_orders().is_empty());
+}
+
+#[test]
+fn close_all_cancels_children_but_keeps_pending_entries() {
+    let mut broker = two_trades(&[FLAT, FLAT, FLAT]);
+    let mut resting = entry(3.0, None, Vec::new());
+    resting.limit = Some(50.0);
+    broker.new_order(resting, 100.0).unwrap();
+
+    broker.close_all_trades(1, 1);
+
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.cancelled_orders.len(), 4);
+    assert_eq!(broker.orders.len(), 1);
+    assert_eq!(broker.orders[0].limit, Some(50.0));
+    assert!(broker.orphaned_orders().is_empty());
+}
+
+#[test]
+fn stop_loss_cancels_take_profits() {
+    // bar 2 only reaches the stop of trade 0
+    let mut broker = two_trades(&[FLAT, FLAT, (100.0, 101.0, 85.0, 88.0)]);
+    broker.next(2);
+
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.closed_trades[0].exit_tag.as_deref(), Some("stop-loss"));
+    let cancelled: Vec<&str> = broker.cancelled_orders.iter().filter_map(|(_, o)| o.tag.as_deref()).collect();
+    assert_eq!(cancelled, vec!["take-profit 1/2", "take-profit 2/2"]);
+    assert_eq!(broker.contingent_orders(0).len(), 1);
+    assert!(broker.orphaned_orders().is_empty());
+}
+
+#[test]
+fn stop_and_take_profit_on_the_same_bar() {
+    // a wide bar hits the stop and both rungs; the stop fills first and takes the rungs with it
+    let mut broker = two_trades(&[FLAT, FLAT, (100.0, 125.0, 85.0, 100.0)]);
+    broker.next(2);
+
+    let exits: Vec<&str> = broker.closed_trades.iter().filter_map(|t| t.exit_tag.as_deref()).collect();
+    assert_eq!(exits, vec!["stop-loss"]);
+    assert_eq!(broker.cancelled_orders.len(), 2);
+    assert!(broker.orphaned_orders().is_empty());
+}
+
+#[test]
+fn filled_ladder_cancels_stop_loss() {
+    // bar 2 reaches both rungs, closing trade 0 in two halves
+    let mut broker = two_trades(&[FLAT, FLAT, (100.0, 125.0, 99.0, 121.0)]);
+    broker.next(2);
+
+    assert_eq!(broker.closed_trades.len(), 2);
+    assert_eq!(broker.trades.len(), 1);
+    let cancelled: Vec<Option<f64>> = broker.cancelled_orders.iter().map(|(_, o)| o.stop).collect();
+    assert_eq!(cancelled, vec![Some(90.0)]);
+    assert_eq!(broker.contingent_orders(0).len(), 1);
+    assert!(broker.orphaned_orders().is_empty());
+}
+
+fn live_entry(instrument: &str, size: f64, sl: f64) -> LiveOrder {
+    LiveOrder {
+        size,
+        sl: Some(sl),
+        instrument: instrument.to_string(),
+        ..Default::default()
+    }
+}
+
+// a long on "A" (stop 90) and a long on "B" (stop 40), both open after tick 0
+fn live_two_trades() -> LiveBroker {
+    let mut broker = live_broker();
+    quote(&mut broker, "A", 100.0, 100.1);
+    quote(&mut broker, "B", 50.0, 50.1);
+    broker.new_order(live_entry("A", 10.0, 90.0), 100.1).unwrap();
+    broker.new_order(live_entry("B", 10.0, 40.0), 50.1).unwrap();
+    broker.next(0);
+    assert_eq!(broker.trades.len(), 2);
+    broker
+}
+
+#[test]
+fn live_close_position_cancels_its_stop() {
+    let mut broker = live_two_trades();
+    broker.close_position(0, 1);
+
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.cancelled_orders.len(), 1);
+    assert_eq!(broker.cancelled_orders[0].1.stop, Some(90.0));
+    let remaining = broker.contingent_orders(0);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].stop, Some(40.0));
+}
+
+#[test]
+fn live_stop_loss_closes_its_trade() {
+    let mut broker = live_two_trades();
+    quote(&mut broker, "A", 89.0, 89.1);
+    broker.next(1);
+
+    // the stop closes trade A instead of opening another position
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].instrument, "B");
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.closed_trades[0].exit_tag.as_deref(), Some("stop-loss"));
+    assert_eq!(broker.contingent_orders(0).len(), 1);
+}
@@ -0,0 +1,64 @@
+// stop-loss cooldown: after a stop-out, entries on the same side of that instrument are rejected for a
+// number of bars, while the other side and other instruments stay tradeable
+mod common;
+
+use common::FLAT;
+use rust_core::engine::{Broker, Order, OrderError};
+
+fn broker(n: usize) -> Broker {
+    // flat at 100 except bar 2, which dips to 90 and takes out a long's stop
+    let bars: Vec<common::Bar> = (0..n).map(|i| if i == 2 { (100.0, 101.0, 90.0, 100.0) } else { FLAT }).collect();
+    let mut broker = common::broker(&bars);
+    broker.stop_loss_cooldown = Some(2);
+    broker
+}
+
+fn entry(size: f64, sl: Option<f64>) -> Order {
+    Order {
+        size,
+        sl,
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+// a long with a 95 stop opened on bar 1 and stopped out on bar 2
+fn stopped_out() -> Broker {
+    let mut broker = broker(8);
+    broker.next(0);
+    broker.new_order(entry(10.0, Some(95.0)), 100.0).unwrap();
+    broker.next(1);
+    broker.next(2);
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.closed_trades[0].exit_tag.as_deref(), Some("stop-loss"));
+    broker
+}
+
+#[test]
+fn same_side_entries_are_rejected_during_the_cooldown() {
+    let mut broker = stopped_out();
+    assert_eq!(broker.new_order(entry(10.0, None), 100.0), Err(OrderError::InCooldown));
+    broker.next(3);
+    broker.next(4);
+    assert_eq!(broker.new_order(entry(10.0, None), 100.0), Err(OrderError::InCooldown));
+    assert_eq!(broker.rejected_orders, vec![(2, OrderError::InCooldown), (4, OrderError::InCooldown)]);
+
+    broker.next(5);
+    assert!(broker.new_order(entry(10.0, None), 100.0).is_ok());
+}
+
+#[test]
+fn other_side_and_instrument_stay_open() {
+    let mut broker = stopped_out();
+    assert!(broker.in_cooldown(2, 1, true));
+    assert!(!broker.in_cooldown(2, 1, false));
+    assert!(!broker.in_cooldown(2, 2, true));
+    assert!(broker.new_order(entry(-10.0, None), 100.0).is_ok());
+}
+
+#[test]
+fn no_cooldown_by_default() {
+    let mut broker = stopped_out();
+    broker.stop_loss_cooldown = None;
+    assert!(broker.new_order(entry(10.0, None), 100.0).is_ok());
+}
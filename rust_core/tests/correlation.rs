@@ -0,0 +1,41 @@
+// correlation matrix of symbols with different histories: each pair is compared on the dates both have
+mod common;
+
+use common::FLAT;
+use rust_core::analysis::{correlation_matrix, correlation_report};
+use rust_core::engine::OhlcData;
+
+// daily closes on 2024-01-<day>
+fn series(days: &[u32], closes: &[f64]) -> OhlcData {
+    let mut data = common::data(&vec![FLAT; days.len()]);
+    data.date = days.iter().map(|day| format!("2024-01-{:02}", day)).collect();
+    data.close = closes.to_vec();
+    data
+}
+
+fn close_to(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn pairs_are_joined_on_their_common_dates() {
+    let a = series(&[1, 2, 3, 4, 5, 6], &[100.0, 101.0, 103.0, 102.0, 105.0, 104.0]);
+    // twice a, without the 4th
+    let b = series(&[1, 2, 3, 5, 6], &[200.0, 202.0, 206.0, 210.0, 208.0]);
+    // twice a, only from the 3rd on
+    let c = series(&[3, 4, 5, 6], &[206.0, 204.0, 210.0, 208.0]);
+    let matrix = correlation_matrix(&[("a".to_string(), a), ("b".to_string(), b), ("c".to_string(), c)]);
+    assert!(close_to(matrix[0][1], 1.0), "{}", matrix[0][1]);
+    assert!(close_to(matrix[0][2], 1.0), "{}", matrix[0][2]);
+    assert!(close_to(matrix[1][2], 1.0), "{}", matrix[1][2]);
+    assert_eq!(matrix[1][0], matrix[0][1]);
+}
+
+#[test]
+fn series_without_common_dates_are_uncorrelated() {
+    let a = series(&[1, 2, 3], &[100.0, 101.0, 99.0]);
+    let b = series(&[4, 5, 6], &[100.0, 101.0, 99.0]);
+    let report = correlation_report(&[("a".to_string(), a), ("b".to_string(), b)]);
+    assert_eq!(report.matrix, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    assert_eq!(report.merges.len(), 1);
+}
@@ -0,0 +1,72 @@
+// cross-sectional momentum: monthly long-short book ranked by trailing return, traded through the
+// rebalancing executor
+use rust_core::engine::Broker;
+use rust_core::rebalance::{RebalanceConfig, TargetPositionStrategy};
+use rust_core::strategies::cross_sectional_momentum::{momentum_book, trailing_return, CrossSectionalMomentumStrategy};
+use rust_core::testing::{scripted_pair, StrategyTester};
+
+// 20 bars per month; instrument 1 rallies and instrument 2 sells off until bar 120, then both reverse
+fn tester() -> StrategyTester<TargetPositionStrategy<CrossSectionalMomentumStrategy>> {
+    let n = 200;
+    let close: Vec<f64> = (0..n).map(|i| if i <= 120 { 100.0 + 0.5 * i as f64 } else { 160.0 - 0.5 * (i - 120) as f64 }).collect();
+    let close2: Vec<f64> = (0..n).map(|i| if i <= 120 { 50.0 - 0.1 * i as f64 } else { 38.0 + 0.1 * (i - 120) as f64 }).collect();
+    let mut data = scripted_pair(&close, &close2);
+    data.date = (0..n).map(|i| format!("2024-{:02}-{:02} 00:00:00", 1 + i / 20, 1 + i % 20)).collect();
+
+    let mut strategy = CrossSectionalMomentumStrategy::new();
+    strategy.lookback = 40;
+    strategy.skip = 0;
+    strategy.gross_leverage = 0.8;
+    // with leverage any size is allowed; instrument 2 orders are sized in units of the primary, so its
+    // targets don't come out in whole units
+    let mut broker = Broker::new(data, 100_000.0, 0.0, 0.0, 0.5, false, true, false, false);
+    broker.quiet = true;
+    // the no-trade band keeps monthly drifts in the targets from piling up top-up trades
+    let config = RebalanceConfig { min_trade_fraction: 0.25, ..RebalanceConfig::default() };
+    StrategyTester::with_broker(TargetPositionStrategy::with_config(strategy, config), broker)
+}
+
+fn position(broker: &Broker, instrument: u8) -> f64 {
+    broker.trades.iter().filter(|t| t.instrument == instrument).map(|t| t.size).sum()
+}
+
+#[test]
+fn book_ranks_and_never_overlaps() {
+    let returns = [Some(0.10), None, Some(-0.05), Some(0.20), Some(0.0)];
+    assert_eq!(momentum_book(&returns, 2, 1), vec![0.5, 0.0, -1.0, 0.5, 0.0]);
+    // two ranked names can't fill two books of two: one long, one short
+    assert_eq!(momentum_book(&[Some(0.1), Some(0.2)], 2, 2), vec![-1.0, 1.0]);
+    assert!((trailing_return(&[100.0, 105.0, 110.0, 90.0], 3, 2, 1).unwrap() - 0.1).abs() < 1e-12);
+    assert_eq!(trailing_return(&[100.0, 105.0], 1, 2, 0), None);
+}
+
+#[test]
+fn rebalances_monthly_into_an_equal_notional_pair() {
+    let mut tester = tester();
+    tester.run_to(59);
+    let rebalances: Vec<usize> = tester.strategy.inner.rebalances.iter().map(|(index, _)| *index).collect();
+    // first bar with a 40 bar return, then the first bar of each month
+    assert_eq!(rebalances, vec![40]);
+
+    let broker = &tester.broker;
+    let long = position(broker, 1);
+    let short = position(broker, 2);
+    assert!(long > 0.0 && short < 0.0, "long {} short {}", long, short);
+    // each leg holds about 40% of the starting equity, in units of its own instrument
+    assert!((long * broker.data.close[40] - 40_000.0).abs() < 100.0, "long notional {}", long * broker.data.close[40]);
+    assert!((-short * broker.data.close2[40] - 40_000.0).abs() < 100.0, "short notional {}", -short * broker.data.close2[40]);
+}
+
+#[test]
+fn book_flips_when_the_ranking_does() {
+    let mut tester = tester();
+    tester.run_all();
+    let rebalances: Vec<usize> = tester.strategy.inner.rebalances.iter().map(|(index, _)| *index).collect();
+    assert_eq!(rebalances, vec![40, 60, 80, 100, 120, 140, 160, 180]);
+    // by bar 160 the 40 bar returns have reversed
+    let (_, targets) = &tester.strategy.inner.rebalances[6];
+    assert!(targets[0].1 < 0.0 && targets[1].1 > 0.0, "{:?}", targets);
+    assert!(position(&tester.broker, 1) < 0.0);
+    assert!(position(&tester.broker, 2) > 0.0);
+    assert!(tester.broker.rejected_orders.is_empty());
+}
@@ -0,0 +1,86 @@
+// cross-validation: the same scripted strategy runs on bars built from the recorded quotes and on the
+// quotes themselves, and its trades are paired up to show where the bar simulation differs
+mod common;
+
+use rust_core::crossval::{cross_validate, CrossValidationConfig, DivergenceReport};
+use rust_core::engine::{Backtest, Broker, OhlcData, Order, Strategy};
+use rust_core::live_engine::{LiveBacktest, LiveBroker, LiveData, LiveStrategy, Order as LiveOrder, TickSnapshot};
+
+// buys 10 units on one index and closes the trade on a later one, in either engine
+struct Scripted {
+    entry: usize,
+    exit: usize,
+}
+
+impl Strategy for Scripted {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        if index == self.entry {
+            let price = broker.data.close[index];
+            broker.new_order(Order { size: 10.0, instrument: 1, ..Default::default() }, price).unwrap();
+        } else if index == self.exit {
+            broker.close_position(0, index);
+        }
+    }
+}
+
+impl LiveStrategy for Scripted {
+    fn init(&mut self, _broker: &mut LiveBroker, _data: &LiveData) {}
+
+    fn next(&mut self, broker: &mut LiveBroker, index: usize) {
+        if index == self.entry {
+            let ask = broker.live_data.current["A"].ask;
+            broker.new_order(LiveOrder { size: 10.0, instrument: "A".to_string(), ..Default::default() }, ask).unwrap();
+        } else if index == self.exit {
+            broker.close_position(0, index);
+        }
+    }
+}
+
+// "A" quoted one wide around mids of 100, 101, ..., one second apart
+fn ticks() -> Vec<TickSnapshot> {
+    (0..6)
+        .map(|i| TickSnapshot {
+            instrument: "A".to_string(),
+            date: format!("2024-01-02T10:00:{:02}Z", i),
+            bid: 99.5 + i as f64,
+            ask: 100.5 + i as f64,
+        })
+        .collect()
+}
+
+fn compare(max_entry_lag: usize) -> DivergenceReport {
+    let scripted = || Scripted { entry: 1, exit: 4 };
+    let mut backtest = Backtest::new(common::data(&[common::FLAT]), Box::new(scripted()), 100_000.0, 0.0, 0.0, 1.0, false, true, false, false);
+    let live_data = LiveData { ticks: Vec::new(), current: Default::default(), events: Vec::new() };
+    let mut live = LiveBacktest::new(live_data, Box::new(scripted()), 100_000.0, 1.0, false, true, false, false);
+    let config = CrossValidationConfig { primary: "A".to_string(), secondary: None, max_entry_lag };
+    cross_validate(&ticks(), &mut backtest, &mut live, &config)
+}
+
+#[test]
+fn a_trade_of_both_engines_is_paired_with_its_differences() {
+    let report = compare(1);
+    assert_eq!((report.ticks, report.bars), (6, 6));
+    assert_eq!((report.bar_trades, report.live_trades), (1, 1));
+    assert_eq!(report.match_rate(), 1.0);
+
+    let pair = &report.pairs[0];
+    // the bar fills at the next bar's mid (102), the live engine at this tick's ask (101.5)
+    assert_eq!((pair.bar.entry_tick, pair.live.entry_tick), (2, 1));
+    assert_eq!(pair.entry_lag, -1);
+    assert_eq!(pair.entry_diff, -0.5);
+    // the bar exits at the mid (104), the live engine at the bid (103.5)
+    assert_eq!(pair.exit_diff, Some(0.5));
+    assert_eq!(pair.pnl_diff, 0.0);
+    assert_eq!((report.bar_pnl, report.live_pnl), (20.0, 20.0));
+}
+
+#[test]
+fn trades_further_apart_than_the_lag_stay_unmatched() {
+    let report = compare(0);
+    assert!(report.pairs.is_empty());
+    assert_eq!((report.unmatched_bar.len(), report.unmatched_live.len()), (1, 1));
+    assert_eq!(report.match_rate(), 0.0);
+}
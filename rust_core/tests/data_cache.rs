@@ -0,0 +1,62 @@
+// the binary data cache: what is written reads back unchanged, and a cache built from another csv or with
+// another MissingDataPolicy is not used
+use rust_core::data_handler::cache::{cache_path, decode, encode, handle_ohlc_cached};
+use rust_core::data_handler::MissingDataPolicy;
+use rust_core::engine::OhlcData;
+
+fn data() -> OhlcData {
+    OhlcData {
+        date: vec!["2024-01-02 10:00:00".to_string(), "2024-01-02 10:01:00".to_string()],
+        open: vec![100.0, 101.0],
+        high: vec![102.0, 103.0],
+        low: vec![99.0, 100.5],
+        close: vec![101.0, 102.5],
+        close2: vec![50.0, 50.25],
+        volume: Some(vec![10.0, 12.0]),
+        halts: Vec::new(),
+    }
+}
+
+fn assert_same(a: &OhlcData, b: &OhlcData) {
+    assert_eq!(a.date, b.date);
+    assert_eq!((&a.open, &a.high, &a.low, &a.close, &a.close2), (&b.open, &b.high, &b.low, &b.close, &b.close2));
+    assert_eq!(a.volume, b.volume);
+}
+
+#[test]
+fn encoded_data_decodes_unchanged() {
+    let policy = MissingDataPolicy::default();
+    let bytes = encode(&data(), 7, policy);
+    assert_same(&decode(&bytes, 7, policy).unwrap(), &data());
+
+    let without_volume = OhlcData { volume: None, ..data() };
+    assert_eq!(decode(&encode(&without_volume, 7, policy), 7, policy).unwrap().volume, None);
+}
+
+#[test]
+fn a_cache_of_another_csv_or_policy_is_stale() {
+    let policy = MissingDataPolicy::ForwardFill { max_staleness: 5 };
+    let bytes = encode(&data(), 7, policy);
+    assert!(decode(&bytes, 8, policy).is_none());
+    assert!(decode(&bytes, 7, MissingDataPolicy::ForwardFill { max_staleness: 3 }).is_none());
+    assert!(decode(&bytes, 7, MissingDataPolicy::Carry).is_none());
+    assert!(decode(&bytes[..bytes.len() - 1], 7, policy).is_none());
+}
+
+#[test]
+fn a_modified_csv_invalidates_the_cache() {
+    let path = std::env::temp_dir().join("rust_bt_data_cache_test.csv").to_string_lossy().to_string();
+    std::fs::write(&path, "date,open,high,low,close\n2024-01-02,1,2,0.5,1.5\n").unwrap();
+    let _ = std::fs::remove_file(cache_path(&path));
+
+    assert_eq!(handle_ohlc_cached(&path).unwrap().close, vec![1.5]);
+    assert!(std::path::Path::new(&cache_path(&path)).exists());
+    // served from the cache
+    assert_eq!(handle_ohlc_cached(&path).unwrap().close, vec![1.5]);
+
+    std::fs::write(&path, "date,open,high,low,close\n2024-01-02,1,2,0.5,1.75\n2024-01-03,2,3,1,2.5\n").unwrap();
+    assert_eq!(handle_ohlc_cached(&path).unwrap().close, vec![1.75, 2.5]);
+
+    let _ = std::fs::remove_file(cache_path(&path));
+    let _ = std::fs::remove_file(&path);
+}
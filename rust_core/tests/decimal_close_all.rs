@@ -0,0 +1,38 @@
+// decimal feature: closing every trade at once books the same exact prices and cash as closing them one
+// by one (cargo test --features decimal)
+#![cfg(feature = "decimal")]
+use rust_core::engine::{Broker, Order};
+use rust_core::testing::scripted_pair;
+
+// a few longs and shorts on both instruments at prices that aren't exact in binary
+fn broker_with_trades() -> Broker {
+    let closes = [100.1, 100.3, 100.7, 101.9];
+    let closes2 = [50.05, 50.15, 49.95, 50.35];
+    let mut broker = Broker::new(scripted_pair(&closes, &closes2), 100_000.0, 0.0007, 0.1, 0.5, false, true, false, false);
+    broker.quiet = true;
+    broker.next(0);
+    for (size, instrument) in [(3.0, 1), (-2.0, 1), (7.0, 2)] {
+        broker.new_order(Order { size, instrument, ..Default::default() }, 100.1).unwrap();
+    }
+    broker.next(1);
+    broker.next(2);
+    assert_eq!(broker.trades.len(), 3);
+    broker
+}
+
+#[test]
+fn close_all_matches_individual_closes() {
+    let mut all = broker_with_trades();
+    all.close_all_trades(3, 3);
+
+    let mut one_by_one = broker_with_trades();
+    while !one_by_one.trades.is_empty() {
+        one_by_one.close_position(0, 3);
+    }
+
+    assert_eq!(all.cash, one_by_one.cash);
+    let exits = |broker: &Broker| broker.closed_trades.iter().map(|t| (t.id, t.exit_price)).collect::<Vec<_>>();
+    assert_eq!(exits(&all), exits(&one_by_one));
+    // exit prices are rounded to price_decimals, cash to cash_decimals
+    assert_eq!((all.cash * 100.0).round() / 100.0, all.cash);
+}
@@ -0,0 +1,37 @@
+// trade diagnostics count entries, not closed parts: a trade closed in several pieces is one entry
+mod common;
+
+use common::{broker, FLAT};
+use rust_core::diagnostics::{broker_diagnostics, DiagnosticsConfig};
+use rust_core::engine::Order;
+
+#[test]
+fn partial_closes_of_one_trade_are_one_entry() {
+    let mut broker = broker(&[FLAT; 6]);
+    broker.next(0);
+    broker.new_order(Order { size: 10.0, instrument: 1, ..Default::default() }, 100.0).unwrap();
+    broker.next(1);
+    broker.close_partial(0, 4.0, 2);
+    broker.close_partial(0, 3.0, 3);
+    broker.close_position(0, 4);
+    assert_eq!(broker.closed_trades.len(), 3);
+
+    let diagnostics = broker_diagnostics(&broker, &DiagnosticsConfig::default());
+    assert_eq!(diagnostics.num_entries, 1);
+    assert!(diagnostics.bursts.is_empty());
+}
+
+#[test]
+fn separate_trades_on_the_same_bar_are_separate_entries() {
+    let mut broker = broker(&[FLAT; 3]);
+    broker.next(0);
+    for _ in 0..2 {
+        broker.new_order(Order { size: 10.0, instrument: 1, ..Default::default() }, 100.0).unwrap();
+    }
+    broker.next(1);
+    broker.close_all_trades(2, 2);
+
+    let diagnostics = broker_diagnostics(&broker, &DiagnosticsConfig::default());
+    assert_eq!(diagnostics.num_entries, 2);
+    assert_eq!(diagnostics.time_between_entries.mean, 0.0);
+}
@@ -0,0 +1,87 @@
+// drawdown circuit breaker: entry sizes are cut as equity falls from its peak and restored once it recovers
+mod common;
+
+use rust_core::engine::{Broker, Order};
+use rust_core::rebalance::RebalanceConfig;
+use rust_core::risk::DrawdownGovernor;
+use rust_core::stats::{compute_stats_with_config, StatsConfig};
+
+// flat bars at the given closes, entries halved from a 10% drawdown and quartered from 20%
+fn broker(close: &[f64]) -> Broker {
+    let bars: Vec<common::Bar> = close.iter().map(|&c| (c, c, c, c)).collect();
+    let mut broker = common::broker(&bars);
+    broker.drawdown_governor = Some(DrawdownGovernor::halving(10.0, 20.0));
+    broker.quiet = true;
+    broker
+}
+
+fn market(size: f64) -> Order {
+    Order {
+        size,
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn governor_steps() {
+    let governor = DrawdownGovernor::new(vec![(20.0, 0.25), (10.0, 0.5)]);
+    assert_eq!(governor.multiplier(0.0), 1.0);
+    assert_eq!(governor.multiplier(9.9), 1.0);
+    assert_eq!(governor.multiplier(10.0), 0.5);
+    assert_eq!(governor.multiplier(35.0), 0.25);
+    assert_eq!(governor.multiplier_at(85.0, 100.0), 0.5);
+}
+
+#[test]
+fn entries_shrink_in_drawdown_and_recover_with_equity() {
+    let close = [100.0, 100.0, 80.0, 80.0, 100.0, 100.0];
+    let mut broker = broker(&close);
+    broker.next(0);
+    broker.new_order(market(800.0), 100.0).unwrap();
+    broker.next(1);
+    // 800 units lose 16k at 80: a 16% drawdown halves new entries
+    broker.next(2);
+    assert_eq!(broker.size_multiplier, 0.5);
+    broker.new_order(market(100.0), 80.0).unwrap();
+    broker.next(3);
+    assert_eq!(broker.trades[1].size, 50.0);
+
+    // back at 100 the account is at a new peak and entries are full size again
+    broker.next(4);
+    assert_eq!(broker.size_multiplier, 1.0);
+    broker.new_order(market(100.0), 100.0).unwrap();
+    broker.next(5);
+    assert_eq!(broker.trades[2].size, 100.0);
+    assert_eq!(broker.size_multiplier_history, vec![1.0, 1.0, 0.5, 0.5, 1.0, 1.0]);
+
+    let config = StatsConfig { size_multipliers: broker.size_multiplier_history.clone(), ..StatsConfig::default() };
+    let stats = compute_stats_with_config(
+        &broker.closed_trades,
+        &broker.equity,
+        &broker.data,
+        0.0,
+        broker.max_margin_usage,
+        &broker.gross_exposure_history(),
+        &broker.cost_summary(),
+        &config,
+    );
+    assert!((stats.de_risked_time_pct - 100.0 / 3.0).abs() < 1e-9);
+    assert_eq!(stats.min_size_multiplier, 0.5);
+}
+
+#[test]
+fn rebalancing_reaches_its_target_in_drawdown() {
+    let close = [100.0, 100.0, 78.0, 78.0];
+    let mut broker = broker(&close);
+    broker.next(0);
+    broker.new_order(market(500.0), 100.0).unwrap();
+    broker.next(1);
+    // 500 units lose 11k at 78: an 11% drawdown halves new entries, but not a rebalance to a target
+    broker.next(2);
+    assert_eq!(broker.size_multiplier, 0.5);
+    assert_eq!(broker.rebalance(2, 1, 700.0, &RebalanceConfig::default()), Ok(200.0));
+    broker.next(3);
+    let position: f64 = broker.trades.iter().map(|t| t.size).sum();
+    assert_eq!(position, 700.0);
+}
@@ -0,0 +1,116 @@
+// equity marking of pairs trades: open trades are marked at the close of their own instrument, so the
+// equity curve agrees with the realized pnl once the trades are closed at those prices
+use rust_core::engine::{Backtest, Broker, OhlcData, Order, Strategy};
+
+const CASH: f64 = 100_000.0;
+
+// long instrument 1 and short instrument 2 on `entry`, both closed at the close of `exit`
+struct PairsRoundTrip {
+    entry: usize,
+    exit: usize,
+}
+
+fn market(size: f64, instrument: u8) -> Order {
+    Order {
+        size,
+        instrument,
+        ..Default::default()
+    }
+}
+
+impl Strategy for PairsRoundTrip {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        if index == self.entry {
+            let legs = vec![
+                (market(10.0, 1), broker.data.close[index]),
+                (market(-10.0, 2), broker.data.close2[index]),
+            ];
+            broker.new_order_group(legs).expect("pairs entry rejected");
+        } else if index == self.exit {
+            broker.close_all_trades(index, index);
+        }
+    }
+}
+
+// instrument 1 trends up from 100, instrument 2 trades around 50 and falls, so marking the hedge leg
+// at the primary close would be off by thousands
+fn pairs_data(n: usize) -> OhlcData {
+    let close: Vec<f64> = (0..n).map(|i| 100.0 + 2.0 * i as f64).collect();
+    let close2: Vec<f64> = (0..n).map(|i| 50.0 - 0.5 * i as f64).collect();
+    OhlcData {
+        date: (0..n).map(|i| format!("2024-01-02 {:02}:{:02}:00", 9 + i / 60, i % 60)).collect(),
+        open: close.clone(),
+        high: close.clone(),
+        low: close.clone(),
+        close,
+        close2,
+        volume: None,
+        halts: Vec::new(),
+    }
+}
+
+fn run(entry: usize, exit: usize, n: usize) -> Backtest {
+    let mut backtest = Backtest::new(
+        pairs_data(n),
+        Box::new(PairsRoundTrip { entry, exit }),
+        CASH,
+        0.0,
+        0.0,
+        1.0,
+        false,
+        true,
+        false,
+        false,
+    );
+    backtest.run_silent();
+    backtest
+}
+
+fn realized_pnl(backtest: &Backtest) -> f64 {
+    backtest.broker.closed_trades.iter().map(|t| t.pnl()).sum()
+}
+
+// with the decimal feature every credit to cash is rounded to cash_decimals (cents)
+fn tolerance(backtest: &Backtest) -> f64 {
+    if cfg!(feature = "decimal") { 0.005 * backtest.broker.closed_trades.len() as f64 } else { 1e-6 }
+}
+
+#[test]
+fn equity_at_exit_bar_matches_realized_pnl() {
+    let backtest = run(2, 8, 12);
+    let broker = &backtest.broker;
+    assert_eq!(broker.closed_trades.len(), 2);
+    assert!(broker.closed_trades.iter().any(|t| t.instrument == 2));
+
+    // equity of the exit bar is marked before the strategy closes the trades at that bar's closes
+    let marked = broker.equity[8];
+    let expected = CASH + realized_pnl(&backtest);
+    assert!((marked - expected).abs() < tolerance(&backtest), "marked {} vs realized {}", marked, expected);
+}
+
+#[test]
+fn final_equity_matches_realized_pnl() {
+    let backtest = run(1, 10, 12);
+    let final_equity = *backtest.broker.equity.last().unwrap();
+    let expected = CASH + realized_pnl(&backtest);
+    assert!((final_equity - expected).abs() < tolerance(&backtest), "final {} vs realized {}", final_equity, expected);
+}
+
+#[test]
+fn hedge_leg_is_marked_at_close2() {
+    let backtest = run(2, 11, 12);
+    let broker = &backtest.broker;
+    // on a bar with both legs open, equity is cash plus each leg marked at its own instrument's close
+    let index = 6;
+    let unrealized: f64 = broker.closed_trades.iter()
+        .map(|t| {
+            let price = if t.instrument == 1 { backtest.data.close[index] } else { backtest.data.close2[index] };
+            t.size * (price - t.entry_price)
+        })
+        .sum();
+    assert_eq!(broker.closed_trades.len(), 2);
+    let expected = CASH + unrealized;
+    assert!((broker.equity[index] - expected).abs() < 1e-6, "equity {} vs marked legs {}", broker.equity[index], expected);
+}
@@ -0,0 +1,88 @@
+// event log: submissions, fills, closed trades and processed bars in the order they happened
+use rust_core::engine::{Backtest, Broker, OhlcData, Order, OrderError, Strategy};
+use rust_core::events::BacktestEvent;
+
+// buys on bar 1 with a 95 stop, tries an oversized order on bar 2
+struct Script;
+
+fn market(size: f64, sl: Option<f64>) -> Order {
+    Order {
+        size,
+        sl,
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+impl Strategy for Script {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        let price = broker.data.close[index];
+        match index {
+            1 => { let _ = broker.new_order(market(10.0, Some(95.0)), price); }
+            2 => { let _ = broker.new_order(market(1e9, None), price); }
+            _ => {}
+        }
+    }
+}
+
+fn backtest() -> Backtest {
+    // bar 4 dips to 90 and takes out the stop
+    let low = vec![99.0, 99.0, 99.0, 99.0, 90.0, 99.0];
+    let n = low.len();
+    let data = OhlcData {
+        date: (0..n).map(|i| format!("2024-01-02 10:{:02}:00", i)).collect(),
+        open: vec![100.0; n],
+        high: vec![101.0; n],
+        low,
+        close: vec![100.0; n],
+        close2: vec![100.0; n],
+        volume: None,
+        halts: Vec::new(),
+    };
+    Backtest::new(data, Box::new(Script), 100_000.0, 0.0, 0.0, 1.0, false, true, false, false)
+}
+
+#[test]
+fn events_follow_the_run() {
+    let mut backtest = backtest();
+    backtest.record_events();
+    backtest.run_silent();
+
+    let kinds: Vec<String> = backtest.events()
+        .filter(|e| !matches!(e, BacktestEvent::BarProcessed { .. }))
+        .map(|e| match e {
+            BacktestEvent::OrderSubmitted { index, .. } => format!("submitted {}", index),
+            BacktestEvent::OrderRejected { index, error, .. } => format!("rejected {} {:?}", index, error),
+            BacktestEvent::OrderCancelled { index, order } => format!("cancelled {} {}", index, order.id),
+            BacktestEvent::OrderModified { index, order } => format!("modified {} {}", index, order.id),
+            BacktestEvent::OrderFilled(fill) => format!("filled {} {}", fill.tick, fill.size),
+            BacktestEvent::TradeClosed(trade) => format!("closed {} {}", e.index(), trade.exit_tag.as_deref().unwrap_or("-")),
+            BacktestEvent::MarginCall { index, .. } => format!("margin call {}", index),
+            BacktestEvent::BarProcessed { .. } => unreachable!(),
+        })
+        .collect();
+    assert_eq!(kinds, vec![
+        "submitted 1".to_string(),
+        "filled 2 10".to_string(),
+        format!("rejected 2 {:?}", OrderError::MarginExceeded),
+        "filled 4 -10".to_string(),
+        "closed 4 stop-loss".to_string(),
+    ]);
+
+    // one BarProcessed per bar, in order, and every event's bar is never earlier than the previous one
+    let bars: Vec<usize> = backtest.events()
+        .filter_map(|e| match e { BacktestEvent::BarProcessed { index, .. } => Some(*index), _ => None })
+        .collect();
+    assert_eq!(bars, (0..6).collect::<Vec<_>>());
+    let indices: Vec<usize> = backtest.events().map(|e| e.index()).collect();
+    assert!(indices.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn no_events_unless_recorded() {
+    let mut backtest = backtest();
+    backtest.run_silent();
+    assert_eq!(backtest.events().count(), 0);
+}
@@ -0,0 +1,112 @@
+// exclusive entries: an accepted entry closes the book at the current close first, a rejected one leaves
+// trades, pending orders and cash as they were
+mod common;
+
+use common::{broker, live_broker, quote, FLAT};
+use rust_core::engine::{Broker, Order, OrderError};
+use rust_core::live_engine::{LiveBroker, Order as LiveOrder, OrderError as LiveOrderError};
+
+fn order(size: f64, sl: Option<f64>) -> Order {
+    Order {
+        size,
+        sl,
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+// a long of 10 with its stop loss at 90, open after bar 1
+fn book() -> Broker {
+    let mut broker = broker(&[FLAT, FLAT, FLAT]);
+    broker.exclusive_orders = true;
+    broker.next(0);
+    broker.new_order(order(10.0, Some(90.0)), 100.0).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.orders.len(), 1);
+    broker
+}
+
+fn assert_unchanged(broker: &Broker, cash: f64, trade_id: u64) {
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].id, trade_id);
+    assert_eq!(broker.orders.len(), 1);
+    assert_eq!(broker.orders[0].stop, Some(90.0));
+    assert!(broker.closed_trades.is_empty());
+    assert!(broker.cancelled_orders.is_empty());
+    assert_eq!(broker.fills.len(), 1);
+    assert_eq!(broker.cash, cash);
+}
+
+#[test]
+fn accepted_entry_closes_the_book() {
+    let mut broker = book();
+    broker.new_order(order(-10.0, None), 100.0).unwrap();
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.orders.len(), 1);
+    assert_eq!(broker.orders[0].size, -10.0);
+}
+
+#[test]
+fn rejected_entry_leaves_the_book_unchanged() {
+    let mut broker = book();
+    let cash = broker.cash;
+    let trade_id = broker.trades[0].id;
+    // 500k notional against 100k of buying power, even after the book is closed
+    assert_eq!(broker.new_order(order(5_000.0, None), 100.0), Err(OrderError::MarginExceeded));
+    assert_unchanged(&broker, cash, trade_id);
+}
+
+#[test]
+fn rejected_group_leaves_the_book_unchanged() {
+    let mut broker = book();
+    let cash = broker.cash;
+    let trade_id = broker.trades[0].id;
+    let hedge = Order { instrument: 2, ..order(-5_000.0, None) };
+    let result = broker.new_order_group(vec![(order(5.0, None), 100.0), (hedge, 100.0)]);
+    assert_eq!(result, Err(OrderError::MarginExceeded));
+    assert_unchanged(&broker, cash, trade_id);
+}
+
+fn live_order(size: f64) -> LiveOrder {
+    LiveOrder {
+        size,
+        sl: Some(90.0),
+        instrument: "A".to_string(),
+        ..Default::default()
+    }
+}
+
+// a live long of 10 on "A" with its stop loss, open after tick 0
+fn live_book() -> LiveBroker {
+    let mut broker = live_broker();
+    broker.live_exclusive_orders = true;
+    quote(&mut broker, "A", 100.0, 100.1);
+    broker.new_order(live_order(10.0), 100.1).unwrap();
+    broker.next(0);
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.orders.len(), 1);
+    broker
+}
+
+#[test]
+fn live_rejected_entry_keeps_the_account_open() {
+    let mut broker = live_book();
+    let cash = broker.live_cash;
+    assert_eq!(broker.new_order(live_order(5_000.0), 100.1), Err(LiveOrderError::MarginExceeded));
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.orders.len(), 1);
+    assert!(broker.closed_trades.is_empty());
+    assert_eq!(broker.live_cash, cash);
+}
+
+#[test]
+fn live_accepted_entry_closes_the_book() {
+    let mut broker = live_book();
+    broker.new_order(live_order(-10.0), 100.0).unwrap();
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.orders.len(), 1);
+    assert_eq!(broker.orders[0].size, -10.0);
+}
@@ -0,0 +1,59 @@
+// notional caps per instrument and on the gross book: entries still waiting to fill count like open trades,
+// and every position is valued with its instrument's contract size
+mod common;
+
+use common::{broker, FLAT};
+use rust_core::engine::{Broker, Order, OrderError};
+use rust_core::instrument::InstrumentSpec;
+
+// 100k account, instrument 1 capped at half the equity
+fn capped() -> Broker {
+    let mut broker = broker(&[FLAT; 6]);
+    broker.exposure_caps.insert(1, 0.5);
+    broker.next(0);
+    broker
+}
+
+fn buy(size: f64) -> Order {
+    Order { size, instrument: 1, ..Default::default() }
+}
+
+#[test]
+fn queued_entries_count_towards_the_cap() {
+    let mut broker = capped();
+    broker.new_order(buy(400.0), 100.0).unwrap();
+    // 40k queued and 20k more is past the 50k cap before either fills
+    assert_eq!(broker.new_order(buy(200.0), 100.0), Err(OrderError::ExposureCapExceeded));
+    assert!(broker.new_order(buy(100.0), 100.0).is_ok());
+}
+
+#[test]
+fn delayed_entries_count_towards_the_cap() {
+    let mut broker = capped();
+    broker.execution_delay = 3;
+    broker.new_order(buy(400.0), 100.0).unwrap();
+    assert_eq!(broker.delayed_orders.len(), 1);
+    assert_eq!(broker.new_order(buy(200.0), 100.0), Err(OrderError::ExposureCapExceeded));
+}
+
+#[test]
+fn the_gross_cap_counts_queued_entries_on_every_instrument() {
+    let mut broker = broker(&[FLAT; 3]);
+    broker.max_gross_exposure = Some(0.5);
+    broker.next(0);
+    broker.new_order(Order { size: -300.0, instrument: 2, ..Default::default() }, 100.0).unwrap();
+    assert_eq!(broker.new_order(buy(300.0), 100.0), Err(OrderError::ExposureCapExceeded));
+    assert!(broker.new_order(buy(200.0), 100.0).is_ok());
+}
+
+#[test]
+fn open_trades_are_valued_with_the_contract_size() {
+    let mut broker = capped();
+    broker.instrument_specs.insert(1, InstrumentSpec { contract_size: 10.0, ..InstrumentSpec::default() });
+    broker.new_order(buy(30.0), 100.0).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 1);
+    // 30 contracts of 10 units at 100 are 30k, so another 30k is past the cap
+    assert_eq!(broker.new_order(buy(30.0), 100.0), Err(OrderError::ExposureCapExceeded));
+    assert!(broker.new_order(buy(20.0), 100.0).is_ok());
+}
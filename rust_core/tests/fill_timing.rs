@@ -0,0 +1,56 @@
+// per-order fill timing: market-on-close and next-open orders mixed in one run, whatever trade_on_close says
+mod common;
+
+use rust_core::engine::{Broker, FillTiming, Order};
+
+// opens differ from the previous closes, so the fill price shows which timing was used
+fn broker(trade_on_close: bool) -> Broker {
+    let mut broker = common::broker(&[(100.0, 101.0, 99.0, 100.0), (103.0, 104.0, 102.0, 103.5), (106.0, 107.0, 105.0, 106.5)]);
+    broker.trade_on_close = trade_on_close;
+    broker
+}
+
+fn market(size: f64, timing: Option<FillTiming>) -> Order {
+    Order {
+        size,
+        instrument: 1,
+        timing,
+        ..Default::default()
+    }
+}
+
+// three orders on bar 0, filled on bar 1
+fn entry_prices(trade_on_close: bool) -> Vec<f64> {
+    let mut broker = broker(trade_on_close);
+    broker.next(0);
+    for timing in [None, Some(FillTiming::OnClose), Some(FillTiming::NextOpen)] {
+        broker.new_order(market(1.0, timing), 100.0).unwrap();
+    }
+    broker.next(1);
+    broker.trades.iter().map(|t| t.entry_price).collect()
+}
+
+#[test]
+fn timing_overrides_trade_on_close() {
+    assert_eq!(entry_prices(false), vec![103.0, 100.0, 103.0]);
+    assert_eq!(entry_prices(true), vec![100.0, 100.0, 103.0]);
+}
+
+// a market entry on bar 0 with the given execution_delay: (fill bar, fill price)
+fn delayed_fill(delay: usize) -> (usize, f64) {
+    let mut broker = broker(false);
+    broker.execution_delay = delay;
+    broker.next(0);
+    broker.new_order(market(1.0, None), 100.0).unwrap();
+    for index in 1..3 {
+        broker.next(index);
+    }
+    let trade = &broker.trades[0];
+    (trade.entry_index, trade.entry_price)
+}
+
+#[test]
+fn execution_delay_fills_at_the_open_delay_bars_later() {
+    assert_eq!(delayed_fill(1), (1, 103.0));
+    assert_eq!(delayed_fill(2), (2, 106.0));
+}
@@ -0,0 +1,98 @@
+// parameter sweeps: the parallel grid gives the same runs as the sequential one and hands every grid
+// point to the sink exactly once
+use rust_core::engine::{Backtest, Broker, OhlcData, Order, Strategy};
+use rust_core::optimize::{run_grid_2d, run_grid_2d_parallel, ResultSink};
+use rust_core::robustness::RunOutcome;
+use std::error::Error;
+
+// enters long with probability `chance` on each flat bar and exits after `hold` bars
+struct CoinFlip {
+    chance: f64,
+    hold: usize,
+}
+
+impl Strategy for CoinFlip {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        if let Some(trade) = broker.trades.first() {
+            if index >= trade.entry_index + self.hold {
+                broker.close_position(0, index);
+            }
+        } else if broker.orders.is_empty() && broker.rng.next_f64() < self.chance {
+            let order = Order {
+                size: 10.0,
+                instrument: 1,
+                ..Default::default()
+            };
+            let _ = broker.new_order(order, broker.data.close[index]);
+        }
+    }
+}
+
+// a fresh backtest seeded from its grid point, so every point is reproducible on any thread
+fn backtest(chance: f64, hold: f64) -> Backtest {
+    let n = 120;
+    let close: Vec<f64> = (0..n).map(|i| 100.0 + 5.0 * (i as f64 / 7.0).sin()).collect();
+    let data = OhlcData {
+        date: (0..n).map(|i| format!("2024-01-01 {:02}:{:02}:00", i / 60, i % 60)).collect(),
+        open: close.clone(),
+        high: close.clone(),
+        low: close.clone(),
+        close: close.clone(),
+        close2: close,
+        volume: None,
+        halts: Vec::new(),
+    };
+    let strategy = CoinFlip { chance, hold: hold as usize };
+    let mut backtest = Backtest::new(data, Box::new(strategy), 100_000.0, 0.0, 0.0, 1.0, false, true, false, false);
+    backtest.broker.set_seed((chance * 100.0) as u64 * 100 + hold as u64);
+    backtest
+}
+
+const CHANCES: [f64; 3] = [0.1, 0.3, 0.5];
+const HOLDS: [f64; 4] = [1.0, 2.0, 3.0, 5.0];
+
+#[derive(Default)]
+struct Recorded(Vec<(f64, f64)>);
+
+impl ResultSink for Recorded {
+    fn record(&mut self, param1: f64, param2: f64, _outcome: &RunOutcome) -> Result<(), Box<dyn Error>> {
+        self.0.push((param1, param2));
+        Ok(())
+    }
+}
+
+fn assert_same_run(a: &RunOutcome, b: &RunOutcome) {
+    assert_eq!(a.seed, b.seed);
+    assert_eq!(a.return_pct, b.return_pct);
+    assert_eq!(a.sharpe_ratio.to_bits(), b.sharpe_ratio.to_bits());
+    assert_eq!(a.max_drawdown_pct, b.max_drawdown_pct);
+    assert_eq!(a.num_trades, b.num_trades);
+}
+
+#[test]
+fn parallel_runs_match_the_sequential_grid() {
+    let sequential = run_grid_2d(backtest, "chance", &CHANCES, "hold", &HOLDS, 0.0);
+    let parallel = run_grid_2d_parallel(backtest, "chance", &CHANCES, "hold", &HOLDS, 0.0, 4, None);
+    assert_eq!(parallel.param1, sequential.param1);
+    assert_eq!(parallel.param2, sequential.param2);
+    for (row, expected) in parallel.outcomes.iter().zip(sequential.outcomes.iter()) {
+        assert_eq!(row.len(), HOLDS.len());
+        for (a, b) in row.iter().zip(expected.iter()) {
+            assert_same_run(a, b);
+        }
+    }
+    // the grid isn't trivially flat
+    assert!(sequential.outcomes.iter().flatten().any(|o| o.num_trades != sequential.outcomes[0][0].num_trades));
+}
+
+#[test]
+fn every_grid_point_reaches_the_sink_once() {
+    let mut sink = Recorded::default();
+    run_grid_2d_parallel(backtest, "chance", &CHANCES, "hold", &HOLDS, 0.0, 3, Some(&mut sink));
+    let mut recorded = sink.0;
+    recorded.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let expected: Vec<(f64, f64)> = CHANCES.iter().flat_map(|&c| HOLDS.iter().map(move |&h| (c, h))).collect();
+    assert_eq!(recorded, expected);
+}
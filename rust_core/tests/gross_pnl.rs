@@ -0,0 +1,44 @@
+// cost attribution: the gross pnl is the price move of the trades, and taking the commission and spread
+// of both fills off it leaves the net pnl
+mod common;
+
+use common::FLAT;
+use rust_core::engine::Order;
+use rust_core::stats::{compute_stats, Stats};
+
+// 10 units bought at the open of bar 1 at 100 and sold at the close of bar 2 at 110
+fn round_trip(commission: f64, spread: f64) -> Stats {
+    let mut broker = common::broker(&[FLAT, FLAT, (100.0, 111.0, 99.0, 110.0), FLAT]);
+    broker.commission = commission;
+    broker.bidask_spread = spread;
+    broker.next(0);
+    broker.new_order(Order { size: 10.0, instrument: 1, ..Default::default() }, 100.0).unwrap();
+    broker.next(1);
+    broker.close_position(0, 2);
+    broker.next(3);
+    let data = broker.data.clone();
+    compute_stats(&broker.closed_trades, &broker.equity, &data, 0.0, broker.max_margin_usage, &broker.gross_exposure_history(), &broker.cost_summary())
+}
+
+fn costs(stats: &Stats) -> f64 {
+    stats.total_commission + stats.total_spread_cost + stats.total_financing_cost
+}
+
+#[test]
+fn gross_less_costs_is_net_on_a_round_trip() {
+    let stats = round_trip(0.001, 0.5);
+    // 0.1% of 1000 and of 1100 notional, half a dollar per unit on both fills
+    assert!((stats.total_commission - 2.1).abs() < 1e-9, "{}", stats.total_commission);
+    assert!((stats.total_spread_cost - 10.0).abs() < 1e-9, "{}", stats.total_spread_cost);
+    assert!((stats.gross_pnl - 100.0).abs() < 1e-9, "{}", stats.gross_pnl);
+    let net = stats.equity_final - 100_000.0;
+    assert!((stats.gross_pnl - costs(&stats) - net).abs() < 1e-9, "gross {} net {}", stats.gross_pnl, net);
+}
+
+#[test]
+fn gross_is_the_same_with_and_without_costs() {
+    let free = round_trip(0.0, 0.0);
+    assert!((free.gross_pnl - 100.0).abs() < 1e-9);
+    assert_eq!(costs(&free), 0.0);
+    assert!((round_trip(0.002, 0.25).gross_pnl - free.gross_pnl).abs() < 1e-9);
+}
@@ -0,0 +1,81 @@
+// TradingEnv: every action becomes an order that fills on the next bar, the reward is the change in equity
+// and the episode ends flat on the last bar
+mod common;
+
+use common::FLAT;
+use rust_core::engine::Broker;
+use rust_core::gym::{Action, EnvConfig, TradingEnv};
+
+// opens at 100, 102, 104 and 106 on bars 1-4, each closing 1 above its open
+const BARS: [common::Bar; 5] = [
+    FLAT,
+    (100.0, 102.0, 99.0, 101.0),
+    (102.0, 104.0, 101.0, 103.0),
+    (104.0, 106.0, 103.0, 105.0),
+    (106.0, 108.0, 105.0, 107.0),
+];
+
+fn env() -> TradingEnv<impl Fn() -> Broker> {
+    let config = EnvConfig { unit_size: 10.0, window: 2, max_position: 20.0, ..EnvConfig::default() };
+    TradingEnv::new(|| common::broker(&BARS), config)
+}
+
+#[test]
+fn a_buy_fills_at_the_next_open_and_is_rewarded_by_the_close() {
+    let mut env = env();
+    env.reset();
+    let step = env.step(Action::Buy);
+    assert_eq!(env.broker.trades[0].entry_price, 100.0);
+    assert_eq!(step.reward, 10.0);
+    assert!(!step.done && !step.rejected);
+    // two returns, position over max_position and equity over starting equity
+    assert_eq!(step.observation.len(), 4);
+    assert_eq!(step.observation[2], 0.5);
+}
+
+#[test]
+fn close_exits_at_the_next_open_too() {
+    let mut env = env();
+    env.reset();
+    env.step(Action::Buy);
+    // closing on bar 1 sells at the open of bar 2, 102
+    let step = env.step(Action::Close);
+    assert!(env.broker.trades.is_empty());
+    let trade = &env.broker.closed_trades[0];
+    assert_eq!((trade.exit_index, trade.exit_price), (Some(2), Some(102.0)));
+    assert_eq!(step.reward, 10.0);
+    assert_eq!(env.position(), 0.0);
+}
+
+#[test]
+fn targets_are_clipped_to_the_maximum_position() {
+    let mut env = env();
+    env.reset();
+    env.step(Action::Target(50.0));
+    assert_eq!(env.position(), 20.0);
+}
+
+#[test]
+fn the_episode_ends_flat_on_the_last_bar() {
+    let mut env = env();
+    env.reset();
+    env.step(Action::Buy);
+    env.step(Action::Hold);
+    env.step(Action::Hold);
+    let last = env.step(Action::Hold);
+    assert!(last.done);
+    assert!(env.broker.trades.is_empty());
+    // bought at 100, flattened at the final close of 107
+    assert_eq!(env.broker.equity[4], 100_070.0);
+    assert!(env.step(Action::Buy).done);
+}
+
+#[test]
+fn reset_starts_a_fresh_episode() {
+    let mut env = env();
+    env.reset();
+    env.step(Action::Buy);
+    env.reset();
+    assert_eq!(env.index, 0);
+    assert!(env.broker.trades.is_empty() && env.broker.orders.is_empty());
+}
@@ -0,0 +1,97 @@
+// trading halts and limit moves: orders wait (or are rejected) while their instrument is halted and can
+// fill at the reopening price
+mod common;
+
+use rust_core::engine::{Broker, OhlcData, Order, OrderError};
+use rust_core::halts::{HaltKind, HaltPolicy};
+
+// bar 2 and 3 are halted; trading reopens on bar 4 with a gap down to 90
+fn data() -> OhlcData {
+    let bars = [
+        (100.0, 101.0, 99.0, 100.0),
+        (100.0, 101.0, 99.0, 100.0),
+        (99.0, 99.0, 95.0, 95.0),
+        (95.0, 95.0, 95.0, 95.0),
+        (90.0, 92.0, 89.0, 91.0),
+        (91.0, 92.0, 90.0, 91.0),
+    ];
+    let mut data = common::data(&bars);
+    assert!(data.halt_between("2024-01-02 10:02:00", "2024-01-02 10:03:00", None, HaltKind::Halt));
+    data
+}
+
+fn order(size: f64, limit: Option<f64>, sl: Option<f64>) -> Order {
+    Order {
+        size,
+        limit,
+        sl,
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+fn broker() -> Broker {
+    common::broker_with(data())
+}
+
+#[test]
+fn orders_wait_out_the_halt() {
+    let mut broker = broker();
+    broker.next(0);
+    broker.new_order(order(10.0, None, Some(97.0)), 100.0).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 1);
+    // the stop at 97 is crossed inside the halt, but can only fill once trading resumes
+    broker.next(2);
+    broker.next(3);
+    assert_eq!(broker.trades.len(), 1);
+    broker.next(4);
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.closed_trades[0].exit_index, Some(4));
+    assert_eq!(broker.closed_trades[0].exit_price, Some(90.0));
+}
+
+#[test]
+fn reject_policy_refuses_orders_during_the_halt() {
+    let mut broker = broker();
+    broker.halt_policy = HaltPolicy::Reject;
+    broker.next(0);
+    broker.next(1);
+    broker.next(2);
+    assert_eq!(broker.new_order(order(10.0, None, None), 95.0), Err(OrderError::TradingHalted));
+    broker.next(3);
+    broker.next(4);
+    assert!(broker.new_order(order(10.0, None, None), 91.0).is_ok());
+}
+
+#[test]
+fn limits_gap_to_the_reopening_price() {
+    for (gap, expected) in [(false, 94.0), (true, 90.0)] {
+        let mut broker = broker();
+        broker.gap_fills_after_halt = gap;
+        broker.next(0);
+        broker.next(1);
+        broker.new_order(order(10.0, Some(94.0), None), 100.0).unwrap();
+        for index in 2..5 {
+            broker.next(index);
+        }
+        assert_eq!(broker.trades.len(), 1);
+        assert_eq!(broker.trades[0].entry_index, 4);
+        assert_eq!(broker.trades[0].entry_price, expected);
+    }
+}
+
+#[test]
+fn limit_down_blocks_sells_only() {
+    let mut data = data();
+    data.halts.clear();
+    assert!(data.halt_between("2024-01-02 10:02:00", "2024-01-02 10:02:00", Some(1), HaltKind::LimitDown));
+    assert!(data.is_halted(2, 1, false));
+    assert!(!data.is_halted(2, 1, true));
+    assert!(!data.is_halted(2, 2, false));
+
+    // halts follow slices of the data
+    let sliced = data.slice(1..4);
+    assert!(sliced.is_halted(1, 1, false));
+    assert!(!sliced.is_halted(0, 1, false));
+}
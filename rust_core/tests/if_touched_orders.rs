@@ -0,0 +1,86 @@
+// limit-if-touched and market-if-touched entries: nothing happens until the price touches the trigger
+// level, then the order works as a limit order (lit) or fills at the level (mit)
+mod common;
+
+use common::{broker, live_broker, quote, run, FLAT};
+use rust_core::engine::Order;
+use rust_core::live_engine::Order as LiveOrder;
+
+fn if_touched(size: f64, touch: f64, limit: Option<f64>) -> Order {
+    Order {
+        size,
+        limit,
+        instrument: 1,
+        touch: Some(touch),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn market_if_touched_buy_fills_at_its_level() {
+    let broker = run(&[FLAT, FLAT, (100.0, 100.5, 96.0, 97.0), FLAT], if_touched(10.0, 97.5, None));
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].entry_index, 2);
+    assert_eq!(broker.trades[0].entry_price, 97.5);
+    // the level is the slippage reference
+    assert_eq!(broker.fills[0].reference_price, 97.5);
+    assert_eq!(broker.fills[0].slippage, 0.0);
+}
+
+#[test]
+fn market_if_touched_fills_at_the_open_after_a_gap() {
+    let broker = run(&[FLAT, FLAT, (95.0, 96.0, 94.0, 95.0)], if_touched(10.0, 97.5, None));
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].entry_price, 95.0);
+}
+
+#[test]
+fn market_if_touched_sell_waits_for_a_rally() {
+    let bars = [FLAT, (100.0, 101.0, 90.0, 92.0), (100.0, 103.0, 99.0, 102.0)];
+    let broker = run(&bars, if_touched(-10.0, 102.5, None));
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].entry_index, 2);
+    assert_eq!(broker.trades[0].entry_price, 102.5);
+}
+
+#[test]
+fn limit_if_touched_rests_as_a_limit_after_the_touch() {
+    // touched at 98 on bar 1, but the 96 limit is only reached on bar 3
+    let bars = [FLAT, (100.0, 100.0, 97.5, 99.0), (99.0, 99.5, 97.0, 98.0), (98.0, 98.0, 95.0, 96.0)];
+    let broker = run(&bars, if_touched(10.0, 98.0, Some(96.0)));
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].entry_index, 3);
+    assert_eq!(broker.trades[0].entry_price, 96.0);
+}
+
+#[test]
+fn untouched_orders_are_not_counted_as_targets() {
+    let mut broker = broker(&[FLAT, FLAT, FLAT]);
+    broker.next(0);
+    broker.new_order(if_touched(10.0, 90.0, None), 100.0).unwrap();
+    broker.next(1);
+    broker.next(2);
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.orders.len(), 1);
+    assert_eq!(broker.target_positions(), [0.0, 0.0]);
+}
+
+#[test]
+fn live_market_if_touched_fills_once_the_ask_touches() {
+    let mut broker = live_broker();
+    quote(&mut broker, "A", 100.0, 100.1);
+    let order = LiveOrder {
+        size: 10.0,
+        instrument: "A".to_string(),
+        touch: Some(99.0),
+        ..Default::default()
+    };
+    broker.new_order(order, 100.1).unwrap();
+    broker.next(0);
+    assert!(broker.trades.is_empty());
+
+    quote(&mut broker, "A", 98.8, 98.9);
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].entry_index, 1);
+}
@@ -0,0 +1,64 @@
+// intrabar equity: open trades marked at the adverse extreme of each bar, for the intrabar drawdown and
+// for margin calls that the close alone would miss
+mod common;
+
+use common::{run, FLAT};
+use rust_core::engine::{Broker, Order};
+use rust_core::stats::{compute_stats_with_config, StatsConfig};
+
+// a bar that dips to 80 and recovers to close where it opened
+const DIP: common::Bar = (100.0, 101.0, 80.0, 100.0);
+const SPIKE: common::Bar = (100.0, 120.0, 99.0, 100.0);
+
+fn entry(size: f64) -> Order {
+    Order { size, instrument: 1, ..Default::default() }
+}
+
+#[test]
+fn longs_are_marked_at_the_low_and_shorts_at_the_high() {
+    let long = run(&[FLAT, FLAT, DIP], entry(500.0));
+    assert_eq!(long.equity[2], 100_000.0);
+    assert_eq!(long.intrabar_equity[2], 90_000.0);
+
+    let short = run(&[FLAT, FLAT, SPIKE], entry(-500.0));
+    assert_eq!(short.equity[2], 100_000.0);
+    assert_eq!(short.intrabar_equity[2], 90_000.0);
+}
+
+#[test]
+fn intrabar_equity_is_never_above_the_close() {
+    // the low of the entry bar is still below the close of a rally
+    let broker = run(&[FLAT, FLAT, (100.0, 111.0, 99.0, 110.0)], entry(100.0));
+    assert_eq!(broker.equity[2], 101_000.0);
+    assert_eq!(broker.intrabar_equity[2], 99_900.0);
+    assert!(broker.intrabar_equity.iter().zip(broker.equity.iter()).all(|(w, e)| w <= e));
+}
+
+#[test]
+fn the_intrabar_drawdown_sees_the_dip_the_close_recovered_from() {
+    let broker = run(&[FLAT, FLAT, DIP, FLAT], entry(500.0));
+    let config = StatsConfig { intrabar_equity: broker.intrabar_equity.clone(), ..StatsConfig::default() };
+    let stats = compute_stats_with_config(
+        &broker.closed_trades, &broker.equity, &broker.data, 0.0, broker.max_margin_usage,
+        &broker.gross_exposure_history(), &broker.cost_summary(), &config,
+    );
+    assert_eq!(stats.max_drawdown_pct, 0.0);
+    assert!((stats.max_intrabar_drawdown_pct + 10.0).abs() < 1e-9, "{}", stats.max_intrabar_drawdown_pct);
+}
+
+#[test]
+fn a_leveraged_book_is_margin_called_on_an_intrabar_dip() {
+    let mut broker: Broker = common::broker(&[FLAT, FLAT, DIP, FLAT]);
+    broker.margin = 0.5;
+    broker.quiet = true;
+    broker.next(0);
+    // 150k of notional on 200k of buying power: 75% usage at every close
+    broker.new_order(entry(1_500.0), 100.0).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 1);
+    // at the low, 70k of equity carries 140k: over 100%
+    broker.next(2);
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.closed_trades[0].exit_index, Some(2));
+    assert_eq!(broker.closed_trades[0].exit_price, Some(100.0));
+}
@@ -0,0 +1,80 @@
+// triple-barrier labels follow the broker: entry at the next open, a stop at the open of the bar that hit
+// it, a target at its price, and the stop first on a bar that reaches both unless it opened through the target
+mod common;
+
+use common::{data, run, Bar, FLAT};
+use rust_core::engine::Order;
+use rust_core::util::{barrier_labels, horizon_labels};
+use rust_ml::labels::{Barrier, BarrierLabel, TripleBarrier};
+
+// 5% target and stop around an entry at the open of bar 1 (100)
+fn config(side: f64) -> TripleBarrier {
+    TripleBarrier { profit_target: 0.05, stop_loss: 0.05, max_holding: 3, side, trade_on_close: false }
+}
+
+// label of a signal on bar 0 with `bar` following the entry bar
+fn label(bar: Bar, side: f64) -> BarrierLabel {
+    barrier_labels(&data(&[FLAT, FLAT, bar]), &config(side))[0].clone().unwrap()
+}
+
+fn exit(label: &BarrierLabel) -> (Barrier, usize, f64) {
+    (label.barrier, label.exit_index, label.exit_price)
+}
+
+#[test]
+fn the_target_fills_at_its_price() {
+    let label = label((100.0, 106.0, 99.0, 104.0), 1.0);
+    assert_eq!(exit(&label), (Barrier::ProfitTarget, 2, 105.0));
+    assert_eq!(label.class(), 1);
+    assert!((label.ret - 0.05).abs() < 1e-12);
+}
+
+#[test]
+fn the_stop_fills_at_the_open_of_the_bar_that_hit_it() {
+    let label = label((98.0, 101.0, 94.0, 96.0), 1.0);
+    assert_eq!(exit(&label), (Barrier::StopLoss, 2, 98.0));
+    assert_eq!(label.class(), -1);
+}
+
+#[test]
+fn a_bar_through_both_barriers_stops_out() {
+    assert_eq!(exit(&label((100.0, 106.0, 94.0, 100.0), 1.0)), (Barrier::StopLoss, 2, 100.0));
+}
+
+#[test]
+fn a_bar_that_opens_through_the_target_takes_profit() {
+    assert_eq!(exit(&label((107.0, 108.0, 94.0, 100.0), 1.0)), (Barrier::ProfitTarget, 2, 105.0));
+    assert_eq!(exit(&label((93.0, 106.0, 92.0, 100.0), -1.0)), (Barrier::ProfitTarget, 2, 95.0));
+}
+
+#[test]
+fn labels_agree_with_the_broker_on_a_bar_through_both_barriers() {
+    for bar in [(100.0, 106.0, 94.0, 100.0), (107.0, 108.0, 94.0, 100.0)] {
+        let broker = run(&[FLAT, FLAT, bar], Order { size: 10.0, sl: Some(95.0), tp: Some(105.0), instrument: 1, ..Default::default() });
+        assert_eq!(broker.closed_trades[0].exit_price, Some(label(bar, 1.0).exit_price), "bar {:?}", bar);
+    }
+}
+
+#[test]
+fn short_labels_are_mirrored() {
+    let label = label((100.0, 101.0, 94.0, 96.0), -1.0);
+    assert_eq!(exit(&label), (Barrier::ProfitTarget, 2, 95.0));
+    assert!((label.ret - 0.05).abs() < 1e-12);
+}
+
+#[test]
+fn unresolved_trades_time_out_or_are_left_unlabelled() {
+    let labels = barrier_labels(&data(&[FLAT; 6]), &config(1.0));
+    // entry on bar 1, time exit at the close of bar 4
+    let first = labels[0].as_ref().unwrap();
+    assert_eq!(exit(first), (Barrier::Timeout, 4, 100.0));
+    assert_eq!(first.class(), 0);
+    assert!(labels[1].is_some());
+    assert!(labels[2..].iter().all(|l| l.is_none()));
+}
+
+#[test]
+fn fixed_horizon_labels_compare_the_close_ahead() {
+    let closes: Vec<Bar> = [100.0, 102.0, 100.5, 99.0].iter().map(|&c| (c, c, c, c)).collect();
+    assert_eq!(horizon_labels(&data(&closes), 1, 0.01), vec![Some(1), Some(-1), Some(-1), None]);
+}
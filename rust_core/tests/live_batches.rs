@@ -0,0 +1,40 @@
+// a batch of quotes from the stream is processed one tick at a time: the strategy on each tick sees that
+// tick's quote as current, not the last quote of the batch
+use rust_core::live_engine::{LiveBacktest, LiveBroker, LiveData, LiveStrategy, TickSnapshot};
+use std::sync::{Arc, Mutex};
+
+// records the current bid of "A" on every tick
+struct CurrentBid {
+    seen: Arc<Mutex<Vec<f64>>>,
+}
+
+impl LiveStrategy for CurrentBid {
+    fn init(&mut self, _broker: &mut LiveBroker, _data: &LiveData) {}
+
+    fn next(&mut self, broker: &mut LiveBroker, _index: usize) {
+        let bid = broker.live_data.current.get("A").map(|tick| tick.bid).unwrap_or(f64::NAN);
+        self.seen.lock().unwrap().push(bid);
+    }
+}
+
+fn quote(bid: f64, second: usize) -> TickSnapshot {
+    let date = format!("2024-01-02T10:00:{:02}Z", second);
+    TickSnapshot { instrument: "A".to_string(), date, bid, ask: bid + 1.0 }
+}
+
+#[test]
+fn each_tick_of_a_batch_is_current_when_it_is_processed() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let data = LiveData { ticks: Vec::new(), current: Default::default(), events: Vec::new() };
+    let strategy = Box::new(CurrentBid { seen: seen.clone() });
+    let mut backtest = LiveBacktest::new(data, strategy, 100_000.0, 1.0, false, true, false, false);
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let batch = vec![quote(100.0, 0), quote(101.0, 1), quote(102.0, 2)];
+    tx.send(LiveData { ticks: batch, current: Default::default(), events: Vec::new() }).unwrap();
+    drop(tx);
+    tokio::runtime::Runtime::new().unwrap().block_on(backtest.run(rx));
+
+    assert_eq!(*seen.lock().unwrap(), vec![100.0, 101.0, 102.0]);
+    assert_eq!(backtest.broker.live_data.current["A"].bid, 102.0);
+}
@@ -0,0 +1,54 @@
+// live fills cross the spread: buys take the ask, sells the bid, and open trades are marked and closed
+// on the opposite side
+mod common;
+
+use common::{live_broker, quote};
+use rust_core::live_engine::{LiveBroker, Order};
+
+// one order on "A" quoted 100 / 101, filled on tick 0
+fn filled(size: f64) -> LiveBroker {
+    let mut broker = live_broker();
+    quote(&mut broker, "A", 100.0, 101.0);
+    let price = if size > 0.0 { 101.0 } else { 100.0 };
+    broker.new_order(Order { size, instrument: "A".to_string(), ..Default::default() }, price).unwrap();
+    broker.next(0);
+    assert_eq!(broker.trades.len(), 1);
+    broker
+}
+
+#[test]
+fn buys_fill_at_the_ask() {
+    let broker = filled(10.0);
+    assert_eq!(broker.trades[0].entry_price, 101.0);
+    let fill = &broker.fills[0];
+    assert_eq!(fill.fill_price, 101.0);
+    assert_eq!(fill.spread, 1.0);
+    assert_eq!(fill.signal_mid, 100.5);
+    // half the spread is paid on entry
+    assert_eq!(fill.slippage, 0.5);
+}
+
+#[test]
+fn sells_fill_at_the_bid() {
+    let broker = filled(-10.0);
+    assert_eq!(broker.trades[0].entry_price, 100.0);
+    assert_eq!(broker.fills[0].slippage, 0.5);
+}
+
+#[test]
+fn longs_are_marked_and_closed_at_the_bid() {
+    let mut broker = filled(10.0);
+    assert_eq!(broker.unrealized_pnl(&broker.trades[0]), -10.0);
+    broker.close_position(0, 1);
+    assert_eq!(broker.closed_trades[0].exit_price, Some(100.0));
+    assert_eq!(broker.live_cash, 99_990.0);
+}
+
+#[test]
+fn shorts_are_marked_and_closed_at_the_ask() {
+    let mut broker = filled(-10.0);
+    assert_eq!(broker.unrealized_pnl(&broker.trades[0]), -10.0);
+    broker.close_position(0, 1);
+    assert_eq!(broker.closed_trades[0].exit_price, Some(101.0));
+    assert_eq!(broker.live_cash, 99_990.0);
+}
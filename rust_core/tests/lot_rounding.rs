@@ -0,0 +1,86 @@
+// InstrumentSpec::round_size and where the broker applies it: after every size adjustment
+mod common;
+
+use common::{broker_with, data, FLAT};
+use rust_core::engine::{Broker, Order, OrderError};
+use rust_core::instrument::{InstrumentSpec, SizeError, SizeRounding};
+
+fn spec(lot_step: f64, min_size: f64, rounding: SizeRounding) -> InstrumentSpec {
+    InstrumentSpec { lot_step, min_size, rounding, ..InstrumentSpec::default() }
+}
+
+#[test]
+fn reject_refuses_partial_lots() {
+    let whole = spec(1.0, 0.0, SizeRounding::Reject);
+    assert_eq!(whole.round_size(3.0), Ok(3.0));
+    assert_eq!(whole.round_size(-3.0), Ok(-3.0));
+    assert_eq!(whole.round_size(1.5), Err(SizeError::NotWholeLot));
+}
+
+#[test]
+fn down_rounds_towards_zero() {
+    let whole = spec(1.0, 0.0, SizeRounding::Down);
+    assert_eq!(whole.round_size(1.9), Ok(1.0));
+    assert_eq!(whole.round_size(-1.9), Ok(-1.0));
+    assert_eq!(spec(0.5, 0.0, SizeRounding::Down).round_size(1.4), Ok(1.0));
+}
+
+#[test]
+fn nearest_rounds_to_the_closest_lot() {
+    let whole = spec(1.0, 0.0, SizeRounding::Nearest);
+    assert_eq!(whole.round_size(1.6), Ok(2.0));
+    assert_eq!(whole.round_size(-1.4), Ok(-1.0));
+}
+
+#[test]
+fn float_noise_near_a_lot_multiple_is_a_whole_lot() {
+    let tenths = spec(0.1, 0.0, SizeRounding::Reject);
+    let size = tenths.round_size(0.1 + 0.2).unwrap();
+    assert!((size - 0.3).abs() < 1e-12, "size {}", size);
+    assert!(tenths.round_size(0.7 * 3.0).is_ok());
+    assert_eq!(tenths.round_size(0.35), Err(SizeError::NotWholeLot));
+    // down rounding doesn't lose a lot to noise just below the multiple
+    let size = spec(0.1, 0.0, SizeRounding::Down).round_size(0.3 - 1e-12).unwrap();
+    assert!((size - 0.3).abs() < 1e-12, "size {}", size);
+}
+
+#[test]
+fn sizes_below_the_minimum_are_refused() {
+    let fives = spec(1.0, 5.0, SizeRounding::Down);
+    assert_eq!(fives.round_size(5.0), Ok(5.0));
+    assert_eq!(fives.round_size(-6.5), Ok(-6.0));
+    assert_eq!(fives.round_size(5.9 - 1.0), Err(SizeError::BelowMinimum));
+    // rounding a non-zero size to zero is refused even without a minimum
+    assert_eq!(spec(1.0, 0.0, SizeRounding::Down).round_size(0.5), Err(SizeError::BelowMinimum));
+    assert_eq!(fives.round_size(0.0), Ok(0.0));
+}
+
+#[test]
+fn zero_lot_step_allows_any_size() {
+    assert_eq!(spec(0.0, 0.0, SizeRounding::Reject).round_size(1.2345), Ok(1.2345));
+}
+
+// instrument 2 at a third of the primary price: a hedge of 10 primary units is 33.3 units
+fn hedge_broker() -> Broker {
+    let mut data = data(&[FLAT, FLAT]);
+    data.close2 = vec![30.0; 2];
+    let mut broker = broker_with(data);
+    broker.next(0);
+    broker
+}
+
+#[test]
+fn the_hedge_ratio_is_applied_before_rounding() {
+    let mut broker = hedge_broker();
+    broker.instrument_specs.insert(2, spec(1.0, 0.0, SizeRounding::Down));
+    broker.new_order(Order { size: 10.0, instrument: 2, ..Default::default() }, 30.0).unwrap();
+    assert_eq!(broker.orders[0].size, 33.0);
+}
+
+#[test]
+fn a_converted_hedge_of_partial_lots_is_rejected() {
+    let mut broker = hedge_broker();
+    let result = broker.new_order(Order { size: 10.0, instrument: 2, ..Default::default() }, 30.0);
+    assert_eq!(result, Err(OrderError::FractionalOrderNotAllowed));
+    assert!(broker.orders.is_empty());
+}
@@ -0,0 +1,69 @@
+// run manifests: the broker settings a run depends on survive capture, save and from_manifest
+use rust_core::data_handler::handle_ohlc;
+use rust_core::engine::{Backtest, CashFlow};
+use rust_core::instrument::{InstrumentSpec, RolloverSchedule, SizeRounding};
+use rust_core::manifest::RunManifest;
+use rust_core::risk::MarginMode;
+use rust_core::strategies::simple_strategy::SimpleStrategy;
+
+fn temp(name: &str) -> String {
+    std::env::temp_dir().join(name).to_string_lossy().to_string()
+}
+
+fn configured(data_path: &str) -> Backtest {
+    let data = handle_ohlc(data_path).unwrap();
+    let mut backtest = Backtest::new(data, Box::new(SimpleStrategy::new()), 100_000.0, 0.001, 0.0, 0.5, false, true, false, false);
+    backtest.broker.maker_commission = -0.0002;
+    backtest.broker.margin_mode = MarginMode::Netted { offset_rate: 0.25 };
+    backtest.broker.instrument_specs.insert(2, InstrumentSpec {
+        lot_step: 0.1,
+        rounding: SizeRounding::Nearest,
+        rollover: Some(RolloverSchedule::index_cfd(0.05, -0.01)),
+        ..InstrumentSpec::default()
+    });
+    backtest.broker.set_cash_flows(vec![CashFlow::new("2024-01-03", 5_000.0)]);
+    backtest
+}
+
+#[test]
+fn broker_settings_are_restored_from_the_manifest() {
+    let data_path = temp("rust_bt_manifest_test.csv");
+    std::fs::write(&data_path, "date,open,high,low,close,close2\n\
+        2024-01-02,100,101,99,100,50\n\
+        2024-01-03,100,101,99,100,50\n\
+        2024-01-04,100,101,99,100,50\n").unwrap();
+    let manifest_path = temp("rust_bt_manifest_test.json");
+    RunManifest::capture(&configured(&data_path), "simple", Vec::new(), &data_path).unwrap().save(&manifest_path).unwrap();
+
+    let restored = Backtest::from_manifest(&manifest_path).unwrap();
+    let broker = &restored.broker;
+    assert_eq!(broker.commission, 0.001);
+    assert_eq!(broker.maker_commission, -0.0002);
+    assert_eq!(broker.margin_mode, MarginMode::Netted { offset_rate: 0.25 });
+    let spec = &broker.instrument_specs[&2];
+    assert_eq!((spec.lot_step, spec.rounding), (0.1, SizeRounding::Nearest));
+    let rollover = spec.rollover.as_ref().unwrap();
+    assert_eq!((rollover.timezone, rollover.long_rate, rollover.short_rate), (chrono_tz::America::New_York, 0.05, -0.01));
+    assert_eq!(broker.cash_flows.len(), 1);
+    assert_eq!((broker.cash_flows[0].date.as_str(), broker.cash_flows[0].amount), ("2024-01-03", 5_000.0));
+
+    let _ = std::fs::remove_file(&manifest_path);
+    let _ = std::fs::remove_file(&data_path);
+}
+
+#[test]
+fn manifests_without_the_newer_settings_still_load() {
+    let data_path = temp("rust_bt_manifest_compat_test.csv");
+    std::fs::write(&data_path, "date,open,high,low,close\n2024-01-02,100,101,99,100\n").unwrap();
+    let mut json = serde_json::to_value(RunManifest::capture(&configured(&data_path), "simple", Vec::new(), &data_path).unwrap()).unwrap();
+    let broker = json["broker"].as_object_mut().unwrap();
+    for key in ["maker_commission", "margin_mode", "instrument_specs", "cash_flows"] {
+        broker.remove(key);
+    }
+    let manifest: RunManifest = serde_json::from_value(json).unwrap();
+    assert_eq!(manifest.broker.maker_commission, None);
+    assert_eq!(manifest.broker.margin_mode, MarginMode::Gross);
+    assert!(manifest.broker.instrument_specs.is_empty() && manifest.broker.cash_flows.is_empty());
+
+    let _ = std::fs::remove_file(&data_path);
+}
@@ -0,0 +1,78 @@
+// aligning a secondary price series with gaps: what each MissingDataPolicy fills, what it drops and
+// what the gap report counts
+mod common;
+
+use common::{data, FLAT};
+use rust_core::data_handler::{align_secondary, read_ohlc, GapReport, MissingDataPolicy};
+
+fn align(secondary: &[Option<f64>], policy: MissingDataPolicy) -> (Vec<String>, Vec<f64>, GapReport) {
+    let bars = data(&vec![FLAT; secondary.len()]);
+    let (aligned, report) = align_secondary(&bars, secondary, policy);
+    assert_eq!(aligned.close.len(), aligned.close2.len());
+    assert_eq!(aligned.date.len(), aligned.close2.len());
+    (aligned.date, aligned.close2, report)
+}
+
+#[test]
+fn carry_keeps_every_row() {
+    let (dates, close2, report) = align(&[None, Some(2.0), None, None, Some(5.0)], MissingDataPolicy::Carry);
+    assert_eq!(dates.len(), 5);
+    assert_eq!(close2, vec![2.0, 2.0, 2.0, 2.0, 5.0]);
+    assert_eq!((report.rows, report.missing, report.filled, report.dropped), (5, 3, 3, 0));
+    assert_eq!(report.gaps.len(), 2);
+    assert_eq!(report.longest_gap(), 2);
+}
+
+#[test]
+fn default_policy_keeps_every_row() {
+    assert_eq!(MissingDataPolicy::default(), MissingDataPolicy::Carry);
+}
+
+#[test]
+fn forward_fill_drops_beyond_max_staleness() {
+    let policy = MissingDataPolicy::ForwardFill { max_staleness: 1 };
+    let (dates, close2, report) = align(&[None, Some(1.0), None, None, Some(4.0)], policy);
+    // the leading gap has nothing to carry and the second missing bar is too stale
+    assert_eq!(close2, vec![1.0, 1.0, 4.0]);
+    assert_eq!(dates, vec!["2024-01-02 10:01:00", "2024-01-02 10:02:00", "2024-01-02 10:04:00"]);
+    assert_eq!((report.missing, report.filled, report.dropped), (3, 1, 2));
+    assert_eq!(report.gaps[1], ("2024-01-02 10:02:00".to_string(), "2024-01-02 10:03:00".to_string(), 2));
+}
+
+#[test]
+fn interpolate_fills_between_prices_and_drops_the_edges() {
+    let (dates, close2, report) = align(&[None, Some(1.0), None, None, Some(4.0), None], MissingDataPolicy::Interpolate);
+    assert_eq!(close2, vec![1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(dates.first().unwrap(), "2024-01-02 10:01:00");
+    assert_eq!(dates.last().unwrap(), "2024-01-02 10:04:00");
+    assert_eq!((report.missing, report.filled, report.dropped), (4, 2, 2));
+}
+
+#[test]
+fn drop_removes_every_missing_row() {
+    let (dates, close2, report) = align(&[Some(1.0), None, Some(3.0)], MissingDataPolicy::Drop);
+    assert_eq!(close2, vec![1.0, 3.0]);
+    assert_eq!(dates, vec!["2024-01-02 10:00:00", "2024-01-02 10:02:00"]);
+    assert_eq!((report.missing, report.filled, report.dropped), (1, 0, 1));
+}
+
+#[test]
+fn a_series_missing_entirely_is_left_as_zeros() {
+    let (dates, close2, report) = align(&[None, None, None], MissingDataPolicy::Drop);
+    assert_eq!(dates.len(), 3);
+    assert_eq!(close2, vec![0.0; 3]);
+    assert_eq!(report.missing, 0);
+}
+
+#[test]
+fn blank_close2_fields_are_carried_when_reading_a_csv() {
+    let csv = "date,open,high,low,close,close2\n\
+        2024-01-02,1,1,1,1,\n\
+        2024-01-03,1,1,1,1,10\n\
+        2024-01-04,1,1,1,1,\n\
+        2024-01-05,1,1,1,1,12\n";
+    let (data, report) = read_ohlc(csv.as_bytes(), MissingDataPolicy::default()).unwrap();
+    assert_eq!(data.date.len(), 4);
+    assert_eq!(data.close2, vec![10.0, 10.0, 10.0, 12.0]);
+    assert_eq!((report.missing, report.dropped), (2, 0));
+}
@@ -0,0 +1,77 @@
+// MarginMode::Netted: offsetting long and short notional is charged at offset_rate, and an order that
+// first offsets the book and then adds to the other side still has to fit cash / margin
+mod common;
+
+use common::{broker, live_broker, quote, FLAT};
+use rust_core::engine::{Broker, Order, OrderError};
+use rust_core::live_engine::{LiveBroker, Order as LiveOrder, OrderError as LiveOrderError};
+use rust_core::risk::MarginMode;
+
+fn leg(size: f64, instrument: u8) -> (Order, f64) {
+    (Order { size, instrument, ..Default::default() }, 100.0)
+}
+
+// a pair of long and short legs on a 100k account without leverage, filled on bar 1
+fn pair(offset_rate: f64, long: f64, short: f64) -> Broker {
+    let mut broker = broker(&[FLAT, FLAT, FLAT]);
+    broker.margin_mode = MarginMode::Netted { offset_rate };
+    broker.next(0);
+    broker.new_order_group(vec![leg(long, 1), leg(short, 2)]).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 2);
+    broker
+}
+
+#[test]
+fn a_fully_offsetting_leg_is_charged_the_offset_rate() {
+    // 10k long against 10k short: both legs at a quarter of their notional
+    let broker = pair(0.25, 100.0, -100.0);
+    assert_eq!(broker.current_exposure(), 5_000.0);
+    assert_eq!(broker.available_buying_power(), 95_000.0);
+}
+
+#[test]
+fn a_reducing_order_cannot_push_exposure_past_cash_over_margin() {
+    // 150k long against 100k short leaves 50k margined and 50k of buying power
+    let mut broker = pair(0.0, 1_500.0, -1_000.0);
+    assert_eq!(broker.current_exposure(), 50_000.0);
+    // offsets the 50k long remainder and adds 110k short: 110k margined
+    let (order, price) = leg(-1_600.0, 2);
+    assert_eq!(broker.new_order(order, price), Err(OrderError::MarginExceeded));
+    // offsets it and adds exactly 100k short
+    let (order, price) = leg(-1_500.0, 2);
+    assert!(broker.new_order(order, price).is_ok());
+}
+
+fn live_order(size: f64, instrument: &str) -> LiveOrder {
+    LiveOrder { size, instrument: instrument.to_string(), ..Default::default() }
+}
+
+// the same pair on the live broker, short leg first so the long can offset it
+fn live_pair(offset_rate: f64, long: f64, short: f64) -> LiveBroker {
+    let mut broker = live_broker();
+    broker.margin_mode = MarginMode::Netted { offset_rate };
+    quote(&mut broker, "A", 100.0, 100.0);
+    quote(&mut broker, "B", 100.0, 100.0);
+    broker.new_order(live_order(short, "B"), 100.0).unwrap();
+    broker.next(0);
+    broker.new_order(live_order(long, "A"), 100.0).unwrap();
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 2);
+    broker
+}
+
+#[test]
+fn live_fully_offsetting_leg_is_charged_the_offset_rate() {
+    let broker = live_pair(0.25, 100.0, -100.0);
+    assert_eq!(broker.current_exposure(), 5_000.0);
+    assert_eq!(broker.available_buying_power(), 95_000.0);
+}
+
+#[test]
+fn live_reducing_order_cannot_push_exposure_past_cash_over_margin() {
+    let mut broker = live_pair(0.0, 1_500.0, -1_000.0);
+    assert_eq!(broker.current_exposure(), 50_000.0);
+    assert_eq!(broker.new_order(live_order(-1_600.0, "B"), 100.0), Err(LiveOrderError::MarginExceeded));
+    assert!(broker.new_order(live_order(-1_500.0, "B"), 100.0).is_ok());
+}
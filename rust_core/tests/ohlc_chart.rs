@@ -0,0 +1,87 @@
+// candle chart with indicator overlays and fill markers, for debugging strategies without leaving rust
+use rust_core::engine::{Backtest, OhlcData};
+use rust_core::indicators::ema;
+use rust_core::plot::{plot_ohlc, ChartMarker, Overlay};
+use rust_core::strategies::bollinger_reversion::BollingerReversionStrategy;
+
+// a swinging market with real candle bodies and wicks
+fn swings(n: usize) -> OhlcData {
+    let close: Vec<f64> = (0..n).map(|i| 100.0 + 5.0 * (i as f64 / 6.0).sin() + 0.3 * (i % 3) as f64).collect();
+    let open: Vec<f64> = (0..n).map(|i| if i == 0 { close[0] } else { close[i - 1] }).collect();
+    OhlcData {
+        date: (0..n).map(|i| format!("2024-01-{:02} {:02}:{:02}:00", 2 + i / 480, 9 + (i / 60) % 8, i % 60)).collect(),
+        high: (0..n).map(|i| open[i].max(close[i]) + 0.4).collect(),
+        low: (0..n).map(|i| open[i].min(close[i]) - 0.4).collect(),
+        close2: close.clone(),
+        open,
+        close,
+        volume: None,
+        halts: Vec::new(),
+    }
+}
+
+fn output(name: &str) -> String {
+    std::env::temp_dir().join(name).to_string_lossy().to_string()
+}
+
+#[test]
+fn ema_is_seeded_with_the_sma() {
+    let values = ema(&[2.0, 4.0, 6.0, 8.0], 3);
+    assert!(values[1].is_nan());
+    assert_eq!(values[2], 4.0);
+    // alpha = 0.5
+    assert_eq!(values[3], 6.0);
+}
+
+#[test]
+fn bollinger_overlay_has_three_lines() {
+    let data = swings(50);
+    let lines = Overlay::Bollinger(20, 2.0).lines(&data.close);
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line.len() == 50 && line[18].is_nan() && line[19].is_finite()));
+    assert!(lines[1][30] > lines[0][30] && lines[2][30] < lines[0][30]);
+    assert_eq!(Overlay::Ema(10).label(), "ema 10");
+}
+
+#[test]
+fn chart_is_written_with_overlays_and_markers() {
+    let data = swings(200);
+    let markers = vec![
+        ChartMarker { index: 30, price: data.low[30], buy: true },
+        ChartMarker { index: 45, price: data.high[45], buy: false },
+        // outside the range: skipped
+        ChartMarker { index: 150, price: 1000.0, buy: true },
+    ];
+    let overlays = [
+        Overlay::Sma(10),
+        Overlay::Ema(10),
+        Overlay::Bollinger(20, 2.0),
+        Overlay::Series("flat".to_string(), vec![100.0; 200]),
+    ];
+    let path = output("rust_core_test_ohlc.png");
+    plot_ohlc(&data, 0..120, &overlays, &markers, Some("test"), &path).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() > 0);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn backtest_marks_its_fills() {
+    let mut backtest = Backtest::new(
+        swings(300),
+        Box::new(BollingerReversionStrategy::new()),
+        100_000.0,
+        0.0,
+        0.0,
+        1.0,
+        false,
+        true,
+        false,
+        false,
+    );
+    backtest.run_silent();
+    assert!(!backtest.broker.fills.is_empty());
+    let path = output("rust_core_test_backtest_ohlc.png");
+    backtest.plot_ohlc(0..300, &[Overlay::Bollinger(20, 2.0)], &path).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() > 0);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,63 @@
+// multi-leg entries are all or nothing: a rejected leg leaves no order, order id or perturbation draw behind
+mod common;
+
+use common::FLAT;
+use rust_core::engine::{Broker, Order, OrderError, Perturbation};
+
+fn broker() -> Broker {
+    let mut broker = common::broker(&[FLAT, FLAT, FLAT]);
+    broker.quiet = true;
+    broker.next(0);
+    broker
+}
+
+fn leg(size: f64, instrument: u8) -> (Order, f64) {
+    (Order { size, instrument, ..Default::default() }, 100.0)
+}
+
+#[test]
+fn every_leg_is_queued_together() {
+    let mut broker = broker();
+    let ids = broker.new_order_group(vec![leg(10.0, 1), leg(-10.0, 2)]).unwrap();
+    assert_eq!(ids.len(), 2);
+    assert_eq!(broker.order(ids[0]).unwrap().instrument, 1);
+    assert_eq!(broker.order(ids[1]).unwrap().instrument, 2);
+}
+
+#[test]
+fn a_rejected_second_leg_queues_nothing_and_consumes_no_id() {
+    let mut broker = broker();
+    let first = broker.new_order(leg(10.0, 1).0, 100.0).unwrap();
+    // a partial lot without leverage
+    let result = broker.new_order_group(vec![leg(10.0, 1), leg(-10.5, 2)]);
+    assert_eq!(result, Err(OrderError::FractionalOrderNotAllowed));
+    assert_eq!(broker.orders.len(), 1);
+    assert_eq!(broker.rejected_orders.len(), 1);
+    // the next accepted order takes the id the rejected first leg would have had
+    assert_eq!(broker.new_order(leg(5.0, 1).0, 100.0), Ok(first + 1));
+}
+
+#[test]
+fn a_rejected_group_consumes_no_perturbation_draw() {
+    let perturbed = || {
+        let mut broker = broker();
+        broker.perturbation = Some(Perturbation::new(3, 0, 0.01, 7));
+        broker
+    };
+    let mut rejected = perturbed();
+    rejected.new_order_group(vec![leg(10.0, 1), leg(-10.5, 2)]).unwrap_err();
+    let mut untouched = perturbed();
+    let next = |broker: &mut Broker| broker.perturbation.as_mut().unwrap().rng.next_u64();
+    assert_eq!(next(&mut rejected), next(&mut untouched));
+}
+
+#[test]
+fn buying_power_is_checked_for_the_legs_together() {
+    // 60k of notional per leg fits the 100k account on its own, 120k for the pair doesn't
+    let mut broker = broker();
+    let result = broker.new_order_group(vec![leg(600.0, 1), leg(-600.0, 2)]);
+    assert_eq!(result, Err(OrderError::MarginExceeded));
+    assert!(broker.orders.is_empty());
+
+    assert!(broker.new_order(leg(600.0, 1).0, 100.0).is_ok());
+}
@@ -0,0 +1,147 @@
+// stable order and trade ids: resting orders can be looked up, cancelled and amended by id, and a trade
+// keeps the id of the order that opened it
+mod common;
+
+use common::FLAT;
+use rust_core::engine::{Broker, Order, OrderError};
+use rust_core::events::BacktestEvent;
+
+fn broker(bars: usize) -> Broker {
+    let mut broker = common::broker(&vec![FLAT; bars]);
+    broker.quiet = true;
+    broker
+}
+
+fn order(size: f64, limit: Option<f64>, sl: Option<f64>) -> Order {
+    Order {
+        size,
+        limit,
+        sl,
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn trades_keep_the_id_of_their_entry_order() {
+    let mut broker = broker(4);
+    broker.next(0);
+    let first = broker.new_order(order(10.0, None, None), 100.0).unwrap();
+    let second = broker.new_order(order(5.0, None, None), 100.0).unwrap();
+    assert!(first > 0 && second > first);
+    assert_eq!(broker.order(second).unwrap().size, 5.0);
+
+    broker.next(1);
+    assert_eq!(broker.trade(first).unwrap().size, 10.0);
+    assert_eq!(broker.trade_index(second), Some(1));
+
+    // closing the first trade shifts the second to position 0, its id stays
+    broker.close_position(0, 2);
+    assert_eq!(broker.trade_index(second), Some(0));
+    assert!(broker.trade(first).is_none());
+    assert_eq!(broker.closed_trades[0].id, first);
+}
+
+#[test]
+fn cancel_removes_a_resting_order() {
+    let mut broker = broker(3);
+    broker.next(0);
+    let id = broker.new_order(order(10.0, Some(95.0), None), 100.0).unwrap();
+    let cancelled = broker.cancel_order(id).unwrap();
+    assert_eq!(cancelled.limit, Some(95.0));
+    assert!(broker.order(id).is_none());
+    assert_eq!(broker.cancelled_orders.last().map(|(_, order)| order.id), Some(id));
+    assert!(matches!(broker.cancel_order(id), Err(OrderError::UnknownOrder)));
+}
+
+#[test]
+fn moved_limit_fills_at_the_new_level() {
+    let mut broker = broker(4);
+    broker.next(0);
+    let id = broker.new_order(order(10.0, Some(95.0), None), 100.0).unwrap();
+    broker.next(1);
+    assert!(broker.trades.is_empty());
+
+    broker.modify_order(id, Some(20.0), Some(99.5), None).unwrap();
+    broker.next(2);
+    let trade = broker.trade(id).unwrap();
+    assert_eq!(trade.size, 20.0);
+    assert_eq!(trade.entry_price, 99.5);
+}
+
+#[test]
+fn stop_loss_is_amended_through_its_id() {
+    let mut broker = broker(4);
+    broker.next(0);
+    let entry = broker.new_order(order(10.0, None, Some(90.0)), 100.0).unwrap();
+    broker.next(1);
+    let stop = broker.orders.iter().find(|o| o.parent_trade.is_some()).map(|o| o.id).unwrap();
+    assert_ne!(stop, entry);
+
+    // an exit always covers its trade, so only its level moves
+    assert!(matches!(broker.modify_order(stop, Some(-5.0), None, None), Err(OrderError::InvalidModification)));
+    assert!(matches!(broker.modify_order(stop, None, Some(110.0), None), Err(OrderError::InvalidModification)));
+    broker.modify_order(stop, None, None, Some(99.5)).unwrap();
+    assert_eq!(broker.stop_level(0), Some(99.5));
+
+    broker.next(2);
+    let trade = &broker.closed_trades[0];
+    assert_eq!(trade.id, entry);
+    assert_eq!(trade.exit_tag.as_deref(), Some("stop-loss"));
+}
+
+#[test]
+fn invalid_modifications_are_rejected() {
+    let mut broker = broker(3);
+    broker.next(0);
+    let id = broker.new_order(order(10.0, Some(95.0), None), 100.0).unwrap();
+    assert!(matches!(broker.modify_order(id + 100, None, Some(96.0), None), Err(OrderError::UnknownOrder)));
+    // the side can't flip and the size can't go to zero
+    assert!(matches!(broker.modify_order(id, Some(-10.0), None, None), Err(OrderError::InvalidModification)));
+    assert!(matches!(broker.modify_order(id, Some(0.0), None, None), Err(OrderError::InvalidModification)));
+    // a limit order has no stop to move
+    assert!(matches!(broker.modify_order(id, None, None, Some(97.0)), Err(OrderError::InvalidModification)));
+    assert!(matches!(broker.modify_order(id, Some(10.5), None, None), Err(OrderError::FractionalOrderNotAllowed)));
+    assert!(matches!(broker.modify_order(id, Some(100_000.0), None, None), Err(OrderError::MarginExceeded)));
+    // a failed modification leaves the order as it was
+    assert_eq!(broker.order(id).unwrap().size, 10.0);
+    assert_eq!(broker.order(id).unwrap().limit, Some(95.0));
+}
+
+#[test]
+fn exits_stay_with_their_trade_when_an_earlier_trade_closes() {
+    let mut broker = broker(4);
+    broker.next(0);
+    let first = broker.new_order(order(10.0, None, Some(90.0)), 100.0).unwrap();
+    let second = broker.new_order(order(5.0, None, Some(95.0)), 100.0).unwrap();
+    broker.next(1);
+    // the stop losses point at their trades' ids, not at positions in `trades`
+    let parents: Vec<Option<u64>> = broker.orders.iter().map(|o| o.parent_trade).collect();
+    assert!(parents.contains(&Some(first)) && parents.contains(&Some(second)));
+
+    broker.close_position(0, 2);
+    assert_eq!(broker.contingent_orders(0).len(), 1);
+    assert_eq!(broker.stop_level(0), Some(95.0));
+    assert!(broker.orphaned_orders().is_empty());
+    assert_eq!(broker.cancelled_orders.last().map(|(_, o)| o.parent_trade), Some(Some(first)));
+}
+
+#[test]
+fn cancels_and_modifications_are_logged_as_events() {
+    let mut broker = broker(3);
+    broker.event_log = Some(Vec::new());
+    broker.next(0);
+    let moved = broker.new_order(order(10.0, Some(95.0), None), 100.0).unwrap();
+    let cancelled = broker.new_order(order(5.0, Some(90.0), None), 100.0).unwrap();
+    broker.modify_order(moved, None, Some(96.0), None).unwrap();
+    broker.cancel_order(cancelled).unwrap();
+
+    let changes: Vec<(usize, u64, Option<f64>, &str)> = broker.event_log.as_ref().unwrap().iter()
+        .filter_map(|event| match event {
+            BacktestEvent::OrderModified { index, order } => Some((*index, order.id, order.limit, "modified")),
+            BacktestEvent::OrderCancelled { index, order } => Some((*index, order.id, order.limit, "cancelled")),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(changes, vec![(0, moved, Some(96.0), "modified"), (0, cancelled, Some(90.0), "cancelled")]);
+}
@@ -0,0 +1,38 @@
+// synthetic quotes: stops and limits trigger against the spread of their own instrument
+use rust_core::engine::{Broker, Order};
+use rust_core::quotes::SpreadModel;
+use rust_core::testing::scripted_pair;
+
+// instrument 1 is flat, instrument 2 swings two points a bar, so the volatility model quotes it much wider
+fn broker() -> Broker {
+    let closes = vec![100.0; 12];
+    let closes2: Vec<f64> = (0..12).map(|i| if i % 2 == 0 { 99.0 } else { 101.0 }).collect();
+    // fractional sizes: instrument 2 orders are converted from primary units
+    let mut broker = Broker::new(scripted_pair(&closes, &closes2), 100_000.0, 0.0, 0.0, 0.5, false, true, false, false);
+    broker.quiet = true;
+    broker.quote_model = Some(SpreadModel::Volatility { min_spread: 0.1, multiple: 1.0, lookback: 5 });
+    broker
+}
+
+fn buy_stop(instrument: u8, stop: f64) -> Order {
+    Order { size: 10.0, stop: Some(stop), instrument, ..Default::default() }
+}
+
+#[test]
+fn stops_use_the_spread_of_their_instrument() {
+    let mut broker = broker();
+    for index in 0..=7 {
+        broker.next(index);
+    }
+    assert_eq!(broker.half_spread(1), 0.05);
+    assert!(broker.half_spread(2) > 1.0, "half spread {}", broker.half_spread(2));
+
+    broker.new_order(buy_stop(1, 100.5), 100.0).unwrap();
+    broker.new_order(buy_stop(2, 100.5), 100.0).unwrap();
+    broker.next(8);
+    // the wide ask of instrument 2 reaches the stop, the tight ask of instrument 1 doesn't
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].instrument, 2);
+    assert_eq!(broker.orders.len(), 1);
+    assert_eq!(broker.orders[0].instrument, 1);
+}
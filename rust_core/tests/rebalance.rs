@@ -0,0 +1,67 @@
+// target-position strategies: the rebalancing executor trades only the difference to the current position
+use rust_core::engine::{Broker, OhlcData};
+use rust_core::rebalance::{RebalanceConfig, TargetPositionStrategy, TargetStrategy};
+use rust_core::testing::{scripted_bars, StrategyTester};
+
+// scripted targets for instrument 1, one per bar
+struct Scripted {
+    targets: Vec<f64>,
+}
+
+impl TargetStrategy for Scripted {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn targets(&mut self, _broker: &Broker, index: usize) -> Vec<(u8, f64)> {
+        vec![(1, self.targets[index])]
+    }
+}
+
+fn tester(targets: &[f64], config: RebalanceConfig) -> StrategyTester<TargetPositionStrategy<Scripted>> {
+    let closes = vec![100.0; targets.len()];
+    let strategy = TargetPositionStrategy::with_config(Scripted { targets: targets.to_vec() }, config);
+    StrategyTester::new(strategy, scripted_bars(&closes))
+}
+
+fn position(broker: &Broker) -> f64 {
+    broker.trades.iter().filter(|t| t.instrument == 1).map(|t| t.size).sum()
+}
+
+#[test]
+fn only_the_delta_is_ordered() {
+    let mut tester = tester(&[10.0, 10.0, 15.0, 15.0, 4.0, -6.0, -6.0], RebalanceConfig::default());
+
+    let orders = tester.step();
+    assert_eq!(orders.len(), 1);
+    assert!((orders[0].order.size - 10.0).abs() < 1e-9);
+    // the entry is still pending on the next bar's open, so the unchanged target orders nothing
+    assert!(tester.step().is_empty());
+    assert!((position(&tester.broker) - 10.0).abs() < 1e-9);
+
+    let orders = tester.step();
+    assert_eq!(orders.len(), 1);
+    assert!((orders[0].order.size - 5.0).abs() < 1e-9);
+    tester.step();
+    assert!((position(&tester.broker) - 15.0).abs() < 1e-9);
+
+    // reducing closes existing trades instead of opening an opposite one
+    assert!(tester.step().is_empty());
+    assert!((position(&tester.broker) - 4.0).abs() < 1e-9);
+    assert!(tester.broker.trades.iter().all(|t| t.size > 0.0));
+
+    // reversing closes what is left and sells the rest
+    let orders = tester.step();
+    assert_eq!(orders.len(), 1);
+    assert!((orders[0].order.size + 6.0).abs() < 1e-9);
+    tester.run_all();
+    assert!((position(&tester.broker) + 6.0).abs() < 1e-9);
+}
+
+#[test]
+fn no_trade_band_skips_small_drifts() {
+    let config = RebalanceConfig { min_trade_fraction: 0.1, ..RebalanceConfig::default() };
+    let mut tester = tester(&[100.0, 100.0, 105.0, 109.0, 120.0, 120.0, 0.0, 0.0], config);
+    tester.run_all();
+    let sizes: Vec<f64> = tester.orders().iter().map(|o| o.order.size).collect();
+    assert_eq!(sizes, vec![100.0, 20.0]);
+    assert!(position(&tester.broker).abs() < 1e-9);
+}
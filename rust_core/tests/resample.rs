@@ -0,0 +1,74 @@
+// resampling bars to another bar length: ohlc aggregation, bucket labels, filling of empty periods and
+// the rule syntax
+use chrono::Duration;
+use rust_core::data_handler::{parse_resample_rule, resample, upsample};
+use rust_core::engine::OhlcData;
+
+// one-minute bars at the given minutes past 10:00
+fn bars(minutes: &[u32]) -> OhlcData {
+    let n = minutes.len();
+    let step = |base: f64| (0..n).map(|i| base + i as f64).collect::<Vec<f64>>();
+    OhlcData {
+        date: minutes.iter().map(|m| format!("2024-01-02 10:{:02}:00", m)).collect(),
+        open: step(1.0),
+        high: step(10.0),
+        low: step(0.0),
+        close: step(1.5),
+        close2: step(100.0),
+        volume: Some(step(1.0)),
+        halts: Vec::new(),
+    }
+}
+
+#[test]
+fn bars_are_aggregated_per_period() {
+    let out = resample(&bars(&[0, 1, 2, 5, 6]), "5min").unwrap();
+    assert_eq!(out.date, vec!["2024-01-02 10:00:00", "2024-01-02 10:05:00"]);
+    assert_eq!(out.open, vec![1.0, 4.0]);
+    assert_eq!(out.high, vec![12.0, 14.0]);
+    assert_eq!(out.low, vec![0.0, 3.0]);
+    assert_eq!(out.close, vec![3.5, 5.5]);
+    assert_eq!(out.close2, vec![102.0, 104.0]);
+    assert_eq!(out.volume, Some(vec![6.0, 9.0]));
+}
+
+#[test]
+fn bars_are_labelled_with_the_start_of_their_period() {
+    let out = resample(&bars(&[3, 59]), "1H").unwrap();
+    assert_eq!(out.date, vec!["2024-01-02 10:00:00"]);
+    // empty periods in between are skipped
+    let out = resample(&bars(&[4, 31]), "15min").unwrap();
+    assert_eq!(out.date, vec!["2024-01-02 10:00:00", "2024-01-02 10:30:00"]);
+}
+
+#[test]
+fn single_instrument_data_keeps_close2_empty() {
+    let data = OhlcData { close2: Vec::new(), ..bars(&[0, 1, 5]) };
+    assert!(resample(&data, "5min").unwrap().close2.is_empty());
+    let filled = upsample(&data, "1min").unwrap();
+    assert_eq!(filled.close.len(), 6);
+    assert!(filled.close2.is_empty());
+}
+
+#[test]
+fn upsample_fills_empty_periods_at_the_previous_close() {
+    let filled = upsample(&bars(&[0, 3]), "1min").unwrap();
+    assert_eq!(filled.date, vec!["2024-01-02 10:00:00", "2024-01-02 10:01:00", "2024-01-02 10:02:00", "2024-01-02 10:03:00"]);
+    assert_eq!(filled.open, vec![1.0, 1.5, 1.5, 2.0]);
+    assert_eq!(filled.high, vec![10.0, 1.5, 1.5, 11.0]);
+    assert_eq!(filled.low, vec![0.0, 1.5, 1.5, 1.0]);
+    assert_eq!(filled.close, vec![1.5, 1.5, 1.5, 2.5]);
+    assert_eq!(filled.close2, vec![100.0, 100.0, 100.0, 101.0]);
+    assert_eq!(filled.volume, Some(vec![1.0, 0.0, 0.0, 2.0]));
+}
+
+#[test]
+fn rules_are_a_count_and_a_unit() {
+    assert_eq!(parse_resample_rule("5min").unwrap(), Duration::minutes(5));
+    assert_eq!(parse_resample_rule("30s").unwrap(), Duration::seconds(30));
+    assert_eq!(parse_resample_rule("4H").unwrap(), Duration::hours(4));
+    assert_eq!(parse_resample_rule("D").unwrap(), Duration::days(1));
+    assert_eq!(parse_resample_rule("0H").unwrap_err().to_string(), "resample rule must be positive: 0H");
+    assert_eq!(parse_resample_rule("xyz").unwrap_err().to_string(), "unknown resample rule: xyz");
+    assert!(parse_resample_rule("-5min").is_err());
+}
@@ -0,0 +1,73 @@
+// live retry policies for entries rejected with MarginExceeded: shrink and resubmit at once, or queue the
+// order and resubmit it unchanged every few ticks
+mod common;
+
+use common::{live_broker, quote};
+use rust_core::live_engine::{LiveBroker, Order, OrderError, RetryPolicy};
+
+fn entry(size: f64) -> Order {
+    Order { size, instrument: "A".to_string(), ..Default::default() }
+}
+
+// 100k account without leverage, "A" quoted at 100
+fn broker(policy: RetryPolicy) -> LiveBroker {
+    let mut broker = live_broker();
+    broker.retry_policy = Some(policy);
+    quote(&mut broker, "A", 100.0, 100.0);
+    broker
+}
+
+#[test]
+fn shrink_size_places_the_first_size_that_fits() {
+    let mut broker = broker(RetryPolicy::ShrinkSize { factor: 0.5, max_attempts: 3 });
+    // 160k doesn't fit, 80k does
+    assert_eq!(broker.new_order(entry(1_600.0), 100.0), Ok(()));
+    assert_eq!(broker.orders[0].size, 800.0);
+    let stats = broker.retry_stats();
+    assert_eq!((stats.retried, stats.succeeded, stats.abandoned), (1, 1, 0));
+    assert!(broker.live_rejected_orders.is_empty());
+}
+
+#[test]
+fn shrink_size_gives_up_after_its_attempts() {
+    let mut broker = broker(RetryPolicy::ShrinkSize { factor: 0.5, max_attempts: 2 });
+    // 500k and 250k still don't fit
+    assert_eq!(broker.new_order(entry(10_000.0), 100.0), Err(OrderError::MarginExceeded));
+    assert!(broker.orders.is_empty());
+    let stats = broker.retry_stats();
+    assert_eq!((stats.retried, stats.succeeded, stats.abandoned), (2, 0, 1));
+    assert_eq!(broker.live_rejected_orders.len(), 1);
+}
+
+#[test]
+fn wait_ticks_resubmits_once_buying_power_is_freed() {
+    let mut broker = broker(RetryPolicy::WaitTicks { ticks: 2, max_attempts: 3 });
+    broker.new_order(entry(800.0), 100.0).unwrap();
+    broker.next(0);
+    // 50k on top of the 80k open: queued for tick 2
+    assert_eq!(broker.new_order(entry(500.0), 100.0), Err(OrderError::MarginExceeded));
+    assert_eq!(broker.retry_stats().pending, 1);
+    broker.close_position(0, 0);
+
+    broker.next(1);
+    assert!(broker.trades.is_empty());
+    broker.next(2);
+    assert_eq!(broker.trades.len(), 1);
+    assert_eq!(broker.trades[0].size, 500.0);
+    let stats = broker.retry_stats();
+    assert_eq!((stats.retried, stats.succeeded, stats.pending), (1, 1, 0));
+}
+
+#[test]
+fn wait_ticks_abandons_an_order_that_never_fits() {
+    let mut broker = broker(RetryPolicy::WaitTicks { ticks: 1, max_attempts: 2 });
+    assert_eq!(broker.new_order(entry(1_600.0), 100.0), Err(OrderError::MarginExceeded));
+    broker.next(1);
+    assert_eq!(broker.retry_stats().pending, 1);
+    broker.next(2);
+    let stats = broker.retry_stats();
+    assert_eq!((stats.retried, stats.abandoned, stats.pending), (2, 1, 0));
+    // the original rejection and the abandoned retry
+    assert_eq!(broker.live_rejected_orders.len(), 2);
+    assert!(broker.orders.is_empty());
+}
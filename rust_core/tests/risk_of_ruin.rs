@@ -0,0 +1,59 @@
+// risk of ruin: ruined paths still count deeper drawdowns, and too few trades give no analytic estimate
+use rust_core::engine::Trade;
+use rust_core::stats::{risk_of_ruin, RuinConfig};
+
+// a closed trade returning `pct` of the 100k equity it was entered with
+fn trade(pct: f64) -> Trade {
+    Trade {
+        size: 1_000.0,
+        entry_price: 100.0,
+        exit_price: Some(100.0 + pct),
+        exit_index: Some(1),
+        ..Default::default()
+    }
+}
+
+fn config(ruin_pct: f64, drawdown_pcts: Vec<f64>, horizon: usize) -> RuinConfig {
+    RuinConfig { ruin_pct, drawdown_pcts, horizon, simulations: 50, ..RuinConfig::default() }
+}
+
+#[test]
+fn drawdowns_beyond_ruin_are_still_counted() {
+    // every path loses 60% twice: ruined (30%) on the first trade, an 84% drawdown after the second
+    let trades = vec![trade(-60.0), trade(-60.0)];
+    let report = risk_of_ruin(&trades, &[100_000.0; 2], &config(30.0, vec![50.0, 80.0, 90.0], 2));
+    assert_eq!(report.simulated_ruin, 1.0);
+    let simulated: Vec<f64> = report.drawdowns.iter().map(|d| d.simulated).collect();
+    assert_eq!(simulated, vec![1.0, 1.0, 0.0]);
+    assert_eq!(report.analytic_ruin, 1.0);
+}
+
+#[test]
+fn fewer_than_two_trades_have_no_analytic_estimate() {
+    let report = risk_of_ruin(&[trade(10.0)], &[100_000.0; 2], &config(50.0, vec![10.0], 0));
+    assert!(report.analytic_ruin.is_nan());
+    assert!(report.drawdowns[0].analytic.is_nan());
+    // the bootstrap still runs on the one trade
+    assert_eq!(report.simulated_ruin, 0.0);
+
+    let report = risk_of_ruin(&[], &[100_000.0], &RuinConfig::default());
+    assert!(report.analytic_ruin.is_nan());
+}
+
+#[test]
+fn a_total_loss_is_ruin_even_on_its_own() {
+    let report = risk_of_ruin(&[trade(-100.0)], &[100_000.0; 2], &RuinConfig::default());
+    assert_eq!(report.analytic_ruin, 1.0);
+}
+
+#[test]
+fn a_total_loss_reaches_a_100_pct_ruin_level() {
+    let report = risk_of_ruin(&[trade(10.0), trade(-100.0)], &[100_000.0; 2], &config(100.0, vec![100.0], 0));
+    assert_eq!(report.analytic_ruin, 1.0);
+    assert_eq!(report.drawdowns[0].analytic, 1.0);
+
+    // without a total loss log equity never gets there
+    let report = risk_of_ruin(&[trade(10.0), trade(-50.0)], &[100_000.0; 2], &config(100.0, vec![100.0], 0));
+    assert_eq!(report.analytic_ruin, 0.0);
+    assert_eq!(report.drawdowns[0].analytic, 0.0);
+}
@@ -0,0 +1,61 @@
+// cfd financing at rollovers: charged once per trading day at 17:00 new york, three times on friday,
+// on the positions held through the rollover, instead of on every bar
+mod common;
+
+use chrono::{DateTime, TimeZone, Utc};
+use rust_core::engine::{Broker, Order};
+use rust_core::instrument::{InstrumentSpec, RolloverSchedule};
+
+fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+}
+
+#[test]
+fn rollovers_follow_new_york_time() {
+    let schedule = RolloverSchedule::index_cfd(0.036, 0.0);
+    // 17:00 new york is 22:00 utc in winter and 21:00 utc in summer
+    assert_eq!(schedule.days_between(utc(2024, 1, 9, 21, 30), utc(2024, 1, 9, 22, 30)), 1.0);
+    assert_eq!(schedule.days_between(utc(2024, 3, 12, 20, 30), utc(2024, 3, 12, 21, 30)), 1.0);
+    assert_eq!(schedule.days_between(utc(2024, 3, 12, 21, 30), utc(2024, 3, 12, 22, 30)), 0.0);
+    // monday noon to the next monday noon: four weekday rollovers and a triple friday
+    assert_eq!(schedule.days_between(utc(2024, 1, 8, 12, 0), utc(2024, 1, 15, 12, 0)), 7.0);
+    // hourly bars within a day never cross more than one rollover
+    let start = utc(2024, 1, 9, 0, 0);
+    let hour = chrono::Duration::hours(1);
+    let hours: f64 = (0..24).map(|h| schedule.days_between(start + hour * h, start + hour * (h + 1))).sum();
+    assert_eq!(hours, 1.0);
+}
+
+// daily bars at noon utc, monday 2024-01-08 to monday 2024-01-15 without the weekend
+fn broker() -> Broker {
+    let dates = ["2024-01-08", "2024-01-09", "2024-01-10", "2024-01-11", "2024-01-12", "2024-01-15"];
+    let mut data = common::data(&[(100.0, 100.0, 100.0, 100.0); 6]);
+    data.date = dates.iter().map(|d| format!("{} 12:00:00", d)).collect();
+    let mut broker = common::broker_with(data);
+    broker.financing_rate = 0.01; // per bar, ignored for instruments on a rollover schedule
+    let spec = InstrumentSpec { rollover: Some(RolloverSchedule::index_cfd(0.036, 0.0)), ..InstrumentSpec::default() };
+    broker.instrument_specs.insert(1, spec);
+    broker
+}
+
+fn buy(size: f64) -> Order {
+    Order {
+        size,
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn positions_held_through_rollovers_are_charged() {
+    let mut broker = broker();
+    broker.next(0);
+    broker.new_order(buy(10.0), 100.0).unwrap();
+    for index in 1..6 {
+        broker.next(index);
+    }
+    // filled on tuesday's bar: held through tue, wed, thu and friday's triple rollover. 1000 notional at
+    // 3.6% on a 360 day year is 0.1 a day
+    assert!((broker.costs.total_financing_cost - 0.6).abs() < 1e-9, "{}", broker.costs.total_financing_cost);
+    assert!((broker.equity[5] - (100_000.0 - 0.6)).abs() < 1e-9);
+}
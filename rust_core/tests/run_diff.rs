@@ -0,0 +1,62 @@
+// diff_results: two runs match when their closed trades and equity curves agree within the tolerances,
+// and the report points at the first trade field and bar that differ
+mod common;
+
+use common::FLAT;
+use rust_core::diff::{diff_results, diff_results_with_config, DiffConfig};
+use rust_core::engine::{Broker, Order};
+
+const BARS: [common::Bar; 5] = [FLAT, FLAT, (100.0, 104.0, 99.0, 103.0), (103.0, 106.0, 102.0, 105.0), FLAT];
+
+// long 10 units from the open of bar 1 to the close of `exit`
+fn round_trip(exit: usize) -> Broker {
+    let mut broker = common::broker(&BARS);
+    for index in 0..BARS.len() {
+        broker.next(index);
+        if index == 0 {
+            broker.new_order(Order { size: 10.0, instrument: 1, ..Default::default() }, 100.0).unwrap();
+        } else if index == exit {
+            broker.close_position(0, index);
+        }
+    }
+    broker
+}
+
+#[test]
+fn identical_runs_match() {
+    let diff = diff_results(&round_trip(2), &round_trip(2));
+    assert!(diff.is_match());
+    assert_eq!(diff.num_trades_a, 1);
+    assert_eq!(diff.max_equity_diff, 0.0);
+}
+
+#[test]
+fn a_later_exit_is_reported_field_by_field() {
+    let diff = diff_results(&round_trip(2), &round_trip(3));
+    assert!(!diff.is_match());
+    assert_eq!(diff.mismatched_trades, 1);
+    let fields: Vec<&str> = diff.trade_mismatches.iter().map(|m| m.field).collect();
+    assert_eq!(fields, vec!["exit_index", "exit_price"]);
+    assert_eq!((diff.pnl_a, diff.pnl_b), (30.0, 50.0));
+    // both hold the trade through bar 2; b is still marked at 105 on bar 3
+    assert_eq!(diff.first_equity_divergence, Some(3));
+    assert_eq!(diff.max_equity_diff, 20.0);
+}
+
+#[test]
+fn differences_within_the_tolerances_still_match() {
+    let mut b = round_trip(2);
+    b.closed_trades[0].exit_price = Some(103.0 + 1e-4);
+    b.equity[4] += 1e-3;
+    assert!(!diff_results(&round_trip(2), &b).is_match());
+    let loose = DiffConfig { price_tolerance: 1e-3, equity_tolerance: 1e-2, ..DiffConfig::default() };
+    assert!(diff_results_with_config(&round_trip(2), &b, &loose).is_match());
+}
+
+#[test]
+fn the_listed_mismatches_are_capped() {
+    let config = DiffConfig { max_listed: 1, ..DiffConfig::default() };
+    let diff = diff_results_with_config(&round_trip(2), &round_trip(3), &config);
+    assert_eq!(diff.mismatched_trades, 1);
+    assert_eq!(diff.trade_mismatches.len(), 1);
+}
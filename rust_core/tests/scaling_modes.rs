@@ -0,0 +1,55 @@
+// margin-based scaling: under ScalingMode::BuyingPower and TargetMarginUsage the broker sizes entries
+// from the account, and the strategy's size only sets the side
+mod common;
+
+use common::FLAT;
+use rust_core::engine::{Broker, Order, OrderError, ScalingMode};
+
+fn broker(mode: ScalingMode) -> Broker {
+    let mut broker = common::broker(&[FLAT, FLAT, FLAT]);
+    broker.scaling_enabled = true;
+    broker.scaling_mode = mode;
+    broker.next(0);
+    broker
+}
+
+fn entry(size: f64) -> Order {
+    Order { size, instrument: 1, ..Default::default() }
+}
+
+#[test]
+fn buying_power_entries_take_a_fraction_of_what_is_left() {
+    let mut broker = broker(ScalingMode::BuyingPower(0.2));
+    broker.new_order(entry(1.0), 100.0).unwrap();
+    // a fifth of 100k at 100
+    assert_eq!(broker.orders[0].size, 200.0);
+    broker.next(1);
+    // a fifth of the 80k still available
+    broker.new_order(entry(-1.0), 100.0).unwrap();
+    assert_eq!(broker.orders[0].size, -160.0);
+}
+
+#[test]
+fn target_margin_entries_fill_up_to_the_target_and_then_stop() {
+    let mut broker = broker(ScalingMode::TargetMarginUsage(0.5));
+    broker.new_order(entry(1.0), 100.0).unwrap();
+    assert_eq!(broker.orders[0].size, 500.0);
+    broker.next(1);
+    assert_eq!(broker.current_exposure(), 50_000.0);
+    assert_eq!(broker.new_order(entry(1.0), 100.0), Err(OrderError::MarginExceeded));
+}
+
+#[test]
+fn exact_size_orders_are_not_rescaled() {
+    let mut broker = broker(ScalingMode::BuyingPower(0.2));
+    broker.new_order(Order { exact_size: true, ..entry(7.0) }, 100.0).unwrap();
+    assert_eq!(broker.orders[0].size, 7.0);
+}
+
+#[test]
+fn the_mode_is_ignored_without_scaling_enabled() {
+    let mut broker = broker(ScalingMode::BuyingPower(0.2));
+    broker.scaling_enabled = false;
+    broker.new_order(entry(7.0), 100.0).unwrap();
+    assert_eq!(broker.orders[0].size, 7.0);
+}
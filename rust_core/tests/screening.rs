@@ -0,0 +1,104 @@
+// universe screening: correlation to the benchmark is computed on shared dates, not bar positions
+use rust_core::engine::OhlcData;
+use rust_core::screening::{compute_symbol_metrics, ScreenCriteria, Screener};
+
+// daily bars from 2024-01-01 with the given closes, skipping the days in `missing`
+fn daily(closes: &[f64], missing: &[usize]) -> OhlcData {
+    let days: Vec<usize> = (0..closes.len()).filter(|day| !missing.contains(day)).collect();
+    let close: Vec<f64> = days.iter().map(|&day| closes[day]).collect();
+    let start = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+    OhlcData {
+        date: days.iter().map(|&day| format!("{} 00:00:00", start + chrono::Duration::days(day as i64))).collect(),
+        open: close.clone(),
+        high: close.clone(),
+        low: close.clone(),
+        close2: close.clone(),
+        close,
+        volume: None,
+        halts: Vec::new(),
+    }
+}
+
+fn index_closes(n: usize) -> Vec<f64> {
+    let mut closes = vec![100.0];
+    for i in 1..n {
+        closes.push(closes[i - 1] * (1.0 + 0.01 * (i as f64 * 1.7).sin()));
+    }
+    closes
+}
+
+#[test]
+fn gap_is_joined_on_the_date() {
+    let benchmark = daily(&index_closes(40), &[]);
+    // the same moves at twice the price, without day 10: after the gap every bar position is off by one
+    let doubled: Vec<f64> = index_closes(40).iter().map(|c| 2.0 * c).collect();
+    let symbol = daily(&doubled, &[10]);
+    let metrics = compute_symbol_metrics("gap", &symbol, Some(&benchmark), symbol.close.len() - 1, 38).unwrap();
+    let correlation = metrics.correlation.unwrap();
+    assert!((correlation - 1.0).abs() < 1e-9, "correlation {}", correlation);
+}
+
+#[test]
+fn later_start_is_joined_on_the_date() {
+    let benchmark = daily(&index_closes(40), &[]);
+    // listed from day 15 on: its first bar lines up with benchmark bar 15, not 0
+    let symbol = daily(&index_closes(40), &(0..15).collect::<Vec<_>>());
+    let mut screener = Screener::new(
+        vec![("late".to_string(), symbol)],
+        Some(benchmark),
+        ScreenCriteria { lookback: 20, min_correlation: Some(0.99), ..ScreenCriteria::default() },
+        0,
+    );
+    assert!(screener.update(24, "2024-01-25 00:00:00"));
+    assert_eq!(screener.universe, vec!["late".to_string()]);
+}
+
+#[test]
+fn every_symbol_is_screened_at_the_bar_date() {
+    let closes: Vec<f64> = (0..40).map(|i| 100.0 * (1.0 + 0.002 * i as f64 * (i % 3) as f64)).collect();
+    let full = daily(&closes, &[]);
+    let late = daily(&closes, &(0..15).collect::<Vec<_>>());
+    let screener = Screener::new(
+        vec![("full".to_string(), full.clone()), ("late".to_string(), late.clone()), ("later".to_string(), daily(&closes, &(0..30).collect::<Vec<_>>()))],
+        None,
+        ScreenCriteria { lookback: 5, ..ScreenCriteria::default() },
+        0,
+    );
+    let metrics = screener.screen("2024-01-25 00:00:00");
+    // "later" has no bar yet on day 24
+    assert_eq!(metrics.len(), 2);
+    assert_eq!(metrics[0].volatility, compute_symbol_metrics("full", &full, None, 24, 5).unwrap().volatility);
+    // day 24 is bar 9 of a symbol listed on day 15
+    assert_eq!(metrics[1].volatility, compute_symbol_metrics("late", &late, None, 9, 5).unwrap().volatility);
+    assert_eq!(metrics[0].volatility, metrics[1].volatility);
+}
+
+#[test]
+fn benchmark_dates_are_parsed_not_compared_as_text() {
+    let mut benchmark = daily(&index_closes(40), &[]);
+    // rfc3339 dates in reverse order: neither sorted nor string-equal to the symbol's dates
+    benchmark.date = benchmark.date.iter().map(|d| format!("{}Z", d.replace(' ', "T"))).collect();
+    benchmark.date.reverse();
+    benchmark.close.reverse();
+    let symbol = daily(&index_closes(40), &[]);
+    let metrics = compute_symbol_metrics("iso", &symbol, Some(&benchmark), 39, 38).unwrap();
+    let correlation = metrics.correlation.unwrap();
+    assert!((correlation - 1.0).abs() < 1e-9, "correlation {}", correlation);
+}
+
+#[test]
+fn symbols_without_a_return_are_skipped() {
+    let empty = daily(&[], &[]);
+    assert!(compute_symbol_metrics("empty", &empty, None, 0, 5).is_none());
+    let one = daily(&[100.0], &[]);
+    assert!(compute_symbol_metrics("one", &one, None, 0, 5).is_none());
+    // listed on day 24: one bar on the screening date
+    let closes = index_closes(40);
+    let screener = Screener::new(
+        vec![("new".to_string(), daily(&closes, &(0..24).collect::<Vec<_>>())), ("empty".to_string(), empty)],
+        None,
+        ScreenCriteria { lookback: 5, ..ScreenCriteria::default() },
+        0,
+    );
+    assert!(screener.screen("2024-01-25 00:00:00").is_empty());
+}
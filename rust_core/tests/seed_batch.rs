@@ -0,0 +1,73 @@
+// seed batch: the same strategy under different rng seeds, aggregated into run-to-run distributions
+use rust_core::engine::{Backtest, Broker, OhlcData, Order, Strategy};
+use rust_core::robustness::{run_seed_batch, SeedBatchConfig};
+
+// enters long at random and exits after three bars
+struct CoinFlip;
+
+impl Strategy for CoinFlip {
+    fn init(&mut self, _broker: &mut Broker, _data: &OhlcData) {}
+
+    fn next(&mut self, broker: &mut Broker, index: usize) {
+        if let Some(trade) = broker.trades.first() {
+            if index >= trade.entry_index + 3 {
+                broker.close_position(0, index);
+            }
+        } else if broker.orders.is_empty() && broker.rng.next_f64() < 0.3 {
+            let order = Order {
+                size: 10.0,
+                instrument: 1,
+                ..Default::default()
+            };
+            let _ = broker.new_order(order, broker.data.close[index]);
+        }
+    }
+}
+
+fn backtest() -> Backtest {
+    let n = 120;
+    let close: Vec<f64> = (0..n).map(|i| 100.0 + 5.0 * (i as f64 / 7.0).sin()).collect();
+    let data = OhlcData {
+        date: (0..n).map(|i| format!("2024-01-01 {:02}:{:02}:00", i / 60, i % 60)).collect(),
+        open: close.clone(),
+        high: close.clone(),
+        low: close.clone(),
+        close: close.clone(),
+        close2: close,
+        volume: None,
+        halts: Vec::new(),
+    };
+    Backtest::new(data, Box::new(CoinFlip), 100_000.0, 0.0, 0.0, 1.0, false, true, false, false)
+}
+
+#[test]
+fn seeds_produce_different_runs() {
+    let config = SeedBatchConfig { runs: 8, seed: 7, risk_free_rate: 0.0 };
+    let report = run_seed_batch(backtest, &config);
+    assert_eq!(report.runs.len(), 8);
+    assert_eq!(report.runs[3].seed, Some(10));
+    assert!(report.return_dist.std > 0.0);
+    assert!(report.trades_dist.mean > 0.0);
+
+    let (worst, best) = report.worst_and_best().unwrap();
+    assert!(worst.return_pct <= report.return_dist.mean && best.return_pct >= report.return_dist.mean);
+    assert!(report.to_string().contains("Seed Batch (8 runs)"));
+}
+
+#[test]
+fn a_seed_reproduces_its_run() {
+    let config = SeedBatchConfig { runs: 3, seed: 1, risk_free_rate: 0.0 };
+    let first = run_seed_batch(backtest, &config);
+    let second = run_seed_batch(backtest, &config);
+    for (a, b) in first.runs.iter().zip(second.runs.iter()) {
+        assert_eq!(a.return_pct, b.return_pct);
+        assert_eq!(a.num_trades, b.num_trades);
+    }
+}
+
+#[test]
+fn distribution_is_exported_from_robustness() {
+    let dist = rust_core::robustness::Distribution::from_values(&[3.0, 1.0, 2.0]);
+    assert_eq!(dist.median, 2.0);
+    assert_eq!(dist.mean, 2.0);
+}
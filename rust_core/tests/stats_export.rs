@@ -0,0 +1,57 @@
+// Stats::to_json and the csv export: the json carries every field including the drawdown episodes, the
+// csv row has one value per header column
+mod common;
+
+use common::FLAT;
+use rust_core::engine::Order;
+use rust_core::stats::{compute_stats, Stats};
+
+// a long of 10 units from 100 that dips to 95 and ends at 110
+fn stats() -> Stats {
+    let bars = [FLAT, FLAT, (100.0, 101.0, 94.0, 95.0), (95.0, 111.0, 95.0, 110.0), FLAT];
+    let mut broker = common::broker(&bars);
+    for index in 0..bars.len() {
+        broker.next(index);
+        if index == 0 {
+            broker.new_order(Order { size: 10.0, instrument: 1, ..Default::default() }, 100.0).unwrap();
+        } else if index == 3 {
+            broker.close_position(0, index);
+        }
+    }
+    let data = broker.data.clone();
+    compute_stats(&broker.closed_trades, &broker.equity, &data, 0.0, broker.max_margin_usage, &broker.gross_exposure_history(), &broker.cost_summary())
+}
+
+#[test]
+fn json_has_the_scalars_and_the_drawdowns() {
+    let stats = stats();
+    let json: serde_json::Value = serde_json::from_str(&stats.to_json().unwrap()).unwrap();
+    assert_eq!(json["equity_final"], 100_100.0);
+    assert_eq!(json["num_trades"], 1);
+    let drawdowns = json["top_drawdowns"].as_array().unwrap();
+    assert_eq!(drawdowns.len(), 1);
+    assert_eq!(drawdowns[0]["trough_index"], 2);
+}
+
+#[test]
+fn non_finite_ratios_are_null_in_json() {
+    let stats = Stats { sharpe_ratio: f64::NAN, profit_factor: f64::INFINITY, ..Stats::default() };
+    let json: serde_json::Value = serde_json::from_str(&stats.to_json().unwrap()).unwrap();
+    assert!(json["sharpe_ratio"].is_null());
+    assert!(json["profit_factor"].is_null());
+}
+
+#[test]
+fn the_csv_row_lines_up_with_the_header() {
+    let stats = stats();
+    let header = Stats::csv_header();
+    let row = stats.to_csv_row();
+    let columns: Vec<&str> = header.split(',').collect();
+    let values: Vec<&str> = row.split(',').collect();
+    assert_eq!(columns.len(), values.len());
+    assert!(!columns.contains(&"top_drawdowns"));
+    let value = |name: &str| values[columns.iter().position(|c| *c == name).unwrap()];
+    assert_eq!(value("equity_final"), "100100");
+    assert_eq!(value("num_trades"), "1");
+    assert_eq!(value("start_date"), "2024-01-02 10:00:00");
+}
@@ -0,0 +1,42 @@
+// stats of an external return series; malformed input is an error rather than a panic
+use rust_core::stats::{compute_stats_from_returns, StatsError};
+
+fn dates(n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("2024-01-{:02} 00:00:00", 1 + i)).collect()
+}
+
+#[test]
+fn returns_compound_into_the_total_return() {
+    let stats = compute_stats_from_returns(&[0.1, -0.05, 0.02], &dates(4), 0.0).unwrap();
+    assert!((stats.return_pct - (1.1 * 0.95 * 1.02 - 1.0) * 100.0).abs() < 1e-9);
+}
+
+#[test]
+fn one_date_per_return_starts_one_spacing_earlier() {
+    // returns ending on jan 2, 3 and 4 start on jan 1, like the same returns with an explicit start
+    let ends: Vec<String> = dates(4)[1..].to_vec();
+    let aligned = compute_stats_from_returns(&[0.1, -0.05, 0.02], &ends, 0.0).unwrap();
+    let explicit = compute_stats_from_returns(&[0.1, -0.05, 0.02], &dates(4), 0.0).unwrap();
+    assert_eq!(aligned.start_date, "2024-01-01 00:00:00");
+    assert_eq!(aligned.duration, explicit.duration);
+    assert_eq!(aligned.return_ann_pct, explicit.return_ann_pct);
+}
+
+#[test]
+fn spans_under_a_day_are_rejected() {
+    // a single return without its start date has no span
+    assert_eq!(compute_stats_from_returns(&[0.1], &dates(1), 0.0).unwrap_err(), StatsError::SpanTooShort);
+    let minutes = vec!["2024-01-01 10:00:00".to_string(), "2024-01-01 10:01:00".to_string()];
+    assert_eq!(compute_stats_from_returns(&[0.1], &minutes, 0.0).unwrap_err(), StatsError::SpanTooShort);
+    assert!(compute_stats_from_returns(&[0.1], &dates(2), 0.0).unwrap().return_ann_pct.is_finite());
+}
+
+#[test]
+fn malformed_input_is_rejected() {
+    assert_eq!(compute_stats_from_returns(&[0.1, 0.2], &dates(5), 0.0).unwrap_err(), StatsError::DateCount(2, 5));
+    assert_eq!(compute_stats_from_returns(&[], &dates(1), 0.0).unwrap_err(), StatsError::NoReturns);
+    let mut bad = dates(3);
+    bad[1] = "yesterday".to_string();
+    assert_eq!(compute_stats_from_returns(&[0.1, 0.2], &bad, 0.0).unwrap_err(), StatsError::InvalidDate("yesterday".to_string()));
+    assert_eq!(StatsError::DateCount(2, 5).to_string(), "2 returns need 3 dates (one per return plus an optional start date), got 5");
+}
@@ -0,0 +1,55 @@
+// StatArbSpreadStrategy bar by bar through the StrategyTester
+use rust_core::engine::Side;
+use rust_core::strategies::statarb_spread::StatArbSpreadStrategy;
+use rust_core::testing::{scripted_bars, ExpectedOrder, StrategyTester};
+
+// twenty quiet bars around 100 fill the lookback, then a spike up, a drop and a return to the mean
+fn closes() -> Vec<f64> {
+    let mut closes: Vec<f64> = (0..20).map(|i| if i % 2 == 0 { 100.0 } else { 100.1 }).collect();
+    closes.extend_from_slice(&[103.0, 97.0, 100.05, 100.05]);
+    closes
+}
+
+fn tester() -> StrategyTester<StatArbSpreadStrategy> {
+    StrategyTester::new(StatArbSpreadStrategy::new(), scripted_bars(&closes()))
+}
+
+#[test]
+fn no_orders_while_the_spread_is_quiet() {
+    let mut tester = tester();
+    tester.run_to(19);
+    assert!(tester.orders().is_empty());
+}
+
+#[test]
+fn spike_up_is_shorted_with_a_stop_above() {
+    let mut tester = tester();
+    tester.run_to(19);
+    let orders = tester.step();
+
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].side(), Side::Short);
+    // stop at price + stop_loss + bid/ask spread
+    tester.assert_orders(20, &[ExpectedOrder::sell().size(20.0).sl(103.0 + 5.0 * 0.0075 + 0.5).tag_prefix("zscore=").accepted()]);
+}
+
+#[test]
+fn drop_is_bought_and_the_mean_closes_the_book() {
+    let mut tester = tester();
+    tester.run_all();
+
+    tester.assert_orders(21, &[ExpectedOrder::buy().size(20.0).sl(97.0 - (5.0 * 0.0075 + 0.5))]);
+    tester.assert_no_orders(22);
+    // back at the mean: both trades closed on bar 22, no new entries
+    assert!(tester.broker.trades.is_empty());
+    assert_eq!(tester.broker.closed_trades.len(), 2);
+    assert!(tester.broker.closed_trades.iter().all(|t| t.exit_index == Some(22)));
+}
+
+#[test]
+#[should_panic(expected = "orders on bar 20 did not match")]
+fn mismatched_expectation_fails() {
+    let mut tester = tester();
+    tester.run_to(20);
+    tester.assert_orders(20, &[ExpectedOrder::buy()]);
+}
@@ -0,0 +1,72 @@
+// bracket exits: an entry's tp becomes a contingent limit order next to its stop loss, and whichever
+// fills first cancels the other
+mod common;
+
+use common::{broker_with, data, run, FLAT};
+use rust_core::engine::Order;
+
+fn bracket(size: f64, sl: f64, tp: f64) -> Order {
+    Order {
+        size,
+        sl: Some(sl),
+        tp: Some(tp),
+        instrument: 1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn take_profit_closes_the_trade_and_cancels_the_stop() {
+    let broker = run(&[FLAT, FLAT, (100.0, 111.0, 99.0, 108.0), FLAT], bracket(10.0, 90.0, 110.0));
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.closed_trades.len(), 1);
+    let trade = &broker.closed_trades[0];
+    assert_eq!(trade.exit_price, Some(110.0));
+    assert_eq!(trade.exit_index, Some(2));
+    assert_eq!(trade.exit_tag.as_deref(), Some("take-profit"));
+    assert_eq!(broker.cancelled_orders.len(), 1);
+    assert_eq!(broker.cancelled_orders[0].1.tag.as_deref(), Some("stop-loss"));
+    assert!(broker.orders.is_empty());
+}
+
+#[test]
+fn stop_loss_wins_when_both_are_inside_the_bar() {
+    let broker = run(&[FLAT, FLAT, (100.0, 112.0, 88.0, 100.0)], bracket(10.0, 90.0, 110.0));
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.closed_trades[0].exit_tag.as_deref(), Some("stop-loss"));
+    assert_eq!(broker.cancelled_orders[0].1.tag.as_deref(), Some("take-profit"));
+}
+
+#[test]
+fn take_profit_wins_when_the_bar_opens_through_it() {
+    let broker = run(&[FLAT, FLAT, (113.0, 114.0, 85.0, 90.0)], bracket(10.0, 90.0, 110.0));
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.closed_trades[0].exit_tag.as_deref(), Some("take-profit"));
+    assert_eq!(broker.closed_trades[0].exit_price, Some(110.0));
+}
+
+#[test]
+fn short_take_profit_buys_back_below_the_entry() {
+    let broker = run(&[FLAT, FLAT, (100.0, 101.0, 89.0, 92.0)], bracket(-10.0, 110.0, 90.0));
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert_eq!(broker.closed_trades[0].exit_price, Some(90.0));
+    assert_eq!(broker.closed_trades[0].pnl(), 100.0);
+}
+
+#[test]
+fn hedge_exits_trigger_on_close2_not_on_the_primary_range() {
+    // the primary bar 2 spans both exits, instrument 2 only reaches its take profit on bar 3
+    let mut data = data(&[FLAT, FLAT, (100.0, 120.0, 30.0, 100.0), FLAT]);
+    data.close2 = vec![50.0, 50.0, 50.0, 56.0];
+    let mut broker = broker_with(data);
+    broker.next(0);
+    broker.new_order(Order { instrument: 2, ..bracket(10.0, 40.0, 55.0) }, 50.0).unwrap();
+    broker.next(1);
+    broker.next(2);
+    assert_eq!(broker.trades.len(), 1);
+    broker.next(3);
+    assert_eq!(broker.closed_trades.len(), 1);
+    let trade = &broker.closed_trades[0];
+    assert_eq!(trade.exit_tag.as_deref(), Some("take-profit"));
+    assert_eq!((trade.exit_index, trade.exit_price), (Some(3), Some(55.0)));
+}
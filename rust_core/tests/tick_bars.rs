@@ -0,0 +1,72 @@
+// quote pseudo-bars: every primary quote is one flat bar at its mid, close2 the latest secondary mid, and
+// their irregular spacing is annualized by elapsed time
+use rust_core::data_handler::{ticks_to_bars, ticks_to_bars_indexed};
+use rust_core::live_engine::TickSnapshot;
+use rust_core::stats::elapsed_time_volatility;
+
+fn tick(instrument: &str, second: u32, mid: f64) -> TickSnapshot {
+    TickSnapshot {
+        instrument: instrument.to_string(),
+        date: format!("2024-01-02T10:00:{:02}Z", second),
+        bid: mid - 0.5,
+        ask: mid + 0.5,
+    }
+}
+
+// A before the first B quote, then A and B interleaved with a quote of an unrelated C
+fn ticks() -> Vec<TickSnapshot> {
+    vec![
+        tick("A", 0, 100.0),
+        tick("B", 1, 50.0),
+        tick("A", 2, 101.0),
+        tick("C", 3, 7.0),
+        tick("B", 4, 51.0),
+        tick("A", 5, 102.0),
+    ]
+}
+
+#[test]
+fn every_primary_quote_is_a_flat_bar_at_its_mid() {
+    let bars = ticks_to_bars(&ticks(), "A", None);
+    assert_eq!(bars.close, vec![100.0, 101.0, 102.0]);
+    assert_eq!(bars.open, bars.close);
+    assert_eq!(bars.high, bars.close);
+    assert_eq!(bars.low, bars.close);
+    assert_eq!(bars.close2, vec![0.0; 3]);
+    assert_eq!(bars.date[1], "2024-01-02T10:00:02Z");
+}
+
+#[test]
+fn close2_is_the_latest_secondary_mid() {
+    let bars = ticks_to_bars(&ticks(), "A", Some("B"));
+    // the first A quote has no B price yet
+    assert_eq!(bars.close, vec![101.0, 102.0]);
+    assert_eq!(bars.close2, vec![50.0, 51.0]);
+}
+
+#[test]
+fn indexed_bars_point_at_their_quotes() {
+    let (bars, sources) = ticks_to_bars_indexed(&ticks(), "A", Some("B"));
+    assert_eq!(bars.close.len(), sources.len());
+    assert_eq!(sources, vec![2, 5]);
+}
+
+#[test]
+fn elapsed_time_volatility_weighs_returns_by_their_interval() {
+    let dates: Vec<String> = ["2024-01-02T10:00:00Z", "2024-01-02T10:00:01Z", "2024-01-02T10:00:03Z"]
+        .iter().map(|d| d.to_string()).collect();
+    // a steady drift, twice as large over the twice as long interval, has no volatility
+    assert!(elapsed_time_volatility(&[0.01, 0.02], &dates).abs() < 1e-12);
+
+    let dates: Vec<String> = ["2024-01-02T10:00:00Z", "2024-01-02T10:00:01Z", "2024-01-02T10:00:02Z"]
+        .iter().map(|d| d.to_string()).collect();
+    // 1e-4 of variance per second, over a year of seconds
+    let expected = (1e-4_f64 * 365.0 * 24.0 * 3600.0).sqrt();
+    assert!((elapsed_time_volatility(&[0.01, -0.01], &dates) - expected).abs() < 1e-9);
+}
+
+#[test]
+fn too_few_returns_have_no_volatility() {
+    let dates = vec!["2024-01-02T10:00:00Z".to_string(), "2024-01-02T10:00:01Z".to_string()];
+    assert_eq!(elapsed_time_volatility(&[0.01], &dates), 0.0);
+}
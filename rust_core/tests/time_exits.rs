@@ -0,0 +1,69 @@
+// time exits: the broker closes a trade once it has been held for max_holding_bars (at that bar's close)
+// or, live, for max_holding_duration on the broker clock
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use common::{live_broker, quote, run, FLAT};
+use rust_core::clock::TestClock;
+use rust_core::engine::Order;
+use rust_core::live_engine::Order as LiveOrder;
+use std::sync::Arc;
+
+// filled at the open of bar 1 at 100, closes 103 and 105 on bars 2 and 3
+const BARS: [common::Bar; 5] = [FLAT, FLAT, (100.0, 104.0, 99.0, 103.0), (103.0, 106.0, 102.0, 105.0), FLAT];
+
+#[test]
+fn a_trade_is_closed_at_the_close_of_its_last_bar() {
+    let broker = run(&BARS, Order { size: 10.0, instrument: 1, max_holding_bars: Some(2), ..Default::default() });
+    assert!(broker.trades.is_empty());
+    let trade = &broker.closed_trades[0];
+    assert_eq!((trade.entry_index, trade.exit_index), (1, Some(3)));
+    assert_eq!(trade.exit_price, Some(105.0));
+    assert_eq!(trade.exit_tag.as_deref(), Some("time exit"));
+    assert_eq!(trade.pnl(), 50.0);
+}
+
+#[test]
+fn the_time_exit_cancels_the_stop_loss() {
+    let broker = run(&BARS, Order { size: 10.0, sl: Some(90.0), instrument: 1, max_holding_bars: Some(2), ..Default::default() });
+    assert_eq!(broker.closed_trades.len(), 1);
+    assert!(broker.orders.is_empty());
+    assert_eq!(broker.cancelled_orders.len(), 1);
+    assert_eq!(broker.cancelled_orders[0].1.tag.as_deref(), Some("stop-loss"));
+}
+
+#[test]
+fn trades_without_a_holding_limit_stay_open() {
+    let broker = run(&BARS, Order { size: 10.0, instrument: 1, ..Default::default() });
+    assert_eq!(broker.trades.len(), 1);
+    assert!(broker.closed_trades.is_empty());
+}
+
+#[test]
+fn live_trades_expire_on_the_broker_clock() {
+    let clock = Arc::new(TestClock::new(Utc.with_ymd_and_hms(2024, 1, 2, 10, 0, 0).unwrap()));
+    let mut broker = live_broker();
+    broker.clock = clock.clone();
+    quote(&mut broker, "A", 100.0, 101.0);
+    let order = LiveOrder {
+        size: 10.0,
+        instrument: "A".to_string(),
+        max_holding_duration: Some(Duration::minutes(5)),
+        ..Default::default()
+    };
+    broker.new_order(order, 101.0).unwrap();
+    broker.next(0);
+    assert_eq!(broker.trades.len(), 1);
+
+    clock.advance(Duration::minutes(4));
+    quote(&mut broker, "A", 102.0, 103.0);
+    broker.next(1);
+    assert_eq!(broker.trades.len(), 1);
+
+    clock.advance(Duration::minutes(1));
+    broker.next(2);
+    assert!(broker.trades.is_empty());
+    let trade = &broker.closed_trades[0];
+    assert_eq!(trade.exit_price, Some(102.0));
+    assert_eq!(trade.exit_tag.as_deref(), Some("time exit"));
+}
@@ -0,0 +1,42 @@
+// top_drawdowns: peak-to-recovery episodes of an equity curve, deepest first
+use rust_core::stats::top_drawdowns;
+
+fn dates(n: usize) -> Vec<String> {
+    (0..n).map(|i| format!("2024-01-{:02}", i + 1)).collect()
+}
+
+// a 10% drawdown that recovers, a 5% one that recovers and a 20% one still open at the end
+const EQUITY: [f64; 10] = [100.0, 95.0, 90.0, 100.0, 105.0, 99.75, 110.0, 100.0, 88.0, 95.0];
+
+#[test]
+fn episodes_are_ordered_deepest_first() {
+    let drawdowns = top_drawdowns(&EQUITY, &dates(EQUITY.len()), 5);
+    let depths: Vec<f64> = drawdowns.iter().map(|d| (d.depth_pct * 100.0).round() / 100.0).collect();
+    assert_eq!(depths, vec![-20.0, -10.0, -5.0]);
+}
+
+#[test]
+fn each_episode_runs_from_its_peak_to_its_recovery() {
+    let drawdowns = top_drawdowns(&EQUITY, &dates(EQUITY.len()), 5);
+    let first = &drawdowns[1];
+    assert_eq!((first.start_index, first.trough_index, first.recovery_index), (0, 2, Some(3)));
+    assert_eq!((first.start_date.as_str(), first.trough_date.as_str()), ("2024-01-01", "2024-01-03"));
+    assert_eq!(first.recovery_date.as_deref(), Some("2024-01-04"));
+    assert_eq!(first.duration(EQUITY.len() - 1), 3);
+}
+
+#[test]
+fn an_open_drawdown_lasts_until_the_end_of_the_data() {
+    let drawdowns = top_drawdowns(&EQUITY, &dates(EQUITY.len()), 1);
+    assert_eq!(drawdowns.len(), 1);
+    let open = &drawdowns[0];
+    assert_eq!((open.start_index, open.trough_index, open.recovery_index), (6, 8, None));
+    assert_eq!(open.recovery_date, None);
+    assert_eq!(open.duration(EQUITY.len() - 1), 3);
+}
+
+#[test]
+fn a_curve_that_never_falls_has_no_drawdowns() {
+    assert!(top_drawdowns(&[100.0, 100.0, 101.0, 102.0], &dates(4), 5).is_empty());
+    assert!(top_drawdowns(&[], &[], 5).is_empty());
+}
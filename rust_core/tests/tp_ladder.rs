@@ -0,0 +1,60 @@
+// take-profit ladders: every rung closes its fraction of the original size at its limit, and what no rung
+// covers stays open with the stop loss
+mod common;
+
+use common::{run, FLAT};
+use rust_core::engine::{Order, TakeProfitLevel};
+
+fn laddered(size: f64, sl: f64, tp_ladder: Vec<TakeProfitLevel>) -> Order {
+    Order { size, sl: Some(sl), instrument: 1, tp_ladder, ..Default::default() }
+}
+
+fn rungs(levels: &[(f64, f64)]) -> Vec<TakeProfitLevel> {
+    levels.iter().map(|&(price, fraction)| TakeProfitLevel { price, fraction }).collect()
+}
+
+#[test]
+fn sigma_levels_are_on_the_side_of_the_trade() {
+    let prices = |size: f64| -> Vec<f64> {
+        TakeProfitLevel::from_sigma(100.0, 2.0, size, &[(1.0, 0.5), (2.0, 0.5)]).iter().map(|l| l.price).collect()
+    };
+    assert_eq!(prices(10.0), vec![102.0, 104.0]);
+    assert_eq!(prices(-10.0), vec![98.0, 96.0]);
+}
+
+#[test]
+fn the_first_rung_scales_out_and_leaves_the_rest_with_its_stop() {
+    let broker = run(&[FLAT, FLAT, (100.0, 112.0, 99.0, 111.0), FLAT], laddered(10.0, 90.0, rungs(&[(110.0, 0.5), (120.0, 0.5)])));
+    assert_eq!(broker.closed_trades.len(), 1);
+    let rung = &broker.closed_trades[0];
+    assert_eq!((rung.size, rung.exit_price), (5.0, Some(110.0)));
+    assert_eq!(rung.exit_tag.as_deref(), Some("take-profit 1/2"));
+
+    assert_eq!(broker.trades[0].size, 5.0);
+    assert_eq!(broker.stop_level(0), Some(90.0));
+    let waiting: Vec<&str> = broker.contingent_orders(0).iter().filter_map(|o| o.tag.as_deref()).collect();
+    assert_eq!(waiting, vec!["stop-loss", "take-profit 2/2"]);
+}
+
+#[test]
+fn a_short_ladder_buys_back_below_the_entry() {
+    let ladder = TakeProfitLevel::from_sigma(100.0, 2.0, -10.0, &[(1.0, 0.5), (2.0, 0.5)]);
+    let broker = run(&[FLAT, FLAT, (100.0, 101.0, 95.0, 96.0)], laddered(-10.0, 110.0, ladder));
+    assert!(broker.trades.is_empty());
+    let exits: Vec<(f64, Option<f64>)> = broker.closed_trades.iter().map(|t| (t.size, t.exit_price)).collect();
+    assert_eq!(exits, vec![(-5.0, Some(98.0)), (-5.0, Some(96.0))]);
+    assert_eq!(broker.closed_trades.iter().map(|t| t.pnl()).sum::<f64>(), 30.0);
+    // the filled ladder took the stop loss with it
+    assert_eq!(broker.cancelled_orders.len(), 1);
+    assert!(broker.orders.is_empty());
+}
+
+#[test]
+fn rungs_are_sized_off_the_original_trade() {
+    // a quarter of 10 units at each rung, both reached: half the trade is left with the stop
+    let broker = run(&[FLAT, FLAT, (100.0, 125.0, 99.0, 121.0)], laddered(10.0, 90.0, rungs(&[(110.0, 0.25), (120.0, 0.25)])));
+    let sizes: Vec<f64> = broker.closed_trades.iter().map(|t| t.size).collect();
+    assert_eq!(sizes, vec![2.5, 2.5]);
+    assert_eq!(broker.trades[0].size, 5.0);
+    assert_eq!(broker.stop_level(0), Some(90.0));
+}
@@ -0,0 +1,101 @@
+// trailing stops: the stop loss follows the best price of the trade and never moves back, in points or
+// percent, on both brokers
+mod common;
+
+use common::{broker, live_broker, quote, Bar, FLAT};
+use rust_core::engine::{Broker, Order, TrailingStop};
+use rust_core::live_engine::Order as LiveOrder;
+
+fn trailing(size: f64, sl: Option<f64>, trailing: TrailingStop) -> Order {
+    Order {
+        size,
+        sl,
+        instrument: 1,
+        trailing: Some(trailing),
+        ..Default::default()
+    }
+}
+
+// enter at the open of bar 1 and run up to and including bar `last`, returning the stop level of trade 0
+// after each bar
+fn run(bars: &[Bar], order: Order, last: usize) -> (Broker, Vec<Option<f64>>) {
+    let mut broker = broker(bars);
+    broker.next(0);
+    broker.new_order(order, 100.0).unwrap();
+    let mut levels = Vec::new();
+    for index in 1..=last {
+        broker.next(index);
+        levels.push(broker.stop_level(0));
+    }
+    (broker, levels)
+}
+
+#[test]
+fn points_stop_ratchets_up_and_closes_the_trade() {
+    let bars = [FLAT, FLAT, (101.0, 105.0, 100.5, 104.0), (104.0, 104.5, 102.0, 103.0), FLAT];
+    let (broker, levels) = run(&bars, trailing(10.0, None, TrailingStop::Points(2.0)), 3);
+    // starts at 98 from the fill, raised to 99 by bar 1's high (it can't trigger on its own bar) and 103 by bar 2's
+    assert_eq!(levels, [Some(99.0), Some(103.0), None]);
+    assert!(broker.trades.is_empty());
+    let trade = &broker.closed_trades[0];
+    assert_eq!(trade.exit_index, Some(3));
+    // a triggered stop fills like a market order, at the bar's open
+    assert_eq!(trade.exit_price, Some(104.0));
+    assert_eq!(trade.exit_tag.as_deref(), Some("stop-loss"));
+}
+
+#[test]
+fn stop_never_loosens() {
+    let bars = [FLAT, (100.0, 110.0, 99.5, 109.0), (109.0, 109.5, 109.0, 109.2), (109.2, 109.4, 109.0, 109.1)];
+    let (broker, levels) = run(&bars, trailing(10.0, None, TrailingStop::Percent(1.0)), 3);
+    // 1% below the 110 high stays in place while the following highs are lower
+    assert_eq!(levels, [Some(108.9), Some(108.9), Some(108.9)]);
+    assert_eq!(broker.trades.len(), 1);
+}
+
+#[test]
+fn short_stop_trails_the_lows() {
+    let bars = [FLAT, (100.0, 100.5, 95.0, 96.0), (95.5, 95.9, 94.0, 95.0), FLAT];
+    let (broker, levels) = run(&bars, trailing(-10.0, None, TrailingStop::Points(1.0)), 3);
+    assert_eq!(levels, [Some(96.0), Some(95.0), None]);
+    let trade = &broker.closed_trades[0];
+    assert_eq!(trade.exit_index, Some(3));
+    assert_eq!(trade.exit_price, Some(100.0));
+}
+
+#[test]
+fn tighter_sl_is_the_starting_level() {
+    let (_, levels) = run(&[FLAT, (100.0, 100.0, 99.5, 100.0)], trailing(10.0, Some(99.0), TrailingStop::Points(5.0)), 1);
+    assert_eq!(levels, [Some(99.0)]);
+}
+
+#[test]
+fn live_stop_trails_the_bid() {
+    let mut broker = live_broker();
+    quote(&mut broker, "A", 100.0, 100.1);
+    let order = LiveOrder {
+        size: 10.0,
+        instrument: "A".to_string(),
+        trailing: Some(TrailingStop::Points(1.0)),
+        ..Default::default()
+    };
+    broker.new_order(order, 100.1).unwrap();
+    broker.next(0);
+    assert_eq!(broker.trades.len(), 1);
+    let start = broker.stop_level(0).unwrap();
+
+    quote(&mut broker, "A", 102.0, 102.1);
+    broker.next(1);
+    assert_eq!(broker.stop_level(0), Some(101.0));
+    assert!(start < 101.0);
+
+    // a pullback above the stop leaves it where it is
+    quote(&mut broker, "A", 101.5, 101.6);
+    broker.next(2);
+    assert_eq!(broker.stop_level(0), Some(101.0));
+
+    quote(&mut broker, "A", 100.9, 101.0);
+    broker.next(3);
+    assert!(broker.trades.is_empty());
+    assert_eq!(broker.stop_level(0), None);
+}
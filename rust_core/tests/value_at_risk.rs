@@ -0,0 +1,68 @@
+// historical var / es: positions revalued under past returns, the loss tail read off the sorted scenarios,
+// and the broker's var budget refusing entries that would push var above it
+mod common;
+
+use common::{broker, Bar};
+use rust_core::engine::{Broker, Order, OrderError};
+use rust_core::risk::{historical_var, VarConfig};
+
+// ten scenarios, the worst two -5% and -2%
+fn returns() -> Vec<f64> {
+    vec![0.01, -0.05, 0.03, 0.0, -0.02, 0.01, 0.02, -0.01, 0.0, 0.01]
+}
+
+#[test]
+fn var_is_the_loss_at_the_tail_and_es_its_average() {
+    let long = [(1_000.0, returns())];
+    let estimate = historical_var(&long, 0.9);
+    assert!((estimate.var - 50.0).abs() < 1e-9);
+    assert!((estimate.es - 50.0).abs() < 1e-9);
+    let estimate = historical_var(&long, 0.8);
+    assert!((estimate.var - 20.0).abs() < 1e-9);
+    assert!((estimate.es - 35.0).abs() < 1e-9);
+}
+
+#[test]
+fn a_short_loses_on_the_up_moves() {
+    let estimate = historical_var(&[(-1_000.0, returns())], 0.9);
+    assert!((estimate.var - 30.0).abs() < 1e-9);
+}
+
+#[test]
+fn offsetting_positions_have_no_var() {
+    let estimate = historical_var(&[(1_000.0, returns()), (-1_000.0, returns())], 0.9);
+    assert_eq!((estimate.var, estimate.es), (0.0, 0.0));
+    assert_eq!(historical_var(&[], 0.95).var, 0.0);
+}
+
+// closes alternating 100 and 95: the worst scenario is -5%
+fn swinging() -> Broker {
+    let bars: Vec<Bar> = (0..6).map(|i| if i % 2 == 0 { 100.0 } else { 95.0 }).map(|c| (c, c + 1.0, c - 1.0, c)).collect();
+    let mut broker = broker(&bars);
+    broker.var_config = Some(VarConfig { lookback: 250, confidence: 0.95, budget_pct: Some(1.0) });
+    for index in 0..5 {
+        broker.next(index);
+    }
+    broker
+}
+
+#[test]
+fn the_budget_refuses_entries_above_it() {
+    let mut broker = swinging();
+    // 5% of 30k is 1.5k, over 1% of the 100k account
+    let big = Order { size: 300.0, instrument: 1, ..Default::default() };
+    assert_eq!(broker.new_order(big, 100.0), Err(OrderError::VarBudgetExceeded));
+    // 5% of 10k is 500
+    assert!(broker.new_order(Order { size: 100.0, instrument: 1, ..Default::default() }, 100.0).is_ok());
+}
+
+#[test]
+fn the_estimate_is_recorded_every_bar() {
+    let mut broker = swinging();
+    broker.new_order(Order { size: 100.0, instrument: 1, ..Default::default() }, 100.0).unwrap();
+    broker.next(5);
+    assert_eq!(broker.var_history.len(), 6);
+    assert_eq!(broker.var_history[4].var, 0.0);
+    // 100 units at 95 after returns of -5% and +5.26%
+    assert!((broker.var_history[5].var - 475.0).abs() < 1e-9, "{}", broker.var_history[5].var);
+}
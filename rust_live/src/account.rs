@@ -0,0 +1,111 @@
+// account balance from saxo's portfolio service, used to fund a live session with the real account value
+// instead of the configured cash
+use dotenv::dotenv;
+use std::env;
+use std::fmt;
+use reqwest::Client;
+use serde_json::Value;
+use crate::config::LiveConfig;
+
+const BALANCES_URL: &str = "https://gateway.saxobank.com/sim/openapi/port/v1/balances/me";
+
+#[derive(Clone, Debug)]
+pub struct AccountBalance {
+    pub currency: String,
+    pub total_value: f64,              // cash plus the value of open positions
+    pub cash_balance: f64,
+    pub margin_available: f64,         // margin available for trading
+    pub margin_used: f64,              // margin used by current positions
+    pub margin_net_exposure: f64,      // net exposure the used margin is charged on
+}
+
+impl AccountBalance {
+    // margin rate the account is charged on its current positions, None without open positions
+    pub fn margin_rate(&self) -> Option<f64> {
+        if self.margin_net_exposure > 0.0 && self.margin_used > 0.0 {
+            Some((self.margin_used / self.margin_net_exposure).min(1.0))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AccountError {
+    Request(String),
+    MissingField(String),
+}
+
+impl fmt::Display for AccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountError::Request(reason) => write!(f, "account balance request failed: {}", reason),
+            AccountError::MissingField(field) => write!(f, "account balance response has no {}", field),
+        }
+    }
+}
+
+impl std::error::Error for AccountError {}
+
+// parse a balances response; TotalValue is required, the other figures default to zero
+pub fn parse_balance_response(response: &Value) -> Result<AccountBalance, AccountError> {
+    let number = |field: &str| response.get(field).and_then(|v| v.as_f64());
+    Ok(AccountBalance {
+        currency: response.get("Currency").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+        total_value: number("TotalValue").ok_or_else(|| AccountError::MissingField("TotalValue".to_string()))?,
+        cash_balance: number("CashBalance").unwrap_or(0.0),
+        margin_available: number("MarginAvailableForTrading").unwrap_or(0.0),
+        margin_used: number("MarginUsedByCurrentPositions").unwrap_or(0.0),
+        margin_net_exposure: number("MarginNetExposure").unwrap_or(0.0),
+    })
+}
+
+/// balance of the account the access token belongs to
+pub async fn fetch_balance(client: &Client, access_token: &str) -> Result<AccountBalance, AccountError> {
+    let response = client
+        .get(BALANCES_URL)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AccountError::Request(e.to_string()))?;
+    let body: Value = response.json().await.map_err(|e| AccountError::Request(e.to_string()))?;
+    parse_balance_response(&body)
+}
+
+/// set the session's cash to `account_allocation` of the account's total value, and its margin to the
+/// rate charged on the account's open positions (if it has any). without an ACCESS_TOKEN, or if the
+/// request fails, the configured cash and margin are kept and None is returned.
+pub async fn fund_from_account(config: &mut LiveConfig) -> Option<AccountBalance> {
+    if !config.fund_from_account {
+        return None;
+    }
+    dotenv().ok();
+    let access_token = match env::var("ACCESS_TOKEN") {
+        Ok(token) => token,
+        Err(_) => {
+            eprintln!("account funding skipped: missing ACCESS_TOKEN in .env, using configured cash {}", config.cash);
+            return None;
+        }
+    };
+    let balance = match fetch_balance(&Client::new(), &access_token).await {
+        Ok(balance) => balance,
+        Err(e) => {
+            eprintln!("{}, using configured cash {}", e, config.cash);
+            return None;
+        }
+    };
+    if balance.total_value <= 0.0 {
+        eprintln!("account value is {}, using configured cash {}", balance.total_value, config.cash);
+        return None;
+    }
+    config.cash = balance.total_value * config.account_allocation;
+    if let Some(rate) = balance.margin_rate() {
+        config.margin = rate;
+    }
+    println!(
+        "funded from account: {:.2} {} of {:.2} (allocation {:.2}), margin {}",
+        config.cash, balance.currency, balance.total_value, config.account_allocation, config.margin
+    );
+    Some(balance)
+}
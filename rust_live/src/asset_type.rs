@@ -0,0 +1,65 @@
+// saxo asset classes that can be subscribed to; names match the openapi AssetType values
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Default)]
+pub enum AssetType {
+    #[default]
+    CfdOnIndex,
+    CfdOnStock,
+    CfdOnFutures,
+    CfdOnEtf,
+    FxSpot,
+    Stock,
+    Etf,
+    ContractFutures,
+    Bond,
+}
+
+impl AssetType {
+    pub const ALL: [AssetType; 9] = [
+        AssetType::CfdOnIndex,
+        AssetType::CfdOnStock,
+        AssetType::CfdOnFutures,
+        AssetType::CfdOnEtf,
+        AssetType::FxSpot,
+        AssetType::Stock,
+        AssetType::Etf,
+        AssetType::ContractFutures,
+        AssetType::Bond,
+    ];
+
+    // value expected by the saxo api
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetType::CfdOnIndex => "CfdOnIndex",
+            AssetType::CfdOnStock => "CfdOnStock",
+            AssetType::CfdOnFutures => "CfdOnFutures",
+            AssetType::CfdOnEtf => "CfdOnEtf",
+            AssetType::FxSpot => "FxSpot",
+            AssetType::Stock => "Stock",
+            AssetType::Etf => "Etf",
+            AssetType::ContractFutures => "ContractFutures",
+            AssetType::Bond => "Bond",
+        }
+    }
+}
+
+impl fmt::Display for AssetType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AssetType {
+    type Err = String;
+
+    // case-insensitive so env values like "fxspot" work
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AssetType::ALL.iter()
+            .find(|a| a.as_str().eq_ignore_ascii_case(s.trim()))
+            .copied()
+            .ok_or_else(|| format!("unknown asset type '{}'", s))
+    }
+}
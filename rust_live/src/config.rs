@@ -0,0 +1,286 @@
+// runtime configuration for the live engine, read from an optional json file and env vars.
+// env vars take precedence over the file so containers can override single values.
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use crate::asset_type::AssetType;
+use rust_core::console::{ConsoleReporter, LogLevel};
+use rust_core::live_engine::EquityThrottle;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct InstrumentConfig {
+    pub reference_id: String,
+    #[serde(default)]
+    pub uic: Option<i32>,    // None: looked up from reference_id at startup
+    #[serde(default)]
+    pub asset_type: AssetType,
+}
+
+impl InstrumentConfig {
+    // uic after startup resolution
+    pub fn resolved_uic(&self) -> i32 {
+        self.uic.expect("uic not resolved; call instruments::resolve_config first")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LiveConfig {
+    pub instruments: Vec<InstrumentConfig>,
+    pub strategy: String,
+    pub cash: f64,
+    pub margin: f64,
+    pub fund_from_account: bool,        // replace cash (and margin) with the saxo account's balance at startup
+    pub account_allocation: f64,        // fraction of the account value given to the session, in (0, 1]
+    pub port: u16,
+    pub bind_address: String,
+    pub headless: bool,                 // no chart server, console output only
+    pub recording_dir: Option<String>,  // where chart sessions are recorded, None disables recording
+    pub backfill_bars: usize,           // bars of history fetched at startup, 0 disables backfill
+    pub backfill_horizon: u32,          // bar length in minutes for the backfill
+    pub ml_device: String,              // inference device for ml strategies: auto, cpu, mps, cuda or cuda:N
+    pub ml_threads: usize,              // intra-op threads for ml inference, 0 keeps the libtorch default
+    pub log_level: String,              // console level: debug, info, warn or error
+    pub summary_seconds: u64,           // stats summary at most every N seconds, 0 disables the time trigger
+    pub summary_ticks: usize,           // stats summary every N ticks, 0 disables the tick trigger
+    pub equity_ms: u64,                 // chart equity update at most every N milliseconds, 0 disables the time trigger
+    pub equity_ticks: usize,            // chart equity update every N ticks, 0 disables the tick trigger
+}
+
+impl Default for LiveConfig {
+    fn default() -> Self {
+        LiveConfig {
+            instruments: vec![
+                InstrumentConfig { reference_id: "US500".to_string(), uic: Some(4913), asset_type: AssetType::CfdOnIndex },
+                InstrumentConfig { reference_id: "DJIA".to_string(), uic: Some(4911), asset_type: AssetType::CfdOnIndex },
+            ],
+            strategy: "statarb_spread".to_string(),
+            cash: 100_000.0,
+            margin: 0.05,
+            fund_from_account: true,
+            account_allocation: 1.0,
+            port: 3000,
+            bind_address: "127.0.0.1".to_string(),
+            headless: false,
+            recording_dir: Some("sessions".to_string()),
+            backfill_bars: 50,
+            backfill_horizon: 1,
+            ml_device: "auto".to_string(),
+            ml_threads: 0,
+            log_level: "info".to_string(),
+            summary_seconds: 10,
+            summary_ticks: 0,
+            equity_ms: 0,
+            equity_ticks: 0,
+        }
+    }
+}
+
+// strategies that can be selected by name
+pub const STRATEGIES: &[&str] = &["statarb_spread", "statarb_pairs", "ml_statarb_spread"];
+
+#[derive(Debug)]
+pub enum ConfigError {
+    File(String, String),     // path, reason
+    Env(String, String),      // variable, reason
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::File(path, reason) => write!(f, "failed to read config file {}: {}", path, reason),
+            ConfigError::Env(var, reason) => write!(f, "invalid value for {}: {}", var, reason),
+            ConfigError::Invalid(reason) => write!(f, "invalid configuration: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// parse an env var if it is set
+fn env_value<T: std::str::FromStr>(var: &str) -> Result<Option<T>, ConfigError> {
+    match env::var(var) {
+        Ok(raw) => raw.trim().parse::<T>()
+            .map(Some)
+            .map_err(|_| ConfigError::Env(var.to_string(), format!("could not parse '{}'", raw))),
+        Err(_) => Ok(None),
+    }
+}
+
+// parse "US500:4913,EURUSD:21:FxSpot" or "US500" (uic looked up); the asset type defaults to CfdOnIndex
+fn parse_instruments(raw: &str) -> Result<Vec<InstrumentConfig>, ConfigError> {
+    let env_error = |reason: String| ConfigError::Env("RUST_LIVE_INSTRUMENTS".to_string(), reason);
+    raw.split(',')
+        .filter(|part| !part.trim().is_empty())
+        .map(|part| {
+            let fields: Vec<&str> = part.trim().split(':').map(|f| f.trim()).collect();
+            let (reference_id, uic, asset_type) = match fields.as_slice() {
+                [reference_id] => (*reference_id, "", None),
+                [reference_id, uic] => (*reference_id, *uic, None),
+                [reference_id, uic, asset_type] => (*reference_id, *uic, Some(*asset_type)),
+                _ => return Err(env_error(format!("expected REFERENCE[:UIC[:ASSET_TYPE]], got '{}'", part))),
+            };
+            let uic = if uic.is_empty() {
+                None
+            } else {
+                Some(uic.parse::<i32>().map_err(|_| env_error(format!("uic '{}' is not a number", uic)))?)
+            };
+            let asset_type = match asset_type {
+                Some(raw) => raw.parse::<AssetType>().map_err(env_error)?,
+                None => AssetType::default(),
+            };
+            Ok(InstrumentConfig { reference_id: reference_id.to_string(), uic, asset_type })
+        })
+        .collect()
+}
+
+impl LiveConfig {
+    /// load configuration: defaults, then the json file in RUST_LIVE_CONFIG (if set), then env vars
+    ///
+    /// RUST_LIVE_INSTRUMENTS  "US500:4913,DJIA:4911" or "EURUSD:21:FxSpot" (asset type defaults to CfdOnIndex)
+    /// RUST_LIVE_STRATEGY     one of STRATEGIES
+    /// RUST_LIVE_CASH, RUST_LIVE_MARGIN, RUST_LIVE_PORT, RUST_LIVE_BIND
+    /// RUST_LIVE_FUND_FROM_ACCOUNT  true/false, RUST_LIVE_ACCOUNT_ALLOCATION  fraction of the account value
+    /// RUST_LIVE_HEADLESS     true/false
+    /// RUST_LIVE_RECORDING_DIR  directory, or empty to disable recording
+    /// RUST_LIVE_BACKFILL_BARS, RUST_LIVE_BACKFILL_HORIZON  history preloaded at startup
+    /// RUST_LIVE_ML_DEVICE, RUST_LIVE_ML_THREADS  inference device and intra-op threads for ml strategies
+    /// RUST_LIVE_LOG_LEVEL    debug, info, warn or error
+    /// RUST_LIVE_SUMMARY_SECS, RUST_LIVE_SUMMARY_TICKS  how often the stats summary is printed (0 disables either)
+    /// RUST_LIVE_EQUITY_MS, RUST_LIVE_EQUITY_TICKS  how often equity is sent to the chart (0 disables either)
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match env::var("RUST_LIVE_CONFIG") {
+            Ok(path) => Self::from_file(&path)?,
+            Err(_) => LiveConfig::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let raw = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::File(path.to_string(), e.to_string()))?;
+        serde_json::from_str(&raw).map_err(|e| ConfigError::File(path.to_string(), e.to_string()))
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Ok(raw) = env::var("RUST_LIVE_INSTRUMENTS") {
+            self.instruments = parse_instruments(&raw)?;
+        }
+        if let Ok(strategy) = env::var("RUST_LIVE_STRATEGY") {
+            self.strategy = strategy.trim().to_string();
+        }
+        if let Some(cash) = env_value::<f64>("RUST_LIVE_CASH")? {
+            self.cash = cash;
+        }
+        if let Some(margin) = env_value::<f64>("RUST_LIVE_MARGIN")? {
+            self.margin = margin;
+        }
+        if let Some(fund) = env_value::<bool>("RUST_LIVE_FUND_FROM_ACCOUNT")? {
+            self.fund_from_account = fund;
+        }
+        if let Some(allocation) = env_value::<f64>("RUST_LIVE_ACCOUNT_ALLOCATION")? {
+            self.account_allocation = allocation;
+        }
+        if let Some(port) = env_value::<u16>("RUST_LIVE_PORT")? {
+            self.port = port;
+        }
+        if let Ok(bind) = env::var("RUST_LIVE_BIND") {
+            self.bind_address = bind.trim().to_string();
+        }
+        if let Some(headless) = env_value::<bool>("RUST_LIVE_HEADLESS")? {
+            self.headless = headless;
+        }
+        if let Ok(dir) = env::var("RUST_LIVE_RECORDING_DIR") {
+            self.recording_dir = if dir.trim().is_empty() { None } else { Some(dir.trim().to_string()) };
+        }
+        if let Some(bars) = env_value::<usize>("RUST_LIVE_BACKFILL_BARS")? {
+            self.backfill_bars = bars;
+        }
+        if let Some(horizon) = env_value::<u32>("RUST_LIVE_BACKFILL_HORIZON")? {
+            self.backfill_horizon = horizon;
+        }
+        if let Ok(device) = env::var("RUST_LIVE_ML_DEVICE") {
+            self.ml_device = device.trim().to_string();
+        }
+        if let Some(threads) = env_value::<usize>("RUST_LIVE_ML_THREADS")? {
+            self.ml_threads = threads;
+        }
+        if let Ok(level) = env::var("RUST_LIVE_LOG_LEVEL") {
+            self.log_level = level.trim().to_string();
+        }
+        if let Some(seconds) = env_value::<u64>("RUST_LIVE_SUMMARY_SECS")? {
+            self.summary_seconds = seconds;
+        }
+        if let Some(ticks) = env_value::<usize>("RUST_LIVE_SUMMARY_TICKS")? {
+            self.summary_ticks = ticks;
+        }
+        if let Some(ms) = env_value::<u64>("RUST_LIVE_EQUITY_MS")? {
+            self.equity_ms = ms;
+        }
+        if let Some(ticks) = env_value::<usize>("RUST_LIVE_EQUITY_TICKS")? {
+            self.equity_ticks = ticks;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.instruments.is_empty() || self.instruments.len() > 2 {
+            return Err(ConfigError::Invalid(format!(
+                "expected one or two instruments, got {}", self.instruments.len()
+            )));
+        }
+        if self.instruments.iter().any(|i| i.reference_id.is_empty()) {
+            return Err(ConfigError::Invalid("instrument reference id must not be empty".to_string()));
+        }
+        if !STRATEGIES.contains(&self.strategy.as_str()) {
+            return Err(ConfigError::Invalid(format!(
+                "unknown strategy '{}', expected one of {:?}", self.strategy, STRATEGIES
+            )));
+        }
+        if self.cash <= 0.0 {
+            return Err(ConfigError::Invalid("cash must be positive".to_string()));
+        }
+        if self.margin <= 0.0 || self.margin > 1.0 {
+            return Err(ConfigError::Invalid("margin must be in (0, 1]".to_string()));
+        }
+        if self.account_allocation <= 0.0 || self.account_allocation > 1.0 {
+            return Err(ConfigError::Invalid("account allocation must be in (0, 1]".to_string()));
+        }
+        if self.bind_address.parse::<std::net::IpAddr>().is_err() {
+            return Err(ConfigError::Invalid(format!("bind address '{}' is not an ip address", self.bind_address)));
+        }
+        if self.backfill_horizon == 0 {
+            return Err(ConfigError::Invalid("backfill horizon must be at least one minute".to_string()));
+        }
+        let device = self.ml_device.to_ascii_lowercase();
+        let valid_device = ["auto", "cpu", "mps", "cuda"].contains(&device.as_str())
+            || device.strip_prefix("cuda:").is_some_and(|index| index.parse::<usize>().is_ok());
+        if !valid_device {
+            return Err(ConfigError::Invalid(format!(
+                "unknown ml device '{}', expected auto, cpu, mps, cuda or cuda:N", self.ml_device
+            )));
+        }
+        self.log_level.parse::<LogLevel>().map_err(ConfigError::Invalid)?;
+        Ok(())
+    }
+
+    // console reporter for the live broker; with both summary triggers at 0 the summary prints every tick
+    pub fn reporter(&self) -> ConsoleReporter {
+        ConsoleReporter::new(
+            self.log_level.parse().unwrap_or(LogLevel::Info),
+            (self.summary_seconds > 0).then(|| chrono::Duration::seconds(self.summary_seconds as i64)),
+            (self.summary_ticks > 0).then_some(self.summary_ticks),
+        )
+    }
+
+    // throttle of the chart's equity updates; with both triggers at 0 equity is sent after every batch of ticks
+    pub fn equity_throttle(&self) -> EquityThrottle {
+        EquityThrottle {
+            interval: (self.equity_ms > 0).then(|| chrono::Duration::milliseconds(self.equity_ms as i64)),
+            ticks: (self.equity_ticks > 0).then_some(self.equity_ticks),
+        }
+    }
+}
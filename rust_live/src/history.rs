@@ -0,0 +1,94 @@
+// startup backfill: fetch recent bars from saxo's chart endpoint so strategies start with a full lookback
+use dotenv::dotenv;
+use std::env;
+use std::error::Error;
+use reqwest::Client;
+use serde_json::Value;
+use rust_core::clock::parse_quote_time;
+use rust_core::live_engine::TickSnapshot;
+use crate::asset_type::AssetType;
+
+const CHART_URL: &str = "https://gateway.saxobank.com/sim/openapi/chart/v1/charts";
+
+// turn a chart response into one tick per bar, using the bar close (bid/ask if present, else close)
+pub fn parse_chart_response(reference_id: &str, response: &Value) -> Vec<TickSnapshot> {
+    let bars = match response.get("Data").and_then(|d| d.as_array()) {
+        Some(bars) => bars,
+        None => return Vec::new(),
+    };
+
+    bars.iter()
+        .filter_map(|bar| {
+            let date = bar.get("Time").and_then(|v| v.as_str())?.to_string();
+            let (ask, bid) = match (
+                bar.get("CloseAsk").and_then(|v| v.as_f64()),
+                bar.get("CloseBid").and_then(|v| v.as_f64()),
+            ) {
+                (Some(ask), Some(bid)) => (ask, bid),
+                _ => {
+                    let close = bar.get("Close").and_then(|v| v.as_f64())?;
+                    (close, close)
+                }
+            };
+            Some(TickSnapshot { instrument: reference_id.to_string(), date, ask, bid })
+        })
+        .collect()
+}
+
+// fetch the last `count` bars of `horizon` minutes for one instrument
+pub async fn fetch_history(
+    client: &Client,
+    access_token: &str,
+    reference_id: &str,
+    uic: i32,
+    asset_type: AssetType,
+    horizon: u32,
+    count: usize,
+) -> Result<Vec<TickSnapshot>, Box<dyn Error>> {
+    let response = client
+        .get(CHART_URL)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .query(&[
+            ("AssetType", asset_type.to_string()),
+            ("Uic", uic.to_string()),
+            ("Horizon", horizon.to_string()),
+            ("Count", count.to_string()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?;
+    let body: Value = response.json().await?;
+    Ok(parse_chart_response(reference_id, &body))
+}
+
+// interleave per-instrument histories into one chronological tick stream
+pub fn merge_history(histories: Vec<Vec<TickSnapshot>>) -> Vec<TickSnapshot> {
+    let mut merged: Vec<TickSnapshot> = histories.into_iter().flatten().collect();
+    merged.sort_by_key(|tick| parse_quote_time(&tick.date));
+    merged
+}
+
+/// backfill every instrument, skipping (and reporting) those whose request fails
+pub async fn backfill(instruments: &[(String, i32, AssetType)], horizon: u32, count: usize) -> Vec<TickSnapshot> {
+    dotenv().ok();
+    let access_token = match env::var("ACCESS_TOKEN") {
+        Ok(token) => token,
+        Err(_) => {
+            eprintln!("backfill skipped: missing ACCESS_TOKEN in .env");
+            return Vec::new();
+        }
+    };
+
+    let client = Client::new();
+    let mut histories = Vec::new();
+    for (reference_id, uic, asset_type) in instruments.iter() {
+        match fetch_history(&client, &access_token, reference_id, *uic, *asset_type, horizon, count).await {
+            Ok(ticks) => {
+                println!("backfilled {} bars for {}", ticks.len(), reference_id);
+                histories.push(ticks);
+            }
+            Err(e) => eprintln!("backfill failed for {}: {}", reference_id, e),
+        }
+    }
+    merge_history(histories)
+}
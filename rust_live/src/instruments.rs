@@ -0,0 +1,124 @@
+// instrument lookup against saxo's reference data, so uics can be resolved from symbols at startup
+use dotenv::dotenv;
+use std::env;
+use std::fmt;
+use reqwest::Client;
+use serde_json::Value;
+use crate::asset_type::AssetType;
+use crate::config::InstrumentConfig;
+
+const INSTRUMENTS_URL: &str = "https://gateway.saxobank.com/sim/openapi/ref/v1/instruments";
+
+#[derive(Clone, Debug)]
+pub struct InstrumentInfo {
+    pub uic: i32,
+    pub symbol: String,
+    pub description: String,
+    pub asset_type: String,
+}
+
+#[derive(Debug)]
+pub enum LookupError {
+    Request(String),
+    NotFound(String),
+    Ambiguous(String, Vec<InstrumentInfo>), // query, candidates
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::Request(reason) => write!(f, "instrument lookup failed: {}", reason),
+            LookupError::NotFound(query) => write!(f, "no instrument found for '{}'", query),
+            LookupError::Ambiguous(query, candidates) => {
+                write!(f, "'{}' matches {} instruments:", query, candidates.len())?;
+                for c in candidates.iter().take(10) {
+                    write!(f, "\n  {} {} ({}, uic {})", c.symbol, c.description, c.asset_type, c.uic)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for LookupError {}
+
+// parse the Data array of a reference data response
+pub fn parse_instruments_response(response: &Value) -> Vec<InstrumentInfo> {
+    let items = match response.get("Data").and_then(|d| d.as_array()) {
+        Some(items) => items,
+        None => return Vec::new(),
+    };
+    items.iter()
+        .filter_map(|item| {
+            Some(InstrumentInfo {
+                uic: item.get("Identifier").and_then(|v| v.as_i64())? as i32,
+                symbol: item.get("Symbol").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                description: item.get("Description").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                asset_type: item.get("AssetType").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+            })
+        })
+        .collect()
+}
+
+// pick the uic for a symbol: an exact symbol match (ignoring case and the ".I"-style exchange
+// suffix) wins, otherwise the search must have returned exactly one instrument
+pub fn pick_uic(query: &str, candidates: &[InstrumentInfo]) -> Result<i32, LookupError> {
+    let base = |symbol: &str| symbol.split('.').next().unwrap_or("").to_ascii_uppercase();
+    let wanted = base(query);
+    let exact: Vec<&InstrumentInfo> = candidates.iter().filter(|c| base(&c.symbol) == wanted).collect();
+
+    match (exact.as_slice(), candidates) {
+        ([only], _) => Ok(only.uic),
+        ([], []) => Err(LookupError::NotFound(query.to_string())),
+        ([], [only]) => Ok(only.uic),
+        ([], _) => Err(LookupError::Ambiguous(query.to_string(), candidates.to_vec())),
+        (many, _) => Err(LookupError::Ambiguous(query.to_string(), many.iter().map(|c| (*c).clone()).collect())),
+    }
+}
+
+/// keyword search, optionally restricted to one asset type
+pub async fn search(
+    client: &Client,
+    access_token: &str,
+    keywords: &str,
+    asset_type: Option<AssetType>,
+) -> Result<Vec<InstrumentInfo>, LookupError> {
+    let mut query = vec![("Keywords", keywords.to_string())];
+    if let Some(asset_type) = asset_type {
+        query.push(("AssetTypes", asset_type.to_string()));
+    }
+    let response = client
+        .get(INSTRUMENTS_URL)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .query(&query)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| LookupError::Request(e.to_string()))?;
+    let body: Value = response.json().await.map_err(|e| LookupError::Request(e.to_string()))?;
+    Ok(parse_instruments_response(&body))
+}
+
+/// resolve a symbol such as "US500" to its uic
+pub async fn resolve(client: &Client, access_token: &str, symbol: &str, asset_type: AssetType) -> Result<i32, LookupError> {
+    let candidates = search(client, access_token, symbol, Some(asset_type)).await?;
+    pick_uic(symbol, &candidates)
+}
+
+/// fill in the uic of every configured instrument that was given by symbol only
+pub async fn resolve_config(instruments: &mut [InstrumentConfig]) -> Result<(), LookupError> {
+    if instruments.iter().all(|i| i.uic.is_some()) {
+        return Ok(());
+    }
+    dotenv().ok();
+    let access_token = env::var("ACCESS_TOKEN")
+        .map_err(|_| LookupError::Request("missing ACCESS_TOKEN in .env".to_string()))?;
+    let client = Client::new();
+
+    for instrument in instruments.iter_mut().filter(|i| i.uic.is_none()) {
+        let uic = resolve(&client, &access_token, &instrument.reference_id, instrument.asset_type).await?;
+        println!("resolved {} ({}) to uic {}", instrument.reference_id, instrument.asset_type, uic);
+        instrument.uic = Some(uic);
+    }
+    Ok(())
+}
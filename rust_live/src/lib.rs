@@ -1,2 +1,11 @@
 pub mod stream;
-pub mod server;
\ No newline at end of file
+pub mod merge;
+pub mod server;
+pub mod config;
+pub mod mock_saxo;
+pub mod history;
+pub mod asset_type;
+pub mod instruments;
+pub mod account;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
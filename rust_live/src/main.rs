@@ -1,13 +1,18 @@
 use tokio::sync::mpsc;
-use rust_live::stream::stream_live_data;
-use rust_live::stream::pairs;
-use rust_live::stream::stream_live_data_pairs;
-use rust_core::live_engine::{LiveBacktest, LiveData, LiveStrategyRef};
+use rust_live::stream::{stream_instruments, ReconnectPolicy, DEFAULT_MERGE_WINDOW_MS};
+use rust_live::config::LiveConfig;
+use rust_live::history::backfill;
+use rust_live::asset_type::AssetType;
+use rust_live::instruments::resolve_config;
+use rust_live::account::fund_from_account;
+use rust_core::live_engine::{LiveBacktest, LiveCommand, LiveData, LiveStrategyRef};
 use rust_core::strategies::live_statarb_spread::LiveStatArbSpreadStrategy;
 use rust_core::strategies::live_statarb_pairs::LiveStatArbPairsStrategy;
-//use rust_core::strategies::live_ml_statarb_spread::LiveMLStatArbSpreadStrategy;
+use rust_core::strategies::live_ml_statarb_spread::LiveMLStatArbSpreadStrategy;
 use rust_live::server::EquityChartServer;
 use std::sync::Arc;
+use tokio::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
 
 
 #[tokio::main]
@@ -15,59 +20,165 @@ async fn main() {
     // print startup message
     println!("starting live testing engine...");
 
-    // Create and spawn the chart server
-    let chart_server = EquityChartServer::new();
-    let chart_server = Arc::new(chart_server);
-    let chart_server_clone = chart_server.clone();
-    
-    // Spawn the chart server
-    tokio::spawn(async move {
-        chart_server_clone.start_server(3000).await;
-    });
+    // load config from RUST_LIVE_CONFIG / RUST_LIVE_* env vars
+    let mut config = match LiveConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
 
-    // create a channel for live data
-    let (tx, mut rx) = mpsc::unbounded_channel::<LiveData>();
+    // look up uics for instruments configured by symbol only
+    if let Err(e) = resolve_config(&mut config.instruments).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    // size the session from the real account balance when credentials are available
+    fund_from_account(&mut config).await;
+    config.reporter().debug(format!("config: {:?}", config));
 
-    let reference_id1 = "US500";
-    let uic1 = 4913;
-    let reference_id2 = "DJIA";
-    let uic2 = 4911;
+    // manual commands from the console and from POST /command on the chart server
+    let (command_tx, command_rx) = mpsc::unbounded_channel::<LiveCommand>();
+    tokio::spawn(read_console_commands(command_tx.clone()));
 
-    // spawn streaming task for instrument 1
-    tokio::spawn({
-        let tx1 = tx.clone();
-        async move {
-            pairs(tx1, reference_id1, uic1, reference_id2, uic2).await;
+    // Create and spawn the chart server unless running headless
+    let chart_server = if config.headless {
+        None
+    } else {
+        // record the session so it can be replayed on /replay/{session_id}
+        let chart_server = match config.recording_dir.as_deref() {
+            Some(dir) => EquityChartServer::with_recording(dir).unwrap_or_else(|e| {
+                eprintln!("Session recording disabled: {}", e);
+                EquityChartServer::new()
+            }),
+            None => EquityChartServer::new(),
+        };
+        // POST /command trades the account, so it is only enabled with a token to check requests against
+        let command_token = std::env::var("RUST_LIVE_COMMAND_TOKEN").unwrap_or_default();
+        if command_token.is_empty() {
+            println!("POST /command disabled: set RUST_LIVE_COMMAND_TOKEN to enable it");
         }
-    });
+        let chart_server = Arc::new(chart_server.with_commands(command_tx, command_token.trim()));
+        let chart_server_clone = chart_server.clone();
+        let ip: std::net::IpAddr = config.bind_address.parse().expect("bind address validated by config");
+        let addr = std::net::SocketAddr::new(ip, config.port);
+
+        // Spawn the chart server
+        tokio::spawn(async move {
+            chart_server_clone.start_server_at(addr).await;
+        });
+        Some(chart_server)
+    };
+
+    // create a channel for live data
+    let (tx, mut rx) = mpsc::unbounded_channel::<LiveData>();
+
+    // spawn the streaming task: every instrument streams on its own connection with sequence gap
+    // detection, merged into one timestamp-ordered stream
+    let instruments: Vec<(String, i32, AssetType)> = config.instruments.iter()
+        .map(|i| (i.reference_id.clone(), i.resolved_uic(), i.asset_type))
+        .collect();
+    tokio::spawn(stream_instruments(tx.clone(), instruments, ReconnectPolicy::default(), Duration::from_millis(DEFAULT_MERGE_WINDOW_MS)));
 
     // wait for initial data from both streams (customize as needed)
     let initial_data1 = rx.recv().await.expect("no live data from instrument 1");
 
-    // create a live strategy (example using the pairs strategy)
-    let strategy: LiveStrategyRef = Box::new(LiveStatArbSpreadStrategy::new());
+    // create the configured live strategy
+    let strategy: LiveStrategyRef = match config.strategy.as_str() {
+        "statarb_pairs" => Box::new(LiveStatArbPairsStrategy::new()),
+        "ml_statarb_spread" => Box::new(LiveMLStatArbSpreadStrategy::from_settings(&config.ml_device, config.ml_threads)),
+        _ => Box::new(LiveStatArbSpreadStrategy::new()),
+    };
 
     // initialize live backtest with one of the initial messages, or merge the two
     let mut live_backtest = LiveBacktest::new(
         initial_data1.clone(), // or a combined data structure if needed
         strategy,
-        100_000.0,  // live cash
-        0.05,       // live margin
-        false,      // trade on close
-        false,      // hedging
-        false,      // exclusive orders
-        false,      // scaling enabled
+        config.cash,    // live cash (the account balance when funded from the account)
+        config.margin,  // live margin
+        false,          // trade on close
+        false,          // hedging
+        false,          // exclusive orders
+        false,          // scaling enabled
     );
     
     // optionally set the second stream data
     live_backtest.broker.live_data = initial_data1;
+    live_backtest.broker.reporter = config.reporter();
+    live_backtest.set_command_channel(command_rx);
+
+    // preload recent bars so the strategy does not have to wait for a full live lookback
+    if config.backfill_bars > 0 {
+        let instruments: Vec<(String, i32, AssetType)> = config.instruments.iter()
+            .map(|i| (i.reference_id.clone(), i.resolved_uic(), i.asset_type))
+            .collect();
+        let history = backfill(&instruments, config.backfill_horizon, config.backfill_bars).await;
+        live_backtest.preload_history(history);
+    }
     
-    // Modify the LiveBacktest to update chart server with equity values
-    let chart_server_for_backtest = chart_server.clone();
-    live_backtest.set_equity_callback(move |equity| {
-        chart_server_for_backtest.update_equity(equity);
-    });
+    if let Some(chart_server) = chart_server {
+        // Modify the LiveBacktest to update chart server with equity values, throttled as configured;
+        // each update carries the high and low since the previous one
+        let chart_server_for_backtest = chart_server.clone();
+        live_backtest.equity_throttle = config.equity_throttle();
+        live_backtest.set_equity_batch_callback(move |batch| {
+            chart_server_for_backtest.update_equity_batch(batch);
+        });
+
+        // benchmark overlay: instrument mid prices and margin usage as separate series
+        let chart_server_for_series = chart_server.clone();
+        live_backtest.set_series_callback(move |series_id, value| {
+            chart_server_for_series.update_series(series_id, value);
+        });
+
+        // structured session stats on /stats and /stats/ws
+        let chart_server_for_stats = chart_server.clone();
+        live_backtest.add_stats_callback(move |stats| {
+            chart_server_for_stats.update_stats(stats);
+        });
+    }
+
+    // durable telemetry in influxdb, with the telemetry feature and RUST_LIVE_INFLUX_URL set
+    #[cfg(feature = "telemetry")]
+    let telemetry_task = match rust_live::telemetry::TelemetryConfig::from_env() {
+        Some(telemetry_config) => {
+            let session = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+            let (sink, task) = rust_live::telemetry::TelemetrySink::spawn(telemetry_config, &config.strategy, &session);
+            println!("writing telemetry for session {}", session);
+            let sink_for_stats = sink.clone();
+            live_backtest.add_stats_callback(move |stats| sink_for_stats.record_stats(stats));
+            live_backtest.set_fill_callback(move |fill, time| sink.record_fill(fill, time));
+            Some(task)
+        }
+        None => None,
+    };
     
     // run the simulation consuming all incoming live data
     live_backtest.run(rx).await;
-}
\ No newline at end of file
+
+    // drop the sink handles so the writer flushes the rest and stops
+    #[cfg(feature = "telemetry")]
+    if let Some(task) = telemetry_task {
+        drop(live_backtest);
+        let _ = task.await;
+    }
+}
+
+// read commands typed on stdin (pause, resume, close <trade>, close all, buy/sell <instrument> <size>)
+async fn read_console_commands(commands: mpsc::UnboundedSender<LiveCommand>) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse::<LiveCommand>() {
+            Ok(command) => {
+                if commands.send(command).is_err() {
+                    return;
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
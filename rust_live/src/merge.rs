@@ -0,0 +1,110 @@
+// merge stage for per-instrument streams: every instrument has its own connection and task, and their
+// updates are combined here into one timestamp-ordered LiveData stream for the live engine
+use rust_core::clock::parse_quote_time;
+use rust_core::live_engine::{LiveData, StreamEvent, TickSnapshot};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+// counters for one merge session
+#[derive(Clone, Debug, Default)]
+pub struct MergeSummary {
+    pub batches: usize, // LiveData updates sent downstream
+    pub ticks: usize,
+    pub late: usize, // ticks older than a tick already sent, forwarded anyway
+}
+
+/// buffers ticks from several streams and releases them sorted by quote time. ticks that arrive within
+/// one flush are ordered exactly; a tick that arrives after a newer one was already released is still
+/// forwarded (and counted as late) rather than dropped. ticks with unparseable dates keep their arrival
+/// order after the dated ones.
+#[derive(Default)]
+pub struct TickMerger {
+    buffer: Vec<(Option<DateTime<Utc>>, TickSnapshot)>,
+    events: Vec<StreamEvent>, // passed through in arrival order
+    last_released: Option<DateTime<Utc>>,
+    pub summary: MergeSummary,
+}
+
+impl TickMerger {
+    pub fn new() -> Self {
+        TickMerger::default()
+    }
+
+    pub fn push(&mut self, data: LiveData) {
+        self.events.extend(data.events);
+        for tick in data.ticks {
+            self.buffer.push((parse_quote_time(&tick.date), tick));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty() && self.events.is_empty()
+    }
+
+    // everything buffered so far as one LiveData, or None if nothing arrived since the last flush
+    pub fn flush(&mut self) -> Option<LiveData> {
+        if self.is_empty() {
+            return None;
+        }
+        // stable sort: equal timestamps keep their arrival order
+        self.buffer.sort_by(|a, b| match (a.0, b.0) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+
+        let mut ticks = Vec::with_capacity(self.buffer.len());
+        let mut current: HashMap<String, TickSnapshot> = HashMap::new();
+        for (time, tick) in self.buffer.drain(..) {
+            if let Some(time) = time {
+                match self.last_released {
+                    Some(last) if time < last => self.summary.late += 1,
+                    _ => self.last_released = Some(time),
+                }
+            }
+            current.insert(tick.instrument.clone(), tick.clone());
+            ticks.push(tick);
+        }
+        self.summary.ticks += ticks.len();
+        self.summary.batches += 1;
+        Some(LiveData { ticks, current, events: std::mem::take(&mut self.events) })
+    }
+}
+
+/// read updates from all instrument streams on `rx` and forward them on `tx`, sorted by quote time
+/// within every `window`. a stream that goes quiet does not hold the others back: whatever arrived is
+/// released at the end of each window. runs until every stream sender is dropped or `tx` closes.
+pub async fn merge_streams(
+    mut rx: UnboundedReceiver<LiveData>,
+    tx: UnboundedSender<LiveData>,
+    window: Duration,
+) -> MergeSummary {
+    let mut merger = TickMerger::new();
+    let mut ticker = interval(window.max(Duration::from_millis(1)));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Some(data) => merger.push(data),
+                None => break,
+            },
+            _ = ticker.tick() => {
+                if let Some(data) = merger.flush() {
+                    if tx.send(data).is_err() {
+                        return merger.summary;
+                    }
+                }
+            }
+        }
+    }
+
+    // streams are done, release what is left
+    if let Some(data) = merger.flush() {
+        let _ = tx.send(data);
+    }
+    merger.summary
+}
@@ -0,0 +1,122 @@
+// mock saxo streaming server for tests: replays scripted binary frames over a local websocket
+use std::net::SocketAddr;
+use futures_util::SinkExt;
+use tokio::net::TcpListener;
+use tokio::time::{sleep, Duration};
+use tokio_tungstenite::accept_async;
+use tungstenite::Message;
+
+pub const HEARTBEAT_REF: &str = "_heartbeat";
+
+// one step of a scripted connection
+#[derive(Clone, Debug)]
+pub enum MockFrame {
+    Binary(Vec<u8>),
+    Text(String),
+    Delay(u64), // pause in milliseconds before the next step
+    Close,      // close the socket; the client is expected to reconnect
+}
+
+/// encode one message in the saxo streaming binary format:
+/// message id (u64 le), 2 reserved bytes, ref id length (u8), ref id (ascii),
+/// payload format (u8, 0 = json), payload size (u32 le), payload
+pub fn saxo_message(message_id: u64, reference_id: &str, payload: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(16 + reference_id.len() + payload.len());
+    buf.extend_from_slice(&message_id.to_le_bytes());
+    buf.extend_from_slice(&[0u8, 0u8]);
+    buf.push(reference_id.len() as u8);
+    buf.extend_from_slice(reference_id.as_bytes());
+    buf.push(0u8);
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    buf
+}
+
+// json payload of a price update as sent by the price subscription
+pub fn quote_payload(reference_id: &str, bid: f64, ask: f64, last_updated: &str) -> String {
+    serde_json::json!({
+        "LastUpdated": last_updated,
+        "Quote": { "Ask": ask, "Bid": bid, "Mid": (ask + bid) / 2.0 },
+        "ReferenceId": reference_id,
+    })
+    .to_string()
+}
+
+// a frame holding a single quote message
+pub fn quote_frame(message_id: u64, reference_id: &str, bid: f64, ask: f64, last_updated: &str) -> Vec<u8> {
+    saxo_message(message_id, reference_id, quote_payload(reference_id, bid, ask, last_updated).as_bytes())
+}
+
+// a heartbeat frame for the given subscriptions
+pub fn heartbeat_frame(message_id: u64, reference_ids: &[&str]) -> Vec<u8> {
+    let heartbeats: Vec<serde_json::Value> = reference_ids.iter()
+        .map(|id| serde_json::json!({ "OriginatingReferenceId": id, "Reason": "NoNewData" }))
+        .collect();
+    let payload = serde_json::json!([{ "ReferenceId": HEARTBEAT_REF, "Heartbeats": heartbeats }]).to_string();
+    saxo_message(message_id, HEARTBEAT_REF, payload.as_bytes())
+}
+
+// several messages concatenated into one websocket frame
+pub fn multipart_frame(messages: &[Vec<u8>]) -> Vec<u8> {
+    messages.concat()
+}
+
+// a frame whose json payload is cut off after `keep` bytes
+pub fn truncated_frame(message_id: u64, reference_id: &str, payload: &str, keep: usize) -> Vec<u8> {
+    let cut = &payload.as_bytes()[..keep.min(payload.len())];
+    saxo_message(message_id, reference_id, cut)
+}
+
+/// serves one script per accepted connection, in order; once the scripts are used up
+/// further connections are closed immediately
+pub struct MockSaxoServer {
+    pub scripts: Vec<Vec<MockFrame>>,
+}
+
+impl MockSaxoServer {
+    pub fn new(scripts: Vec<Vec<MockFrame>>) -> Self {
+        MockSaxoServer { scripts }
+    }
+
+    // bind to an ephemeral local port and serve in the background; returns the bound address
+    pub async fn start(self) -> std::io::Result<SocketAddr> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+
+        tokio::spawn(async move {
+            let mut scripts = self.scripts.into_iter();
+            while let Ok((stream, _)) = listener.accept().await {
+                let script = scripts.next().unwrap_or_default();
+                let mut ws = match accept_async(stream).await {
+                    Ok(ws) => ws,
+                    Err(e) => {
+                        eprintln!("mock saxo handshake failed: {:?}", e);
+                        continue;
+                    }
+                };
+                for frame in script {
+                    let sent = match frame {
+                        MockFrame::Binary(bin) => ws.send(Message::Binary(bin.into())).await,
+                        MockFrame::Text(text) => ws.send(Message::Text(text.into())).await,
+                        MockFrame::Delay(ms) => {
+                            sleep(Duration::from_millis(ms)).await;
+                            Ok(())
+                        }
+                        MockFrame::Close => break,
+                    };
+                    if sent.is_err() {
+                        break;
+                    }
+                }
+                let _ = ws.close(None).await;
+            }
+        });
+
+        Ok(addr)
+    }
+
+    // websocket url of a started server
+    pub fn url(addr: SocketAddr) -> String {
+        format!("ws://{}/sim/openapi/streamingws/connect", addr)
+    }
+}
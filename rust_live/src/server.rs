@@ -1,10 +1,25 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use warp::Filter;
 use futures::{StreamExt, SinkExt};
-use tokio::time::{sleep, Duration};
-use chrono::Utc;
-use serde::Serialize;
-use warp::cors::Cors;
+use tokio::time::{sleep, Duration, Instant};
+use chrono::{NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use rust_core::clock::{system_clock, ClockRef};
+use rust_core::live_engine::{EquityBatch, LiveCommand, LiveStatsSnapshot};
+use rust_core::plot::resample_locf;
+use tokio::sync::mpsc::UnboundedSender;
+
+// series ids used by the live engine; any other id can be pushed with update_series
+pub const EQUITY_SERIES: &str = "equity";
+pub const MARGIN_USAGE_SERIES: &str = "margin_usage";
+// raw updates kept per series for resampling; older ones are dropped (about a day at one update a second)
+pub const MAX_SERIES_POINTS: usize = 100_000;
+// window /resample covers when the request doesn't set one, in seconds
+const DEFAULT_RESAMPLE_WINDOW_SECS: f64 = 3600.0;
 
 #[derive(Clone, Serialize)]
 pub struct EquityUpdate {
@@ -15,98 +30,449 @@ pub struct EquityUpdate {
     close: f64,
 }
 
-#[derive(Clone)]
-pub struct EquityChartServer {
-    equity_data: Arc<Mutex<Vec<EquityUpdate>>>,
-    current_candle: Arc<Mutex<Option<EquityUpdate>>>,
+// message sent to the frontend: all candles of one named series
+#[derive(Clone, Serialize)]
+pub struct SeriesMessage {
+    series: String,
+    data: Vec<EquityUpdate>,
 }
 
-impl EquityChartServer {
-    pub fn new() -> Self {
-        EquityChartServer {
-            equity_data: Arc::new(Mutex::new(Vec::new())),
-            current_candle: Arc::new(Mutex::new(None)),
-        }
+// one point of a resampled series
+#[derive(Clone, Serialize)]
+pub struct SeriesPoint {
+    time: i64, // unix time in seconds, as for candles
+    value: f64,
+}
+
+// one recorded series update; sessions are stored as one json record per line
+#[derive(Clone, Serialize, Deserialize)]
+struct RecordedUpdate {
+    t: i64, // unix time in milliseconds
+    series: String,
+    value: f64,
+}
+
+// completed candles plus the candle currently being built for one series
+#[derive(Clone, Default)]
+struct SeriesState {
+    candles: Vec<EquityUpdate>,
+    current: Option<EquityUpdate>,
+    // the latest MAX_SERIES_POINTS raw updates as (unix time in milliseconds, value), for resampling
+    points: VecDeque<(i64, f64)>,
+}
+
+impl SeriesState {
+    // fold a new value into 10 second candles
+    fn update(&mut self, value: f64, timestamp_ms: i64) {
+        self.update_range(value, value, value, value, timestamp_ms);
     }
 
-    // Update equity and manage candles
-    pub fn update_equity(&self, value: f64) {
-        let timestamp = Utc::now().timestamp();
+    // fold a throttled batch of values (first, min, max, last) into the candles
+    fn update_range(&mut self, first: f64, min: f64, max: f64, value: f64, timestamp_ms: i64) {
+        if self.points.len() == MAX_SERIES_POINTS {
+            self.points.pop_front();
+        }
+        self.points.push_back((timestamp_ms, value));
+        let timestamp = timestamp_ms.div_euclid(1000);
         let ten_sec_timestamp = timestamp - (timestamp % 10); // Round to nearest 10 seconds
-        
-        let mut current_candle = self.current_candle.lock().unwrap();
-        
-        match &mut *current_candle {
+
+        match &mut self.current {
             Some(candle) if candle.time == ten_sec_timestamp => {
                 // Update existing candle
-                candle.high = candle.high.max(value);
-                candle.low = candle.low.min(value);
+                candle.high = candle.high.max(max);
+                candle.low = candle.low.min(min);
                 candle.close = value;
             }
             _ => {
                 // Create new candle
-                if let Some(completed_candle) = current_candle.take() {
-                    let mut data = self.equity_data.lock().unwrap();
-                    data.push(completed_candle);
+                if let Some(completed_candle) = self.current.take() {
+                    self.candles.push(completed_candle);
                 }
 
-                *current_candle = Some(EquityUpdate {
+                self.current = Some(EquityUpdate {
                     time: ten_sec_timestamp,
-                    open: value,
-                    high: value,
-                    low: value,
+                    open: first,
+                    high: max,
+                    low: min,
                     close: value,
                 });
             }
         }
     }
 
+    // most recent value of the series
+    fn latest(&self) -> Option<f64> {
+        self.current.as_ref().map(|candle| candle.close)
+    }
+
+    // completed candles plus the open one
+    fn snapshot(&self) -> Vec<EquityUpdate> {
+        let mut all_data = self.candles.clone();
+        if let Some(current_candle) = self.current.as_ref() {
+            all_data.push(current_candle.clone());
+        }
+        all_data
+    }
+
+    // raw updates on a fixed interval grid, last observation carried forward. with a window only the
+    // updates of its last `window` are resampled, starting from the value the series had when it began
+    fn resampled(&self, interval: chrono::Duration, window: Option<chrono::Duration>) -> Vec<(NaiveDateTime, f64)> {
+        let (Some(&(last, _)), Some(window)) = (self.points.back(), window) else {
+            return resample_locf(&to_naive(self.points.iter().copied()), interval);
+        };
+        let start = last - window.num_milliseconds();
+        let first_inside = self.points.partition_point(|&(t, _)| t < start);
+        // the update before the window carries its value into the window's first grid time
+        let carried = first_inside.checked_sub(1).map(|i| (start, self.points[i].1));
+        let points = carried.into_iter().chain(self.points.range(first_inside..).copied());
+        resample_locf(&to_naive(points), interval)
+    }
+}
+
+fn to_naive(points: impl Iterator<Item = (i64, f64)>) -> Vec<(NaiveDateTime, f64)> {
+    points
+        .filter_map(|(t, value)| chrono::DateTime::from_timestamp_millis(t).map(|dt| (dt.naive_utc(), value)))
+        .collect()
+}
+
+#[derive(Clone)]
+pub struct EquityChartServer {
+    series: Arc<Mutex<BTreeMap<String, SeriesState>>>,
+    session_id: String,
+    // directory holding <session_id>.jsonl recordings; None disables recording and replay
+    recording_dir: Option<PathBuf>,
+    recorder: Arc<Mutex<Option<LineWriter<File>>>>,
+    // time source for candle bucketing and recording timestamps
+    clock: ClockRef,
+    // latest session stats, served on /stats and /stats/ws
+    stats: Arc<Mutex<Option<LiveStatsSnapshot>>>,
+    // manual commands posted to /command are forwarded here, with the token callers must send in the
+    // x-command-token header; None disables the route
+    commands: Option<(UnboundedSender<LiveCommand>, String)>,
+}
+
+impl EquityChartServer {
+    pub fn new() -> Self {
+        EquityChartServer {
+            series: Arc::new(Mutex::new(BTreeMap::new())),
+            session_id: format!("session_{}", Utc::now().format("%Y%m%d_%H%M%S")),
+            recording_dir: None,
+            recorder: Arc::new(Mutex::new(None)),
+            clock: system_clock(),
+            stats: Arc::new(Mutex::new(None)),
+            commands: None,
+        }
+    }
+
+    // Use a different time source, e.g. a TestClock for deterministic candles
+    pub fn with_clock(mut self, clock: ClockRef) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    // Record every update of this session to <dir>/<session_id>.jsonl and serve
+    // finished sessions from that directory on /replay/{session_id}
+    pub fn with_recording(dir: &str) -> std::io::Result<Self> {
+        let mut server = EquityChartServer::new();
+        std::fs::create_dir_all(dir)?;
+        let path = PathBuf::from(dir).join(format!("{}.jsonl", server.session_id));
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        println!("Recording chart session to {}", path.display());
+        server.recording_dir = Some(PathBuf::from(dir));
+        server.recorder = Arc::new(Mutex::new(Some(LineWriter::new(file))));
+        Ok(server)
+    }
+
+    // Accept LiveCommands as json on POST /command, e.g. {"command":"pause"}, from callers sending `token`
+    // in the x-command-token header. an empty token leaves the route disabled
+    pub fn with_commands(mut self, commands: UnboundedSender<LiveCommand>, token: &str) -> Self {
+        self.commands = (!token.is_empty()).then(|| (commands, token.to_string()));
+        self
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    // Update equity and manage candles
+    pub fn update_equity(&self, value: f64) {
+        self.update_series(EQUITY_SERIES, value);
+    }
+
+    // Update equity with everything since the last throttled callback, keeping its high and low
+    pub fn update_equity_batch(&self, batch: &EquityBatch) {
+        self.update_series_range(EQUITY_SERIES, batch.first, batch.min, batch.max, batch.last);
+    }
+
+    // Update any named series (e.g. an instrument mid price or margin usage); created on first use
+    pub fn update_series(&self, series_id: &str, value: f64) {
+        self.update_series_range(series_id, value, value, value, value);
+    }
+
+    // Update a series with a batch of values; only the last one is kept for resampling and recording
+    pub fn update_series_range(&self, series_id: &str, first: f64, min: f64, max: f64, value: f64) {
+        let now = self.clock.now();
+        let mut series = self.series.lock().unwrap();
+        series.entry(series_id.to_string()).or_default().update_range(first, min, max, value, now.timestamp_millis());
+
+        // persist the raw update so the session can be replayed later
+        if let Some(writer) = self.recorder.lock().unwrap().as_mut() {
+            let record = RecordedUpdate {
+                t: now.timestamp_millis(),
+                series: series_id.to_string(),
+                value,
+            };
+            if let Ok(line) = serde_json::to_string(&record) {
+                if let Err(e) = writeln!(writer, "{}", line) {
+                    eprintln!("Error recording chart update: {}", e);
+                }
+            }
+        }
+    }
+
+    // A series resampled to a fixed interval with last-observation-carried-forward, e.g. live equity
+    // for plot::plot_equity or for stats that need a regular time base; empty for unknown series.
+    // `window` limits it to the last part of the series, None resamples every retained update
+    pub fn resampled(&self, series_id: &str, interval: chrono::Duration, window: Option<chrono::Duration>) -> Vec<(NaiveDateTime, f64)> {
+        self.series.lock().unwrap().get(series_id)
+            .map(|state| state.resampled(interval, window))
+            .unwrap_or_default()
+    }
+
+    // Replace the latest session stats
+    pub fn update_stats(&self, stats: &LiveStatsSnapshot) {
+        *self.stats.lock().unwrap() = Some(stats.clone());
+    }
+
     pub async fn start_server(&self, port: u16) {
-        let equity = self.equity_data.clone();
-        let current = self.current_candle.clone();
-        
+        self.start_server_at(([127, 0, 0, 1], port).into()).await;
+    }
+
+    // Serve on an explicit address, e.g. 0.0.0.0 inside a container
+    pub async fn start_server_at(&self, addr: std::net::SocketAddr) {
+        let series = self.series.clone();
+
         // Add CORS support
         let cors = warp::cors()
             .allow_any_origin()
             .allow_methods(vec!["GET", "POST"])
             .allow_headers(vec!["Content-Type"]);
-        
+
         let ws_route = warp::path("ws")
             .and(warp::ws())
             .map(move |ws: warp::ws::Ws| {
-                let equity = equity.clone();
-                let current = current.clone();
-                ws.on_upgrade(move |websocket| handle_connection(websocket, equity, current))
+                let series = series.clone();
+                ws.on_upgrade(move |websocket| handle_connection(websocket, series))
+            });
+
+        // latest value of every series as plain json, e.g. for polling model hit rate or margin usage
+        let metrics_series = self.series.clone();
+        let metrics_route = warp::path("metrics")
+            .and(warp::get())
+            .map(move || {
+                let latest: BTreeMap<String, f64> = metrics_series.lock().unwrap().iter()
+                    .filter_map(|(id, state)| state.latest().map(|value| (id.clone(), value)))
+                    .collect();
+                warp::reply::json(&latest)
+            });
+
+        // replay a recorded session over websocket; ?speed=N accelerates it (default 10x)
+        let recording_dir = self.recording_dir.clone();
+        let replay_route = warp::path!("replay" / String)
+            .and(warp::query::<HashMap<String, String>>())
+            .and(warp::ws())
+            .map(move |session_id: String, query: HashMap<String, String>, ws: warp::ws::Ws| {
+                let speed = query.get("speed")
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .filter(|s| *s > 0.0)
+                    .unwrap_or(10.0);
+                let path = recording_dir.as_ref().and_then(|dir| session_path(dir, &session_id));
+                ws.on_upgrade(move |websocket| handle_replay(websocket, path, speed))
+            });
+
+        // the last ?window=N seconds (default an hour) of one series, resampled to ?interval=N seconds
+        // (default 10) with last-observation-carried-forward
+        let resample_series = self.series.clone();
+        let resample_route = warp::path!("resample" / String)
+            .and(warp::get())
+            .and(warp::query::<HashMap<String, String>>())
+            .map(move |series_id: String, query: HashMap<String, String>| {
+                let interval_ms = query.get("interval")
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .filter(|s| *s > 0.0)
+                    .map(|s| (s * 1000.0) as i64)
+                    .unwrap_or(10_000)
+                    .max(1);
+                let window_ms = query.get("window")
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .filter(|s| *s > 0.0)
+                    .unwrap_or(DEFAULT_RESAMPLE_WINDOW_SECS) * 1000.0;
+                let window = chrono::Duration::milliseconds(window_ms as i64);
+                let points: Vec<SeriesPoint> = resample_series.lock().unwrap().get(&series_id)
+                    .map(|state| state.resampled(chrono::Duration::milliseconds(interval_ms), Some(window)))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(time, value)| SeriesPoint { time: time.and_utc().timestamp(), value })
+                    .collect();
+                warp::reply::json(&points)
+            });
+
+        // latest LiveStatsSnapshot as json (null before the first tick)
+        let stats = self.stats.clone();
+        let stats_route = warp::path!("stats")
+            .and(warp::get())
+            .map(move || warp::reply::json(&*stats.lock().unwrap()));
+
+        // the same snapshot pushed over websocket whenever it changes
+        let stats = self.stats.clone();
+        let stats_ws_route = warp::path!("stats" / "ws")
+            .and(warp::ws())
+            .map(move |ws: warp::ws::Ws| {
+                let stats = stats.clone();
+                ws.on_upgrade(move |websocket| handle_stats(websocket, stats))
             });
 
-        let routes = ws_route.with(cors);
-        
-        println!("Chart server running at http://localhost:{}", port);
-        warp::serve(routes).run(([127, 0, 0, 1], port)).await;
+        // the command route is left out of cors, so browsers can't send it cross-origin
+        let routes = stats_ws_route.or(stats_route).or(ws_route).or(replay_route).or(metrics_route).or(resample_route).with(cors);
+        let routes = self.command_route().or(routes);
+
+        println!("Chart server running at http://{}", addr);
+        warp::serve(routes).run(addr).await;
     }
+
+    // manual supervision on POST /command: pause/resume the strategy, close trades, submit orders. it
+    // trades the account, so requests need the command token, and anything sent by a web page (which
+    // carries an Origin header) is refused
+    pub fn command_route(&self) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let commands = self.commands.clone();
+        warp::path!("command")
+            .and(warp::post())
+            .and(warp::header::optional::<String>("origin"))
+            .and(warp::header::optional::<String>("x-command-token"))
+            .and(warp::body::bytes())
+            .map(move |origin: Option<String>, token: Option<String>, body: warp::hyper::body::Bytes| {
+                use warp::http::StatusCode;
+                let status = match commands.as_ref() {
+                    None => StatusCode::SERVICE_UNAVAILABLE,
+                    Some(_) if origin.is_some() => StatusCode::FORBIDDEN,
+                    Some((_, expected)) if !token_matches(token.as_deref(), expected) => StatusCode::UNAUTHORIZED,
+                    Some((tx, _)) => match serde_json::from_slice::<LiveCommand>(&body) {
+                        Ok(command) => match tx.send(command) {
+                            Ok(()) => StatusCode::ACCEPTED,
+                            Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+                        },
+                        Err(_) => StatusCode::BAD_REQUEST,
+                    },
+                };
+                warp::reply::with_status(warp::reply(), status)
+            })
+    }
+}
+
+// compare without returning early on the first differing byte
+fn token_matches(token: Option<&str>, expected: &str) -> bool {
+    let token = token.unwrap_or("").as_bytes();
+    let expected = expected.as_bytes();
+    token.len() == expected.len() && token.iter().zip(expected).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
 }
 
 async fn handle_connection(
     ws: warp::ws::WebSocket,
-    equity: Arc<Mutex<Vec<EquityUpdate>>>,
-    current: Arc<Mutex<Option<EquityUpdate>>>
+    series: Arc<Mutex<BTreeMap<String, SeriesState>>>,
 ) {
     let (mut tx, _) = ws.split();
-    
+
     loop {
-        // Send both historical and current candle data
-        let data = {
-            let mut all_data = equity.lock().unwrap().clone();
-            if let Some(current_candle) = current.lock().unwrap().as_ref() {
-                all_data.push(current_candle.clone());
-            }
-            serde_json::to_string(&all_data).unwrap()
-        };
-        
+        // Send historical and current candle data of every series, tagged with its series id
+        let data = series_json(&series.lock().unwrap());
+
         if tx.send(warp::ws::Message::text(data)).await.is_err() {
             break;
         }
-        
+
         sleep(Duration::from_millis(100)).await;
     }
 }
+
+async fn handle_stats(ws: warp::ws::WebSocket, stats: Arc<Mutex<Option<LiveStatsSnapshot>>>) {
+    let (mut tx, _) = ws.split();
+    let mut last_sent: Option<(usize, String)> = None;
+
+    loop {
+        let message = stats.lock().unwrap().as_ref()
+            .filter(|s| last_sent.as_ref() != Some(&(s.tick, s.time.clone())))
+            .map(|s| ((s.tick, s.time.clone()), serde_json::to_string(s).unwrap()));
+        if let Some((key, json)) = message {
+            if tx.send(warp::ws::Message::text(json)).await.is_err() {
+                break;
+            }
+            last_sent = Some(key);
+        }
+
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
+// resolve a session id to its recording, rejecting anything that is not a plain file name
+fn session_path(dir: &PathBuf, session_id: &str) -> Option<PathBuf> {
+    let valid = !session_id.is_empty()
+        && session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Some(dir.join(format!("{}.jsonl", session_id)))
+    } else {
+        None
+    }
+}
+
+// load a recorded session, skipping malformed lines
+fn load_session(path: &PathBuf) -> std::io::Result<Vec<RecordedUpdate>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(reader.lines()
+        .filter_map(|line| line.ok())
+        .filter_map(|line| serde_json::from_str::<RecordedUpdate>(&line).ok())
+        .collect())
+}
+
+async fn handle_replay(ws: warp::ws::WebSocket, path: Option<PathBuf>, speed: f64) {
+    let (mut tx, _) = ws.split();
+
+    let updates = match path.as_ref().map(load_session) {
+        Some(Ok(updates)) => updates,
+        _ => {
+            let _ = tx.send(warp::ws::Message::text("{\"error\":\"unknown session\"}")).await;
+            return;
+        }
+    };
+
+    // rebuild the candles from the raw updates, pacing them by the recorded time gaps
+    let mut series: BTreeMap<String, SeriesState> = BTreeMap::new();
+    let mut last_sent = Instant::now();
+    let mut prev_t = updates.first().map(|u| u.t).unwrap_or(0);
+    for update in updates.iter() {
+        let gap_ms = ((update.t - prev_t).max(0) as f64 / speed) as u64;
+        if gap_ms > 0 {
+            sleep(Duration::from_millis(gap_ms)).await;
+        }
+        prev_t = update.t;
+        series.entry(update.series.clone()).or_default().update(update.value, update.t);
+
+        if last_sent.elapsed() >= Duration::from_millis(100) {
+            if tx.send(warp::ws::Message::text(series_json(&series))).await.is_err() {
+                return;
+            }
+            last_sent = Instant::now();
+        }
+    }
+    let _ = tx.send(warp::ws::Message::text(series_json(&series))).await;
+}
+
+// serialize every series as the same message schema the live socket uses
+fn series_json(series: &BTreeMap<String, SeriesState>) -> String {
+    let messages: Vec<SeriesMessage> = series.iter()
+        .map(|(id, state)| SeriesMessage {
+            series: id.clone(),
+            data: state.snapshot(),
+        })
+        .collect();
+    serde_json::to_string(&messages).unwrap()
+}
@@ -5,80 +5,175 @@ use tungstenite::Message;
 use futures_util::StreamExt;
 use reqwest::Client;
 use chrono::Utc;
-use rust_core::data_handler::{parse_live_data_with_reference_nom2, parse_live_data_with_reference_nom, parse_multipart_live_data};
-use rust_core::live_engine::LiveData;
-use tokio::sync::mpsc::UnboundedSender;
-use regex::Regex;
-
-
-fn clean_raw_text(raw: &str, ref_ids: &[&str]) -> String {
-    // Remove all null characters.
-    let cleaned = raw.replace("\0", "");
-    
-    // Find the beginning of the JSON block.
-    if let Some(json_start) = cleaned.find("{\"") {
-        let prefix = &cleaned[..json_start];
-        let json_part = &cleaned[json_start..];
-
-        // Build a new prefix that only contains the reference IDs (if present).
-        let mut kept = String::new();
-        for ref_id in ref_ids {
-            if prefix.contains(ref_id) {
-                if !kept.is_empty() {
-                    kept.push(' ');
-                }
-                kept.push_str(ref_id);
-            }
+use rust_core::data_handler::{parse_saxo_frame, parse_saxo_frame_sequenced, tick_from_price_update};
+use rust_core::live_engine::{DataGap, LiveData, StreamEvent};
+use crate::asset_type::AssetType;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::time::{sleep, Duration};
+use futures_util::Stream;
+use crate::merge::merge_streams;
+
+// how long the merge stage collects ticks from the instrument streams before releasing them in time order
+pub const DEFAULT_MERGE_WINDOW_MS: u64 = 50;
+
+
+// how often and how fast to reconnect after the websocket drops
+#[derive(Clone, Debug)]
+pub struct ReconnectPolicy {
+    pub max_reconnects: usize,
+    pub backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_reconnects: 5,
+            backoff: Duration::from_secs(1),
         }
-        // Optionally, trim any extra whitespace.
-        return format!("{}{}", kept.trim(), json_part);
     }
-    // If no JSON block is found, return the cleaned string.
-    cleaned
 }
 
+// counters for one streaming session across reconnects
+#[derive(Clone, Debug, Default)]
+pub struct StreamSummary {
+    pub connections: usize,
+    pub frames: usize,
+    pub updates: usize, // frames that produced at least one tick
+    pub gaps: usize,    // breaks in the message sequence
+}
 
-fn split_messages(raw: &str, ref_ids: &[&str]) -> Vec<String> {
-    // Remove all null characters.
-    let cleaned = raw.replace("\0", "");
-    
-    // Build a regex pattern that matches any one of the reference IDs followed by '{'
-    // For example, if ref_ids are ["DJIA", "US500"], pattern becomes: (DJIA|US500)\{
-    let pattern = format!("({})\\{{", ref_ids.join("|"));
-    let re = Regex::new(&pattern).unwrap();
-    
-    // Collect the start positions for each new message.
-    let mut indices = Vec::new();
-    for mat in re.find_iter(&cleaned) {
-        indices.push(mat.start());
-    }
-    
-    // If no reference id boundary is found, return the whole cleaned string.
-    if indices.is_empty() {
-        return vec![cleaned];
-    }
-    
-    // Now split the cleaned string at these indices.
-    let mut segments = Vec::new();
-    // Ensure we include the very beginning if needed.
-    if indices[0] != 0 {
-        segments.push(cleaned[0..indices[0]].trim().to_string());
+/// message ids of one connection. saxo numbers the messages of a connection consecutively, control
+/// messages included; with one subscription per connection (stream_instrument) this is the sequence
+/// of that subscription. a jump in the ids is reported as a gap on every subscription seen so far.
+#[derive(Clone, Debug, Default)]
+pub struct SequenceTracker {
+    last: Option<u64>,
+    subscriptions: Vec<String>,
+}
+
+impl SequenceTracker {
+    pub fn observe(&mut self, message_id: u64, reference_id: &str) -> Vec<DataGap> {
+        if !reference_id.starts_with('_') && !self.subscriptions.iter().any(|r| r == reference_id) {
+            self.subscriptions.push(reference_id.to_string());
+        }
+        let gaps = match self.last {
+            Some(last) if message_id > last.saturating_add(1) => self.subscriptions.iter()
+                .map(|instrument| DataGap { instrument: instrument.clone(), expected: last.saturating_add(1), received: message_id })
+                .collect(),
+            _ => Vec::new(),
+        };
+        // older or repeated ids do not move the sequence back
+        if self.last.map_or(true, |last| message_id > last) {
+            self.last = Some(message_id);
+        }
+        gaps
     }
-    for i in 0..indices.len() {
-        let start = indices[i];
-        let end = if i + 1 < indices.len() { indices[i+1] } else { cleaned.len() };
-        let seg = cleaned[start..end].trim().to_string();
-        if !seg.is_empty() {
-            segments.push(seg);
+}
+
+// forward binary frames from a saxo websocket to the channel until the socket closes or errors.
+// sequence gaps are sent along as StreamEvent::DataGap and the instrument is requested on `resync`
+pub async fn forward_frames<S>(
+    read: &mut S,
+    tx: &UnboundedSender<LiveData>,
+    summary: &mut StreamSummary,
+    resync: Option<&UnboundedSender<String>>,
+)
+where
+    S: Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+{
+    // ids restart with every connection
+    let mut sequence = SequenceTracker::default();
+    while let Some(msg) = read.next().await {
+        match msg {
+            Ok(Message::Binary(bin)) => {
+                summary.frames += 1;
+                // decode the binary frame; the reference id of each message names the instrument
+                let (mut live_data, ids) = parse_saxo_frame_sequenced(&bin);
+                for (message_id, reference_id) in ids.iter() {
+                    for gap in sequence.observe(*message_id, reference_id) {
+                        println!("{}: missed messages {} to {}", gap.instrument, gap.expected, gap.received - 1);
+                        summary.gaps += 1;
+                        if let Some(resync) = resync {
+                            let _ = resync.send(gap.instrument.clone());
+                        }
+                        live_data.events.push(StreamEvent::DataGap(gap));
+                    }
+                }
+
+                // Only send if we have data to send
+                if !live_data.ticks.is_empty() || !live_data.events.is_empty() {
+                    if !live_data.ticks.is_empty() {
+                        summary.updates += 1;
+                    }
+                    if let Err(e) = tx.send(live_data) {
+                        eprintln!("Error sending live data: {}", e);
+                        return;
+                    }
+                }
+            }
+            Ok(Message::Close(frame)) => {
+                println!("websocket closed: {:?}", frame);
+                return;
+            }
+            Ok(other) => {
+                println!("Received non-binary message: {:?}", other);
+            }
+            Err(e) => {
+                println!("WebSocket error: {:?}", e);
+                return;
+            }
         }
     }
-    segments
 }
 
+// keep forwarding from an already connected socket, reconnecting to `url` when it drops
+pub async fn resume_stream<S>(
+    mut read: S,
+    tx: &UnboundedSender<LiveData>,
+    url: &str,
+    policy: &ReconnectPolicy,
+    mut summary: StreamSummary,
+    resync: Option<&UnboundedSender<String>>,
+) -> StreamSummary
+where
+    S: Stream<Item = Result<Message, tungstenite::Error>> + Unpin,
+{
+    forward_frames(&mut read, tx, &mut summary, resync).await;
+
+    let mut reconnects = 0;
+    while reconnects < policy.max_reconnects && !tx.is_closed() {
+        reconnects += 1;
+        sleep(policy.backoff).await;
+        println!("reconnecting ({}/{})...", reconnects, policy.max_reconnects);
+        match connect_async(url).await {
+            Ok((ws_stream, _)) => {
+                summary.connections += 1;
+                let (_write, mut read) = ws_stream.split();
+                forward_frames(&mut read, tx, &mut summary, resync).await;
+            }
+            Err(e) => println!("reconnect failed: {:?}", e),
+        }
+    }
+    summary
+}
 
+// connect to a streaming url and forward frames, reconnecting per the policy; used against the mock server in tests
+pub async fn stream_with_reconnect(tx: &UnboundedSender<LiveData>, url: &str, policy: &ReconnectPolicy) -> StreamSummary {
+    match connect_async(url).await {
+        Ok((ws_stream, _)) => {
+            let (_write, read) = ws_stream.split();
+            let summary = StreamSummary { connections: 1, ..Default::default() };
+            resume_stream(read, tx, url, policy, summary, None).await
+        }
+        Err(e) => {
+            println!("failed to connect to {}: {:?}", url, e);
+            StreamSummary::default()
+        }
+    }
+}
 
 // continuously streams live data and sends parsed messages over the channel
-pub async fn stream_live_data(tx: UnboundedSender<LiveData>, reference_id: &str, uic: i32) {
+pub async fn stream_live_data(tx: UnboundedSender<LiveData>, reference_id: &str, uic: i32, asset_type: AssetType) {
     dotenv().ok();
 
     // load api credentials from .env
@@ -111,7 +206,7 @@ pub async fn stream_live_data(tx: UnboundedSender<LiveData>, reference_id: &str,
         "Arguments": {
             "ClientKey": client_key,
             "AccountKey": account_key,
-            "AssetType": "CfdOnIndex",
+            "AssetType": asset_type.as_str(),
             "Uic": uic
         }
     });
@@ -133,10 +228,10 @@ pub async fn stream_live_data(tx: UnboundedSender<LiveData>, reference_id: &str,
             
             }
             Ok(Message::Binary(bin)) => {
-                let text = String::from_utf8_lossy(&bin);
-                //println!("text: {:?}", text);
-                let live_data = parse_live_data_with_reference_nom(&text, &reference_id);
-                let _ = tx.send(live_data.clone());
+                let live_data = parse_saxo_frame(&bin);
+                if !live_data.ticks.is_empty() {
+                    let _ = tx.send(live_data);
+                }
                 //println!("live data: {:?}", live_data);
             }
             Ok(other) => {
@@ -150,7 +245,15 @@ pub async fn stream_live_data(tx: UnboundedSender<LiveData>, reference_id: &str,
 }
 
 
-pub async fn pairs(tx: UnboundedSender<LiveData>, reference_id_1: &str, uic_1: i32, reference_id_2: &str, uic_2: i32) {
+// one instrument on its own websocket and context id, reconnecting per the policy; since every
+// instrument has its own connection an outage on one does not stall the others
+pub async fn stream_instrument(
+    tx: UnboundedSender<LiveData>,
+    reference_id: &str,
+    uic: i32,
+    asset_type: AssetType,
+    policy: &ReconnectPolicy,
+) -> StreamSummary {
     dotenv().ok();
 
     // Load API credentials from .env
@@ -158,101 +261,156 @@ pub async fn pairs(tx: UnboundedSender<LiveData>, reference_id_1: &str, uic_1: i
     let account_key = env::var("ACCOUNT_KEY").expect("Missing ACCOUNT_KEY in .env");
     let client_key = env::var("CLIENT_KEY").expect("Missing CLIENT_KEY in .env");
 
-    // Build a context ID and streamer URL
-    let context_id = format!("MyApp42069{}", Utc::now().timestamp_millis());
+    // separate context per instrument, so each subscription is bound to its own connection
+    let context_id = format!("MyApp42069{}{}", reference_id, Utc::now().timestamp_millis());
     let streamer_url = format!(
         "wss://sim-streaming.saxobank.com/sim/oapi/streaming/ws/connect?contextId={}&authorization=BEARER%20{}",
         context_id, access_token
     );
 
-    println!("Connecting to Saxo Bank WebSocket...");
-    let (ws_stream, _) = connect_async(&streamer_url).await.unwrap_or_else(|e| {
-        panic!("Failed to connect to Saxo WebSocket: {:?}", e);
-    });
-
-    // Split the WebSocket stream into write (unused) and read parts.
-    let (_write, mut read) = ws_stream.split();
+    println!("{}: connecting to Saxo Bank WebSocket...", reference_id);
+    let read = match connect_async(&streamer_url).await {
+        Ok((ws_stream, _)) => ws_stream.split().1,
+        Err(e) => {
+            println!("{}: failed to connect: {:?}", reference_id, e);
+            return StreamSummary::default();
+        }
+    };
 
-    // Create two subscription payloads with different Uic values and ReferenceIds.
-    let subscription_payload_1 = serde_json::json!({
+    let subscription_payload = serde_json::json!({
         "ContextId": context_id,
         "RefreshRate": 2000,
-        "ReferenceId": reference_id_1,
+        "ReferenceId": reference_id,
         "Arguments": {
             "ClientKey": client_key,
             "AccountKey": account_key,
-            "AssetType": "CfdOnIndex",
-            "Uic": uic_1
+            "AssetType": asset_type.as_str(),
+            "Uic": uic
         }
     });
+    let client = Client::new();
+    match subscribe(&client, &access_token, &subscription_payload).await {
+        Ok(body) => println!("{}: subscription response: {}", reference_id, body),
+        Err(e) => {
+            println!("{}: subscription request failed: {:?}", reference_id, e);
+            return StreamSummary { connections: 1, ..Default::default() };
+        }
+    }
 
-    let subscription_payload_2 = serde_json::json!({
-        "ContextId": context_id,
-        "RefreshRate": 2000,
-        "ReferenceId": reference_id_2,
-        "Arguments": {
-            "ClientKey": client_key,
-            "AccountKey": account_key,
-            "AssetType": "CfdOnIndex",
-            "Uic": uic_2
+    // after a sequence gap the subscription is replaced; saxo answers a new subscription with a full
+    // snapshot, which is forwarded as a tick together with StreamEvent::Resynced
+    let (resync_tx, mut resync_rx) = unbounded_channel::<String>();
+    tokio::spawn({
+        let tx = tx.clone();
+        let policy = policy.clone();
+        let subscription_url = format!("{}/{}/{}", SUBSCRIPTIONS_URL, context_id, reference_id);
+        async move {
+            while let Some(instrument) = resync_rx.recv().await {
+                // one snapshot covers every gap reported so far
+                while resync_rx.try_recv().is_ok() {}
+                for attempt in 0..=policy.max_reconnects {
+                    if attempt > 0 {
+                        sleep(policy.backoff).await;
+                    }
+                    let _ = client
+                        .delete(&subscription_url)
+                        .header("Authorization", format!("Bearer {}", access_token))
+                        .send()
+                        .await;
+                    let snapshot = subscribe(&client, &access_token, &subscription_payload).await
+                        .map_err(|e| format!("{:?}", e))
+                        .and_then(|body| snapshot_data(&instrument, &body).ok_or_else(|| "no quote in snapshot".to_string()));
+                    match snapshot {
+                        Ok(data) => {
+                            let _ = tx.send(data);
+                            break;
+                        }
+                        Err(e) => println!("{}: snapshot request failed: {}", instrument, e),
+                    }
+                }
+            }
         }
     });
 
-    let client = Client::new();
+    // subscriptions live on the context id so a reconnect resumes them
+    let summary = StreamSummary { connections: 1, ..Default::default() };
+    resume_stream(read, &tx, &streamer_url, policy, summary, Some(&resync_tx)).await
+}
 
-    // Send the first subscription request
-    let response1 = client
-        .post("https://gateway.saxobank.com/sim/openapi/trade/v1/prices/subscriptions")
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", access_token))
-        .json(&subscription_payload_1)
-        .send()
-        .await
-        .expect("Failed to send subscription request for instrument 1");
-    println!("Subscription response 1: {:?}", response1.text().await.unwrap());
+const SUBSCRIPTIONS_URL: &str = "https://gateway.saxobank.com/sim/openapi/trade/v1/prices/subscriptions";
 
-    // Send the second subscription request
-    let response2 = client
-        .post("https://gateway.saxobank.com/sim/openapi/trade/v1/prices/subscriptions")
+// create a price subscription; returns the response body, which holds the initial snapshot
+async fn subscribe(client: &Client, access_token: &str, payload: &serde_json::Value) -> Result<String, reqwest::Error> {
+    client
+        .post(SUBSCRIPTIONS_URL)
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Bearer {}", access_token))
-        .json(&subscription_payload_2)
+        .json(payload)
         .send()
+        .await?
+        .text()
         .await
-        .expect("Failed to send subscription request for instrument 2");
-    println!("Subscription response 2: {:?}", response2.text().await.unwrap());
+}
 
-    // Process incoming WebSocket messages
-    while let Some(msg) = read.next().await {
-        match msg {
-            Ok(Message::Binary(bin)) => {
-                // Convert binary data to string, replacing invalid UTF-8 sequences
-                let text = String::from_utf8_lossy(&bin);
-                
-                
-                // Process the entire message with our robust parser
-                let live_data = parse_multipart_live_data(&text);
-                
-                // Only send if we have data to send
-                if !live_data.ticks.is_empty() {
-                    if let Err(e) = tx.send(live_data) {
-                        eprintln!("Error sending live data: {}", e);
-                    }
-                }
-            }
-            Ok(other) => {
-                println!("Received non-binary message: {:?}", other);
-            }
-            Err(e) => {
-                println!("WebSocket error: {:?}", e);
-                // Add a small delay before continuing
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-            }
-        }
+/// the quote in the "Snapshot" of a subscription response as live data marked Resynced
+pub fn snapshot_data(instrument: &str, response_body: &str) -> Option<LiveData> {
+    let response: serde_json::Value = serde_json::from_str(response_body).ok()?;
+    let tick = tick_from_price_update(instrument, response.get("Snapshot")?)?;
+    let mut current = std::collections::HashMap::new();
+    current.insert(instrument.to_string(), tick.clone());
+    Some(LiveData { ticks: vec![tick], current, events: vec![StreamEvent::Resynced(instrument.to_string())] })
+}
+
+/// stream any number of instruments, each on its own task and connection, merged into one
+/// timestamp-ordered LiveData stream on `tx` (see merge::merge_streams). runs until every
+/// instrument stream has ended.
+pub async fn stream_instruments(
+    tx: UnboundedSender<LiveData>,
+    instruments: Vec<(String, i32, AssetType)>,
+    policy: ReconnectPolicy,
+    merge_window: Duration,
+) {
+    let (merge_tx, merge_rx) = unbounded_channel::<LiveData>();
+    for (reference_id, uic, asset_type) in instruments {
+        let merge_tx = merge_tx.clone();
+        let policy = policy.clone();
+        tokio::spawn(async move {
+            let summary = stream_instrument(merge_tx, &reference_id, uic, asset_type, &policy).await;
+            println!("{}: stream ended: {:?}", reference_id, summary);
+        });
     }
+    // the merge ends once the last instrument task drops its sender
+    drop(merge_tx);
+    let summary = merge_streams(merge_rx, tx, merge_window).await;
+    println!("merged stream ended: {:?}", summary);
+}
+
+// two instruments, each on its own connection, merged into one stream
+pub async fn pairs(
+    tx: UnboundedSender<LiveData>,
+    reference_id_1: &str,
+    uic_1: i32,
+    asset_type_1: AssetType,
+    reference_id_2: &str,
+    uic_2: i32,
+    asset_type_2: AssetType,
+) {
+    let instruments = vec![
+        (reference_id_1.to_string(), uic_1, asset_type_1),
+        (reference_id_2.to_string(), uic_2, asset_type_2),
+    ];
+    stream_instruments(tx, instruments, ReconnectPolicy::default(), Duration::from_millis(DEFAULT_MERGE_WINDOW_MS)).await;
 }
 
-pub async fn stream_live_data_pairs(tx: UnboundedSender<LiveData>, reference_id_1: &str, uic_1: i32, reference_id_2: &str, uic_2: i32) {
+pub async fn stream_live_data_pairs(
+    tx: UnboundedSender<LiveData>,
+    reference_id_1: &str,
+    uic_1: i32,
+    asset_type_1: AssetType,
+    reference_id_2: &str,
+    uic_2: i32,
+    asset_type_2: AssetType,
+) {
     dotenv().ok();
 
     // load api credentials from .env
@@ -284,7 +442,7 @@ pub async fn stream_live_data_pairs(tx: UnboundedSender<LiveData>, reference_id_
         "Arguments": {
             "ClientKey": client_key,
             "AccountKey": account_key,
-            "AssetType": "CfdOnIndex",
+            "AssetType": asset_type_1.as_str(),
             "Uic": uic_1
         }
     });
@@ -296,7 +454,7 @@ pub async fn stream_live_data_pairs(tx: UnboundedSender<LiveData>, reference_id_
         "Arguments": {
             "ClientKey": client_key,
             "AccountKey": account_key,
-            "AssetType": "CfdOnIndex",
+            "AssetType": asset_type_2.as_str(),
             "Uic": uic_2
         }
     });
@@ -330,9 +488,10 @@ pub async fn stream_live_data_pairs(tx: UnboundedSender<LiveData>, reference_id_
                 
             }
             Ok(Message::Binary(bin)) => {
-                let text = String::from_utf8_lossy(&bin);
-                let live_data_vec = parse_live_data_with_reference_nom2(&text, &reference_id_1, &reference_id_2);
-                
+                let live_data = parse_saxo_frame(&bin);
+                if !live_data.ticks.is_empty() {
+                    let _ = tx.send(live_data);
+                }
             }
             Ok(other) => {
                 println!("received non-text message: {:?}", other);
@@ -0,0 +1,143 @@
+// durable telemetry of a live session: equity, margin usage and fills written to influxdb (v2 write api,
+// line protocol) from a background task, so long sessions can be queried after the chart server is gone.
+// only built with the "telemetry" feature.
+use dotenv::dotenv;
+use std::env;
+use reqwest::Client;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+use rust_core::clock::parse_quote_time;
+use rust_core::live_engine::{FillRecord, LiveStatsSnapshot};
+
+#[derive(Clone, Debug)]
+pub struct TelemetryConfig {
+    pub url: String,        // e.g. http://localhost:8086, or a telegraf influxdb_v2 listener
+    pub org: String,
+    pub bucket: String,
+    pub token: String,
+    pub flush_ms: u64,      // how often buffered points are written
+    pub max_buffered: usize, // points kept while the database is unreachable; the oldest are dropped beyond this
+}
+
+impl TelemetryConfig {
+    /// from RUST_LIVE_INFLUX_URL, RUST_LIVE_INFLUX_ORG, RUST_LIVE_INFLUX_BUCKET and INFLUX_TOKEN (.env);
+    /// None if no url is set
+    pub fn from_env() -> Option<Self> {
+        dotenv().ok();
+        let url = env::var("RUST_LIVE_INFLUX_URL").ok().filter(|u| !u.trim().is_empty())?;
+        Some(TelemetryConfig {
+            url: url.trim().trim_end_matches('/').to_string(),
+            org: env::var("RUST_LIVE_INFLUX_ORG").unwrap_or_default(),
+            bucket: env::var("RUST_LIVE_INFLUX_BUCKET").unwrap_or_else(|_| "rust_live".to_string()),
+            token: env::var("INFLUX_TOKEN").unwrap_or_default(),
+            flush_ms: 1000,
+            max_buffered: 100_000,
+        })
+    }
+}
+
+// escape a tag value for the line protocol
+fn escape_tag(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+// quote time in milliseconds since the epoch, None if it can't be parsed
+fn timestamp_ms(time: &str) -> Option<i64> {
+    parse_quote_time(time).map(|t| t.timestamp_millis())
+}
+
+/// `equity` point of a stats snapshot: equity, cash, margin usage and open trades at the broker clock
+pub fn stats_line(tags: &str, stats: &LiveStatsSnapshot) -> Option<String> {
+    let time = timestamp_ms(&stats.time)?;
+    Some(format!(
+        "equity,{} equity={},cash={},margin_usage={},realized_pnl={},open_trades={}i,tick={}i {}",
+        tags, stats.equity, stats.cash, stats.margin_usage, stats.realized_pnl,
+        stats.open_trades.len(), stats.tick, time
+    ))
+}
+
+/// `fill` point of one execution, at the quote time of its tick
+pub fn fill_line(tags: &str, fill: &FillRecord, time: &str) -> Option<String> {
+    let time = timestamp_ms(time)?;
+    Some(format!(
+        "fill,{},instrument={} size={},price={},spread={},slippage={},tick={}i {}",
+        tags, escape_tag(&fill.instrument), fill.size, fill.fill_price, fill.spread, fill.slippage, fill.tick, time
+    ))
+}
+
+// cloneable handle; points are queued without blocking the engine and written by the background task
+#[derive(Clone)]
+pub struct TelemetrySink {
+    tags: String,
+    lines: UnboundedSender<String>,
+}
+
+impl TelemetrySink {
+    /// start the writer task; every point is tagged with the strategy and session name.
+    /// the task flushes what is left and ends once every handle has been dropped.
+    pub fn spawn(config: TelemetryConfig, strategy: &str, session: &str) -> (Self, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let tags = format!("strategy={},session={}", escape_tag(strategy), escape_tag(session));
+        let task = tokio::spawn(write_loop(config, rx));
+        (TelemetrySink { tags, lines: tx }, task)
+    }
+
+    pub fn record_stats(&self, stats: &LiveStatsSnapshot) {
+        if let Some(line) = stats_line(&self.tags, stats) {
+            let _ = self.lines.send(line);
+        }
+    }
+
+    pub fn record_fill(&self, fill: &FillRecord, time: &str) {
+        if let Some(line) = fill_line(&self.tags, fill, time) {
+            let _ = self.lines.send(line);
+        }
+    }
+}
+
+// batch queued lines and write them every flush_ms; failed batches stay buffered for the next flush
+async fn write_loop(config: TelemetryConfig, mut rx: UnboundedReceiver<String>) {
+    let client = Client::new();
+    let write_url = format!("{}/api/v2/write", config.url);
+    let mut buffer: Vec<String> = Vec::new();
+    let mut ticker = interval(Duration::from_millis(config.flush_ms.max(1)));
+    let mut open = true;
+    while open || !buffer.is_empty() {
+        tokio::select! {
+            line = rx.recv(), if open => match line {
+                Some(line) => {
+                    buffer.push(line);
+                    continue;
+                }
+                None => open = false,
+            },
+            _ = ticker.tick() => {}
+        }
+        if buffer.is_empty() {
+            continue;
+        }
+        let result = client
+            .post(&write_url)
+            .query(&[("org", config.org.as_str()), ("bucket", config.bucket.as_str()), ("precision", "ms")])
+            .header("Authorization", format!("Token {}", config.token))
+            .body(buffer.join("\n"))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        match result {
+            Ok(_) => buffer.clear(),
+            Err(e) => {
+                eprintln!("telemetry write failed ({} points buffered): {}", buffer.len(), e);
+                if buffer.len() > config.max_buffered {
+                    let drop = buffer.len() - config.max_buffered;
+                    buffer.drain(..drop);
+                }
+                // after shutdown, give up instead of retrying forever
+                if !open {
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// POST /command trades the account: it needs the command token and refuses requests sent by web pages
+use rust_core::live_engine::LiveCommand;
+use rust_live::server::EquityChartServer;
+use tokio::sync::mpsc;
+
+const TOKEN: &str = "secret-token";
+
+fn server() -> (EquityChartServer, mpsc::UnboundedReceiver<LiveCommand>) {
+    let (tx, rx) = mpsc::unbounded_channel::<LiveCommand>();
+    (EquityChartServer::new().with_commands(tx, TOKEN), rx)
+}
+
+fn post() -> warp::test::RequestBuilder {
+    warp::test::request().method("POST").path("/command").body(r#"{"command":"pause"}"#)
+}
+
+#[tokio::test]
+async fn command_with_the_token_is_forwarded() {
+    let (server, mut rx) = server();
+    let response = post().header("x-command-token", TOKEN).reply(&server.command_route()).await;
+    assert_eq!(response.status(), 202);
+    assert_eq!(rx.try_recv().unwrap(), LiveCommand::Pause);
+}
+
+#[tokio::test]
+async fn missing_or_wrong_token_is_rejected() {
+    let (server, mut rx) = server();
+    let route = server.command_route();
+    assert_eq!(post().reply(&route).await.status(), 401);
+    assert_eq!(post().header("x-command-token", "secret-tokem").reply(&route).await.status(), 401);
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn cross_origin_request_is_rejected() {
+    let (server, mut rx) = server();
+    // a page open in the browser sends its origin, even with a valid token
+    let response = post()
+        .header("origin", "https://example.com")
+        .header("x-command-token", TOKEN)
+        .reply(&server.command_route())
+        .await;
+    assert_eq!(response.status(), 403);
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn route_is_disabled_without_a_token() {
+    let (tx, mut rx) = mpsc::unbounded_channel::<LiveCommand>();
+    let server = EquityChartServer::new().with_commands(tx, "");
+    let response = post().header("x-command-token", "").reply(&server.command_route()).await;
+    assert_eq!(response.status(), 503);
+    assert!(rx.try_recv().is_err());
+}
@@ -0,0 +1,255 @@
+// stream parsing and reconnection against the mock saxo server
+use rust_core::live_engine::{DataGap, LiveData, StreamEvent};
+use rust_live::mock_saxo::{
+    heartbeat_frame, multipart_frame, quote_frame, quote_payload, saxo_message, truncated_frame,
+    MockFrame, MockSaxoServer,
+};
+use rust_live::merge::{merge_streams, MergeSummary};
+use rust_live::stream::{snapshot_data, stream_with_reconnect, ReconnectPolicy, StreamSummary};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+const T0: &str = "2025-03-03T14:30:00.000000Z";
+
+fn no_reconnect() -> ReconnectPolicy {
+    ReconnectPolicy { max_reconnects: 0, backoff: Duration::from_millis(10) }
+}
+
+// run the stream against one or more scripted connections and collect everything sent on the channel
+async fn run(scripts: Vec<Vec<MockFrame>>, policy: ReconnectPolicy) -> (StreamSummary, Vec<LiveData>) {
+    let addr = MockSaxoServer::new(scripts).start().await.expect("mock server failed to start");
+    let (tx, mut rx) = mpsc::unbounded_channel::<LiveData>();
+    let summary = stream_with_reconnect(&tx, &MockSaxoServer::url(addr), &policy).await;
+    drop(tx);
+    let mut received = Vec::new();
+    while let Some(data) = rx.recv().await {
+        received.push(data);
+    }
+    (summary, received)
+}
+
+#[tokio::test]
+async fn single_quote_frame() {
+    let script = vec![MockFrame::Binary(quote_frame(1, "US500", 5000.0, 5000.5, T0))];
+    let (summary, received) = run(vec![script], no_reconnect()).await;
+
+    assert_eq!(summary.frames, 1);
+    assert_eq!(received.len(), 1);
+    let tick = &received[0].current["US500"];
+    assert_eq!(tick.bid, 5000.0);
+    assert_eq!(tick.ask, 5000.5);
+    assert_eq!(tick.date, T0);
+}
+
+#[tokio::test]
+async fn multipart_frame_yields_every_instrument() {
+    let frame = multipart_frame(&[
+        quote_frame(1, "US500", 5000.0, 5000.5, T0),
+        quote_frame(2, "DJIA", 39000.0, 39002.0, T0),
+    ]);
+    let (_, received) = run(vec![vec![MockFrame::Binary(frame)]], no_reconnect()).await;
+
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].ticks.len(), 2);
+    assert_eq!(received[0].current["DJIA"].ask, 39002.0);
+    assert_eq!(received[0].current["US500"].bid, 5000.0);
+}
+
+#[tokio::test]
+async fn heartbeats_and_text_frames_produce_no_ticks() {
+    let script = vec![
+        MockFrame::Binary(heartbeat_frame(1, &["US500", "DJIA"])),
+        MockFrame::Text("hello".to_string()),
+        MockFrame::Binary(quote_frame(2, "US500", 5001.0, 5001.5, T0)),
+    ];
+    let (summary, received) = run(vec![script], no_reconnect()).await;
+
+    assert_eq!(summary.frames, 2);
+    assert_eq!(summary.updates, 1);
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].current["US500"].bid, 5001.0);
+}
+
+#[tokio::test]
+async fn partial_json_is_dropped_without_losing_later_frames() {
+    let payload = quote_payload("US500", 5000.0, 5000.5, T0);
+    let script = vec![
+        MockFrame::Binary(truncated_frame(1, "US500", &payload, payload.len() / 2)),
+        MockFrame::Binary(saxo_message(2, "DJIA", quote_payload("DJIA", 39000.0, 39001.0, T0).as_bytes())),
+    ];
+    let (summary, received) = run(vec![script], no_reconnect()).await;
+
+    assert_eq!(summary.frames, 2);
+    assert_eq!(received.len(), 1);
+    assert!(received[0].current.contains_key("DJIA"));
+    assert!(!received[0].current.contains_key("US500"));
+}
+
+#[tokio::test]
+async fn reconnects_after_the_socket_drops() {
+    let first = vec![
+        MockFrame::Binary(quote_frame(1, "US500", 5000.0, 5000.5, T0)),
+        MockFrame::Close,
+    ];
+    let second = vec![
+        MockFrame::Delay(20),
+        MockFrame::Binary(quote_frame(2, "US500", 5002.0, 5002.5, T0)),
+    ];
+    let policy = ReconnectPolicy { max_reconnects: 1, backoff: Duration::from_millis(10) };
+    let (summary, received) = run(vec![first, second], policy).await;
+
+    assert_eq!(summary.connections, 2);
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[1].current["US500"].bid, 5002.0);
+}
+
+#[tokio::test]
+async fn skipped_message_ids_are_reported_as_a_gap() {
+    let script = vec![
+        MockFrame::Binary(quote_frame(1, "US500", 5000.0, 5000.5, T0)),
+        MockFrame::Binary(heartbeat_frame(2, &["US500"])),
+        MockFrame::Binary(quote_frame(5, "US500", 5003.0, 5003.5, T0)),
+    ];
+    let (summary, received) = run(vec![script], no_reconnect()).await;
+
+    assert_eq!(summary.gaps, 1);
+    assert_eq!(received.len(), 2);
+    assert!(received[0].events.is_empty());
+    let gap = DataGap { instrument: "US500".to_string(), expected: 3, received: 5 };
+    assert_eq!(gap.missed(), 2);
+    assert_eq!(received[1].events, vec![StreamEvent::DataGap(gap)]);
+    assert_eq!(received[1].current["US500"].bid, 5003.0);
+}
+
+#[tokio::test]
+async fn message_ids_restart_on_reconnect_without_a_gap() {
+    let first = vec![
+        MockFrame::Binary(quote_frame(7, "US500", 5000.0, 5000.5, T0)),
+        MockFrame::Close,
+    ];
+    let second = vec![
+        MockFrame::Delay(20),
+        MockFrame::Binary(quote_frame(1, "US500", 5002.0, 5002.5, T0)),
+        MockFrame::Binary(quote_frame(2, "US500", 5003.0, 5003.5, T0)),
+    ];
+    let policy = ReconnectPolicy { max_reconnects: 1, backoff: Duration::from_millis(10) };
+    let (summary, received) = run(vec![first, second], policy).await;
+
+    assert_eq!(summary.gaps, 0);
+    assert!(received.iter().all(|d| d.events.is_empty()));
+}
+
+#[test]
+fn snapshot_response_is_a_resync() {
+    let body = serde_json::json!({
+        "ContextId": "ctx",
+        "ReferenceId": "US500",
+        "Snapshot": { "LastUpdated": T0, "Quote": { "Ask": 5000.5, "Bid": 5000.0 } },
+    })
+    .to_string();
+    let data = snapshot_data("US500", &body).expect("snapshot quote");
+    assert_eq!(data.current["US500"].ask, 5000.5);
+    assert_eq!(data.events, vec![StreamEvent::Resynced("US500".to_string())]);
+    assert!(snapshot_data("US500", "{}").is_none());
+}
+
+#[tokio::test]
+async fn payload_size_bytes_do_not_corrupt_the_frame() {
+    // a 123 byte payload puts '{' (0x7b) into the size header, which the old text-based parser mistook for json
+    let mut payload = quote_payload("US500", 5000.0, 5000.5, T0);
+    while payload.len() < 123 {
+        payload.push(' ');
+    }
+    assert_eq!(payload.len(), 123);
+    let frame = multipart_frame(&[
+        saxo_message(1, "US500", payload.as_bytes()),
+        quote_frame(2, "DJIA", 39000.0, 39001.0, T0),
+    ]);
+    let (_, received) = run(vec![vec![MockFrame::Binary(frame)]], no_reconnect()).await;
+
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].current["US500"].ask, 5000.5);
+    assert_eq!(received[0].current["DJIA"].ask, 39001.0);
+}
+
+#[tokio::test]
+async fn instruments_are_named_by_reference_id() {
+    // any subscription reference id works, not just the ones the old parser knew about
+    let script = vec![MockFrame::Binary(quote_frame(1, "GER40", 18000.0, 18001.0, T0))];
+    let (_, received) = run(vec![script], no_reconnect()).await;
+
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].current["GER40"].bid, 18000.0);
+}
+
+// one mock server per instrument, each streamed on its own task into the merge stage
+async fn run_merged(servers: Vec<Vec<Vec<MockFrame>>>) -> (MergeSummary, Vec<LiveData>) {
+    let (merge_tx, merge_rx) = mpsc::unbounded_channel::<LiveData>();
+    for scripts in servers {
+        let addr = MockSaxoServer::new(scripts).start().await.expect("mock server failed to start");
+        let merge_tx = merge_tx.clone();
+        tokio::spawn(async move {
+            stream_with_reconnect(&merge_tx, &MockSaxoServer::url(addr), &no_reconnect()).await;
+        });
+    }
+    drop(merge_tx);
+    let (tx, mut rx) = mpsc::unbounded_channel::<LiveData>();
+    let summary = merge_streams(merge_rx, tx, Duration::from_millis(200)).await;
+    let mut received = Vec::new();
+    while let Some(data) = rx.recv().await {
+        received.push(data);
+    }
+    (summary, received)
+}
+
+#[tokio::test]
+async fn merged_streams_are_ordered_by_quote_time() {
+    let us500 = vec![
+        MockFrame::Binary(quote_frame(1, "US500", 5000.0, 5000.5, "2025-03-03T14:30:01.000000Z")),
+        MockFrame::Binary(quote_frame(2, "US500", 5001.0, 5001.5, "2025-03-03T14:30:03.000000Z")),
+    ];
+    let djia = vec![
+        MockFrame::Binary(quote_frame(1, "DJIA", 39000.0, 39002.0, "2025-03-03T14:30:00.000000Z")),
+        MockFrame::Binary(quote_frame(2, "DJIA", 39001.0, 39003.0, "2025-03-03T14:30:02.000000Z")),
+    ];
+    let (summary, received) = run_merged(vec![vec![us500], vec![djia]]).await;
+
+    let dates: Vec<String> = received.iter().flat_map(|d| d.ticks.iter().map(|t| t.date.clone())).collect();
+    let mut sorted = dates.clone();
+    sorted.sort();
+    assert_eq!(dates.len(), 4);
+    assert_eq!(dates, sorted);
+    assert_eq!(summary.ticks, 4);
+    assert_eq!(summary.late, 0);
+}
+
+#[tokio::test]
+async fn one_stream_down_does_not_stall_the_other() {
+    // the DJIA server closes every connection straight away
+    let us500 = vec![
+        MockFrame::Binary(quote_frame(1, "US500", 5000.0, 5000.5, T0)),
+        MockFrame::Delay(50),
+        MockFrame::Binary(quote_frame(2, "US500", 5001.0, 5001.5, "2025-03-03T14:30:01.000000Z")),
+    ];
+    let (summary, received) = run_merged(vec![vec![us500], vec![]]).await;
+
+    assert_eq!(summary.ticks, 2);
+    let last = received.last().expect("no merged data");
+    assert_eq!(last.current["US500"].bid, 5001.0);
+    assert!(received.iter().all(|d| !d.current.contains_key("DJIA")));
+}
+
+#[tokio::test]
+async fn late_ticks_are_forwarded_and_counted() {
+    // the second stream delivers an older quote after the first batch was already released
+    let us500 = vec![MockFrame::Binary(quote_frame(1, "US500", 5000.0, 5000.5, "2025-03-03T14:30:05.000000Z"))];
+    let djia = vec![
+        MockFrame::Delay(500),
+        MockFrame::Binary(quote_frame(1, "DJIA", 39000.0, 39002.0, T0)),
+    ];
+    let (summary, received) = run_merged(vec![vec![us500], vec![djia]]).await;
+
+    assert_eq!(summary.ticks, 2);
+    assert_eq!(summary.late, 1);
+    assert_eq!(received.last().unwrap().current["DJIA"].ask, 39002.0);
+}
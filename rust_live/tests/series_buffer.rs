@@ -0,0 +1,38 @@
+// raw chart updates are kept in a bounded buffer, and resampling only covers the requested window
+use chrono::{Duration, TimeZone, Utc};
+use rust_core::clock::TestClock;
+use rust_live::server::{EquityChartServer, MAX_SERIES_POINTS};
+use std::sync::Arc;
+
+fn server() -> (EquityChartServer, Arc<TestClock>) {
+    let clock = Arc::new(TestClock::new(Utc.with_ymd_and_hms(2025, 3, 3, 14, 30, 0).unwrap()));
+    (EquityChartServer::new().with_clock(clock.clone()), clock)
+}
+
+#[test]
+fn oldest_updates_are_dropped() {
+    let (server, clock) = server();
+    for i in 0..MAX_SERIES_POINTS + 10 {
+        server.update_series("equity", i as f64);
+        clock.advance(Duration::seconds(1));
+    }
+    let points = server.resampled("equity", Duration::seconds(1), None);
+    assert_eq!(points.len(), MAX_SERIES_POINTS);
+    assert_eq!(points[0].1, 10.0);
+}
+
+#[test]
+fn window_starts_from_the_value_before_it() {
+    let (server, clock) = server();
+    // 0, 10, ..., 100 every ten seconds
+    for i in 0..=10 {
+        server.update_series("equity", 10.0 * i as f64);
+        clock.advance(Duration::seconds(10));
+    }
+    let points = server.resampled("equity", Duration::seconds(5), Some(Duration::seconds(35)));
+    let values: Vec<f64> = points.iter().map(|(_, value)| *value).collect();
+    // the window starts 35 seconds before the last update, while the series was at 60
+    assert_eq!(values, [60.0, 70.0, 70.0, 80.0, 80.0, 90.0, 90.0, 100.0]);
+    assert_eq!(points[0].0, Utc.with_ymd_and_hms(2025, 3, 3, 14, 31, 5).unwrap().naive_utc());
+    assert_eq!(server.resampled("equity", Duration::seconds(10), None).len(), 11);
+}
@@ -0,0 +1,113 @@
+use crate::scaler::ScalerParams;
+use std::collections::VecDeque;
+use std::fmt;
+
+// what a strategy should do while inputs have drifted
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DriftAction {
+    Warn,  // log and keep trading
+    Pause, // log and stop opening new positions until inputs return to range
+}
+
+#[derive(Clone, Debug)]
+pub struct DriftConfig {
+    pub window: usize,          // live inputs kept per feature
+    pub mean_threshold: f64,    // max |rolling mean - training mean| in units of the training scale
+    pub scale_threshold: f64,   // max ratio between rolling std and training scale (either direction)
+    pub action: DriftAction,
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        DriftConfig {
+            window: 200,
+            mean_threshold: 3.0,
+            scale_threshold: 4.0,
+            action: DriftAction::Warn,
+        }
+    }
+}
+
+// one feature outside its training range
+#[derive(Clone, Debug)]
+pub struct FeatureDrift {
+    pub feature: usize,
+    pub mean_z: f64,      // rolling mean shift in training scales
+    pub scale_ratio: f64, // rolling std / training scale
+}
+
+impl fmt::Display for FeatureDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "feature {}: mean shift {:.2} scales, std ratio {:.2}", self.feature, self.mean_z, self.scale_ratio)
+    }
+}
+
+// compares rolling statistics of live model inputs against the scaler the model was trained with
+pub struct DriftMonitor {
+    pub config: DriftConfig,
+    mean: Vec<f64>,
+    scale: Vec<f64>,
+    windows: Vec<VecDeque<f64>>,
+    drifting: bool,
+}
+
+impl DriftMonitor {
+    pub fn new(params: &ScalerParams, config: DriftConfig) -> Self {
+        let n = params.mean.len();
+        DriftMonitor {
+            mean: params.mean.iter().map(|&m| m as f64).collect(),
+            scale: params.scale.iter().map(|&s| s as f64).collect(),
+            windows: vec![VecDeque::with_capacity(config.window); n],
+            config,
+            drifting: false,
+        }
+    }
+
+    // add one unscaled input vector and return the features currently out of range;
+    // nothing is reported until the window is full
+    pub fn update(&mut self, input: &[f32]) -> Vec<FeatureDrift> {
+        for (window, &x) in self.windows.iter_mut().zip(input.iter()) {
+            window.push_back(x as f64);
+            if window.len() > self.config.window {
+                window.pop_front();
+            }
+        }
+
+        let mut drifted = Vec::new();
+        for (feature, window) in self.windows.iter().enumerate() {
+            if window.len() < self.config.window || window.len() < 2 || self.scale[feature] <= 0.0 {
+                continue;
+            }
+            let n = window.len() as f64;
+            let mean = window.iter().sum::<f64>() / n;
+            let std = (window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+            let mean_z = (mean - self.mean[feature]) / self.scale[feature];
+            let scale_ratio = std / self.scale[feature];
+            let scale_out = scale_ratio > self.config.scale_threshold
+                || scale_ratio < 1.0 / self.config.scale_threshold;
+            if mean_z.abs() > self.config.mean_threshold || scale_out {
+                drifted.push(FeatureDrift { feature, mean_z, scale_ratio });
+            }
+        }
+
+        // log transitions only, not every tick
+        let now_drifting = !drifted.is_empty();
+        if now_drifting && !self.drifting {
+            let details: Vec<String> = drifted.iter().map(|d| d.to_string()).collect();
+            eprintln!("warning: model inputs drifted from training distribution ({})", details.join("; "));
+        } else if !now_drifting && self.drifting {
+            println!("model inputs back within training range");
+        }
+        self.drifting = now_drifting;
+        drifted
+    }
+
+    pub fn is_drifting(&self) -> bool {
+        self.drifting
+    }
+
+    // true if the configured action says new positions should not be opened right now
+    pub fn should_pause(&self) -> bool {
+        self.drifting && self.config.action == DriftAction::Pause
+    }
+}
@@ -1,27 +1,120 @@
-use tch::{CModule, Tensor};
+use tch::{CModule, Device, Kind, Tensor};
 use anyhow::Result;
+use std::str::FromStr;
+
+// where inference runs; Auto picks cuda, then mps, then cpu
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum InferenceDevice {
+    #[default]
+    Auto,
+    Cpu,
+    Cuda(usize),
+    Mps,
+}
+
+impl InferenceDevice {
+    pub fn to_tch(self) -> Device {
+        match self {
+            InferenceDevice::Auto => {
+                if tch::Cuda::is_available() {
+                    Device::Cuda(0)
+                } else if tch::utils::has_mps() {
+                    Device::Mps
+                } else {
+                    Device::Cpu
+                }
+            }
+            InferenceDevice::Cpu => Device::Cpu,
+            InferenceDevice::Cuda(index) => Device::Cuda(index),
+            InferenceDevice::Mps => Device::Mps,
+        }
+    }
+}
+
+// parse "auto", "cpu", "mps", "cuda" or "cuda:N"
+impl FromStr for InferenceDevice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(InferenceDevice::Auto),
+            "cpu" => Ok(InferenceDevice::Cpu),
+            "mps" => Ok(InferenceDevice::Mps),
+            "cuda" => Ok(InferenceDevice::Cuda(0)),
+            other => other.strip_prefix("cuda:")
+                .and_then(|index| index.parse().ok())
+                .map(InferenceDevice::Cuda)
+                .ok_or_else(|| format!("unknown inference device '{}', expected auto, cpu, mps, cuda or cuda:N", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InferenceConfig {
+    pub device: InferenceDevice,
+    pub intra_op_threads: Option<i32>, // None keeps the libtorch default
+    pub warm_up_runs: usize,           // forward passes on dummy input right after loading
+    pub input_size: usize,             // number of features per prediction
+}
+
+impl Default for InferenceConfig {
+    fn default() -> Self {
+        InferenceConfig {
+            device: InferenceDevice::Auto,
+            intra_op_threads: None,
+            warm_up_runs: 3,
+            input_size: 4,
+        }
+    }
+}
 
 pub struct NeuralNet {
     model: CModule,
+    device: Device,
+    input_size: usize,
 }
 
 impl NeuralNet {
-    // load the TorchScript model from file
-    pub fn new(rel_path: &str) -> Result<Self, tch::TchError> {
+    // load the TorchScript model from file onto the configured device
+    pub fn new(rel_path: &str, config: &InferenceConfig) -> Result<Self, tch::TchError> {
+        // thread count is process wide in libtorch, so set it before the first forward pass
+        if let Some(threads) = config.intra_op_threads {
+            tch::set_num_threads(threads);
+        }
         // build path relative to rust_ml's manifest
         let base = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
         let model_path = base.join(rel_path);
+        let device = config.device.to_tch();
         // load torchscript model from the resolved path
-        let model = tch::CModule::load(model_path)?;
-        Ok(NeuralNet { model })
+        let mut model = tch::CModule::load_on_device(model_path, device)?;
+        model.set_eval();
+        let net = NeuralNet { model, device, input_size: config.input_size };
+        net.warm_up(config.warm_up_runs)?;
+        Ok(net)
+    }
+
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    // run a few forward passes on zeros so lazy initialization (kernel selection, allocator
+    // growth) happens before the first real prediction
+    pub fn warm_up(&self, runs: usize) -> Result<(), tch::TchError> {
+        let input = Tensor::zeros([1, self.input_size as i64], (Kind::Float, self.device));
+        for _ in 0..runs {
+            tch::no_grad(|| self.model.forward_ts(&[&input]))?;
+        }
+        Ok(())
     }
 
     // run a forward pass given a slice of input data (adjust dimensions as needed)
     pub fn predict(&self, input: &[f32]) -> Result<Tensor> {
         // create a tensor from input data and add a batch dimension (unsqueeze)
-        let input_tensor = Tensor::from(input).reshape(&[1, 4]);
-        let output = self.model.forward_ts(&[input_tensor])?;
-        Ok(output)
+        let input_tensor = Tensor::from_slice(input)
+            .reshape([1, input.len() as i64])
+            .to_device(self.device);
+        let output = tch::no_grad(|| self.model.forward_ts(&[input_tensor]))?;
+        // hand results back on the cpu so callers can read values directly
+        Ok(output.to_device(Device::Cpu))
     }
 }
-
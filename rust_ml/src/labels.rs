@@ -0,0 +1,143 @@
+// training labels built with the same fill and exit rules as the backtest broker, so a model
+// learns the outcomes the strategies can actually realize:
+// - a signal on bar t enters on bar t + 1 (at its open, or at close[t] with trade_on_close)
+// - stop and target are first checked on the bar after the entry
+// - a stop hit fills at that bar's open (close of the previous bar with trade_on_close),
+//   a target hit fills at the target price, and the stop wins when both are hit on one bar unless the bar
+//   opened through the target
+// - a trade still open after max_holding bars exits at that bar's close
+
+#[derive(Clone, Debug)]
+pub struct TripleBarrier {
+    pub profit_target: f64,  // fraction of the entry price, e.g. 0.01 for 1%; 0.0 disables
+    pub stop_loss: f64,      // fraction of the entry price; 0.0 disables
+    pub max_holding: usize,  // bars after entry before the time exit
+    pub side: f64,           // 1.0 labels long entries, -1.0 short entries
+    pub trade_on_close: bool,
+}
+
+impl Default for TripleBarrier {
+    fn default() -> Self {
+        TripleBarrier {
+            profit_target: 0.01,
+            stop_loss: 0.005,
+            max_holding: 20,
+            side: 1.0,
+            trade_on_close: false,
+        }
+    }
+}
+
+// which barrier ended the trade
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Barrier {
+    ProfitTarget,
+    StopLoss,
+    Timeout,
+}
+
+#[derive(Clone, Debug)]
+pub struct BarrierLabel {
+    pub barrier: Barrier,
+    pub entry_index: usize,
+    pub exit_index: usize,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub ret: f64, // return in the direction of `side`
+}
+
+impl BarrierLabel {
+    // 1 target, -1 stop, 0 timeout
+    pub fn class(&self) -> i8 {
+        match self.barrier {
+            Barrier::ProfitTarget => 1,
+            Barrier::StopLoss => -1,
+            Barrier::Timeout => 0,
+        }
+    }
+}
+
+/// fixed-horizon labels from close to close: 1 if the return over `horizon` bars is above `threshold`,
+/// -1 if below `-threshold`, 0 in between; None where the horizon runs past the data
+pub fn fixed_horizon_labels(close: &[f64], horizon: usize, threshold: f64) -> Vec<Option<i8>> {
+    (0..close.len())
+        .map(|t| {
+            let future = close.get(t + horizon)?;
+            if close[t] == 0.0 {
+                return None;
+            }
+            let ret = future / close[t] - 1.0;
+            Some(if ret > threshold {
+                1
+            } else if ret < -threshold {
+                -1
+            } else {
+                0
+            })
+        })
+        .collect()
+}
+
+/// triple-barrier label for a signal on every bar; None where the trade cannot be resolved
+/// before the data ends
+pub fn triple_barrier_labels(
+    open: &[f64],
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    config: &TripleBarrier,
+) -> Vec<Option<BarrierLabel>> {
+    (0..close.len())
+        .map(|t| triple_barrier_label(open, high, low, close, t, config))
+        .collect()
+}
+
+// label for a single signal bar
+pub fn triple_barrier_label(
+    open: &[f64],
+    high: &[f64],
+    low: &[f64],
+    close: &[f64],
+    signal_index: usize,
+    config: &TripleBarrier,
+) -> Option<BarrierLabel> {
+    let entry_index = signal_index + 1;
+    if entry_index >= close.len() {
+        return None;
+    }
+    let entry_price = if config.trade_on_close { close[signal_index] } else { open[entry_index] };
+    let side = config.side.signum();
+    let target = (config.profit_target > 0.0).then(|| entry_price * (1.0 + side * config.profit_target));
+    let stop = (config.stop_loss > 0.0).then(|| entry_price * (1.0 - side * config.stop_loss));
+    let label = |barrier, exit_index, exit_price: f64| BarrierLabel {
+        barrier,
+        entry_index,
+        exit_index,
+        entry_price,
+        exit_price,
+        ret: side * (exit_price / entry_price - 1.0),
+    };
+
+    let last = entry_index + config.max_holding;
+    for i in (entry_index + 1)..=last.min(close.len() - 1) {
+        // as in the broker, a bar that reaches both exits the stop, unless it opened through the target
+        let stop_hit = stop.is_some_and(|stop| if side > 0.0 { low[i] <= stop } else { high[i] >= stop });
+        if let Some(target) = target {
+            let hit = if side > 0.0 { high[i] >= target } else { low[i] <= target };
+            let opened_through = if side > 0.0 { open[i] >= target } else { open[i] <= target };
+            if hit && (!stop_hit || opened_through) {
+                return Some(label(Barrier::ProfitTarget, i, target));
+            }
+        }
+        if stop_hit {
+            let fill = if config.trade_on_close { close[i - 1] } else { open[i] };
+            return Some(label(Barrier::StopLoss, i, fill));
+        }
+    }
+
+    if last < close.len() {
+        Some(label(Barrier::Timeout, last, close[last]))
+    } else {
+        None
+    }
+}
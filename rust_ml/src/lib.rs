@@ -1,2 +1,5 @@
 pub mod scaler;
 pub mod inference;
+pub mod drift;
+pub mod tracking;
+pub mod labels;
@@ -20,6 +20,14 @@ pub fn load_scaler_params(rel_path: &str) -> ScalerParams {
     serde_json::from_reader(reader).expect("failed to read scaler params")
 }
 
+// like load_scaler_params, but returns an error instead of panicking when the file is missing or malformed
+pub fn try_load_scaler_params(rel_path: &str) -> Result<ScalerParams, String> {
+    let base = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let full_path = base.join(rel_path);
+    let file = File::open(&full_path).map_err(|e| format!("{}: {}", full_path.display(), e))?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("{}: {}", full_path.display(), e))
+}
+
 // apply scaling to the input vector
 pub fn scale_input(input: &[f32], params: &ScalerParams) -> Vec<f32> {
     input.iter()
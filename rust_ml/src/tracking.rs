@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+// a prediction waiting for its horizon to pass
+#[derive(Clone, Debug)]
+struct PendingPrediction {
+    tick: usize,
+    score: f64, // signed: > 0 expects the price to rise, < 0 to fall, 0 no view
+    price: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ModelMetrics {
+    pub predictions: usize, // total recorded
+    pub resolved: usize,    // predictions in the rolling window whose horizon has passed
+    pub hit_rate: f64,      // share of directional predictions in the window with the right sign
+    pub ic: f64,            // correlation between score and realized forward return in the window
+}
+
+impl fmt::Display for ModelMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "model: {} predictions, {} resolved, hit rate {:.1}%, ic {:.3}",
+            self.predictions, self.resolved, self.hit_rate * 100.0, self.ic)
+    }
+}
+
+// pairs each live prediction with the return realized `horizon` ticks later
+pub struct PredictionTracker {
+    pub horizon: usize, // ticks between prediction and evaluation
+    pub window: usize,  // resolved predictions kept for the rolling metrics
+    pending: VecDeque<PendingPrediction>,
+    resolved: VecDeque<(f64, f64)>, // (score, forward return)
+    predictions: usize,
+}
+
+impl PredictionTracker {
+    pub fn new(horizon: usize, window: usize) -> Self {
+        PredictionTracker {
+            horizon,
+            window,
+            pending: VecDeque::new(),
+            resolved: VecDeque::with_capacity(window),
+            predictions: 0,
+        }
+    }
+
+    pub fn record(&mut self, tick: usize, score: f64, price: f64) {
+        self.pending.push_back(PendingPrediction { tick, score, price });
+        self.predictions += 1;
+    }
+
+    // resolve every pending prediction whose horizon has passed at `tick`
+    pub fn update(&mut self, tick: usize, price: f64) {
+        while let Some(front) = self.pending.front() {
+            if tick < front.tick + self.horizon {
+                break;
+            }
+            let prediction = self.pending.pop_front().unwrap();
+            if prediction.price != 0.0 {
+                self.resolved.push_back((prediction.score, price / prediction.price - 1.0));
+                if self.resolved.len() > self.window {
+                    self.resolved.pop_front();
+                }
+            }
+        }
+    }
+
+    pub fn metrics(&self) -> ModelMetrics {
+        let directional: Vec<&(f64, f64)> = self.resolved.iter().filter(|(score, _)| *score != 0.0).collect();
+        let hits = directional.iter().filter(|(score, ret)| score.signum() == ret.signum()).count();
+        let hit_rate = if directional.is_empty() { 0.0 } else { hits as f64 / directional.len() as f64 };
+
+        ModelMetrics {
+            predictions: self.predictions,
+            resolved: self.resolved.len(),
+            hit_rate,
+            ic: correlation(&self.resolved),
+        }
+    }
+}
+
+// pearson correlation of (x, y) pairs, 0.0 if either side is constant
+fn correlation(pairs: &VecDeque<(f64, f64)>) -> f64 {
+    let n = pairs.len() as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let cov: f64 = pairs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let var_x: f64 = pairs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let var_y: f64 = pairs.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    if var_x == 0.0 || var_y == 0.0 {
+        0.0
+    } else {
+        cov / (var_x * var_y).sqrt()
+    }
+}